@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = Startseite
+title-login = Anmelden
+title-error = Fehler
+title-forbidden = Nicht erlaubt
+title-form-validation = Formularprüfung
+title-create-stack = Stapel erstellen
+title-edit-stack = Stapel bearbeiten
+title-review = Wiederholen
+title-review-session = Wiederholungssitzung
+title-quiz = Quiz
+title-match = Zuordnen
+title-stats = Deine Statistik
+title-search = Stapel suchen
+title-collection = Sammlung
+title-stack = Stapel
+title-create-collection = Sammlung erstellen
+title-edit-collection = Sammlung bearbeiten
+
+## Layout
+
+app-tagline = Karteikarten in der Atmosphäre.
+locale-label = Sprache der Oberfläche
+locale-apply = Ändern
+error-prefix = Fehler:
+go-home = Zur Startseite
+
+## Home
+
+home-greeting = Hallo, { $name }.
+home-friend = Freund
+log-in = Anmelden
+log-out = Abmelden
+home-no-stacks = Noch keine Stapel.
+home-create-one = Warum nicht einen erstellen?
+home-new-stack = Neuer Stapel
+home-front-lang = Sprache der Vorderseite:
+home-back-lang = Sprache der Rückseite:
+home-lang-unspecified = (Nicht angegeben)
+home-review = Wiederholen
+home-review-session = Stapelübergreifend wiederholen
+home-any-lang = (Alle)
+home-quiz = Quiz
+home-match = Zuordnen
+home-stats = Deine Statistik
+home-search = Stapel suchen
+home-tag = Schlagwort:
+home-any-tag = (Alle)
+home-all-stacks = Alle Stapel
+home-no-tagged-stacks = Keiner deiner Stapel hat das Schlagwort { $tag }.
+home-collections = Deine Sammlungen
+home-new-collection = Neue Sammlung
+
+## Login
+
+login-handle-placeholder = Gib dein Handle ein (z. B. alice.bsky.social)
+login-no-account = Noch kein Konto in der Atmosphäre?
+login-sign-up = Registriere dich bei Bluesky, um jetzt eins zu erstellen!
+login-oauth-error = OAuth-Fehler
+login-invalid-handle = Das ist kein gültiges Handle.
+
+## Stacks
+
+stack-label = Name des Stapels
+stack-label-placeholder = Kapitel 1
+stack-front-lang = Sprache der Vorderseite (optional)
+stack-back-lang = Sprache der Rückseite (optional)
+stack-create = Stapel erstellen
+stack-edit = Stapel bearbeiten
+stack-delete = Stapel löschen
+stack-delete-confirm = Möchtest du { $label } wirklich löschen?
+stack-directions = Abfragerichtung
+stack-directions-forward = Vorderseite zu Rückseite
+stack-directions-reverse = Rückseite zu Vorderseite
+stack-directions-both = In beide Richtungen
+stack-description = Beschreibung (optional)
+stack-description-placeholder = Worum es im Stapel geht und für wen er gedacht ist
+stack-source = Quelle (optional)
+stack-source-placeholder = Das Lehrbuch, der Kurs oder die Wortliste, aus der die Karten stammen
+stack-credit = Quelle
+stack-license = Lizenz
+stack-license-none = (Keine)
+stack-license-unspecified = Nicht angegeben
+stack-license-all-rights-reserved = Alle Rechte vorbehalten
+stack-reuse-licensed = Du darfst diese Karten zu den Bedingungen der Lizenz weiterverwenden und bearbeiten.
+stack-reuse-reserved = Der Autor hat die Weiterverwendung dieser Karten nicht erlaubt.
+stack-reuse-other = Prüfe die Bedingungen der Lizenz, bevor du diese Karten weiterverwendest.
+stack-reuse-unspecified = Der Autor hat nicht angegeben, ob diese Karten weiterverwendet werden dürfen. Frag nach, bevor du eine Kopie teilst.
+stack-difficulty = Schwierigkeit
+stack-difficulty-none = (Nicht angegeben)
+stack-difficulty-beginner = Anfänger
+stack-difficulty-intermediate = Mittelstufe
+stack-difficulty-advanced = Fortgeschritten
+stack-cover = Titelbild
+stack-cards = Karten
+stack-view = Seite des Stapels ansehen
+stack-edit-page = Bearbeiten
+tags = Schlagwörter
+tags-placeholder = Verben, Reisen, Goethe A1
+
+## Cards
+
+card-front-lang = Sprache der Vorderseite
+card-front-text = Text der Vorderseite
+card-back-lang = Sprache der Rückseite
+card-back-text = Text der Rückseite
+card-kind = Kartentyp
+card-kind-basic = Vorder- und Rückseite
+card-kind-cloze = Lückentext
+card-format = Textformat
+card-format-plain = Reiner Text
+card-format-markdown = Markdown und Ruby
+card-front-image = Bild der Vorderseite
+card-back-image = Bild der Rückseite
+card-image-remove = Bild entfernen
+card-audio = Audio
+card-audio-record = Aufnehmen
+card-audio-stop = Stopp
+card-audio-remove = Audio entfernen
+card-audio-speak = Erzeugtes Audio
+card-audio-speak-none = Nicht erzeugen
+card-audio-speak-front = Aus der Vorderseite erzeugen
+card-audio-speak-back = Aus der Rückseite erzeugen
+stack-speech = Audio für Karten ohne Audio erzeugen
+card-create = Karte erstellen
+card-update = Karte aktualisieren
+card-delete = Karte löschen
+card-delete-confirm = Möchtest du diese Karte wirklich löschen?
+card-drag = Zum Umsortieren ziehen oder die Pfeiltasten verwenden
+card-order-drag = Ziehe Karten an ihren Griffen, um die Reihenfolge zu ändern, in der sie gelernt werden.
+card-order-uris = Kartenreihenfolge, eine Karten-URI pro Zeile
+card-order-save = Reihenfolge speichern
+
+## Language pickers
+
+lang-search = Sprachen suchen
+lang-none = (Keine)
+lang-choose = Bitte wähle eine Sprache
+lang-group-selected = Ausgewählt
+lang-group-yours = Deine Sprachen
+lang-group-common = Häufige Sprachen
+lang-group-other = Weitere Sprachen
+
+## Review
+
+review-show = Antwort zeigen
+review-again = Nochmal
+review-hard = Schwer
+review-good = Gut
+review-easy = Leicht
+review-due = { $count } fällig
+review-new = { $count } neu
+review-done = Im Moment gibt es nichts mehr zu wiederholen.
+review-mode = Antworten durch
+review-mode-flip = Umdrehen der Karte
+review-mode-typed = Eintippen der Antwort
+review-ignore-accents = Akzente ignorieren
+review-new-order = Neue Karten
+review-new-order-in-order = In Stapelreihenfolge
+review-new-order-shuffled = Gemischt
+review-apply = Übernehmen
+review-typed = Antwort eintippen
+review-check = Prüfen
+review-verdict-exact = Richtig!
+review-verdict-accents = Richtig, bis auf die Akzente.
+review-verdict-close = Fast: Achte auf die Schreibweise.
+review-verdict-wrong = Nicht ganz.
+review-new-per-day = Neue Karten pro Tag
+review-reviews-per-day = Wiederholungen pro Tag
+review-save-limits = Limits speichern
+review-held-back = { $count } weitere warten wegen deiner Tageslimits auf einen anderen Tag.
+
+## Quiz
+
+quiz-submit = Antworten prüfen
+quiz-score = { $correct } von { $total } richtig ({ $percent } %)
+quiz-chosen = Deine Wahl:
+quiz-unanswered = Nicht beantwortet
+quiz-again = Noch ein Quiz
+quiz-empty = Dieser Stapel hat noch keine Karten für ein Quiz.
+
+## Match
+
+match-pairs = Paare
+match-new = Neues Spielfeld
+match-time = In { $seconds } Sekunden zugeordnet
+match-best = Deine Bestzeit: { $seconds } Sekunden
+match-new-best = Neue Bestzeit!
+match-log-in = Melde dich an, um deine Bestzeiten zu speichern.
+match-again = Nochmal spielen
+match-too-few = Zum Spielen braucht dieser Stapel mindestens zwei Karten mit unterschiedlichen Vorder- und Rückseiten.
+
+## Stats
+
+stats-period = Wiederholungen, Behaltensquote und Zeit beziehen sich auf die letzten { $days } Tage. Tage laufen von Mitternacht bis Mitternacht UTC.
+stats-reviews-today = Wiederholungen heute
+stats-streak = Aktuelle Serie in Tagen
+stats-longest-streak = Längste Serie in Tagen
+stats-reviews = Wiederholungen
+stats-retention = Behaltensquote
+stats-minutes = Minuten
+stats-due = Heute fällig
+stats-per-day = Wiederholungen pro Tag, letzte { $days } Tage
+stats-forecast = Fällig pro Tag, nächste { $days } Tage
+stats-maturity = Karten nach Reife
+stats-learning = Lernen
+stats-young = Jung
+stats-mature = Reif
+stats-calendar = Serienkalender
+stats-by-stack = Nach Stapel
+stats-by-langs = Nach Sprachpaar
+stats-stack = Stapel
+stats-langs = Sprachen
+stats-empty = Noch keine Wiederholungen. Wiederhole einen Stapel, dann erscheint hier deine Statistik.
+
+## Search
+
+search-text = Name:
+search-submit = Suchen
+search-none = Keine Stapel gefunden.
+search-clone = In meine Stapel kopieren
+
+## Collections
+
+collection-label = Name der Sammlung
+collection-label-placeholder = Genki I
+collection-description = Beschreibung (optional)
+collection-stacks = Stapel, in Lernreihenfolge
+collection-stacks-uris = Stapel-URIs, eine pro Zeile
+collection-pick-stack = Wähle einen deiner Stapel
+collection-add-stack = Hinzufügen
+collection-move-up = Nach oben
+collection-move-down = Nach unten
+collection-remove-stack = Entfernen
+collection-create = Sammlung erstellen
+collection-save = Sammlung speichern
+collection-view = Sammlung ansehen
+collection-edit = Bearbeiten
+collection-delete = Sammlung löschen
+collection-delete-confirm = Möchtest du { $label } wirklich löschen? Die Stapel bleiben erhalten.
+collection-clone = In meine Sammlungen kopieren
+collection-empty = Diese Sammlung enthält noch keine Stapel.
+
+## Validation
+
+invalid-record-uri = Ungültige Datensatz-URI { $uri }
+invalid-front-lang = Ungültige Sprache der Vorderseite: { $lang }
+invalid-back-lang = Ungültige Sprache der Rückseite: { $lang }
+invalid-directions = Ungültige Abfragerichtung: { $directions }
+invalid-description-length = Beschreibungen dürfen höchstens { $max } Zeichen lang sein
+invalid-source-length = Quellenangaben dürfen höchstens { $max } Zeichen lang sein
+invalid-license = Ungültige Lizenz: { $license }
+invalid-difficulty = Unbekannter Schwierigkeitsgrad: { $difficulty }
+invalid-tag-length = Schlagwörter dürfen höchstens { $max } Zeichen lang sein: { $tag }
+invalid-tag-count = Höchstens { $max } Schlagwörter sind erlaubt
+invalid-collection-label = Eine Sammlung braucht einen Namen
+invalid-label-length = Namen dürfen höchstens { $max } Zeichen lang sein
+invalid-stack-uri = Kein Stapel: { $uri }
+invalid-stack-count = Höchstens { $max } Stapel sind erlaubt
+invalid-stack-not-found = Stapel nicht gefunden: { $uri }
+invalid-stack-card = Keine Karte in diesem Stapel: { $uri }
+invalid-format = Ungültiges Textformat: { $format }
+invalid-kind = Ungültige Kartenart: { $kind }
+invalid-position = Ungültige Position: { $position }
+invalid-cloze-unclosed = Eine Lücke ist nicht mit {"}}"} geschlossen
+invalid-cloze-malformed = Lücken sehen so aus: {"{{c1::Antwort}}"} oder {"{{c1::Antwort::Hinweis}}"}
+invalid-cloze-empty = Lücke c{ $ordinal } ist leer
+invalid-cloze-none = Lückentext braucht mindestens eine Lücke, etwa {"{{c1::Antwort}}"}
+invalid-image-type = Ungültiger Bildtyp: { $type }
+invalid-image-file = Bilder müssen PNG, JPEG, GIF oder WebP sein
+invalid-image-size = Bilder dürfen höchstens { $max } kB groß sein
+invalid-audio-type = Ungültiger Audiotyp: { $type }
+invalid-audio-file = Audio muss MP3, Ogg, WAV, FLAC, WebM oder MP4 sein
+invalid-audio-size = Audio darf höchstens { $max } MB groß sein
+invalid-blob = Ungültige Datei
+invalid-speech-text = Es gibt keinen Text zum Vorlesen
+invalid-speech-lang = Für { $lang } gibt es keine Stimme
+invalid-speech-length = Der vorzulesende Text darf höchstens { $max } Zeichen lang sein
+invalid-daily-limits = Tageslimits müssen zwischen 0 und { $max } liegen
+invalid-review-item = Ungültige Abfrage: { $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = Fehler beim Abrufen des AtProto-Agenten
+error-stack-not-found = Stapel nicht gefunden
+error-forbidden = Du hast keine Berechtigung für diese Aktion
+error-db-query = Fehler bei der Datenbankabfrage
+error-unknown = Ein unbekannter Fehler ist aufgetreten.
+error-create-stack = Beim Erstellen des Stapels ist ein Fehler aufgetreten
+error-login-to-create-stack = Du musst angemeldet sein, um einen Stapel zu erstellen.
+error-login-to-edit-stacks = Du musst angemeldet sein, um Stapel zu bearbeiten
+error-retrieve-stack = Fehler beim Abrufen des Stapels
+error-retrieve-cards = Die Karten konnten nicht abgerufen werden, bitte versuche es erneut.
+error-update-stack = Fehler beim Aktualisieren des Stapels
+error-delete-stack = Fehler beim Löschen des Stapels.
+error-collection-not-found = Sammlung nicht gefunden
+error-retrieve-collection = Fehler beim Abrufen der Sammlung
+error-create-collection = Beim Erstellen der Sammlung ist ein Fehler aufgetreten
+error-update-collection = Fehler beim Aktualisieren der Sammlung
+error-delete-collection = Fehler beim Löschen der Sammlung.
+error-clone-incomplete = Die Kopie wurde angelegt, aber diese Karten konnten nicht mitkopiert werden: { $cards }
+error-login-to-edit-collections = Du musst angemeldet sein, um Sammlungen zu bearbeiten
+error-edit-card = Fehler beim Bearbeiten der Karte
+error-delete-card = Fehler beim Löschen der Karte.
+error-reorder-cards = Fehler beim Umsortieren der Karten.
+error-speech-unavailable = Audio-Erzeugung ist auf diesem Server nicht eingerichtet.
+error-speech = Fehler beim Erzeugen des Audios
+error-login-to-review = Du musst angemeldet sein, um Stapel zu wiederholen
+error-card-not-found = Karte nicht gefunden
+error-review = Fehler beim Speichern der Wiederholung
+error-match-not-started = Dieses Spiel ist schon beendet oder wurde nie gestartet. Lade die Seite neu für ein neues.
+error-match-unfinished = Es sind noch nicht alle Paare gefunden, deshalb zählt diese Zeit nicht.
+error-match = Fehler beim Speichern deiner Zeit
+error-login-to-see-stats = Du musst angemeldet sein, um deine Statistik zu sehen
+error-oauth = OAuth-Fehler, siehe Logs
+error-oauth-no-did = Der OAuth-Agent hat keine DID zurückgegeben. Versuche, dich erneut anzumelden.
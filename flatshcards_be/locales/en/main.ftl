@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = Home
+title-login = Log in
+title-error = Error
+title-forbidden = Forbidden
+title-form-validation = Form Validation
+title-create-stack = Create Stack
+title-edit-stack = Edit Stack
+title-review = Review
+title-review-session = Review Session
+title-quiz = Quiz
+title-match = Match
+title-stats = Your Stats
+title-search = Search Stacks
+title-collection = Collection
+title-stack = Stack
+title-create-collection = Create Collection
+title-edit-collection = Edit Collection
+
+## Layout
+
+app-tagline = Flashcards on the Atmosphere.
+locale-label = Interface language
+locale-apply = Change
+error-prefix = Error:
+go-home = Go Home
+
+## Home
+
+home-greeting = Hi, { $name }.
+home-friend = friend
+log-in = Log in
+log-out = Log out
+home-no-stacks = No stacks yet.
+home-create-one = Why not create one?
+home-new-stack = New stack
+home-front-lang = Front Language:
+home-back-lang = Back Language:
+home-lang-unspecified = (Not Specified)
+home-review = Review
+home-review-session = Review across stacks
+home-any-lang = (Any)
+home-quiz = Quiz
+home-match = Match
+home-stats = Your stats
+home-search = Search stacks
+home-tag = Tag:
+home-any-tag = (Any)
+home-all-stacks = All stacks
+home-no-tagged-stacks = None of your stacks are tagged { $tag }.
+home-collections = Your collections
+home-new-collection = New collection
+
+## Login
+
+login-handle-placeholder = Enter your handle (eg alice.bsky.social)
+login-no-account = Don't have an account on the Atmosphere?
+login-sign-up = Sign up for Bluesky to create one now!
+login-oauth-error = OAuth error
+login-invalid-handle = That isn't a valid handle.
+
+## Stacks
+
+stack-label = Stack Label
+stack-label-placeholder = Chapter 1
+stack-front-lang = Front Language (Optional)
+stack-back-lang = Back Language (Optional)
+stack-create = Create Stack
+stack-edit = Edit Stack
+stack-delete = Delete Stack
+stack-delete-confirm = Are you sure you want to delete { $label }?
+stack-directions = Review Directions
+stack-directions-forward = Front to back
+stack-directions-reverse = Back to front
+stack-directions-both = Both ways
+stack-description = Description (Optional)
+stack-description-placeholder = What the stack covers, and who it's for
+stack-source = Source (Optional)
+stack-source-placeholder = The textbook, course or word list the cards came from
+stack-credit = Source
+stack-license = License
+stack-license-none = (None)
+stack-license-unspecified = Not given
+stack-license-all-rights-reserved = All rights reserved
+stack-reuse-licensed = You may reuse and adapt these cards on the license's terms.
+stack-reuse-reserved = The author hasn't allowed these cards to be reused.
+stack-reuse-other = Check the license's terms before reusing these cards.
+stack-reuse-unspecified = The author hasn't said whether these cards may be reused. Ask them before sharing a copy.
+stack-difficulty = Difficulty
+stack-difficulty-none = (Not specified)
+stack-difficulty-beginner = Beginner
+stack-difficulty-intermediate = Intermediate
+stack-difficulty-advanced = Advanced
+stack-cover = Cover Image
+stack-cards = Cards
+stack-view = View stack page
+stack-edit-page = Edit
+tags = Tags
+tags-placeholder = verbs, travel, JLPT N5
+
+## Cards
+
+card-front-lang = Front Language
+card-front-text = Front Text
+card-back-lang = Back Language
+card-back-text = Back Text
+card-kind = Card Type
+card-kind-basic = Front and back
+card-kind-cloze = Cloze deletion
+card-format = Text Format
+card-format-plain = Plain text
+card-format-markdown = Markdown and ruby
+card-front-image = Front Image
+card-back-image = Back Image
+card-image-remove = Remove image
+card-audio = Audio
+card-audio-record = Record
+card-audio-stop = Stop
+card-audio-remove = Remove audio
+card-audio-speak = Generated audio
+card-audio-speak-none = Don't generate
+card-audio-speak-front = Generate from the front
+card-audio-speak-back = Generate from the back
+stack-speech = Generate audio for cards without any
+card-create = Create Card
+card-update = Update Card
+card-delete = Delete Card
+card-delete-confirm = Are you sure you want to delete this card?
+card-drag = Drag to reorder, or use the arrow keys
+card-order-drag = Drag cards by their handles to change the order they're learned in.
+card-order-uris = Card order, one card URI per line
+card-order-save = Save Order
+
+## Language pickers
+
+lang-search = Search languages
+lang-none = (None)
+lang-choose = Please choose a language
+lang-group-selected = Selected
+lang-group-yours = Your languages
+lang-group-common = Common languages
+lang-group-other = Other languages
+
+## Review
+
+review-show = Show answer
+review-again = Again
+review-hard = Hard
+review-good = Good
+review-easy = Easy
+review-due = { $count } due
+review-new = { $count } new
+review-done = Nothing left to review for now.
+review-mode = Answer by
+review-mode-flip = Flipping the card
+review-mode-typed = Typing the answer
+review-ignore-accents = Ignore accents
+review-new-order = New cards
+review-new-order-in-order = In stack order
+review-new-order-shuffled = Shuffled
+review-apply = Apply
+review-typed = Type the answer
+review-check = Check
+review-verdict-exact = Correct!
+review-verdict-accents = Correct, apart from the accents.
+review-verdict-close = Almost: check the spelling.
+review-verdict-wrong = Not quite.
+review-new-per-day = New cards a day
+review-reviews-per-day = Reviews a day
+review-save-limits = Save limits
+review-held-back = Your daily limits have left { $count } for another day.
+
+## Quiz
+
+quiz-submit = Check answers
+quiz-score = { $correct } of { $total } correct ({ $percent }%)
+quiz-chosen = You chose:
+quiz-unanswered = Not answered
+quiz-again = Another quiz
+quiz-empty = This stack has no cards to quiz on yet.
+
+## Match
+
+match-pairs = Pairs
+match-new = New board
+match-time = Matched in { $seconds } seconds
+match-best = Your best: { $seconds } seconds
+match-new-best = A new best time!
+match-log-in = Log in to keep your best times.
+match-again = Play again
+match-too-few = This stack needs at least two cards with different fronts and backs to play.
+
+## Stats
+
+stats-period = Reviews, retention and time are from the last { $days } days. Days run midnight to midnight UTC.
+stats-reviews-today = Reviews today
+stats-streak = Current streak, in days
+stats-longest-streak = Longest streak, in days
+stats-reviews = Reviews
+stats-retention = Retention
+stats-minutes = Minutes spent
+stats-due = Due today
+stats-per-day = Reviews a day, last { $days } days
+stats-forecast = Due a day, next { $days } days
+stats-maturity = Cards by maturity
+stats-learning = Learning
+stats-young = Young
+stats-mature = Mature
+stats-calendar = Streak calendar
+stats-by-stack = By stack
+stats-by-langs = By language pair
+stats-stack = Stack
+stats-langs = Languages
+stats-empty = No reviews yet. Review a stack and your stats will show up here.
+
+## Search
+
+search-text = Label:
+search-submit = Search
+search-none = No stacks found.
+search-clone = Copy to my stacks
+
+## Collections
+
+collection-label = Collection Label
+collection-label-placeholder = Genki I
+collection-description = Description (Optional)
+collection-stacks = Stacks, in study order
+collection-stacks-uris = Stack URIs, one a line
+collection-pick-stack = Choose one of your stacks
+collection-add-stack = Add
+collection-move-up = Move up
+collection-move-down = Move down
+collection-remove-stack = Remove
+collection-create = Create Collection
+collection-save = Save Collection
+collection-view = View collection
+collection-edit = Edit
+collection-delete = Delete Collection
+collection-delete-confirm = Are you sure you want to delete { $label }? Its stacks will be kept.
+collection-clone = Copy to my collections
+collection-empty = This collection has no stacks yet.
+
+## Validation
+
+invalid-record-uri = Invalid record URI { $uri }
+invalid-front-lang = Invalid front language { $lang }
+invalid-back-lang = Invalid back language { $lang }
+invalid-directions = Invalid review directions { $directions }
+invalid-description-length = Descriptions can be at most { $max } characters
+invalid-source-length = Sources can be at most { $max } characters
+invalid-license = Invalid license { $license }
+invalid-difficulty = Unknown difficulty { $difficulty }
+invalid-tag-length = Tags can be at most { $max } characters: { $tag }
+invalid-tag-count = At most { $max } tags are allowed
+invalid-collection-label = A collection needs a label
+invalid-label-length = Labels can be at most { $max } characters
+invalid-stack-uri = Not a stack: { $uri }
+invalid-stack-count = At most { $max } stacks are allowed
+invalid-stack-not-found = Stack not found: { $uri }
+invalid-stack-card = Not a card in this stack: { $uri }
+invalid-format = Invalid text format { $format }
+invalid-kind = Invalid card kind { $kind }
+invalid-position = Invalid position { $position }
+invalid-cloze-unclosed = A cloze deletion isn’t closed with {"}}"}
+invalid-cloze-malformed = Cloze deletions look like {"{{c1::answer}}"} or {"{{c1::answer::hint}}"}
+invalid-cloze-empty = Cloze deletion c{ $ordinal } is empty
+invalid-cloze-none = Cloze text needs at least one deletion, like {"{{c1::answer}}"}
+invalid-image-type = Invalid image type { $type }
+invalid-image-file = Images must be PNG, JPEG, GIF or WebP
+invalid-image-size = Images must be at most { $max } kB
+invalid-audio-type = Invalid audio type { $type }
+invalid-audio-file = Audio must be MP3, Ogg, WAV, FLAC, WebM or MP4
+invalid-audio-size = Audio must be at most { $max } MB
+invalid-blob = Invalid blob
+invalid-speech-text = There’s no text to speak
+invalid-speech-lang = There’s no voice for { $lang }
+invalid-speech-length = Text to speak can be at most { $max } characters
+invalid-daily-limits = Daily limits must be between 0 and { $max }
+invalid-review-item = Invalid review item { $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = Error retrieving AtProto agent
+error-stack-not-found = Stack not found
+error-forbidden = You do not have permission to perform this action
+error-db-query = Error querying database
+error-unknown = An unknown error has occurred.
+error-create-stack = There was an error creating the stack
+error-login-to-create-stack = You must be logged in to create a stack.
+error-login-to-edit-stacks = You must be logged in to edit stacks
+error-retrieve-stack = Error retrieving stack
+error-retrieve-cards = Could not retrieve cards, please try again.
+error-update-stack = Error updating stack
+error-delete-stack = Error deleting stack.
+error-collection-not-found = Collection not found
+error-retrieve-collection = Error retrieving collection
+error-create-collection = There was an error creating the collection
+error-update-collection = Error updating collection
+error-delete-collection = Error deleting collection.
+error-clone-incomplete = The copy was made, but these cards couldn’t be copied into it: { $cards }
+error-login-to-edit-collections = You must be logged in to edit collections
+error-edit-card = Error editing card
+error-delete-card = Error deleting card.
+error-reorder-cards = Error reordering cards.
+error-speech-unavailable = Audio generation isn’t set up on this server.
+error-speech = Error generating audio
+error-login-to-review = You must be logged in to review stacks
+error-card-not-found = Card not found
+error-review = Error saving review
+error-match-not-started = This game has already finished, or never started. Reload the page for a new one.
+error-match-unfinished = Not every pair has been matched yet, so this time can't count.
+error-match = Error saving your time
+error-login-to-see-stats = You must be logged in to see your stats
+error-oauth = OAuth error, check the logs
+error-oauth-no-did = The OAuth agent did not return a DID. May try re-logging in.
@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = Inicio
+title-login = Iniciar sesión
+title-error = Error
+title-forbidden = Prohibido
+title-form-validation = Validación del formulario
+title-create-stack = Crear mazo
+title-edit-stack = Editar mazo
+title-review = Repasar
+title-review-session = Sesión de repaso
+title-quiz = Cuestionario
+title-match = Emparejar
+title-stats = Tus estadísticas
+title-search = Buscar mazos
+title-collection = Colección
+title-stack = Mazo
+title-create-collection = Crear colección
+title-edit-collection = Editar colección
+
+## Layout
+
+app-tagline = Tarjetas de memoria en la Atmósfera.
+locale-label = Idioma de la interfaz
+locale-apply = Cambiar
+error-prefix = Error:
+go-home = Volver al inicio
+
+## Home
+
+home-greeting = Hola, { $name }.
+home-friend = amigo
+log-in = Iniciar sesión
+log-out = Cerrar sesión
+home-no-stacks = Todavía no tienes mazos.
+home-create-one = ¿Por qué no creas uno?
+home-new-stack = Nuevo mazo
+home-front-lang = Idioma del anverso:
+home-back-lang = Idioma del reverso:
+home-lang-unspecified = (Sin especificar)
+home-review = Repasar
+home-review-session = Repasar varios mazos
+home-any-lang = (Cualquiera)
+home-quiz = Cuestionario
+home-match = Emparejar
+home-stats = Tus estadísticas
+home-search = Buscar mazos
+home-tag = Etiqueta:
+home-any-tag = (Cualquiera)
+home-all-stacks = Todos los mazos
+home-no-tagged-stacks = Ninguno de tus mazos tiene la etiqueta { $tag }.
+home-collections = Tus colecciones
+home-new-collection = Nueva colección
+
+## Login
+
+login-handle-placeholder = Escribe tu identificador (p. ej. alice.bsky.social)
+login-no-account = ¿No tienes una cuenta en la Atmósfera?
+login-sign-up = ¡Regístrate en Bluesky para crear una ahora!
+login-oauth-error = Error de OAuth
+login-invalid-handle = Ese identificador no es válido.
+
+## Stacks
+
+stack-label = Nombre del mazo
+stack-label-placeholder = Capítulo 1
+stack-front-lang = Idioma del anverso (opcional)
+stack-back-lang = Idioma del reverso (opcional)
+stack-create = Crear mazo
+stack-edit = Editar mazo
+stack-delete = Eliminar mazo
+stack-delete-confirm = ¿Seguro que quieres eliminar { $label }?
+stack-directions = Sentido de repaso
+stack-directions-forward = Del anverso al reverso
+stack-directions-reverse = Del reverso al anverso
+stack-directions-both = En ambos sentidos
+stack-description = Descripción (opcional)
+stack-description-placeholder = Qué abarca el mazo y para quién es
+stack-source = Fuente (opcional)
+stack-source-placeholder = El libro de texto, curso o lista de palabras de donde salen las tarjetas
+stack-credit = Fuente
+stack-license = Licencia
+stack-license-none = (Ninguna)
+stack-license-unspecified = No indicada
+stack-license-all-rights-reserved = Todos los derechos reservados
+stack-reuse-licensed = Puedes reutilizar y adaptar estas tarjetas según los términos de la licencia.
+stack-reuse-reserved = El autor no ha permitido reutilizar estas tarjetas.
+stack-reuse-other = Consulta los términos de la licencia antes de reutilizar estas tarjetas.
+stack-reuse-unspecified = El autor no ha indicado si se pueden reutilizar estas tarjetas. Pregúntale antes de compartir una copia.
+stack-difficulty = Dificultad
+stack-difficulty-none = (Sin especificar)
+stack-difficulty-beginner = Principiante
+stack-difficulty-intermediate = Intermedio
+stack-difficulty-advanced = Avanzado
+stack-cover = Imagen de portada
+stack-cards = Tarjetas
+stack-view = Ver la página del mazo
+stack-edit-page = Editar
+tags = Etiquetas
+tags-placeholder = verbos, viajes, DELE A1
+
+## Cards
+
+card-front-lang = Idioma del anverso
+card-front-text = Texto del anverso
+card-back-lang = Idioma del reverso
+card-back-text = Texto del reverso
+card-kind = Tipo de tarjeta
+card-kind-basic = Anverso y reverso
+card-kind-cloze = Texto con huecos
+card-format = Formato del texto
+card-format-plain = Texto sin formato
+card-format-markdown = Markdown y ruby
+card-front-image = Imagen del anverso
+card-back-image = Imagen del reverso
+card-image-remove = Quitar imagen
+card-audio = Audio
+card-audio-record = Grabar
+card-audio-stop = Detener
+card-audio-remove = Quitar audio
+card-audio-speak = Audio generado
+card-audio-speak-none = No generar
+card-audio-speak-front = Generar desde el anverso
+card-audio-speak-back = Generar desde el reverso
+stack-speech = Generar audio para las tarjetas que no tienen
+card-create = Crear tarjeta
+card-update = Actualizar tarjeta
+card-delete = Eliminar tarjeta
+card-delete-confirm = ¿Seguro que quieres eliminar esta tarjeta?
+card-drag = Arrastra para reordenar, o usa las flechas del teclado
+card-order-drag = Arrastra las tarjetas por su asa para cambiar el orden en que se aprenden.
+card-order-uris = Orden de las tarjetas, una URI de tarjeta por línea
+card-order-save = Guardar orden
+
+## Language pickers
+
+lang-search = Buscar idiomas
+lang-none = (Ninguno)
+lang-choose = Elige un idioma
+lang-group-selected = Seleccionado
+lang-group-yours = Tus idiomas
+lang-group-common = Idiomas comunes
+lang-group-other = Otros idiomas
+
+## Review
+
+review-show = Mostrar respuesta
+review-again = Otra vez
+review-hard = Difícil
+review-good = Bien
+review-easy = Fácil
+review-due = { $count } pendientes
+review-new = { $count } nuevas
+review-done = No queda nada por repasar por ahora.
+review-mode = Responder
+review-mode-flip = Girando la tarjeta
+review-mode-typed = Escribiendo la respuesta
+review-ignore-accents = Ignorar tildes
+review-new-order = Tarjetas nuevas
+review-new-order-in-order = En el orden del mazo
+review-new-order-shuffled = Barajadas
+review-apply = Aplicar
+review-typed = Escribe la respuesta
+review-check = Comprobar
+review-verdict-exact = ¡Correcto!
+review-verdict-accents = Correcto, salvo las tildes.
+review-verdict-close = Casi: revisa la ortografía.
+review-verdict-wrong = No exactamente.
+review-new-per-day = Tarjetas nuevas al día
+review-reviews-per-day = Repasos al día
+review-save-limits = Guardar límites
+review-held-back = { $count } más esperan a otro día por tus límites diarios.
+
+## Quiz
+
+quiz-submit = Comprobar respuestas
+quiz-score = { $correct } de { $total } correctas ({ $percent } %)
+quiz-chosen = Elegiste:
+quiz-unanswered = Sin responder
+quiz-again = Otro cuestionario
+quiz-empty = Este mazo aún no tiene tarjetas para un cuestionario.
+
+## Match
+
+match-pairs = Parejas
+match-new = Nuevo tablero
+match-time = Emparejado en { $seconds } segundos
+match-best = Tu mejor tiempo: { $seconds } segundos
+match-new-best = ¡Nuevo récord!
+match-log-in = Inicia sesión para guardar tus mejores tiempos.
+match-again = Jugar otra vez
+match-too-few = Este mazo necesita al menos dos tarjetas con anversos y reversos distintos para jugar.
+
+## Stats
+
+stats-period = Los repasos, la retención y el tiempo son de los últimos { $days } días. Los días van de medianoche a medianoche UTC.
+stats-reviews-today = Repasos de hoy
+stats-streak = Racha actual, en días
+stats-longest-streak = Racha más larga, en días
+stats-reviews = Repasos
+stats-retention = Retención
+stats-minutes = Minutos
+stats-due = Para hoy
+stats-per-day = Repasos por día, últimos { $days } días
+stats-forecast = Pendientes por día, próximos { $days } días
+stats-maturity = Tarjetas por madurez
+stats-learning = Aprendiendo
+stats-young = Jóvenes
+stats-mature = Maduras
+stats-calendar = Calendario de rachas
+stats-by-stack = Por mazo
+stats-by-langs = Por par de idiomas
+stats-stack = Mazo
+stats-langs = Idiomas
+stats-empty = Aún no hay repasos. Repasa un mazo y tus estadísticas aparecerán aquí.
+
+## Search
+
+search-text = Nombre:
+search-submit = Buscar
+search-none = No se encontraron mazos.
+search-clone = Copiar a mis mazos
+
+## Collections
+
+collection-label = Nombre de la colección
+collection-label-placeholder = Genki I
+collection-description = Descripción (opcional)
+collection-stacks = Mazos, en orden de estudio
+collection-stacks-uris = URI de los mazos, una por línea
+collection-pick-stack = Elige uno de tus mazos
+collection-add-stack = Añadir
+collection-move-up = Subir
+collection-move-down = Bajar
+collection-remove-stack = Quitar
+collection-create = Crear colección
+collection-save = Guardar colección
+collection-view = Ver colección
+collection-edit = Editar
+collection-delete = Eliminar colección
+collection-delete-confirm = ¿Seguro que quieres eliminar { $label }? Sus mazos se conservarán.
+collection-clone = Copiar a mis colecciones
+collection-empty = Esta colección aún no tiene mazos.
+
+## Validation
+
+invalid-record-uri = URI de registro no válida: { $uri }
+invalid-front-lang = Idioma del anverso no válido: { $lang }
+invalid-back-lang = Idioma del reverso no válido: { $lang }
+invalid-directions = Sentido de repaso no válido: { $directions }
+invalid-description-length = Las descripciones pueden tener como máximo { $max } caracteres
+invalid-source-length = Las fuentes pueden tener como máximo { $max } caracteres
+invalid-license = Licencia no válida: { $license }
+invalid-difficulty = Dificultad desconocida: { $difficulty }
+invalid-tag-length = Las etiquetas pueden tener como máximo { $max } caracteres: { $tag }
+invalid-tag-count = Se permiten como máximo { $max } etiquetas
+invalid-collection-label = Una colección necesita un nombre
+invalid-label-length = Los nombres pueden tener como máximo { $max } caracteres
+invalid-stack-uri = No es un mazo: { $uri }
+invalid-stack-count = Se permiten como máximo { $max } mazos
+invalid-stack-not-found = No se encontró el mazo: { $uri }
+invalid-stack-card = No es una tarjeta de este mazo: { $uri }
+invalid-format = Formato de texto no válido: { $format }
+invalid-kind = Tipo de tarjeta no válido: { $kind }
+invalid-position = Posición no válida: { $position }
+invalid-cloze-unclosed = Un hueco no está cerrado con {"}}"}
+invalid-cloze-malformed = Los huecos se escriben así: {"{{c1::respuesta}}"} o {"{{c1::respuesta::pista}}"}
+invalid-cloze-empty = El hueco c{ $ordinal } está vacío
+invalid-cloze-none = El texto con huecos necesita al menos uno, como {"{{c1::respuesta}}"}
+invalid-image-type = Tipo de imagen no válido: { $type }
+invalid-image-file = Las imágenes deben ser PNG, JPEG, GIF o WebP
+invalid-image-size = Las imágenes pueden ocupar como máximo { $max } kB
+invalid-audio-type = Tipo de audio no válido: { $type }
+invalid-audio-file = El audio debe ser MP3, Ogg, WAV, FLAC, WebM o MP4
+invalid-audio-size = El audio puede ocupar como máximo { $max } MB
+invalid-blob = Archivo no válido
+invalid-speech-text = No hay texto que leer en voz alta
+invalid-speech-lang = No hay voz para { $lang }
+invalid-speech-length = El texto que se lee en voz alta puede tener como máximo { $max } caracteres
+invalid-daily-limits = Los límites diarios deben estar entre 0 y { $max }
+invalid-review-item = Elemento de repaso no válido: { $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = Error al recuperar el agente de AtProto
+error-stack-not-found = No se encontró el mazo
+error-forbidden = No tienes permiso para realizar esta acción
+error-db-query = Error al consultar la base de datos
+error-unknown = Se ha producido un error desconocido.
+error-create-stack = Se ha producido un error al crear el mazo
+error-login-to-create-stack = Tienes que iniciar sesión para crear un mazo.
+error-login-to-edit-stacks = Tienes que iniciar sesión para editar mazos
+error-retrieve-stack = Error al recuperar el mazo
+error-retrieve-cards = No se pudieron recuperar las tarjetas; inténtalo de nuevo.
+error-update-stack = Error al actualizar el mazo
+error-delete-stack = Error al eliminar el mazo.
+error-collection-not-found = No se encontró la colección
+error-retrieve-collection = Error al obtener la colección
+error-create-collection = Se ha producido un error al crear la colección
+error-update-collection = Error al actualizar la colección
+error-delete-collection = Error al eliminar la colección.
+error-clone-incomplete = Se hizo la copia, pero estas tarjetas no se pudieron copiar: { $cards }
+error-login-to-edit-collections = Debes iniciar sesión para editar colecciones
+error-edit-card = Error al editar la tarjeta
+error-delete-card = Error al eliminar la tarjeta.
+error-reorder-cards = Error al reordenar las tarjetas.
+error-speech-unavailable = La generación de audio no está configurada en este servidor.
+error-speech = Error al generar el audio
+error-login-to-review = Debes iniciar sesión para repasar mazos
+error-card-not-found = Tarjeta no encontrada
+error-review = Error al guardar el repaso
+error-match-not-started = Esta partida ya terminó o nunca empezó. Recarga la página para una nueva.
+error-match-unfinished = Aún no se han emparejado todos los pares, así que este tiempo no cuenta.
+error-match = Error al guardar tu tiempo
+error-login-to-see-stats = Debes iniciar sesión para ver tus estadísticas
+error-oauth = Error de OAuth; revisa los registros
+error-oauth-no-did = El agente de OAuth no devolvió un DID. Prueba a iniciar sesión de nuevo.
@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = Accueil
+title-login = Connexion
+title-error = Erreur
+title-forbidden = Accès interdit
+title-form-validation = Validation du formulaire
+title-create-stack = Créer un paquet
+title-edit-stack = Modifier le paquet
+title-review = Réviser
+title-review-session = Session de révision
+title-quiz = Quiz
+title-match = Associer
+title-stats = Vos statistiques
+title-search = Rechercher des paquets
+title-collection = Collection
+title-stack = Paquet
+title-create-collection = Créer une collection
+title-edit-collection = Modifier la collection
+
+## Layout
+
+app-tagline = Des cartes mémoire dans l’Atmosphère.
+locale-label = Langue de l’interface
+locale-apply = Changer
+error-prefix = Erreur :
+go-home = Retour à l’accueil
+
+## Home
+
+home-greeting = Bonjour, { $name }.
+home-friend = l’ami
+log-in = Se connecter
+log-out = Se déconnecter
+home-no-stacks = Pas encore de paquets.
+home-create-one = Pourquoi ne pas en créer un ?
+home-new-stack = Nouveau paquet
+home-front-lang = Langue du recto :
+home-back-lang = Langue du verso :
+home-lang-unspecified = (Non précisée)
+home-review = Réviser
+home-review-session = Réviser plusieurs paquets
+home-any-lang = (Toutes)
+home-quiz = Quiz
+home-match = Associer
+home-stats = Vos statistiques
+home-search = Rechercher des paquets
+home-tag = Étiquette :
+home-any-tag = (Toutes)
+home-all-stacks = Tous les paquets
+home-no-tagged-stacks = Aucun de vos paquets n’a l’étiquette { $tag }.
+home-collections = Vos collections
+home-new-collection = Nouvelle collection
+
+## Login
+
+login-handle-placeholder = Saisissez votre identifiant (ex. alice.bsky.social)
+login-no-account = Pas encore de compte sur l’Atmosphère ?
+login-sign-up = Inscrivez-vous sur Bluesky pour en créer un dès maintenant !
+login-oauth-error = Erreur OAuth
+login-invalid-handle = Cet identifiant n’est pas valide.
+
+## Stacks
+
+stack-label = Nom du paquet
+stack-label-placeholder = Chapitre 1
+stack-front-lang = Langue du recto (facultatif)
+stack-back-lang = Langue du verso (facultatif)
+stack-create = Créer le paquet
+stack-edit = Modifier le paquet
+stack-delete = Supprimer le paquet
+stack-delete-confirm = Voulez-vous vraiment supprimer { $label } ?
+stack-directions = Sens de révision
+stack-directions-forward = Du recto au verso
+stack-directions-reverse = Du verso au recto
+stack-directions-both = Dans les deux sens
+stack-description = Description (facultative)
+stack-description-placeholder = Ce que couvre le paquet, et à qui il s’adresse
+stack-source = Source (facultative)
+stack-source-placeholder = Le manuel, le cours ou la liste de mots d’où viennent les cartes
+stack-credit = Source
+stack-license = Licence
+stack-license-none = (Aucune)
+stack-license-unspecified = Non précisée
+stack-license-all-rights-reserved = Tous droits réservés
+stack-reuse-licensed = Vous pouvez réutiliser et adapter ces cartes selon les conditions de la licence.
+stack-reuse-reserved = L’auteur n’a pas autorisé la réutilisation de ces cartes.
+stack-reuse-other = Vérifiez les conditions de la licence avant de réutiliser ces cartes.
+stack-reuse-unspecified = L’auteur n’a pas précisé si ces cartes peuvent être réutilisées. Demandez-lui avant d’en partager une copie.
+stack-difficulty = Niveau
+stack-difficulty-none = (Non précisé)
+stack-difficulty-beginner = Débutant
+stack-difficulty-intermediate = Intermédiaire
+stack-difficulty-advanced = Avancé
+stack-cover = Image de couverture
+stack-cards = Cartes
+stack-view = Voir la page du paquet
+stack-edit-page = Modifier
+tags = Étiquettes
+tags-placeholder = verbes, voyage, DELF A1
+
+## Cards
+
+card-front-lang = Langue du recto
+card-front-text = Texte du recto
+card-back-lang = Langue du verso
+card-back-text = Texte du verso
+card-kind = Type de carte
+card-kind-basic = Recto et verso
+card-kind-cloze = Texte à trous
+card-format = Format du texte
+card-format-plain = Texte brut
+card-format-markdown = Markdown et ruby
+card-front-image = Image du recto
+card-back-image = Image du verso
+card-image-remove = Retirer l’image
+card-audio = Audio
+card-audio-record = Enregistrer
+card-audio-stop = Arrêter
+card-audio-remove = Retirer l’audio
+card-audio-speak = Audio généré
+card-audio-speak-none = Ne pas générer
+card-audio-speak-front = Générer depuis le recto
+card-audio-speak-back = Générer depuis le verso
+stack-speech = Générer l’audio des cartes qui n’en ont pas
+card-create = Créer la carte
+card-update = Mettre à jour la carte
+card-delete = Supprimer la carte
+card-delete-confirm = Voulez-vous vraiment supprimer cette carte ?
+card-drag = Faites glisser pour réordonner, ou utilisez les flèches du clavier
+card-order-drag = Faites glisser les cartes par leur poignée pour changer l'ordre dans lequel elles sont apprises.
+card-order-uris = Ordre des cartes, une URI de carte par ligne
+card-order-save = Enregistrer l'ordre
+
+## Language pickers
+
+lang-search = Rechercher une langue
+lang-none = (Aucune)
+lang-choose = Choisissez une langue
+lang-group-selected = Sélectionnée
+lang-group-yours = Vos langues
+lang-group-common = Langues courantes
+lang-group-other = Autres langues
+
+## Review
+
+review-show = Voir la réponse
+review-again = À revoir
+review-hard = Difficile
+review-good = Bien
+review-easy = Facile
+review-due = { $count } à réviser
+review-new = { $count } nouvelles
+review-done = Plus rien à réviser pour l’instant.
+review-mode = Répondre en
+review-mode-flip = Retournant la carte
+review-mode-typed = Tapant la réponse
+review-ignore-accents = Ignorer les accents
+review-new-order = Nouvelles cartes
+review-new-order-in-order = Dans l'ordre du paquet
+review-new-order-shuffled = Mélangées
+review-apply = Appliquer
+review-typed = Tapez la réponse
+review-check = Vérifier
+review-verdict-exact = Correct !
+review-verdict-accents = Correct, aux accents près.
+review-verdict-close = Presque : vérifiez l’orthographe.
+review-verdict-wrong = Pas tout à fait.
+review-new-per-day = Nouvelles cartes par jour
+review-reviews-per-day = Révisions par jour
+review-save-limits = Enregistrer les limites
+review-held-back = { $count } de plus attendent un autre jour, selon vos limites quotidiennes.
+
+## Quiz
+
+quiz-submit = Vérifier les réponses
+quiz-score = { $correct } sur { $total } correctes ({ $percent } %)
+quiz-chosen = Votre choix :
+quiz-unanswered = Sans réponse
+quiz-again = Un autre quiz
+quiz-empty = Ce paquet n’a pas encore de cartes pour un quiz.
+
+## Match
+
+match-pairs = Paires
+match-new = Nouveau plateau
+match-time = Associé en { $seconds } secondes
+match-best = Votre meilleur temps : { $seconds } secondes
+match-new-best = Nouveau record !
+match-log-in = Connectez-vous pour garder vos meilleurs temps.
+match-again = Rejouer
+match-too-few = Ce paquet a besoin d’au moins deux cartes aux recto et verso différents pour jouer.
+
+## Stats
+
+stats-period = Les révisions, la rétention et le temps portent sur les { $days } derniers jours. Les jours vont de minuit à minuit UTC.
+stats-reviews-today = Révisions aujourd’hui
+stats-streak = Série en cours, en jours
+stats-longest-streak = Plus longue série, en jours
+stats-reviews = Révisions
+stats-retention = Rétention
+stats-minutes = Minutes
+stats-due = À réviser aujourd’hui
+stats-per-day = Révisions par jour, { $days } derniers jours
+stats-forecast = À réviser par jour, { $days } prochains jours
+stats-maturity = Cartes par maturité
+stats-learning = En apprentissage
+stats-young = Jeunes
+stats-mature = Matures
+stats-calendar = Calendrier des séries
+stats-by-stack = Par paquet
+stats-by-langs = Par paire de langues
+stats-stack = Paquet
+stats-langs = Langues
+stats-empty = Pas encore de révisions. Révisez un paquet et vos statistiques apparaîtront ici.
+
+## Search
+
+search-text = Nom :
+search-submit = Rechercher
+search-none = Aucun paquet trouvé.
+search-clone = Copier dans mes paquets
+
+## Collections
+
+collection-label = Nom de la collection
+collection-label-placeholder = Genki I
+collection-description = Description (facultative)
+collection-stacks = Paquets, dans l’ordre d’étude
+collection-stacks-uris = URI des paquets, une par ligne
+collection-pick-stack = Choisissez l’un de vos paquets
+collection-add-stack = Ajouter
+collection-move-up = Monter
+collection-move-down = Descendre
+collection-remove-stack = Retirer
+collection-create = Créer la collection
+collection-save = Enregistrer la collection
+collection-view = Voir la collection
+collection-edit = Modifier
+collection-delete = Supprimer la collection
+collection-delete-confirm = Voulez-vous vraiment supprimer { $label } ? Ses paquets seront conservés.
+collection-clone = Copier dans mes collections
+collection-empty = Cette collection n’a pas encore de paquets.
+
+## Validation
+
+invalid-record-uri = URI d’enregistrement non valide : { $uri }
+invalid-front-lang = Langue du recto non valide : { $lang }
+invalid-back-lang = Langue du verso non valide : { $lang }
+invalid-directions = Sens de révision non valide : { $directions }
+invalid-description-length = Les descriptions peuvent faire au plus { $max } caractères
+invalid-source-length = Les sources peuvent faire au plus { $max } caractères
+invalid-license = Licence non valide : { $license }
+invalid-difficulty = Difficulté inconnue : { $difficulty }
+invalid-tag-length = Les étiquettes peuvent faire au plus { $max } caractères : { $tag }
+invalid-tag-count = Vous pouvez mettre au plus { $max } étiquettes
+invalid-collection-label = Une collection doit avoir un nom
+invalid-label-length = Les noms peuvent faire au plus { $max } caractères
+invalid-stack-uri = Ce n’est pas un paquet : { $uri }
+invalid-stack-count = Vous pouvez mettre au plus { $max } paquets
+invalid-stack-not-found = Paquet introuvable : { $uri }
+invalid-stack-card = Cette carte n’est pas dans ce paquet : { $uri }
+invalid-format = Format de texte non valide : { $format }
+invalid-kind = Type de carte non valide : { $kind }
+invalid-position = Position non valide : { $position }
+invalid-cloze-unclosed = Un trou n’est pas fermé par {"}}"}
+invalid-cloze-malformed = Les trous s’écrivent {"{{c1::réponse}}"} ou {"{{c1::réponse::indice}}"}
+invalid-cloze-empty = Le trou c{ $ordinal } est vide
+invalid-cloze-none = Un texte à trous doit en avoir au moins un, comme {"{{c1::réponse}}"}
+invalid-image-type = Type d’image non valide : { $type }
+invalid-image-file = Les images doivent être au format PNG, JPEG, GIF ou WebP
+invalid-image-size = Les images peuvent faire au plus { $max } ko
+invalid-audio-type = Type d’audio non valide : { $type }
+invalid-audio-file = L’audio doit être au format MP3, Ogg, WAV, FLAC, WebM ou MP4
+invalid-audio-size = L’audio peut faire au plus { $max } Mo
+invalid-blob = Fichier non valide
+invalid-speech-text = Il n’y a pas de texte à lire
+invalid-speech-lang = Il n’y a pas de voix pour { $lang }
+invalid-speech-length = Le texte à lire peut contenir au plus { $max } caractères
+invalid-daily-limits = Les limites quotidiennes doivent être comprises entre 0 et { $max }
+invalid-review-item = Élément de révision non valide : { $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = Erreur lors de la récupération de l’agent AtProto
+error-stack-not-found = Paquet introuvable
+error-forbidden = Vous n’avez pas l’autorisation d’effectuer cette action
+error-db-query = Erreur lors de l’interrogation de la base de données
+error-unknown = Une erreur inconnue s’est produite.
+error-create-stack = Une erreur s’est produite lors de la création du paquet
+error-login-to-create-stack = Vous devez être connecté pour créer un paquet.
+error-login-to-edit-stacks = Vous devez être connecté pour modifier des paquets
+error-retrieve-stack = Erreur lors de la récupération du paquet
+error-retrieve-cards = Impossible de récupérer les cartes, veuillez réessayer.
+error-update-stack = Erreur lors de la mise à jour du paquet
+error-delete-stack = Erreur lors de la suppression du paquet.
+error-collection-not-found = Collection introuvable
+error-retrieve-collection = Erreur lors de la récupération de la collection
+error-create-collection = Une erreur s’est produite lors de la création de la collection
+error-update-collection = Erreur lors de la mise à jour de la collection
+error-delete-collection = Erreur lors de la suppression de la collection.
+error-clone-incomplete = La copie a été faite, mais ces cartes n’ont pas pu y être copiées : { $cards }
+error-login-to-edit-collections = Vous devez être connecté pour modifier des collections
+error-edit-card = Erreur lors de la modification de la carte
+error-delete-card = Erreur lors de la suppression de la carte.
+error-reorder-cards = Erreur lors du réordonnancement des cartes.
+error-speech-unavailable = La génération d’audio n’est pas configurée sur ce serveur.
+error-speech = Erreur lors de la génération de l’audio
+error-login-to-review = Vous devez être connecté pour réviser des paquets
+error-card-not-found = Carte introuvable
+error-review = Erreur lors de l’enregistrement de la révision
+error-match-not-started = Cette partie est déjà terminée, ou n’a jamais commencé. Rechargez la page pour en lancer une autre.
+error-match-unfinished = Toutes les paires n’ont pas encore été associées, ce temps ne compte donc pas.
+error-match = Erreur lors de l’enregistrement de votre temps
+error-login-to-see-stats = Vous devez être connecté pour voir vos statistiques
+error-oauth = Erreur OAuth, consultez les journaux
+error-oauth-no-did = L’agent OAuth n’a pas renvoyé de DID. Essayez de vous reconnecter.
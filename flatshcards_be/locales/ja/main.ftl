@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = ホーム
+title-login = ログイン
+title-error = エラー
+title-forbidden = アクセス禁止
+title-form-validation = 入力エラー
+title-create-stack = デッキを作成
+title-edit-stack = デッキを編集
+title-review = 復習
+title-review-session = 復習セッション
+title-quiz = クイズ
+title-match = マッチ
+title-stats = 学習統計
+title-search = デッキを検索
+title-collection = コレクション
+title-stack = デッキ
+title-create-collection = コレクションを作成
+title-edit-collection = コレクションを編集
+
+## Layout
+
+app-tagline = アトモスフィアで使える単語カード。
+locale-label = 表示言語
+locale-apply = 変更
+error-prefix = エラー：
+go-home = ホームに戻る
+
+## Home
+
+home-greeting = こんにちは、{ $name }さん。
+home-friend = ゲスト
+log-in = ログイン
+log-out = ログアウト
+home-no-stacks = デッキはまだありません。
+home-create-one = 作ってみませんか？
+home-new-stack = 新しいデッキ
+home-front-lang = 表の言語：
+home-back-lang = 裏の言語：
+home-lang-unspecified = （未指定）
+home-review = 復習
+home-review-session = デッキをまとめて復習
+home-any-lang = （すべて）
+home-quiz = クイズ
+home-match = マッチ
+home-stats = 学習統計
+home-search = デッキを検索
+home-tag = タグ：
+home-any-tag = （すべて）
+home-all-stacks = すべてのデッキ
+home-no-tagged-stacks = タグ「{ $tag }」の付いたデッキはありません。
+home-collections = あなたのコレクション
+home-new-collection = 新しいコレクション
+
+## Login
+
+login-handle-placeholder = ハンドルを入力してください（例：alice.bsky.social）
+login-no-account = アトモスフィアのアカウントをお持ちでないですか？
+login-sign-up = Bluesky に登録して今すぐアカウントを作成しましょう！
+login-oauth-error = OAuth エラー
+login-invalid-handle = 有効なハンドルではありません。
+
+## Stacks
+
+stack-label = デッキ名
+stack-label-placeholder = 第1章
+stack-front-lang = 表の言語（任意）
+stack-back-lang = 裏の言語（任意）
+stack-create = デッキを作成
+stack-edit = デッキを更新
+stack-delete = デッキを削除
+stack-delete-confirm = { $label } を削除してもよろしいですか？
+stack-directions = 出題の向き
+stack-directions-forward = 表から裏
+stack-directions-reverse = 裏から表
+stack-directions-both = 両方向
+stack-description = 説明（任意）
+stack-description-placeholder = デッキの内容と対象者
+stack-source = 出典（任意）
+stack-source-placeholder = カードの元になった教科書、講座、単語リストなど
+stack-credit = 出典
+stack-license = ライセンス
+stack-license-none = （なし）
+stack-license-unspecified = 指定なし
+stack-license-all-rights-reserved = 全著作権所有
+stack-reuse-licensed = ライセンスの条件に従って、これらのカードを再利用・改変できます。
+stack-reuse-reserved = 作成者はこれらのカードの再利用を許可していません。
+stack-reuse-other = これらのカードを再利用する前に、ライセンスの条件を確認してください。
+stack-reuse-unspecified = 作成者はこれらのカードの再利用について何も指定していません。コピーを共有する前に確認してください。
+stack-difficulty = 難易度
+stack-difficulty-none = （未指定）
+stack-difficulty-beginner = 初級
+stack-difficulty-intermediate = 中級
+stack-difficulty-advanced = 上級
+stack-cover = カバー画像
+stack-cards = カード
+stack-view = デッキのページを見る
+stack-edit-page = 編集
+tags = タグ
+tags-placeholder = 動詞, 旅行, JLPT N5
+
+## Cards
+
+card-front-lang = 表の言語
+card-front-text = 表のテキスト
+card-back-lang = 裏の言語
+card-back-text = 裏のテキスト
+card-kind = カードの種類
+card-kind-basic = 表と裏
+card-kind-cloze = 穴埋め
+card-format = テキスト形式
+card-format-plain = プレーンテキスト
+card-format-markdown = Markdownとルビ
+card-front-image = 表の画像
+card-back-image = 裏の画像
+card-image-remove = 画像を削除
+card-audio = 音声
+card-audio-record = 録音
+card-audio-stop = 停止
+card-audio-remove = 音声を削除
+card-audio-speak = 音声の生成
+card-audio-speak-none = 生成しない
+card-audio-speak-front = 表から生成
+card-audio-speak-back = 裏から生成
+stack-speech = 音声のないカードに音声を生成
+card-create = カードを作成
+card-update = カードを更新
+card-delete = カードを削除
+card-delete-confirm = このカードを削除してもよろしいですか？
+card-drag = ドラッグするか矢印キーで並べ替え
+card-order-drag = ハンドルをドラッグして、カードを学習する順番を変更できます。
+card-order-uris = カードの順番（1行に1つのカードURI）
+card-order-save = 順番を保存
+
+## Language pickers
+
+lang-search = 言語を検索
+lang-none = （なし）
+lang-choose = 言語を選んでください
+lang-group-selected = 選択中
+lang-group-yours = よく使う言語
+lang-group-common = 主な言語
+lang-group-other = その他の言語
+
+## Review
+
+review-show = 答えを見る
+review-again = もう一度
+review-hard = 難しい
+review-good = 普通
+review-easy = 簡単
+review-due = 復習 { $count } 件
+review-new = 新規 { $count } 件
+review-done = 今は復習するものがありません。
+review-mode = 答え方
+review-mode-flip = カードをめくる
+review-mode-typed = 答えを入力する
+review-ignore-accents = アクセント記号を無視
+review-new-order = 新しいカード
+review-new-order-in-order = デッキの順番
+review-new-order-shuffled = シャッフル
+review-apply = 適用
+review-typed = 答えを入力
+review-check = 確認
+review-verdict-exact = 正解！
+review-verdict-accents = アクセント記号以外は正解です。
+review-verdict-close = 惜しい：つづりを確認しましょう。
+review-verdict-wrong = 不正解です。
+review-new-per-day = 1日の新規カード数
+review-reviews-per-day = 1日の復習数
+review-save-limits = 上限を保存
+review-held-back = 1日の上限により、あと { $count } 件は別の日に回されます。
+
+## Quiz
+
+quiz-submit = 答え合わせ
+quiz-score = { $total } 問中 { $correct } 問正解（{ $percent }%）
+quiz-chosen = あなたの答え：
+quiz-unanswered = 未回答
+quiz-again = もう一度クイズ
+quiz-empty = このデッキにはまだクイズにできるカードがありません。
+
+## Match
+
+match-pairs = ペア数
+match-new = 新しいボード
+match-time = { $seconds } 秒でクリア
+match-best = 自己ベスト：{ $seconds } 秒
+match-new-best = 自己ベスト更新！
+match-log-in = ログインすると自己ベストが保存されます。
+match-again = もう一度
+match-too-few = このデッキで遊ぶには、表と裏がそれぞれ異なるカードが2枚以上必要です。
+
+## Stats
+
+stats-period = 復習数・定着率・時間は直近 { $days } 日間の集計です。日付は UTC の0時で区切ります。
+stats-reviews-today = 今日の復習
+stats-streak = 現在の連続日数
+stats-longest-streak = 最長連続日数
+stats-reviews = 復習数
+stats-retention = 定着率
+stats-minutes = 学習時間（分）
+stats-due = 今日の予定
+stats-per-day = 1日あたりの復習数（直近 { $days } 日）
+stats-forecast = 1日あたりの予定数（今後 { $days } 日）
+stats-maturity = 習熟度別カード数
+stats-learning = 学習中
+stats-young = 若い
+stats-mature = 定着
+stats-calendar = 連続学習カレンダー
+stats-by-stack = デッキ別
+stats-by-langs = 言語ペア別
+stats-stack = デッキ
+stats-langs = 言語
+stats-empty = まだ復習がありません。デッキを復習すると、ここに統計が表示されます。
+
+## Search
+
+search-text = 名前：
+search-submit = 検索
+search-none = デッキが見つかりませんでした。
+search-clone = 自分のデッキにコピー
+
+## Collections
+
+collection-label = コレクション名
+collection-label-placeholder = げんき I
+collection-description = 説明（任意）
+collection-stacks = デッキ（学習順）
+collection-stacks-uris = デッキの URI（1 行に 1 つ）
+collection-pick-stack = 自分のデッキを選択
+collection-add-stack = 追加
+collection-move-up = 上へ
+collection-move-down = 下へ
+collection-remove-stack = 外す
+collection-create = コレクションを作成
+collection-save = コレクションを保存
+collection-view = コレクションを見る
+collection-edit = 編集
+collection-delete = コレクションを削除
+collection-delete-confirm = { $label } を削除してもよろしいですか？デッキは残ります。
+collection-clone = 自分のコレクションにコピー
+collection-empty = このコレクションにはまだデッキがありません。
+
+## Validation
+
+invalid-record-uri = レコードの URI が無効です：{ $uri }
+invalid-front-lang = 表面の言語が無効です：{ $lang }
+invalid-back-lang = 裏面の言語が無効です：{ $lang }
+invalid-directions = 復習の方向が無効です：{ $directions }
+invalid-description-length = 説明は { $max } 文字以内にしてください
+invalid-source-length = 出典は { $max } 文字以内にしてください
+invalid-license = ライセンスが無効です：{ $license }
+invalid-difficulty = 不明な難易度です：{ $difficulty }
+invalid-tag-length = タグは { $max } 文字以内にしてください：{ $tag }
+invalid-tag-count = タグは { $max } 個までです
+invalid-collection-label = コレクションには名前が必要です
+invalid-label-length = 名前は { $max } 文字以内にしてください
+invalid-stack-uri = デッキではありません：{ $uri }
+invalid-stack-count = デッキは { $max } 個までです
+invalid-stack-not-found = デッキが見つかりません：{ $uri }
+invalid-stack-card = このデッキのカードではありません：{ $uri }
+invalid-format = テキスト形式が無効です：{ $format }
+invalid-kind = カードの種類が無効です：{ $kind }
+invalid-position = 位置が無効です：{ $position }
+invalid-cloze-unclosed = 穴埋めが {"}}"} で閉じられていません
+invalid-cloze-malformed = 穴埋めは {"{{c1::答え}}"} または {"{{c1::答え::ヒント}}"} のように書きます
+invalid-cloze-empty = 穴埋め c{ $ordinal } が空です
+invalid-cloze-none = 穴埋めテキストには {"{{c1::答え}}"} のような穴埋めが 1 つ以上必要です
+invalid-image-type = 画像の種類が無効です：{ $type }
+invalid-image-file = 画像は PNG、JPEG、GIF、WebP のいずれかにしてください
+invalid-image-size = 画像は { $max } kB 以下にしてください
+invalid-audio-type = 音声の種類が無効です：{ $type }
+invalid-audio-file = 音声は MP3、Ogg、WAV、FLAC、WebM、MP4 のいずれかにしてください
+invalid-audio-size = 音声は { $max } MB 以下にしてください
+invalid-blob = ファイルが無効です
+invalid-speech-text = 読み上げるテキストがありません
+invalid-speech-lang = { $lang } の音声がありません
+invalid-speech-length = 読み上げるテキストは{ $max }文字までです
+invalid-daily-limits = 1 日の上限は 0 から { $max } の間にしてください
+invalid-review-item = 復習項目が無効です：{ $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = AtProto エージェントを取得できませんでした
+error-stack-not-found = デッキが見つかりません
+error-forbidden = この操作を行う権限がありません
+error-db-query = データベースの問い合わせに失敗しました
+error-unknown = 不明なエラーが発生しました。
+error-create-stack = デッキの作成中にエラーが発生しました
+error-login-to-create-stack = デッキを作成するにはログインしてください。
+error-login-to-edit-stacks = デッキを編集するにはログインしてください
+error-retrieve-stack = デッキを取得できませんでした
+error-retrieve-cards = カードを取得できませんでした。もう一度お試しください。
+error-update-stack = デッキの更新中にエラーが発生しました
+error-delete-stack = デッキの削除中にエラーが発生しました。
+error-collection-not-found = コレクションが見つかりません
+error-retrieve-collection = コレクションの取得中にエラーが発生しました
+error-create-collection = コレクションの作成中にエラーが発生しました
+error-update-collection = コレクションの更新中にエラーが発生しました
+error-delete-collection = コレクションの削除中にエラーが発生しました。
+error-clone-incomplete = コピーは作成されましたが、次のカードはコピーできませんでした：{ $cards }
+error-login-to-edit-collections = コレクションを編集するにはログインしてください
+error-edit-card = カードの編集中にエラーが発生しました
+error-delete-card = カードの削除中にエラーが発生しました。
+error-reorder-cards = カードの並べ替え中にエラーが発生しました。
+error-speech-unavailable = このサーバーでは音声の生成が設定されていません。
+error-speech = 音声の生成中にエラーが発生しました
+error-login-to-review = デッキを復習するにはログインしてください
+error-card-not-found = カードが見つかりません
+error-review = 復習の保存中にエラーが発生しました
+error-match-not-started = このゲームは既に終了しているか、開始されていません。ページを再読み込みしてください。
+error-match-unfinished = まだすべてのペアが揃っていないため、このタイムは記録されません。
+error-match = タイムの保存中にエラーが発生しました
+error-login-to-see-stats = 統計を見るにはログインしてください
+error-oauth = OAuth エラーです。ログを確認してください
+error-oauth-no-did = OAuth エージェントから DID が返されませんでした。もう一度ログインしてみてください。
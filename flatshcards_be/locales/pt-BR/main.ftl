@@ -0,0 +1,317 @@
+## Page titles
+
+title-home = Início
+title-login = Entrar
+title-error = Erro
+title-forbidden = Proibido
+title-form-validation = Validação do formulário
+title-create-stack = Criar baralho
+title-edit-stack = Editar baralho
+title-review = Revisar
+title-review-session = Sessão de revisão
+title-quiz = Quiz
+title-match = Combinar
+title-stats = Suas estatísticas
+title-search = Buscar baralhos
+title-collection = Coleção
+title-stack = Baralho
+title-create-collection = Criar coleção
+title-edit-collection = Editar coleção
+
+## Layout
+
+app-tagline = Flashcards na Atmosfera.
+locale-label = Idioma da interface
+locale-apply = Alterar
+error-prefix = Erro:
+go-home = Voltar ao início
+
+## Home
+
+home-greeting = Olá, { $name }.
+home-friend = amigo
+log-in = Entrar
+log-out = Sair
+home-no-stacks = Nenhum baralho ainda.
+home-create-one = Que tal criar um?
+home-new-stack = Novo baralho
+home-front-lang = Idioma da frente:
+home-back-lang = Idioma do verso:
+home-lang-unspecified = (Não especificado)
+home-review = Revisar
+home-review-session = Revisar vários baralhos
+home-any-lang = (Qualquer)
+home-quiz = Quiz
+home-match = Combinar
+home-stats = Suas estatísticas
+home-search = Buscar baralhos
+home-tag = Etiqueta:
+home-any-tag = (Qualquer)
+home-all-stacks = Todos os baralhos
+home-no-tagged-stacks = Nenhum dos seus baralhos tem a etiqueta { $tag }.
+home-collections = Suas coleções
+home-new-collection = Nova coleção
+
+## Login
+
+login-handle-placeholder = Digite seu identificador (ex.: alice.bsky.social)
+login-no-account = Não tem uma conta na Atmosfera?
+login-sign-up = Cadastre-se no Bluesky para criar uma agora!
+login-oauth-error = Erro de OAuth
+login-invalid-handle = Esse identificador não é válido.
+
+## Stacks
+
+stack-label = Nome do baralho
+stack-label-placeholder = Capítulo 1
+stack-front-lang = Idioma da frente (opcional)
+stack-back-lang = Idioma do verso (opcional)
+stack-create = Criar baralho
+stack-edit = Editar baralho
+stack-delete = Excluir baralho
+stack-delete-confirm = Tem certeza de que deseja excluir { $label }?
+stack-directions = Sentido da revisão
+stack-directions-forward = Da frente para o verso
+stack-directions-reverse = Do verso para a frente
+stack-directions-both = Nos dois sentidos
+stack-description = Descrição (opcional)
+stack-description-placeholder = O que o baralho aborda e para quem ele é
+stack-source = Fonte (opcional)
+stack-source-placeholder = O livro didático, curso ou lista de palavras de onde vieram os cartões
+stack-credit = Fonte
+stack-license = Licença
+stack-license-none = (Nenhuma)
+stack-license-unspecified = Não informada
+stack-license-all-rights-reserved = Todos os direitos reservados
+stack-reuse-licensed = Você pode reutilizar e adaptar estes cartões nos termos da licença.
+stack-reuse-reserved = O autor não permitiu que estes cartões sejam reutilizados.
+stack-reuse-other = Confira os termos da licença antes de reutilizar estes cartões.
+stack-reuse-unspecified = O autor não disse se estes cartões podem ser reutilizados. Pergunte antes de compartilhar uma cópia.
+stack-difficulty = Dificuldade
+stack-difficulty-none = (Não especificada)
+stack-difficulty-beginner = Iniciante
+stack-difficulty-intermediate = Intermediário
+stack-difficulty-advanced = Avançado
+stack-cover = Imagem de capa
+stack-cards = Cartões
+stack-view = Ver página do baralho
+stack-edit-page = Editar
+tags = Etiquetas
+tags-placeholder = verbos, viagem, Celpe-Bras
+
+## Cards
+
+card-front-lang = Idioma da frente
+card-front-text = Texto da frente
+card-back-lang = Idioma do verso
+card-back-text = Texto do verso
+card-kind = Tipo de cartão
+card-kind-basic = Frente e verso
+card-kind-cloze = Lacunas
+card-format = Formato do texto
+card-format-plain = Texto simples
+card-format-markdown = Markdown e ruby
+card-front-image = Imagem da frente
+card-back-image = Imagem do verso
+card-image-remove = Remover imagem
+card-audio = Áudio
+card-audio-record = Gravar
+card-audio-stop = Parar
+card-audio-remove = Remover áudio
+card-audio-speak = Áudio gerado
+card-audio-speak-none = Não gerar
+card-audio-speak-front = Gerar a partir da frente
+card-audio-speak-back = Gerar a partir do verso
+stack-speech = Gerar áudio para os cartões sem áudio
+card-create = Criar cartão
+card-update = Atualizar cartão
+card-delete = Excluir cartão
+card-delete-confirm = Tem certeza de que deseja excluir este cartão?
+card-drag = Arraste para reordenar, ou use as setas do teclado
+card-order-drag = Arraste os cartões pela alça para mudar a ordem em que são aprendidos.
+card-order-uris = Ordem dos cartões, uma URI de cartão por linha
+card-order-save = Salvar ordem
+
+## Language pickers
+
+lang-search = Pesquisar idiomas
+lang-none = (Nenhum)
+lang-choose = Escolha um idioma
+lang-group-selected = Selecionado
+lang-group-yours = Seus idiomas
+lang-group-common = Idiomas comuns
+lang-group-other = Outros idiomas
+
+## Review
+
+review-show = Mostrar resposta
+review-again = De novo
+review-hard = Difícil
+review-good = Bom
+review-easy = Fácil
+review-due = { $count } para revisar
+review-new = { $count } novos
+review-done = Nada para revisar por enquanto.
+review-mode = Responder
+review-mode-flip = Virando o cartão
+review-mode-typed = Digitando a resposta
+review-ignore-accents = Ignorar acentos
+review-new-order = Cartões novos
+review-new-order-in-order = Na ordem do baralho
+review-new-order-shuffled = Embaralhados
+review-apply = Aplicar
+review-typed = Digite a resposta
+review-check = Verificar
+review-verdict-exact = Correto!
+review-verdict-accents = Correto, exceto pelos acentos.
+review-verdict-close = Quase: confira a ortografia.
+review-verdict-wrong = Não exatamente.
+review-new-per-day = Cartões novos por dia
+review-reviews-per-day = Revisões por dia
+review-save-limits = Salvar limites
+review-held-back = Mais { $count } esperam outro dia, pelos seus limites diários.
+
+## Quiz
+
+quiz-submit = Verificar respostas
+quiz-score = { $correct } de { $total } corretas ({ $percent }%)
+quiz-chosen = Você escolheu:
+quiz-unanswered = Sem resposta
+quiz-again = Outro quiz
+quiz-empty = Este baralho ainda não tem cartões para um quiz.
+
+## Match
+
+match-pairs = Pares
+match-new = Novo tabuleiro
+match-time = Combinado em { $seconds } segundos
+match-best = Seu melhor tempo: { $seconds } segundos
+match-new-best = Novo recorde!
+match-log-in = Entre para guardar seus melhores tempos.
+match-again = Jogar de novo
+match-too-few = Este baralho precisa de pelo menos dois cartões com frentes e versos diferentes para jogar.
+
+## Stats
+
+stats-period = Revisões, retenção e tempo são dos últimos { $days } dias. Os dias vão de meia-noite a meia-noite UTC.
+stats-reviews-today = Revisões hoje
+stats-streak = Sequência atual, em dias
+stats-longest-streak = Maior sequência, em dias
+stats-reviews = Revisões
+stats-retention = Retenção
+stats-minutes = Minutos
+stats-due = Para hoje
+stats-per-day = Revisões por dia, últimos { $days } dias
+stats-forecast = Pendentes por dia, próximos { $days } dias
+stats-maturity = Cartões por maturidade
+stats-learning = Aprendendo
+stats-young = Jovens
+stats-mature = Maduros
+stats-calendar = Calendário de sequências
+stats-by-stack = Por baralho
+stats-by-langs = Por par de idiomas
+stats-stack = Baralho
+stats-langs = Idiomas
+stats-empty = Ainda não há revisões. Revise um baralho e suas estatísticas aparecerão aqui.
+
+## Search
+
+search-text = Nome:
+search-submit = Buscar
+search-none = Nenhum baralho encontrado.
+search-clone = Copiar para meus baralhos
+
+## Collections
+
+collection-label = Nome da coleção
+collection-label-placeholder = Genki I
+collection-description = Descrição (opcional)
+collection-stacks = Baralhos, na ordem de estudo
+collection-stacks-uris = URIs dos baralhos, uma por linha
+collection-pick-stack = Escolha um dos seus baralhos
+collection-add-stack = Adicionar
+collection-move-up = Subir
+collection-move-down = Descer
+collection-remove-stack = Remover
+collection-create = Criar coleção
+collection-save = Salvar coleção
+collection-view = Ver coleção
+collection-edit = Editar
+collection-delete = Excluir coleção
+collection-delete-confirm = Tem certeza de que deseja excluir { $label }? Os baralhos serão mantidos.
+collection-clone = Copiar para minhas coleções
+collection-empty = Esta coleção ainda não tem baralhos.
+
+## Validation
+
+invalid-record-uri = URI de registro inválida: { $uri }
+invalid-front-lang = Idioma da frente inválido: { $lang }
+invalid-back-lang = Idioma do verso inválido: { $lang }
+invalid-directions = Direção de revisão inválida: { $directions }
+invalid-description-length = As descrições podem ter no máximo { $max } caracteres
+invalid-source-length = As fontes podem ter no máximo { $max } caracteres
+invalid-license = Licença inválida: { $license }
+invalid-difficulty = Dificuldade desconhecida: { $difficulty }
+invalid-tag-length = As tags podem ter no máximo { $max } caracteres: { $tag }
+invalid-tag-count = São permitidas no máximo { $max } tags
+invalid-collection-label = Uma coleção precisa de um nome
+invalid-label-length = Os nomes podem ter no máximo { $max } caracteres
+invalid-stack-uri = Não é um baralho: { $uri }
+invalid-stack-count = São permitidos no máximo { $max } baralhos
+invalid-stack-not-found = Baralho não encontrado: { $uri }
+invalid-stack-card = Não é um cartão deste baralho: { $uri }
+invalid-format = Formato de texto inválido: { $format }
+invalid-kind = Tipo de cartão inválido: { $kind }
+invalid-position = Posição inválida: { $position }
+invalid-cloze-unclosed = Uma lacuna não está fechada com {"}}"}
+invalid-cloze-malformed = As lacunas são escritas assim: {"{{c1::resposta}}"} ou {"{{c1::resposta::dica}}"}
+invalid-cloze-empty = A lacuna c{ $ordinal } está vazia
+invalid-cloze-none = O texto com lacunas precisa de pelo menos uma, como {"{{c1::resposta}}"}
+invalid-image-type = Tipo de imagem inválido: { $type }
+invalid-image-file = As imagens devem ser PNG, JPEG, GIF ou WebP
+invalid-image-size = As imagens podem ter no máximo { $max } kB
+invalid-audio-type = Tipo de áudio inválido: { $type }
+invalid-audio-file = O áudio deve ser MP3, Ogg, WAV, FLAC, WebM ou MP4
+invalid-audio-size = O áudio pode ter no máximo { $max } MB
+invalid-blob = Arquivo inválido
+invalid-speech-text = Não há texto para ler em voz alta
+invalid-speech-lang = Não há voz para { $lang }
+invalid-speech-length = O texto a ser lido pode ter no máximo { $max } caracteres
+invalid-daily-limits = Os limites diários devem estar entre 0 e { $max }
+invalid-review-item = Item de revisão inválido: { $direction } { $ordinal }
+
+## Errors
+
+error-session-agent = Erro ao recuperar o agente do AtProto
+error-stack-not-found = Baralho não encontrado
+error-forbidden = Você não tem permissão para realizar esta ação
+error-db-query = Erro ao consultar o banco de dados
+error-unknown = Ocorreu um erro desconhecido.
+error-create-stack = Ocorreu um erro ao criar o baralho
+error-login-to-create-stack = Você precisa entrar para criar um baralho.
+error-login-to-edit-stacks = Você precisa entrar para editar baralhos
+error-retrieve-stack = Erro ao recuperar o baralho
+error-retrieve-cards = Não foi possível recuperar os cartões, tente novamente.
+error-update-stack = Erro ao atualizar o baralho
+error-delete-stack = Erro ao excluir o baralho.
+error-collection-not-found = Coleção não encontrada
+error-retrieve-collection = Erro ao obter a coleção
+error-create-collection = Ocorreu um erro ao criar a coleção
+error-update-collection = Erro ao atualizar a coleção
+error-delete-collection = Erro ao excluir a coleção.
+error-clone-incomplete = A cópia foi feita, mas estes cartões não puderam ser copiados: { $cards }
+error-login-to-edit-collections = Você precisa estar conectado para editar coleções
+error-edit-card = Erro ao editar o cartão
+error-delete-card = Erro ao excluir o cartão.
+error-reorder-cards = Erro ao reordenar os cartões.
+error-speech-unavailable = A geração de áudio não está configurada neste servidor.
+error-speech = Erro ao gerar o áudio
+error-login-to-review = Você precisa entrar para revisar baralhos
+error-card-not-found = Cartão não encontrado
+error-review = Erro ao salvar a revisão
+error-match-not-started = Este jogo já terminou ou nunca começou. Recarregue a página para um novo.
+error-match-unfinished = Nem todos os pares foram combinados ainda, então este tempo não conta.
+error-match = Erro ao salvar seu tempo
+error-login-to-see-stats = Você precisa entrar para ver suas estatísticas
+error-oauth = Erro de OAuth, verifique os logs
+error-oauth-no-did = O agente de OAuth não retornou um DID. Tente entrar novamente.
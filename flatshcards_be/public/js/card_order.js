@@ -0,0 +1,97 @@
+// Drags a stack's cards into a new order, which is saved as soon as one is dropped. A card
+// is dragged by its handle, or moved with the arrow keys while its handle has focus.
+// Without scripts the order is edited as uris, one a line, instead. Cards are added and
+// edited by swapping in their editors, so handles are shown on every load.
+htmx.onLoad((loaded) => {
+  for (const order of loaded.querySelectorAll(".card-order")) {
+    setUpOrder(order);
+  }
+  for (const handle of loaded.querySelectorAll(".card-drag-handle")) {
+    handle.hidden = false;
+  }
+});
+
+function setUpOrder(form) {
+  const list = form.closest("#editCards");
+  const uris = form.querySelector("textarea");
+  let dragged = null;
+  const save = () => {
+    const order = [...list.querySelectorAll(".edit-card")]
+      .map((card) => card.dataset.cardUri)
+      .join("\n");
+    if (order !== uris.value.trim()) {
+      uris.value = order;
+      htmx.trigger(form, "submit");
+    }
+  };
+  // Only the handle starts a drag, so text in the card can still be selected
+  list.addEventListener("pointerdown", (event) => {
+    const handle = event.target.closest(".card-drag-handle");
+    if (handle) {
+      handle.closest(".edit-card").draggable = true;
+    }
+  });
+  list.addEventListener("pointerup", (event) => {
+    const card = event.target.closest(".edit-card");
+    if (card && card !== dragged) {
+      card.draggable = false;
+    }
+  });
+  list.addEventListener("dragstart", (event) => {
+    dragged = event.target.closest(".edit-card");
+    if (dragged) {
+      event.dataTransfer.effectAllowed = "move";
+      dragged.classList.add("dragging");
+    }
+  });
+  list.addEventListener("dragover", (event) => {
+    const over = event.target.closest(".edit-card");
+    if (!dragged || !over) {
+      return;
+    }
+    event.preventDefault();
+    if (over !== dragged) {
+      const box = over.getBoundingClientRect();
+      if (event.clientY < box.top + box.height / 2) {
+        over.before(dragged);
+      } else {
+        over.after(dragged);
+      }
+    }
+  });
+  list.addEventListener("drop", (event) => {
+    if (dragged) {
+      event.preventDefault();
+    }
+  });
+  list.addEventListener("dragend", () => {
+    if (!dragged) {
+      return;
+    }
+    dragged.classList.remove("dragging");
+    dragged.draggable = false;
+    dragged = null;
+    save();
+  });
+  list.addEventListener("keydown", (event) => {
+    const handle = event.target.closest(".card-drag-handle");
+    if (!handle) {
+      return;
+    }
+    const card = handle.closest(".edit-card");
+    const previous = card.previousElementSibling;
+    const next = card.nextElementSibling;
+    if (event.key === "ArrowUp" && previous?.matches(".edit-card")) {
+      previous.before(card);
+    } else if (event.key === "ArrowDown" && next?.matches(".edit-card")) {
+      next.after(card);
+    } else {
+      return;
+    }
+    event.preventDefault();
+    handle.focus();
+    save();
+  });
+  form.querySelector(".card-order-text").hidden = true;
+  form.querySelector(".card-order-hint").hidden = false;
+}
@@ -0,0 +1,59 @@
+// Edits a collection's stacks as a list to add to and reorder, keeping the uris the form
+// submits, one a line, in step with it. Without scripts the uris are edited by hand
+// instead. htmx swaps the editor in again after it's saved, so it's set up on every load.
+htmx.onLoad((loaded) => {
+  for (const editor of loaded.querySelectorAll(".collection-editor")) {
+    setUpEditor(editor);
+  }
+});
+
+function setUpEditor(editor) {
+  const text = editor.querySelector(".collection-stacks-text");
+  const uris = text.querySelector("textarea");
+  const list = editor.querySelector(".collection-stacks");
+  const add = editor.querySelector(".collection-add");
+  const pick = add.querySelector("select");
+  const template = editor.querySelector(".collection-stack-template");
+  const sync = () => {
+    const added = [...list.children].map((item) => item.dataset.uri);
+    uris.value = added.join("\n");
+    for (const option of pick.options) {
+      option.disabled = added.includes(option.value);
+    }
+  };
+  list.addEventListener("click", (event) => {
+    const button = event.target.closest("button[data-move]");
+    if (!button) {
+      return;
+    }
+    const item = button.closest("li");
+    switch (button.dataset.move) {
+      case "up":
+        item.previousElementSibling?.before(item);
+        break;
+      case "down":
+        item.nextElementSibling?.after(item);
+        break;
+      case "remove":
+        item.remove();
+        break;
+    }
+    sync();
+  });
+  add.querySelector("button").addEventListener("click", () => {
+    const option = pick.selectedOptions[0];
+    if (!option || !option.value || option.disabled) {
+      return;
+    }
+    const item = template.content.firstElementChild.cloneNode(true);
+    item.dataset.uri = option.value;
+    item.querySelector(".collection-stack-label").textContent = option.text;
+    list.append(item);
+    pick.value = "";
+    sync();
+  });
+  text.hidden = true;
+  list.hidden = false;
+  add.hidden = false;
+  sync();
+}
@@ -0,0 +1,76 @@
+// Plays a matching game: pick a tile from each column, and if the server says they're the
+// same pair they're cleared, otherwise they're let go after a moment. The clock here is
+// only for show; the time that counts is the server's, from sending the board to hearing
+// it's been cleared, and only the server knows the pairs.
+document.addEventListener("DOMContentLoaded", () => {
+  const board = document.querySelector(".match-board");
+  if (!board) {
+    return;
+  }
+  const timer = document.getElementById("match-timer");
+  const started = Date.now();
+  const ticker = setInterval(() => {
+    timer.textContent = ((Date.now() - started) / 1000).toFixed(1);
+  }, 100);
+  let picked = null;
+  let busy = false;
+  board.addEventListener("click", (event) => {
+    const tile = event.target.closest(".match-tile");
+    if (!tile || busy || tile.classList.contains("match-done")) {
+      return;
+    }
+    if (!picked || picked.parentElement === tile.parentElement) {
+      if (picked) {
+        picked.classList.remove("match-picked");
+      }
+      picked = tile;
+      tile.classList.add("match-picked");
+      return;
+    }
+    const other = picked;
+    picked = null;
+    busy = true;
+    const [front, back] =
+      other.parentElement.dataset.side === "front" ? [other, tile] : [tile, other];
+    fetch(board.dataset.pair, {
+      method: "POST",
+      body: new URLSearchParams({ front: front.dataset.tile, back: back.dataset.tile }),
+    })
+      .then(async (response) => {
+        if (!response.ok) {
+          throw await response.text();
+        }
+        return response.json();
+      })
+      .then(({ matched, cleared }) => {
+        if (matched) {
+          for (const t of [other, tile]) {
+            t.classList.remove("match-picked");
+            t.classList.add("match-done");
+            t.disabled = true;
+          }
+          busy = false;
+          if (cleared) {
+            clearInterval(ticker);
+            htmx.ajax("POST", board.dataset.finish, {
+              target: "#match-result",
+              swap: "outerHTML",
+            });
+          }
+          return;
+        }
+        tile.classList.add("match-wrong");
+        other.classList.add("match-wrong");
+        setTimeout(() => {
+          for (const t of [other, tile]) {
+            t.classList.remove("match-picked", "match-wrong");
+          }
+          busy = false;
+        }, 600);
+      })
+      .catch((error) => {
+        clearInterval(ticker);
+        document.querySelector("#root .error").innerHTML = error;
+      });
+  });
+});
@@ -0,0 +1,49 @@
+// Records a card's audio in the browser and puts it in the form's audio file input, so it's
+// uploaded just like a chosen file. Card forms come and go with htmx swaps, so the
+// listeners are on the document.
+document.addEventListener("click", async (event) => {
+  const button = event.target.closest(".audio-record");
+  if (!button) {
+    return;
+  }
+  if (button.recorder) {
+    button.recorder.stop();
+    return;
+  }
+  let stream;
+  try {
+    stream = await navigator.mediaDevices.getUserMedia({ audio: true });
+  } catch {
+    button.disabled = true;
+    return;
+  }
+  const recorder = new MediaRecorder(stream);
+  const chunks = [];
+  recorder.ondataavailable = (e) => chunks.push(e.data);
+  recorder.onstop = () => {
+    stream.getTracks().forEach((track) => track.stop());
+    button.recorder = null;
+    button.textContent = button.dataset.record;
+    // Chrome and Firefox record WebM, Safari MP4
+    const type = recorder.mimeType.split(";")[0] || "audio/webm";
+    const name = type.endsWith("mp4") ? "recording.mp4" : "recording.webm";
+    const file = new File(chunks, name, { type });
+    const fields = button.closest(".card-audio-fields");
+    const files = new DataTransfer();
+    files.items.add(file);
+    fields.querySelector(".card-audio-file").files = files.files;
+    const preview = fields.querySelector(".card-audio-recorded");
+    preview.src = URL.createObjectURL(file);
+    preview.hidden = false;
+  };
+  recorder.start();
+  button.recorder = recorder;
+  button.textContent = button.dataset.stop;
+});
+
+document.addEventListener("reset", (event) => {
+  for (const preview of event.target.querySelectorAll(".card-audio-recorded")) {
+    preview.hidden = true;
+    preview.removeAttribute("src");
+  }
+});
@@ -0,0 +1,368 @@
+//! Typed answers, checked against what's on the card. Both are normalized first, by the
+//! rules of the answer's language, so that case, punctuation, spacing and (optionally)
+//! accents don't count against the learner, then compared with a little tolerance for
+//! typos. The check only suggests a grade: the learner has the last word.
+use crate::review::Grade;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Alternative answers on a card are separated by this, as in `color; colour`
+pub const ANSWER_SEPARATOR: char = ';';
+
+/// Languages written without spaces between words, where spacing can't matter
+const UNSPACED_LANGS: &[&str] = &["ja", "zh", "th", "lo", "km", "my"];
+/// Languages whose vowel points are usually left unwritten, so they never count
+const POINTED_LANGS: &[&str] = &["ar", "fa", "he", "ur", "yi"];
+
+/// How a typed answer compared with the card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Exact,
+    /// Only the accents were different, and they're being ignored
+    Accents,
+    /// A typo or two away
+    Close,
+    Wrong,
+}
+
+#[derive(Clone, Debug)]
+pub struct Checked {
+    pub verdict: Verdict,
+    /// Edits between the typed answer and the nearest accepted one, once both are
+    /// normalized
+    pub distance: usize,
+    pub suggested: Grade,
+}
+
+impl Checked {
+    /// A message id saying how it went
+    pub fn message(&self) -> &'static str {
+        match self.verdict {
+            Verdict::Exact => "review-verdict-exact",
+            Verdict::Accents => "review-verdict-accents",
+            Verdict::Close => "review-verdict-close",
+            Verdict::Wrong => "review-verdict-wrong",
+        }
+    }
+
+    pub fn is_suggested(&self, grade: &str) -> bool {
+        self.suggested.as_str() == grade
+    }
+}
+
+fn primary(lang: &str) -> String {
+    lang.split('-').next().unwrap_or(lang).to_ascii_lowercase()
+}
+
+/// Marks that are accents on a letter rather than part of it: those on Latin, Greek and
+/// Cyrillic letters. Devanagari vowel signs and Japanese dakuten are marks too, but
+/// they make a different letter, so they stay.
+fn is_accent(base: Option<char>, mark: char) -> bool {
+    is_combining_mark(mark) && base.is_some_and(|b| b < '\u{0530}')
+}
+
+/// Hebrew points and Arabic harakat
+fn is_vowel_point(c: char) -> bool {
+    matches!(c, '\u{0591}'..='\u{05C7}' | '\u{064B}'..='\u{065F}' | '\u{0670}')
+}
+
+/// Letters that don't decompose into a base and an accent, but are read as one
+fn unaccented(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ø' => "o",
+        'æ' => "ae",
+        'œ' => "oe",
+        'ł' => "l",
+        'đ' => "d",
+        'ħ' => "h",
+        'ı' => "i",
+        'ŧ' => "t",
+        _ => return None,
+    })
+}
+
+/// `text` in language `lang` reduced to what's compared: compatibility forms folded,
+/// lowercased, punctuation gone and spacing collapsed, or gone altogether where words
+/// aren't spaced. Accents are taken off too if `ignore_accents`.
+pub fn normalize(text: &str, lang: &str, ignore_accents: bool) -> String {
+    let lang = primary(lang);
+    let text: String = text.nfkc().collect();
+    let lower = match lang.as_str() {
+        // Dotted and dotless i are different letters
+        "tr" | "az" => text.replace('I', "ı").replace('İ', "i").to_lowercase(),
+        _ => text.to_lowercase(),
+    };
+    let lower = if lang == "de" {
+        lower.replace('ß', "ss")
+    } else {
+        lower
+    };
+    let pointed = POINTED_LANGS.contains(&lang.as_str());
+    let mut out = String::new();
+    let mut base = None;
+    for c in lower.nfd() {
+        if pointed && is_vowel_point(c) {
+            continue;
+        }
+        if is_combining_mark(c) {
+            if !(ignore_accents && is_accent(base, c)) {
+                out.push(c);
+            }
+            continue;
+        }
+        base = Some(c);
+        if c.is_alphanumeric() {
+            match unaccented(c).filter(|_| ignore_accents) {
+                Some(plain) => out.push_str(plain),
+                None => out.push(c),
+            }
+        } else if c.is_whitespace() || !out.ends_with(' ') {
+            // Punctuation separates words as much as a space does
+            out.push(' ');
+        }
+    }
+    let out: String = out.nfc().collect();
+    if UNSPACED_LANGS.contains(&lang.as_str()) {
+        out.split_whitespace().collect()
+    } else {
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Edits to get from `a` to `b`, counted in characters. Two letters swapped is the
+/// commonest typo there is, so it counts as one edit rather than two.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut prev, row);
+    }
+    prev[b.len()]
+}
+
+/// How many typos an answer of `len` characters can have and still be close: none for
+/// short words, where one letter is often the whole difference, then one per five.
+fn tolerance(len: usize) -> usize {
+    len / 5
+}
+
+/// Checks `typed` against `expected`, the text of a card in language `lang`, which may
+/// list alternatives separated by [`ANSWER_SEPARATOR`]
+pub fn check(expected: &str, typed: &str, lang: &str, ignore_accents: bool) -> Checked {
+    let typed_strict = normalize(typed, lang, false);
+    let typed_loose = normalize(typed, lang, true);
+    let mut best: Option<Checked> = None;
+    for answer in expected.split(ANSWER_SEPARATOR) {
+        let strict = normalize(answer, lang, false);
+        if strict.is_empty() {
+            continue;
+        }
+        let checked = if typed_strict == strict {
+            Checked {
+                verdict: Verdict::Exact,
+                distance: 0,
+                suggested: Grade::Good,
+            }
+        } else if ignore_accents && typed_loose == normalize(answer, lang, true) {
+            Checked {
+                verdict: Verdict::Accents,
+                distance: 0,
+                suggested: Grade::Good,
+            }
+        } else {
+            let distance = if ignore_accents {
+                levenshtein(&typed_loose, &normalize(answer, lang, true))
+            } else {
+                levenshtein(&typed_strict, &strict)
+            };
+            if distance <= tolerance(strict.chars().count()) {
+                Checked {
+                    verdict: Verdict::Close,
+                    distance,
+                    suggested: Grade::Hard,
+                }
+            } else {
+                Checked {
+                    verdict: Verdict::Wrong,
+                    distance,
+                    suggested: Grade::Again,
+                }
+            }
+        };
+        let better = best.as_ref().is_none_or(|b| {
+            (checked.verdict as u8, checked.distance) < (b.verdict as u8, b.distance)
+        });
+        if better {
+            best = Some(checked);
+        }
+    }
+    best.unwrap_or(Checked {
+        verdict: Verdict::Wrong,
+        distance: typed_strict.chars().count(),
+        suggested: Grade::Again,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_are_counted() {
+        for (a, b, edits) in [
+            ("", "", 0),
+            ("abc", "", 3),
+            ("", "abc", 3),
+            ("kitten", "sitting", 3),
+            ("teh", "the", 1),
+            ("recieve", "receive", 1),
+            ("abcd", "badc", 2),
+            ("日本", "日本語", 1),
+            ("café", "cafe", 1),
+        ] {
+            assert_eq!(levenshtein(a, b), edits, "{a} → {b}");
+            assert_eq!(levenshtein(b, a), edits, "{b} → {a}");
+        }
+    }
+
+    #[test]
+    fn answers_are_normalized() {
+        for (text, lang, ignore_accents, normalized) in [
+            ("  Hello,  World! ", "en", false, "hello world"),
+            ("don't", "en", false, "don t"),
+            ("Straße", "de", false, "strasse"),
+            ("Straße", "de-AT", false, "strasse"),
+            ("İSTANBUL", "tr", false, "istanbul"),
+            ("IRMAK", "tr", false, "ırmak"),
+            ("IRMAK", "en", false, "irmak"),
+            ("Café", "fr", false, "café"),
+            ("Café", "fr", true, "cafe"),
+            ("Søren Kierkegaard", "da", true, "soren kierkegaard"),
+            ("Łódź", "pl", true, "lodz"),
+            ("Ελληνικά", "el", true, "ελληνικα"),
+            ("ｶﾀｶﾅ", "ja", false, "カタカナ"),
+            ("日本 語。", "ja", false, "日本語"),
+            ("がっこう", "ja", true, "がっこう"),
+            ("नमस्ते", "hi", true, "नमस्ते"),
+            ("שָׁלוֹם", "he", false, "שלום"),
+            ("كِتَاب", "ar", false, "كتاب"),
+        ] {
+            assert_eq!(
+                normalize(text, lang, ignore_accents),
+                normalized,
+                "{text} ({lang})"
+            );
+        }
+    }
+
+    #[test]
+    fn answers_are_checked() {
+        use Verdict::*;
+        for (expected, typed, lang, ignore_accents, verdict, distance, grade) in [
+            // Exact, give or take case, punctuation and spacing
+            ("colour", "colour", "en", false, Exact, 0, Grade::Good),
+            ("Colour", "  COLOUR! ", "en", false, Exact, 0, Grade::Good),
+            ("Straße", "strasse", "de", false, Exact, 0, Grade::Good),
+            ("学校", "学 校", "ja", false, Exact, 0, Grade::Good),
+            // Any alternative will do, and the nearest one counts
+            (
+                "color; colour",
+                "colour",
+                "en",
+                false,
+                Exact,
+                0,
+                Grade::Good,
+            ),
+            ("color; colour", "Color", "en", false, Exact, 0, Grade::Good),
+            (
+                "elephant; pachyderm",
+                "elephnat",
+                "en",
+                false,
+                Close,
+                1,
+                Grade::Hard,
+            ),
+            // Accents only matter if they're not being ignored
+            ("café", "cafe", "fr", true, Accents, 0, Grade::Good),
+            (
+                "Wörterbuch",
+                "worterbuch",
+                "de",
+                true,
+                Accents,
+                0,
+                Grade::Good,
+            ),
+            ("café", "cafe", "fr", false, Wrong, 1, Grade::Again),
+            (
+                "Wörterbuch",
+                "worterbuch",
+                "de",
+                false,
+                Close,
+                1,
+                Grade::Hard,
+            ),
+            // One typo per five letters, and none in short words
+            ("cat", "bat", "en", false, Wrong, 1, Grade::Again),
+            ("house", "hous", "en", false, Close, 1, Grade::Hard),
+            (
+                "necessary",
+                "neccessary",
+                "en",
+                false,
+                Close,
+                1,
+                Grade::Hard,
+            ),
+            (
+                "Wörterbuch",
+                "Wörtebruch",
+                "de",
+                false,
+                Close,
+                1,
+                Grade::Hard,
+            ),
+            (
+                "Wörterbuch",
+                "Wortebruch",
+                "de",
+                true,
+                Close,
+                1,
+                Grade::Hard,
+            ),
+            (
+                "Wörterbuch",
+                "Wortebruch",
+                "de",
+                false,
+                Close,
+                2,
+                Grade::Hard,
+            ),
+            ("elephant", "elefant", "en", false, Wrong, 2, Grade::Again),
+            ("cat", "dog", "en", false, Wrong, 3, Grade::Again),
+            // Nothing to compare with
+            ("; ;", "anything", "en", false, Wrong, 8, Grade::Again),
+        ] {
+            let checked = check(expected, typed, lang, ignore_accents);
+            let case = format!("{typed} for {expected} ({lang}, ignore_accents: {ignore_accents})");
+            assert_eq!(checked.verdict, verdict, "{case}");
+            assert_eq!(checked.distance, distance, "{case}");
+            assert_eq!(checked.suggested, grade, "{case}");
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Pictures and recordings on cards are blobs in the author's repo, and only their refs are
+//! indexed. The bytes are fetched from the author's PDS the first time someone views them,
+//! then kept on disk: a cid always names the same bytes, so a cached blob never goes stale.
+//! Whatever a PDS sends is checked against the cid before it's kept, so no PDS can put
+//! anything else in the cache under someone else's cid.
+use atrium_api::types::{BlobRef, TypedBlobRef, string::Did};
+use atrium_common::resolver::Resolver;
+use atrium_identity::did::CommonDidResolver;
+use atrium_oauth::DefaultHttpClient;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// What the card lexicon's `frontImage` and `backImage` accept
+pub const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+pub const MAX_IMAGE_SIZE: usize = 1_000_000;
+/// What the card lexicon's `audio` accepts; browsers record to WebM or, in Safari, MP4
+pub const AUDIO_TYPES: &[&str] = &[
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "audio/flac",
+    "audio/webm",
+    "audio/mp4",
+];
+pub const MAX_AUDIO_SIZE: usize = 5_000_000;
+/// The most any blob field accepts, so the most that's ever fetched for one
+pub const MAX_BLOB_SIZE: usize = if MAX_IMAGE_SIZE > MAX_AUDIO_SIZE {
+    MAX_IMAGE_SIZE
+} else {
+    MAX_AUDIO_SIZE
+};
+/// The multicodec of a blob's cid, which is over its bytes as they are
+pub const CODEC_RAW: u8 = 0x55;
+
+/// The type of an image in one of [`IMAGE_TYPES`], going by its first bytes rather than
+/// what the uploader claimed
+pub fn image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// The type of a recording in one of [`AUDIO_TYPES`], going by its first bytes. WebM and
+/// MP4 are containers that could hold video too, which is taken on trust.
+pub fn audio_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"ID3") || (data.len() >= 2 && data[0] == 0xff && data[1] & 0xe0 == 0xe0) {
+        Some("audio/mpeg")
+    } else if data.starts_with(b"OggS") {
+        Some("audio/ogg")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WAVE" {
+        Some("audio/wav")
+    } else if data.starts_with(b"fLaC") {
+        Some("audio/flac")
+    } else if data.starts_with(b"\x1a\x45\xdf\xa3") {
+        Some("audio/webm")
+    } else if data.len() >= 8 && &data[4..8] == b"ftyp" {
+        Some("audio/mp4")
+    } else {
+        None
+    }
+}
+
+/// Whichever of [`image_type`] or [`audio_type`] the bytes are
+pub fn media_type(data: &[u8]) -> Option<&'static str> {
+    image_type(data).or_else(|| audio_type(data))
+}
+
+/// A base32 CIDv1 with multicodec `codec` over the sha2-256 of `content`, the form every
+/// blob's cid takes
+pub fn cid(codec: u8, content: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut bytes = vec![0x01, codec, 0x12, 0x20];
+    bytes.extend(Sha256::digest(content));
+    let mut cid = String::from("b");
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            cid.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        cid.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    cid
+}
+
+pub fn blob_cid(blob: &BlobRef) -> String {
+    match blob {
+        BlobRef::Typed(TypedBlobRef::Blob(blob)) => blob.r#ref.0.to_string(),
+        BlobRef::Untyped(blob) => blob.cid.clone(),
+    }
+}
+
+pub fn blob_mime_type(blob: &BlobRef) -> &str {
+    match blob {
+        BlobRef::Typed(TypedBlobRef::Blob(blob)) => &blob.mime_type,
+        BlobRef::Untyped(blob) => &blob.mime_type,
+    }
+}
+
+/// Where a blob of `author_did`'s is served from here
+pub fn blob_url(author_did: &str, blob: &BlobRef) -> String {
+    format!("/blobs/{author_did}/{}", blob_cid(blob))
+}
+
+#[derive(Error, Debug)]
+pub enum BlobError {
+    #[error("Not a blob cid: {0}")]
+    BadCid(String),
+    #[error("Could not resolve {0}: {1}")]
+    Resolve(String, String),
+    #[error("{0} has no PDS")]
+    NoPds(String),
+    #[error("Error fetching blob: {0}")]
+    Fetch(String),
+    #[error("Blob {0} is over {MAX_BLOB_SIZE} bytes")]
+    TooLarge(String),
+    #[error("Blob fetched as {0} doesn't match it")]
+    Mismatch(String),
+    #[error("Error caching blob: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Blobs fetched from their authors' PDSes, cached in `dir` by cid
+pub struct BlobCache {
+    dir: PathBuf,
+    did_resolver: Arc<CommonDidResolver<DefaultHttpClient>>,
+    http_client: reqwest::Client,
+    /// Fetch everything from here instead, when writes go to the mock PDS
+    pds_override: Option<String>,
+}
+
+impl BlobCache {
+    pub fn new(
+        dir: PathBuf,
+        did_resolver: Arc<CommonDidResolver<DefaultHttpClient>>,
+        pds_override: Option<String>,
+    ) -> Self {
+        Self {
+            dir,
+            did_resolver,
+            http_client: reqwest::Client::new(),
+            pds_override,
+        }
+    }
+
+    /// The bytes of `author_did`'s blob `cid`, from the cache if they're there
+    pub async fn get(&self, author_did: &str, cid: &str) -> Result<Vec<u8>, BlobError> {
+        // Cids are base32, so this also keeps the path inside `dir`
+        if cid.is_empty() || !cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(BlobError::BadCid(cid.to_string()));
+        }
+        let path = self.dir.join(cid);
+        if let Ok(data) = tokio::fs::read(&path).await {
+            return Ok(data);
+        }
+        let data = self.fetch(author_did, cid).await?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Written aside then renamed, so a concurrent read never sees half a blob
+        let partial = self.dir.join(format!("{cid}.partial"));
+        tokio::fs::write(&partial, &data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(data)
+    }
+
+    async fn fetch(&self, author_did: &str, cid: &str) -> Result<Vec<u8>, BlobError> {
+        let base_uri = match &self.pds_override {
+            Some(base_uri) => base_uri.clone(),
+            None => {
+                let did = Did::new(author_did.to_string())
+                    .map_err(|e| BlobError::Resolve(author_did.to_string(), e.to_string()))?;
+                self.did_resolver
+                    .resolve(&did)
+                    .await
+                    .map_err(|e| BlobError::Resolve(author_did.to_string(), e.to_string()))?
+                    .get_pds_endpoint()
+                    .ok_or_else(|| BlobError::NoPds(author_did.to_string()))?
+            }
+        };
+        let fetch_error = |e: reqwest::Error| BlobError::Fetch(e.to_string());
+        let mut response = self
+            .http_client
+            .get(format!(
+                "{base_uri}/xrpc/com.atproto.sync.getBlob?did={author_did}&cid={cid}"
+            ))
+            .send()
+            .await
+            .map_err(fetch_error)?;
+        if !response.status().is_success() {
+            return Err(BlobError::Fetch(format!(
+                "{} from {base_uri}",
+                response.status()
+            )));
+        }
+        let too_large = || BlobError::TooLarge(cid.to_string());
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_BLOB_SIZE as u64)
+        {
+            return Err(too_large());
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(fetch_error)? {
+            if data.len() + chunk.len() > MAX_BLOB_SIZE {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if self::cid(CODEC_RAW, &data) != cid {
+            return Err(BlobError::Mismatch(cid.to_string()));
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,206 @@
+//! Cloze deletions: card text like `Je {{c1::suis}} étudiant`, where each numbered
+//! deletion is hidden in turn. One cloze card is as many review items as it has distinct
+//! numbers; deletions sharing a number are hidden together. A deletion can carry a hint,
+//! `{{c1::suis::être}}`, shown in its place.
+use crate::i18n::Message;
+use crate::rich::FORMAT_MARKDOWN;
+use thiserror::Error;
+
+/// The `kind` of a plain front/back card, and of every card from before there were kinds
+pub const KIND_BASIC: &str = "basic";
+/// The `kind` of a card whose front text has cloze deletions in it
+pub const KIND_CLOZE: &str = "cloze";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ClozeError {
+    #[error("A cloze deletion isn't closed with }}}}")]
+    Unclosed,
+    #[error("Cloze deletions look like {{{{c1::answer}}}} or {{{{c1::answer::hint}}}}")]
+    Malformed,
+    #[error("Cloze deletion c{0} is empty")]
+    Empty(u32),
+    #[error("Cloze text needs at least one deletion, like {{{{c1::answer}}}}")]
+    NoDeletions,
+}
+
+impl ClozeError {
+    /// What's wrong, to show whoever wrote the text
+    pub fn message(&self) -> Message {
+        match self {
+            ClozeError::Unclosed => Message::new("invalid-cloze-unclosed"),
+            ClozeError::Malformed => Message::new("invalid-cloze-malformed"),
+            ClozeError::Empty(ordinal) => {
+                Message::new("invalid-cloze-empty").arg("ordinal", ordinal)
+            }
+            ClozeError::NoDeletions => Message::new("invalid-cloze-none"),
+        }
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Deletion {
+        ordinal: u32,
+        answer: &'a str,
+        hint: Option<&'a str>,
+    },
+}
+
+fn parse(text: &str) -> Result<Vec<Segment<'_>>, ClozeError> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or(ClozeError::Unclosed)?;
+        let (number, body) = after[..end].split_once("::").ok_or(ClozeError::Malformed)?;
+        let ordinal = number
+            .strip_prefix(['c', 'C'])
+            .and_then(|n| n.parse().ok())
+            .filter(|n| *n > 0)
+            .ok_or(ClozeError::Malformed)?;
+        let (answer, hint) = match body.split_once("::") {
+            Some((answer, hint)) => (answer, Some(hint)),
+            None => (body, None),
+        };
+        if answer.trim().is_empty() {
+            return Err(ClozeError::Empty(ordinal));
+        }
+        segments.push(Segment::Deletion {
+            ordinal,
+            answer,
+            hint,
+        });
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+/// One thing to review from a cloze card
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClozeItem {
+    pub ordinal: u32,
+    /// The text with this item's deletions hidden and every other one filled in
+    pub prompt: String,
+    /// What was hidden, in order, joined with `, `
+    pub answer: String,
+}
+
+/// Cloze `text` with every deletion filled in, as it reads once it's all been answered.
+/// Text that doesn't parse is left as it is.
+pub fn filled(text: &str) -> String {
+    match parse(text) {
+        Ok(segments) => segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => *text,
+                Segment::Deletion { answer, .. } => *answer,
+            })
+            .collect(),
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Expands cloze `text` into its review items, in order of their numbers
+pub fn review_items(text: &str) -> Result<Vec<ClozeItem>, ClozeError> {
+    let segments = parse(text)?;
+    let mut ordinals: Vec<u32> = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Deletion { ordinal, .. } => Some(*ordinal),
+            Segment::Text(_) => None,
+        })
+        .collect();
+    if ordinals.is_empty() {
+        return Err(ClozeError::NoDeletions);
+    }
+    ordinals.sort_unstable();
+    ordinals.dedup();
+    Ok(ordinals
+        .into_iter()
+        .map(|hidden| ClozeItem {
+            ordinal: hidden,
+            prompt: prompt(&segments, hidden, "["),
+            answer: segments
+                .iter()
+                .filter_map(|segment| match segment {
+                    Segment::Deletion {
+                        ordinal, answer, ..
+                    } if *ordinal == hidden => Some(*answer),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        })
+        .collect())
+}
+
+/// The prompt of item `hidden` of cloze `text` as card text in `format`. In Markdown the
+/// blank's bracket is escaped, so it isn't read as the reading of the word before it.
+pub fn prompt_in(text: &str, hidden: u32, format: &str) -> String {
+    let open = if format == FORMAT_MARKDOWN {
+        "\\["
+    } else {
+        "["
+    };
+    parse(text)
+        .map(|segments| prompt(&segments, hidden, open))
+        .unwrap_or_default()
+}
+
+fn prompt(segments: &[Segment], hidden: u32, open: &str) -> String {
+    let mut prompt = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => prompt.push_str(text),
+            Segment::Deletion { ordinal, hint, .. } if *ordinal == hidden => {
+                prompt.push_str(&format!("{open}{}]", hint.unwrap_or("…")));
+            }
+            Segment::Deletion { answer, .. } => prompt.push_str(answer),
+        }
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rich::{FORMAT_PLAIN, to_html};
+
+    #[test]
+    fn items_hide_one_number_at_a_time() {
+        let items = review_items("{{c1::Je}} {{c2::suis::être}} {{c1::étudiant}}").unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].prompt, "[…] suis […]");
+        assert_eq!(items[0].answer, "Je, étudiant");
+        assert_eq!(items[1].prompt, "Je [être] étudiant");
+        assert_eq!(prompt_in("{{c1::Je}} suis", 1, FORMAT_PLAIN), "[…] suis");
+    }
+
+    #[test]
+    fn blanks_in_markdown_are_not_ruby() {
+        for (text, blank) in [
+            ("日本語{{c1::です}}", "[…]"),
+            ("日本語{{c1::です::copula}}", "[copula]"),
+            ("word{{c1::s}}", "[…]"),
+        ] {
+            let html = to_html(&prompt_in(text, 1, FORMAT_MARKDOWN), FORMAT_MARKDOWN);
+            assert!(!html.contains("<ruby>"), "{html}");
+            assert!(html.contains(blank), "{html}");
+        }
+        let html = to_html(
+            &prompt_in("漢字[かんじ]{{c1::です}}", 1, FORMAT_MARKDOWN),
+            FORMAT_MARKDOWN,
+        );
+        assert!(
+            html.contains("<ruby>漢字<rp>(</rp><rt>かんじ</rt>"),
+            "{html}"
+        );
+        assert!(html.contains("</ruby>[…]"), "{html}");
+    }
+}
@@ -0,0 +1,71 @@
+//! Collections: stacks grouped in order, like a textbook with one stack per chapter. A
+//! collection only points at its stacks, which can be anyone's, so it's studied a stack at
+//! a time, and copying it copies each of them.
+use crate::i18n::Message;
+use crate::lexicons::xyz::flatshcards::Stack;
+use atrium_api::types::Collection as _;
+use serde::{Deserialize, Deserializer};
+
+/// Stacks in one collection, at most
+pub const MAX_STACKS: usize = 100;
+/// Characters in a collection's description, at most
+pub const MAX_DESCRIPTION_LENGTH: usize = 300;
+
+/// Whether `uri` is where a stack record would be
+pub fn is_stack_uri(uri: &str) -> bool {
+    uri.strip_prefix("at://")
+        .and_then(|rest| rest.split_once('/'))
+        .and_then(|(did, rest)| {
+            rest.split_once('/')
+                .map(|(nsid, rkey)| !did.is_empty() && nsid == Stack::NSID && !rkey.is_empty())
+        })
+        .unwrap_or_default()
+}
+
+/// Why `stacks` can't be saved as a collection's, if they can't
+pub fn validate(stacks: &[String]) -> Option<Message> {
+    if let Some(uri) = stacks.iter().find(|uri| !is_stack_uri(uri.trim())) {
+        Some(Message::new("invalid-stack-uri").arg("uri", uri))
+    } else if normalize(stacks).len() > MAX_STACKS {
+        Some(Message::new("invalid-stack-count").arg("max", MAX_STACKS))
+    } else {
+        None
+    }
+}
+
+/// `stacks` as they're stored: trimmed, in order, with anything that isn't a stack and
+/// any repeats dropped. Records from elsewhere can break the rules, so anything past
+/// [`MAX_STACKS`] is dropped too.
+pub fn normalize(stacks: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for uri in stacks.iter().map(|uri| uri.trim()) {
+        if is_stack_uri(uri) && !out.iter().any(|u| u == uri) {
+            out.push(uri.to_string());
+        }
+    }
+    out.truncate(MAX_STACKS);
+    out
+}
+
+/// A collection's description as it's stored, with nothing for a blank one
+pub fn description(description: &str) -> Option<String> {
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// Record uris as either a list, from the JSON API, or one a line, from an editor: a
+/// collection's stacks, or a stack's cards in their new order
+pub fn deserialize_uris<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Uris {
+        List(Vec<String>),
+        Text(String),
+    }
+    Ok(match Uris::deserialize(deserializer)? {
+        Uris::List(uris) => uris,
+        Uris::Text(text) => text.split_whitespace().map(str::to_string).collect(),
+    })
+}
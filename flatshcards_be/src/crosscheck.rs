@@ -0,0 +1,205 @@
+//! Compares what we've indexed with what's actually in each author's repo, to catch
+//! events the ingester missed or mangled. It only reports; the firehose, or a replay,
+//! is still what fixes rows.
+use crate::db::{RecordVersion, Repo};
+use crate::lexicons::xyz::flatshcards::{self, Card, Stack};
+use atrium_api::{
+    client::AtpServiceClient,
+    com::atproto::repo::list_records,
+    types::{Collection, string::Did},
+    xrpc::{
+        HttpClient, XrpcClient,
+        http::{Request, Response},
+    },
+};
+use atrium_common::resolver::Resolver;
+use atrium_identity::did::CommonDidResolver;
+use atrium_oauth::DefaultHttpClient;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CrossCheckError {
+    #[error("Database error: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("Could not resolve {0}: {1}")]
+    Resolve(String, String),
+    #[error("{0} has no PDS")]
+    NoPds(String),
+    #[error("Error listing records: {0}")]
+    Xrpc(String),
+}
+
+/// How one record differs between here and the author's PDS
+#[derive(Error, Debug)]
+pub enum Discrepancy {
+    #[error("{0} is on the PDS, but was never indexed")]
+    Missing(String),
+    #[error("{0} is indexed, but gone from the PDS")]
+    Stale(String),
+    #[error("{0} is indexed at a different cid from the PDS")]
+    Changed(String),
+}
+
+/// Unauthenticated XRPC calls to one PDS; `listRecords` is public
+struct PublicClient {
+    base_uri: String,
+    http_client: DefaultHttpClient,
+}
+
+impl HttpClient for PublicClient {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.http_client.send_http(request).await
+    }
+}
+
+impl XrpcClient for PublicClient {
+    fn base_uri(&self) -> String {
+        self.base_uri.clone()
+    }
+}
+
+pub struct CrossCheck {
+    repo: Arc<dyn Repo>,
+    did_resolver: Arc<CommonDidResolver<DefaultHttpClient>>,
+}
+
+impl CrossCheck {
+    pub fn new(
+        repo: Arc<dyn Repo>,
+        did_resolver: Arc<CommonDidResolver<DefaultHttpClient>>,
+    ) -> Self {
+        Self { repo, did_resolver }
+    }
+
+    /// Checks every author we've indexed, logging what differs
+    pub async fn run(&self) {
+        let authors = match self.repo.indexed_authors().await {
+            Ok(authors) => authors,
+            Err(err) => {
+                error!("cross-check could not list authors: {err}");
+                return;
+            }
+        };
+        let mut differing = 0;
+        for author in &authors {
+            match self.check_author(author).await {
+                Ok(discrepancies) => {
+                    for discrepancy in &discrepancies {
+                        warn!("cross-check {author}: {discrepancy}");
+                    }
+                    if !discrepancies.is_empty() {
+                        differing += 1;
+                    }
+                }
+                Err(err) => error!("cross-check {author}: {err}"),
+            }
+        }
+        info!(
+            "cross-check done, {differing} of {} authors differ from their PDS",
+            authors.len()
+        );
+    }
+
+    pub async fn check_author(
+        &self,
+        author_did: &str,
+    ) -> Result<Vec<Discrepancy>, CrossCheckError> {
+        let did = Did::new(author_did.to_string())
+            .map_err(|e| CrossCheckError::Resolve(author_did.to_string(), e.to_string()))?;
+        let base_uri = self
+            .did_resolver
+            .resolve(&did)
+            .await
+            .map_err(|e| CrossCheckError::Resolve(author_did.to_string(), e.to_string()))?
+            .get_pds_endpoint()
+            .ok_or_else(|| CrossCheckError::NoPds(author_did.to_string()))?;
+        let client = AtpServiceClient::new(PublicClient {
+            base_uri,
+            http_client: DefaultHttpClient::default(),
+        });
+        let mut discrepancies = compare(
+            self.repo.stack_versions(author_did).await?,
+            remote_versions(&client, &did, Stack::NSID).await?,
+        );
+        discrepancies.extend(compare(
+            self.repo.card_versions(author_did).await?,
+            remote_versions(&client, &did, Card::NSID).await?,
+        ));
+        discrepancies.extend(compare(
+            self.repo.collection_versions(author_did).await?,
+            remote_versions(&client, &did, flatshcards::Collection::NSID).await?,
+        ));
+        Ok(discrepancies)
+    }
+}
+
+/// Every record in `collection` of `did`'s repo, as uri => cid
+async fn remote_versions(
+    client: &AtpServiceClient<PublicClient>,
+    did: &Did,
+    collection: &str,
+) -> Result<HashMap<String, String>, CrossCheckError> {
+    let mut versions = HashMap::new();
+    let mut cursor = None;
+    loop {
+        let output = client
+            .service
+            .com
+            .atproto
+            .repo
+            .list_records(
+                list_records::ParametersData {
+                    collection: collection
+                        .parse()
+                        .map_err(|e: &str| CrossCheckError::Xrpc(e.into()))?,
+                    cursor: cursor.take(),
+                    limit: 100.try_into().ok(),
+                    repo: did.clone().into(),
+                    reverse: None,
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| CrossCheckError::Xrpc(err.to_string()))?;
+        let page_len = output.records.len();
+        for record in &output.records {
+            versions.insert(record.uri.clone(), record.cid.as_ref().to_string());
+        }
+        match output.data.cursor {
+            Some(next) if page_len > 0 => cursor = Some(next),
+            _ => return Ok(versions),
+        }
+    }
+}
+
+fn compare(local: Vec<RecordVersion>, mut remote: HashMap<String, String>) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    for RecordVersion { uri, cid } in local {
+        match (remote.remove(&uri), cid) {
+            (None, _) => discrepancies.push(Discrepancy::Stale(uri)),
+            (Some(remote_cid), Some(cid)) if remote_cid != cid => {
+                discrepancies.push(Discrepancy::Changed(uri))
+            }
+            // No cid locally means we wrote it ourselves and the firehose hasn't caught up
+            _ => {}
+        }
+    }
+    discrepancies.extend(remote.into_keys().map(Discrepancy::Missing));
+    discrepancies
+}
+
+/// Runs the cross-check every `every`, forever
+pub async fn start_cross_check(check: CrossCheck, every: Duration) {
+    let mut interval = actix_web::rt::time::interval(every);
+    loop {
+        interval.tick().await;
+        check.run().await;
+    }
+}
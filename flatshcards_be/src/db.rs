@@ -1,547 +0,0 @@
-use chrono::{DateTime, Utc};
-use sqlx::{
-    FromRow, Row,
-    postgres::{PgPool, Postgres},
-};
-// use rusqlite::types::Type;
-use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
-
-pub async fn create_tables_in_database(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "
-CREATE TABLE IF NOT EXISTS stack (
-  uri TEXT PRIMARY KEY,
-  author_did TEXT NOT NULL,
-  back_lang VARCHAR(2),
-  front_lang VARCHAR(2),
-  label VARCHAR(100),
-  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
-  indexed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
-)",
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        "CREATE UNIQUE INDEX IF NOT EXISTS stack_author_label ON stack (author_did, label)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS stack_author ON stack (author_did)")
-        .execute(pool)
-        .await?;
-
-    sqlx::query(
-        "
-CREATE TABLE IF NOT EXISTS card (
-  uri TEXT PRIMARY KEY,
-  author_did TEXT NOT NULL,
-  back_lang VARCHAR (2) NOT NULL,
-  back_text TEXT NOT NULL,
-  front_lang VARCHAR (2) NOT NULL,
-  front_text TEXT NOT NULL,
-  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
-  indexed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
-  stack_id TEXT REFERENCES stack(uri) ON DELETE CASCADE
-)
-",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS card_langs ON card (front_lang, back_lang);")
-        .execute(pool)
-        .await?;
-    sqlx::query(
-        "
-CREATE TABLE IF NOT EXISTS auth_state (
-  key TEXT PRIMARY KEY,
-  state TEXT NOT NULL
-)
-",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "
-CREATE TABLE IF NOT EXISTS auth_session (
-  key TEXT PRIMARY KEY,
-  session TEXT NOT NULL
-);
-",
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct DbStack {
-    pub uri: String,
-    pub author_did: String,
-    pub back_lang: Option<String>,
-    pub front_lang: Option<String>,
-    pub label: String,
-    pub created_at: DateTime<Utc>,
-    pub indexed_at: DateTime<Utc>,
-}
-
-impl DbStack {
-    pub fn new(
-        StackArgs {
-            uri,
-            author_did,
-            back_lang,
-            front_lang,
-            label,
-            indexed_at,
-        }: StackArgs,
-    ) -> Self {
-        let ia = indexed_at.unwrap_or_else(Utc::now);
-        Self {
-            uri,
-            author_did,
-            back_lang,
-            front_lang,
-            label,
-            created_at: ia,
-            indexed_at: ia,
-        }
-    }
-    pub async fn save(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        self.save_with_executor(pool).await
-    }
-    async fn save_with_executor<'a, Ex>(&self, executor: Ex) -> Result<(), sqlx::Error>
-    where
-        Ex: sqlx::Executor<'a, Database = Postgres>,
-    {
-        sqlx::query(
-            "
-      INSERT INTO stack (uri, author_did, back_lang, front_lang, label, created_at, indexed_at)
-      VALUES ($1, $2, $3, $4, $5, $6, $7);
-    ",
-        )
-        .bind(&self.uri)
-        .bind(&self.author_did)
-        .bind(&self.back_lang)
-        .bind(&self.front_lang)
-        .bind(self.created_at)
-        .bind(self.indexed_at)
-        .execute(executor)
-        .await?;
-        Ok(())
-    }
-    pub async fn upsert(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "
-      INSERT INTO stack (uri, author_did, back_lang, front_lang, label, created_at, indexed_at)
-      VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT UPDATE;
-    ",
-        )
-        .bind(&self.uri)
-        .bind(&self.author_did)
-        .bind(&self.back_lang)
-        .bind(&self.front_lang)
-        .bind(self.created_at)
-        .bind(self.indexed_at)
-        .execute(pool)
-        .await?;
-        Ok(())
-    }
-    pub async fn delete_by_uri(uri: &str, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM stack WHERE uri = $1")
-            .bind(uri)
-            .execute(pool)
-            .await?;
-        Ok(())
-    }
-    pub async fn is_owned_by(
-        author_did: &str,
-        stack_uri: &str,
-        pool: &PgPool,
-    ) -> Result<bool, sqlx::Error> {
-        sqlx::query(
-            r#"SELECT EXISTS(SELECT 1 FROM stack WHERE author_did = $1 AND uri = $2) AS "exists"#,
-        )
-        .bind(author_did)
-        .bind(stack_uri)
-        .fetch_one(pool)
-        .await
-        .map(|r| r.get("exists"))
-    }
-    pub async fn get_owned_by(
-        author_did: &str,
-        stack_uri: &str,
-        pool: &PgPool,
-    ) -> Result<Option<StackDetails>, sqlx::Error> {
-        sqlx::query_as(
-        "SELECT uri, back_lang, front_lang, label FROM stack WHERE author_did = $1 AND uri = $2 LIMIT 1"
-        ).bind(author_did).bind(stack_uri).fetch_optional(pool).await
-    }
-    pub async fn get_clone_data(
-        stack_uri: &str,
-        pool: &PgPool,
-    ) -> Result<Option<StackCloneData>, sqlx::Error> {
-        sqlx::query_as(
-            "
-        SELECT back_lang, front_lang, label FROM stack WHERE uri = $1 LIMIT 1
-        ",
-        )
-        .bind(stack_uri)
-        .fetch_optional(pool)
-        .await
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StackArgs {
-    pub uri: String,
-    pub author_did: String,
-    pub back_lang: Option<String>,
-    pub front_lang: Option<String>,
-    pub label: String,
-    pub indexed_at: Option<DateTime<Utc>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StackUpdateArgs {
-    pub uri: String,
-    pub author_did: String,
-    pub back_lang: Option<String>,
-    pub front_lang: Option<String>,
-    pub label: String,
-}
-
-impl StackUpdateArgs {
-    pub async fn update_owned(&self, pool: &PgPool) -> Result<Option<StackDetails>, sqlx::Error> {
-        let res = sqlx::query_as(
-            "
-    UPDATE stack SET back_lang = $3, front_lang = $4, label = $5 WHERE uri = $1 AND author_did = $2
-    RETURNING uri, back_lang, front_lang, label",
-        )
-        .bind(&self.uri)
-        .bind(&self.author_did)
-        .bind(&self.back_lang)
-        .bind(&self.front_lang)
-        .bind(&self.label)
-        .fetch_optional(pool)
-        .await?;
-        Ok(res)
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct StackDetails {
-    pub uri: String,
-    pub back_lang: Option<String>,
-    pub front_lang: Option<String>,
-    pub label: String,
-}
-
-impl StackDetails {
-    pub async fn user_stacks(did: &str, pool: &PgPool) -> Result<Vec<Self>, sqlx::Error> {
-        sqlx::query_as(
-            "
-SELECT uri, back_lang, front_lang, label FROM stack WHERE author_did = $1",
-        )
-        .bind(did)
-        .fetch_all(pool)
-        .await
-    }
-    pub fn back_lang_selected(&self, lang: &str) -> bool {
-        if let Some(ref l) = self.back_lang {
-            l == lang
-        } else {
-            lang.is_empty()
-        }
-    }
-    pub fn front_lang_selected(&self, lang: &str) -> bool {
-        if let Some(ref l) = self.front_lang {
-            l == lang
-        } else {
-            lang.is_empty()
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct StackCloneData {
-    pub back_lang: Option<String>,
-    pub front_lang: Option<String>,
-    pub label: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct DbCard {
-    pub uri: String,
-    pub author_did: String,
-    pub back_lang: String,
-    pub back_text: String,
-    pub front_lang: String,
-    pub front_text: String,
-    pub created_at: DateTime<Utc>,
-    pub indexed_at: DateTime<Utc>,
-    pub stack_id: String,
-}
-
-impl DbCard {
-    pub fn new(
-        CardArgs {
-            uri,
-            author_did,
-            back_lang,
-            back_text,
-            front_lang,
-            front_text,
-            indexed_at,
-            stack_id,
-        }: CardArgs,
-    ) -> Self {
-        let ia = indexed_at.unwrap_or_else(Utc::now);
-        Self {
-            uri,
-            author_did,
-            back_lang,
-            back_text,
-            front_lang,
-            front_text,
-            created_at: ia,
-            indexed_at: ia,
-            stack_id,
-        }
-    }
-    async fn save_with_executor<'a, Ex>(&self, executor: Ex) -> Result<(), sqlx::Error>
-    where
-        Ex: sqlx::Executor<'a, Database = Postgres>,
-    {
-        sqlx::query(
-            "
-      INSERT INTO card (uri, author_did, back_lang, back_text, front_lang, front_text, created_at, indexed_at, stack_id)
-      VALUES ($1, $2, $3, $4, $5, $6, $7, $8);
-    ",
-        )
-        .bind(&self.uri)
-        .bind(&self.author_did)
-        .bind(&self.back_lang)
-        .bind(&self.back_text)
-        .bind(&self.front_lang)
-        .bind(&self.front_text)
-        .bind(self.created_at)
-        .bind(self.indexed_at)
-        .bind(&self.stack_id)
-        .execute(executor)
-        .await?;
-        Ok(())
-    }
-    pub async fn save(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        self.save_with_executor(pool).await
-    }
-    pub async fn upsert(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "
-      INSERT INTO card (uri, author_did, back_lang, back_text, front_lang, front_text, created_at, indexed_at, stack_id)
-      VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT DO UPDATE;
-    ",
-        )
-        .bind(&self.uri)
-        .bind(&self.author_did)
-        .bind(&self.back_lang)
-        .bind(&self.back_text)
-        .bind(&self.front_lang)
-        .bind(&self.front_text)
-        .bind(self.created_at)
-        .bind(self.indexed_at)
-        .bind(&self.stack_id)
-        .execute(pool)
-        .await?;
-        Ok(())
-    }
-    pub async fn delete_by_uri(uri: &str, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM card WHERE uri = $1")
-            .bind(uri)
-            .execute(pool)
-            .await?;
-        Ok(())
-    }
-    pub async fn is_owned_by(
-        author_did: &str,
-        card_uri: &str,
-        pool: &PgPool,
-    ) -> Result<bool, sqlx::Error> {
-        sqlx::query(
-            r#"SELECT EXISTS(SELECT 1 FROM card WHERE author_did = $1 AND uri = $2) AS "exists"#,
-        )
-        .bind(author_did)
-        .bind(card_uri)
-        .fetch_one(pool)
-        .await
-        .map(|r| r.get("exists"))
-    }
-    pub async fn get_clone_data(
-        stack_uri: &str,
-        pool: &PgPool,
-    ) -> Result<Vec<CardCloneData>, sqlx::Error> {
-        let res = sqlx::query_as(
-            "
-        SELECT back_lang, back_text, front_lang, front_text FROM card WHERE stack_id = $1
-        ",
-        )
-        .bind(stack_uri)
-        .fetch_all(pool)
-        .await?;
-        Ok(res)
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CardArgs {
-    pub uri: String,
-    pub author_did: String,
-    pub back_lang: String,
-    pub back_text: String,
-    pub front_lang: String,
-    pub front_text: String,
-    pub indexed_at: Option<DateTime<Utc>>,
-    pub stack_id: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct DisplayCard {
-    pub uri: String,
-    pub back_lang: String,
-    pub back_text: String,
-    pub front_lang: String,
-    pub front_text: String,
-}
-
-impl DisplayCard {
-    pub async fn stack_cards(stack_uri: &str, pool: &PgPool) -> Result<Vec<Self>, sqlx::Error> {
-        sqlx::query_as(
-            "
-SELECT uri, back_lang, back_text, front_lang, front_text FROM card WHERE stack_uri = $1
-",
-        )
-        .bind(stack_uri)
-        .fetch_all(pool)
-        .await
-    }
-    pub fn front_lang_selected(&self, lang: &str) -> bool {
-        self.front_text == lang
-    }
-    pub fn back_lang_selected(&self, lang: &str) -> bool {
-        self.back_text == lang
-    }
-}
-
-impl From<DbCard> for DisplayCard {
-    fn from(val: DbCard) -> Self {
-        Self {
-            uri: val.uri,
-            back_lang: val.back_lang,
-            back_text: val.back_text,
-            front_lang: val.front_lang,
-            front_text: val.front_text,
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct CardCloneData {
-    pub back_lang: String,
-    pub back_text: String,
-    pub front_lang: String,
-    pub front_text: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct AuthSession {
-    pub key: String,
-    pub session: String,
-}
-
-impl AuthSession {
-    pub fn new<V: Serialize>(key: String, session: V) -> Self {
-        let session = serde_json::to_string(&session).unwrap();
-        Self { key, session }
-    }
-    pub async fn get_by_did(did: &str, pool: &PgPool) -> Result<Option<Self>, sqlx::Error> {
-        sqlx::query_as(
-            "
-SELECT key, session FROM auth_session WHERE key = $1 LIMIT 1
-",
-        )
-        .bind(did)
-        .fetch_optional(pool)
-        .await
-    }
-    pub async fn save_or_update(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "INSERT INTO auth_session (key, session) VALUES ($1, $2) ON CONFLICT DO NOTHING",
-        )
-        .bind(&self.key)
-        .bind(&self.session)
-        .execute(pool)
-        .await?;
-        Ok(())
-    }
-    pub async fn delete_all(pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM auth_session")
-            .execute(pool)
-            .await?;
-        Ok(())
-    }
-    pub async fn delete_by_did(did: &str, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM auth_session WHERE key = $1")
-            .bind(did)
-            .execute(pool)
-            .await?;
-        Ok(())
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct AuthState {
-    pub key: String,
-    pub state: String,
-}
-
-impl AuthState {
-    pub fn new<V: Serialize>(key: String, state: V) -> Self {
-        let state = serde_json::to_string(&state).unwrap();
-        Self { key, state }
-    }
-    pub async fn save_or_update(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("INSERT INTO auth_state (key, state) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-            .bind(&self.key)
-            .bind(&self.state)
-            .execute(pool)
-            .await?;
-        Ok(())
-    }
-    pub async fn delete_all(pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM auth_state").execute(pool).await?;
-        Ok(())
-    }
-    pub async fn get_by_key(key: &str, pool: &PgPool) -> Result<Option<Self>, sqlx::Error> {
-        let res = sqlx::query_as(
-            "
-        SELECT key, state FROM auth_state WHERE key = $1 LIMIT 1
-                ",
-        )
-        .bind(key)
-        .fetch_optional(pool)
-        .await?;
-        Ok(res)
-    }
-    pub async fn delete_by_key(key: &str, pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "
-DELETE FROM auth_state WHERE key = $1
-",
-        )
-        .bind(key)
-        .execute(pool)
-        .await?;
-        Ok(())
-    }
-}
@@ -0,0 +1,827 @@
+use super::{
+    ApiToken, AuthRepo, AuthSession, AuthState, CardCloneData, CardRepo, CardUpdateArgs,
+    CollectionDetails, CollectionRepo, CollectionUpdateArgs, DbCard, DbCollection, DbStack,
+    DisplayCard, GameRepo, MatchScore, RecordVersion, Review, ReviewCounts, ReviewLimits,
+    ReviewRepo, ReviewState, StackCloneData, StackDetails, StackRepo, StackSearch, StackUpdateArgs,
+    StackedReview, StackedReviewState,
+};
+use crate::blobs::blob_cid;
+use crate::tags::{has_tag, tag_key};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex, MutexGuard};
+
+/// Keeps every table in process. Nothing survives a restart.
+#[derive(Default)]
+pub struct MemoryRepo {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    stacks: BTreeMap<String, DbStack>,
+    cards: BTreeMap<String, DbCard>,
+    collections: BTreeMap<String, DbCollection>,
+    auth_sessions: BTreeMap<String, AuthSession>,
+    auth_states: BTreeMap<String, AuthState>,
+    api_tokens: BTreeMap<String, ApiToken>,
+    /// Keyed by reviewer, card, direction and ordinal
+    review_states: BTreeMap<(String, String, String, i32), ReviewState>,
+    reviews: Vec<Review>,
+    review_limits: BTreeMap<String, ReviewLimits>,
+    /// Keyed by player, stack and pairs
+    match_scores: BTreeMap<(String, String, i32), MatchScore>,
+    /// Each tag as first spelled, keyed by [`tag_key`]
+    tags: BTreeMap<String, String>,
+    /// The latest rev each deleted record was deleted at
+    deleted: BTreeMap<String, String>,
+}
+
+impl Tables {
+    /// Remembers that the record at `uri` was deleted at `rev`, if we know when
+    fn tombstone(&mut self, uri: &str, rev: Option<&str>) {
+        if let Some(rev) = rev {
+            let deleted = self.deleted.entry(uri.to_string()).or_default();
+            if rev > deleted.as_str() {
+                *deleted = rev.to_string();
+            }
+        }
+    }
+
+    /// Whether a version of the record at `uri` from `rev` is from before it was deleted
+    fn deleted_since(&self, uri: &str, rev: &Option<String>) -> bool {
+        rev.as_ref()
+            .zip(self.deleted.get(uri))
+            .is_some_and(|(rev, deleted)| rev <= deleted)
+    }
+
+    /// `tags` spelled as they were first used, in the order the Postgres queries give
+    fn spell(&mut self, tags: &[String]) -> Vec<String> {
+        let mut spelled: Vec<String> = tags
+            .iter()
+            .map(|tag| {
+                self.tags
+                    .entry(tag_key(tag))
+                    .or_insert_with(|| tag.clone())
+                    .clone()
+            })
+            .collect();
+        spelled.sort_by_key(|tag| tag.to_lowercase());
+        spelled
+    }
+}
+
+impl MemoryRepo {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap()
+    }
+    /// The stack's cards, in the order `stack_cards` gives them
+    fn stack_cards_in_order(&self, stack_uri: &str) -> Vec<DbCard> {
+        let mut cards: Vec<DbCard> = self
+            .tables()
+            .cards
+            .values()
+            .filter(|c| c.stack_id == stack_uri)
+            .cloned()
+            .collect();
+        cards.sort_by(|a, b| {
+            (a.position.is_none(), a.position, a.created_at, &a.uri).cmp(&(
+                b.position.is_none(),
+                b.position,
+                b.created_at,
+                &b.uri,
+            ))
+        });
+        cards
+    }
+}
+
+fn duplicate_key(table: &str, key: &str) -> sqlx::Error {
+    sqlx::Error::Protocol(format!("duplicate key {key} in {table}"))
+}
+
+/// Whether an incoming record should replace the stored one, mirroring the `WHERE` on the
+/// Postgres upserts: not if it's from an earlier rev (revs are TIDs, so they order as
+/// strings), and not if it's the same record.
+fn supersedes(
+    stored_rev: &Option<String>,
+    stored_cid: &Option<String>,
+    rev: &Option<String>,
+    cid: &Option<String>,
+) -> bool {
+    match (stored_rev, rev) {
+        (Some(stored_rev), Some(rev)) if rev < stored_rev => false,
+        _ => stored_cid.is_none() || stored_cid != cid,
+    }
+}
+
+#[async_trait]
+impl StackRepo for MemoryRepo {
+    async fn save_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.stacks.contains_key(&stack.uri) {
+            return Err(duplicate_key("stack", &stack.uri));
+        }
+        let tags = tables.spell(&stack.tags);
+        tables.stacks.insert(
+            stack.uri.clone(),
+            DbStack {
+                tags,
+                ..stack.clone()
+            },
+        );
+        Ok(())
+    }
+    async fn upsert_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.deleted_since(&stack.uri, &stack.rev)
+            || tables
+                .stacks
+                .get(&stack.uri)
+                .is_some_and(|stored| !supersedes(&stored.rev, &stored.cid, &stack.rev, &stack.cid))
+        {
+            return Ok(());
+        }
+        let tags = tables.spell(&stack.tags);
+        tables.stacks.insert(
+            stack.uri.clone(),
+            DbStack {
+                tags,
+                ..stack.clone()
+            },
+        );
+        Ok(())
+    }
+    async fn delete_stack(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        tables.tombstone(uri, rev);
+        tables.stacks.remove(uri);
+        tables.cards.retain(|_, card| card.stack_id != uri);
+        Ok(())
+    }
+    async fn stack_is_owned_by(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .tables()
+            .stacks
+            .get(stack_uri)
+            .is_some_and(|s| s.author_did == author_did))
+    }
+    async fn get_stack(&self, stack_uri: &str) -> Result<Option<StackDetails>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .stacks
+            .get(stack_uri)
+            .cloned()
+            .map(StackDetails::from))
+    }
+    async fn get_stacks(&self, stack_uris: &[String]) -> Result<Vec<StackDetails>, sqlx::Error> {
+        let tables = self.tables();
+        Ok(stack_uris
+            .iter()
+            .filter_map(|uri| tables.stacks.get(uri))
+            .cloned()
+            .map(StackDetails::from)
+            .collect())
+    }
+    async fn get_owned_stack(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<Option<StackDetails>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .stacks
+            .get(stack_uri)
+            .filter(|s| s.author_did == author_did)
+            .cloned()
+            .map(StackDetails::from))
+    }
+    async fn stack_clone_data(
+        &self,
+        stack_uri: &str,
+    ) -> Result<Option<StackCloneData>, sqlx::Error> {
+        Ok(self.tables().stacks.get(stack_uri).map(|s| StackCloneData {
+            back_lang: s.back_lang.clone(),
+            front_lang: s.front_lang.clone(),
+            label: s.label.clone(),
+            directions: s.directions.clone(),
+            description: s.description.clone(),
+            source: s.source.clone(),
+            license: s.license.clone(),
+            difficulty: s.difficulty.clone(),
+            cover: s.cover.clone(),
+            tags: s.tags.clone(),
+        }))
+    }
+    async fn update_owned_stack(
+        &self,
+        args: &StackUpdateArgs,
+    ) -> Result<Option<StackDetails>, sqlx::Error> {
+        let mut tables = self.tables();
+        let tags = tables.spell(&args.tags);
+        Ok(tables
+            .stacks
+            .get_mut(&args.uri)
+            .filter(|s| s.author_did == args.author_did)
+            .map(|s| {
+                s.back_lang = args.back_lang.clone();
+                s.front_lang = args.front_lang.clone();
+                s.label = args.label.clone();
+                s.directions = args.directions.clone();
+                s.description = args.description.clone();
+                s.source = args.source.clone();
+                s.license = args.license.clone();
+                s.difficulty = args.difficulty.clone();
+                s.cover = args.cover.clone();
+                s.tags = tags;
+                s.clone().into()
+            }))
+    }
+    async fn user_stacks(&self, did: &str) -> Result<Vec<StackDetails>, sqlx::Error> {
+        let mut stacks: Vec<StackDetails> = self
+            .tables()
+            .stacks
+            .values()
+            .filter(|s| s.author_did == did)
+            .cloned()
+            .map(StackDetails::from)
+            .collect();
+        stacks.sort_by_key(|s| s.label.to_lowercase());
+        Ok(stacks)
+    }
+    async fn indexed_authors(&self) -> Result<Vec<String>, sqlx::Error> {
+        let tables = self.tables();
+        let authors: BTreeSet<String> = tables
+            .stacks
+            .values()
+            .map(|s| s.author_did.clone())
+            .chain(tables.collections.values().map(|c| c.author_did.clone()))
+            .collect();
+        Ok(authors.into_iter().collect())
+    }
+    async fn stack_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .stacks
+            .values()
+            .filter(|s| s.author_did == author_did)
+            .map(|s| RecordVersion {
+                uri: s.uri.clone(),
+                cid: s.cid.clone(),
+            })
+            .collect())
+    }
+    async fn stack_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .tables()
+            .stacks
+            .values()
+            .filter(|s| s.author_did == author_did)
+            .filter_map(|s| s.cover.as_ref())
+            .any(|blob| blob_cid(blob) == cid))
+    }
+    async fn search_stacks(
+        &self,
+        search: &StackSearch,
+        limit: i64,
+    ) -> Result<Vec<StackDetails>, sqlx::Error> {
+        let tables = self.tables();
+        let text = search.text.to_lowercase();
+        let mut found: Vec<&DbStack> = tables
+            .stacks
+            .values()
+            .filter(|s| {
+                search
+                    .author_did
+                    .as_ref()
+                    .is_none_or(|did| &s.author_did == did)
+            })
+            .filter(|s| s.label.to_lowercase().contains(&text))
+            .filter(|s| {
+                search.tag.as_deref().is_none_or(|tag| {
+                    has_tag(&s.tags, tag)
+                        || tables
+                            .cards
+                            .values()
+                            .any(|c| c.stack_id == s.uri && has_tag(&c.tags, tag))
+                })
+            })
+            .collect();
+        found.sort_by_key(|s| std::cmp::Reverse(s.indexed_at));
+        Ok(found
+            .into_iter()
+            .take(limit.try_into().unwrap_or_default())
+            .cloned()
+            .map(StackDetails::from)
+            .collect())
+    }
+    async fn user_tags(&self, author_did: &str) -> Result<Vec<String>, sqlx::Error> {
+        let tables = self.tables();
+        let stack_tags = tables
+            .stacks
+            .values()
+            .filter(|s| s.author_did == author_did)
+            .flat_map(|s| &s.tags);
+        let card_tags = tables
+            .cards
+            .values()
+            .filter(|c| c.author_did == author_did)
+            .flat_map(|c| &c.tags);
+        let tags: BTreeMap<String, String> = stack_tags
+            .chain(card_tags)
+            .map(|tag| (tag.to_lowercase(), tag.clone()))
+            .collect();
+        Ok(tags.into_values().collect())
+    }
+}
+
+#[async_trait]
+impl CardRepo for MemoryRepo {
+    async fn save_card(&self, card: &DbCard) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.cards.contains_key(&card.uri) {
+            return Err(duplicate_key("card", &card.uri));
+        }
+        let tags = tables.spell(&card.tags);
+        tables.cards.insert(
+            card.uri.clone(),
+            DbCard {
+                tags,
+                ..card.clone()
+            },
+        );
+        Ok(())
+    }
+    async fn upsert_card(&self, card: &DbCard) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.deleted_since(&card.uri, &card.rev)
+            || tables
+                .cards
+                .get(&card.uri)
+                .is_some_and(|stored| !supersedes(&stored.rev, &stored.cid, &card.rev, &card.cid))
+        {
+            return Ok(());
+        }
+        let tags = tables.spell(&card.tags);
+        tables.cards.insert(
+            card.uri.clone(),
+            DbCard {
+                tags,
+                ..card.clone()
+            },
+        );
+        Ok(())
+    }
+    async fn delete_card(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        tables.tombstone(uri, rev);
+        tables.cards.remove(uri);
+        Ok(())
+    }
+    async fn card_is_owned_by(
+        &self,
+        author_did: &str,
+        card_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .tables()
+            .cards
+            .get(card_uri)
+            .is_some_and(|c| c.author_did == author_did))
+    }
+    async fn get_owned_card(
+        &self,
+        author_did: &str,
+        card_uri: &str,
+    ) -> Result<Option<DbCard>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .cards
+            .get(card_uri)
+            .filter(|c| c.author_did == author_did)
+            .cloned())
+    }
+    async fn card_clone_data(&self, stack_uri: &str) -> Result<Vec<CardCloneData>, sqlx::Error> {
+        Ok(self
+            .stack_cards_in_order(stack_uri)
+            .iter()
+            .map(CardCloneData::from)
+            .collect())
+    }
+    async fn update_owned_card(
+        &self,
+        args: &CardUpdateArgs,
+    ) -> Result<Option<DisplayCard>, sqlx::Error> {
+        let mut tables = self.tables();
+        let tags = tables.spell(&args.tags);
+        Ok(tables
+            .cards
+            .get_mut(&args.uri)
+            .filter(|c| c.author_did == args.author_did)
+            .map(|c| {
+                c.back_lang = args.back_lang.clone();
+                c.back_text = args.back_text.clone();
+                c.front_lang = args.front_lang.clone();
+                c.front_text = args.front_text.clone();
+                c.kind = args.kind.clone();
+                c.format = args.format.clone();
+                c.front_image = args.front_image.clone();
+                c.back_image = args.back_image.clone();
+                c.audio = args.audio.clone();
+                c.tags = tags;
+                c.position = args.position;
+                c.stack_id = args.stack_id.clone();
+                c.clone().into()
+            }))
+    }
+    async fn stack_cards(&self, stack_uri: &str) -> Result<Vec<DisplayCard>, sqlx::Error> {
+        Ok(self
+            .stack_cards_in_order(stack_uri)
+            .into_iter()
+            .map(DisplayCard::from)
+            .collect())
+    }
+    async fn lang_pair_cards(
+        &self,
+        front_lang: &str,
+        back_lang: &str,
+        limit: i64,
+    ) -> Result<Vec<DisplayCard>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .cards
+            .values()
+            .filter(|c| c.front_lang == front_lang && c.back_lang == back_lang)
+            .take(limit as usize)
+            .cloned()
+            .map(DisplayCard::from)
+            .collect())
+    }
+    async fn card_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .cards
+            .values()
+            .filter(|c| c.author_did == author_did)
+            .map(|c| RecordVersion {
+                uri: c.uri.clone(),
+                cid: c.cid.clone(),
+            })
+            .collect())
+    }
+    async fn card_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .tables()
+            .cards
+            .values()
+            .filter(|c| c.author_did == author_did)
+            .flat_map(|c| [&c.front_image, &c.back_image, &c.audio])
+            .flatten()
+            .any(|blob| blob_cid(blob) == cid))
+    }
+    async fn most_used_langs(
+        &self,
+        author_did: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for card in self
+            .tables()
+            .cards
+            .values()
+            .filter(|c| c.author_did == author_did)
+        {
+            *counts.entry(card.front_lang.clone()).or_default() += 1;
+            *counts.entry(card.back_lang.clone()).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        // Stable, so ties stay in tag order like the SQL
+        counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+        Ok(counts
+            .into_iter()
+            .take(limit.try_into().unwrap_or_default())
+            .map(|(lang, _)| lang)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl CollectionRepo for MemoryRepo {
+    async fn save_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.collections.contains_key(&collection.uri) {
+            return Err(duplicate_key("collection", &collection.uri));
+        }
+        tables
+            .collections
+            .insert(collection.uri.clone(), collection.clone());
+        Ok(())
+    }
+    async fn upsert_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.deleted_since(&collection.uri, &collection.rev)
+            || tables
+                .collections
+                .get(&collection.uri)
+                .is_some_and(|stored| {
+                    !supersedes(&stored.rev, &stored.cid, &collection.rev, &collection.cid)
+                })
+        {
+            return Ok(());
+        }
+        tables
+            .collections
+            .insert(collection.uri.clone(), collection.clone());
+        Ok(())
+    }
+    async fn delete_collection(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        tables.tombstone(uri, rev);
+        tables.collections.remove(uri);
+        Ok(())
+    }
+    async fn collection_is_owned_by(
+        &self,
+        author_did: &str,
+        collection_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .tables()
+            .collections
+            .get(collection_uri)
+            .is_some_and(|c| c.author_did == author_did))
+    }
+    async fn get_collection(
+        &self,
+        collection_uri: &str,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .collections
+            .get(collection_uri)
+            .cloned()
+            .map(CollectionDetails::from))
+    }
+    async fn update_owned_collection(
+        &self,
+        args: &CollectionUpdateArgs,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .collections
+            .get_mut(&args.uri)
+            .filter(|c| c.author_did == args.author_did)
+            .map(|c| {
+                c.label = args.label.clone();
+                c.description = args.description.clone();
+                c.stacks = args.stacks.clone();
+                c.clone().into()
+            }))
+    }
+    async fn user_collections(&self, did: &str) -> Result<Vec<CollectionDetails>, sqlx::Error> {
+        let mut collections: Vec<CollectionDetails> = self
+            .tables()
+            .collections
+            .values()
+            .filter(|c| c.author_did == did)
+            .cloned()
+            .map(CollectionDetails::from)
+            .collect();
+        collections.sort_by_key(|c| c.label.to_lowercase());
+        Ok(collections)
+    }
+    async fn collection_versions(
+        &self,
+        author_did: &str,
+    ) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .collections
+            .values()
+            .filter(|c| c.author_did == author_did)
+            .map(|c| RecordVersion {
+                uri: c.uri.clone(),
+                cid: c.cid.clone(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ReviewRepo for MemoryRepo {
+    async fn stack_review_states(
+        &self,
+        reviewer_did: &str,
+        stack_uri: &str,
+    ) -> Result<Vec<ReviewState>, sqlx::Error> {
+        let tables = self.tables();
+        Ok(tables
+            .review_states
+            .values()
+            .filter(|r| r.reviewer_did == reviewer_did)
+            .filter(|r| {
+                tables
+                    .cards
+                    .get(&r.card_uri)
+                    .is_some_and(|c| c.stack_id == stack_uri)
+            })
+            .cloned()
+            .collect())
+    }
+    async fn save_review(&self, state: &ReviewState, review: &Review) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        let key = (
+            state.reviewer_did.clone(),
+            state.card_uri.clone(),
+            state.direction.clone(),
+            state.ordinal,
+        );
+        tables.review_states.insert(key, state.clone());
+        tables.reviews.push(review.clone());
+        Ok(())
+    }
+    async fn reviewer_history(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StackedReview>, sqlx::Error> {
+        let tables = self.tables();
+        // Saved in order, so already oldest first
+        Ok(tables
+            .reviews
+            .iter()
+            .filter(|r| r.reviewer_did == reviewer_did && r.reviewed_at >= since)
+            .map(|r| StackedReview {
+                review: r.clone(),
+                stack_uri: tables.cards.get(&r.card_uri).map(|c| c.stack_id.clone()),
+            })
+            .collect())
+    }
+    async fn reviewer_states(
+        &self,
+        reviewer_did: &str,
+    ) -> Result<Vec<StackedReviewState>, sqlx::Error> {
+        let tables = self.tables();
+        Ok(tables
+            .review_states
+            .values()
+            .filter(|r| r.reviewer_did == reviewer_did)
+            .filter_map(|r| {
+                tables.cards.get(&r.card_uri).map(|c| StackedReviewState {
+                    state: r.clone(),
+                    stack_uri: c.stack_id.clone(),
+                })
+            })
+            .collect())
+    }
+    async fn reviewed_stacks(&self, reviewer_did: &str) -> Result<Vec<StackDetails>, sqlx::Error> {
+        let tables = self.tables();
+        let uris: BTreeSet<&String> = tables
+            .review_states
+            .values()
+            .filter(|r| r.reviewer_did == reviewer_did)
+            .filter_map(|r| tables.cards.get(&r.card_uri).map(|c| &c.stack_id))
+            .collect();
+        Ok(uris
+            .into_iter()
+            .filter_map(|uri| tables.stacks.get(uri))
+            .cloned()
+            .map(StackDetails::from)
+            .collect())
+    }
+    async fn review_counts(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<ReviewCounts, sqlx::Error> {
+        let tables = self.tables();
+        let mut first_at: BTreeMap<(&str, &str, i32), DateTime<Utc>> = BTreeMap::new();
+        for r in tables
+            .reviews
+            .iter()
+            .filter(|r| r.reviewer_did == reviewer_did)
+        {
+            first_at
+                .entry((&r.card_uri, &r.direction, r.ordinal))
+                .or_insert(r.reviewed_at);
+        }
+        let mut counts = ReviewCounts::default();
+        for r in tables
+            .reviews
+            .iter()
+            .filter(|r| r.reviewer_did == reviewer_did)
+        {
+            if r.reviewed_at < since {
+                continue;
+            }
+            if first_at[&(r.card_uri.as_str(), r.direction.as_str(), r.ordinal)] < since {
+                counts.reviews += 1;
+            }
+        }
+        counts.new_items = first_at.values().filter(|at| **at >= since).count() as i64;
+        Ok(counts)
+    }
+    async fn review_limits(&self, reviewer_did: &str) -> Result<Option<ReviewLimits>, sqlx::Error> {
+        Ok(self.tables().review_limits.get(reviewer_did).cloned())
+    }
+    async fn save_review_limits(&self, limits: &ReviewLimits) -> Result<(), sqlx::Error> {
+        self.tables()
+            .review_limits
+            .insert(limits.reviewer_did.clone(), limits.clone());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameRepo for MemoryRepo {
+    async fn best_match(
+        &self,
+        player_did: &str,
+        stack_uri: &str,
+        pairs: i32,
+    ) -> Result<Option<MatchScore>, sqlx::Error> {
+        let key = (player_did.to_string(), stack_uri.to_string(), pairs);
+        Ok(self.tables().match_scores.get(&key).cloned())
+    }
+    async fn save_match(&self, score: &MatchScore) -> Result<bool, sqlx::Error> {
+        let mut tables = self.tables();
+        let key = (
+            score.player_did.clone(),
+            score.stack_uri.clone(),
+            score.pairs,
+        );
+        if tables
+            .match_scores
+            .get(&key)
+            .is_some_and(|best| best.duration_ms <= score.duration_ms)
+        {
+            return Ok(false);
+        }
+        tables.match_scores.insert(key, score.clone());
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl AuthRepo for MemoryRepo {
+    async fn get_session(&self, did: &str) -> Result<Option<AuthSession>, sqlx::Error> {
+        Ok(self.tables().auth_sessions.get(did).cloned())
+    }
+    async fn save_session(&self, session: &AuthSession) -> Result<(), sqlx::Error> {
+        self.tables()
+            .auth_sessions
+            .insert(session.key.clone(), session.clone());
+        Ok(())
+    }
+    async fn delete_session(&self, did: &str) -> Result<(), sqlx::Error> {
+        self.tables().auth_sessions.remove(did);
+        Ok(())
+    }
+    async fn clear_sessions(&self) -> Result<(), sqlx::Error> {
+        self.tables().auth_sessions.clear();
+        Ok(())
+    }
+    async fn get_state(&self, key: &str) -> Result<Option<AuthState>, sqlx::Error> {
+        Ok(self.tables().auth_states.get(key).cloned())
+    }
+    async fn save_state(&self, state: &AuthState) -> Result<(), sqlx::Error> {
+        self.tables()
+            .auth_states
+            .insert(state.key.clone(), state.clone());
+        Ok(())
+    }
+    async fn delete_state(&self, key: &str) -> Result<(), sqlx::Error> {
+        self.tables().auth_states.remove(key);
+        Ok(())
+    }
+    async fn clear_states(&self) -> Result<(), sqlx::Error> {
+        self.tables().auth_states.clear();
+        Ok(())
+    }
+    async fn save_api_token(&self, token: &ApiToken) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        if tables.api_tokens.contains_key(&token.token_hash) {
+            return Err(duplicate_key("api_token", &token.token_hash));
+        }
+        tables
+            .api_tokens
+            .insert(token.token_hash.clone(), token.clone());
+        Ok(())
+    }
+    async fn api_token_did(&self, token_hash: &str) -> Result<Option<String>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .api_tokens
+            .get(token_hash)
+            .map(|t| t.did.clone()))
+    }
+    async fn delete_api_token(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        self.tables().api_tokens.remove(token_hash);
+        Ok(())
+    }
+}
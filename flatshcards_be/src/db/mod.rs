@@ -0,0 +1,1300 @@
+//! The locally mirrored stacks and cards, plus OAuth and API token state.
+//!
+//! Storage is reached through the [`StackRepo`], [`CardRepo`], [`CollectionRepo`],
+//! [`ReviewRepo`], [`GameRepo`] and [`AuthRepo`] traits.
+//! [`PgPool`](sqlx::postgres::PgPool) implements them all and is what runs in production;
+//! [`MemoryRepo`] keeps everything in process, for tests and for running without Postgres.
+mod memory;
+mod postgres;
+
+pub use memory::MemoryRepo;
+pub use postgres::create_tables_in_database;
+
+use crate::blobs::blob_url;
+use crate::cloze::{ClozeItem, KIND_CLOZE, review_items};
+use crate::lang::{TextLayout, text_layout};
+use crate::review::Schedule;
+use crate::rich::{FORMAT_MARKDOWN, to_html};
+use crate::stacks;
+use crate::tags::{has_tag, normalize_tag, tags_text};
+use async_trait::async_trait;
+use atrium_api::types::BlobRef;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt::Debug;
+
+#[async_trait]
+pub trait StackRepo: Send + Sync {
+    /// Saves the stack's tags along with it, creating any tags not seen before
+    async fn save_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error>;
+    /// Inserts or replaces the stack at `stack.uri`, unless what's stored came from a
+    /// later revision of the author's repo or is already this exact record, or the stack
+    /// was deleted at this revision or a later one
+    async fn upsert_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error>;
+    /// Deletes the stack and its cards. Given the revision it was deleted at, that's
+    /// remembered, so a create or update from before it arriving late can't bring it back.
+    async fn delete_stack(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error>;
+    async fn stack_is_owned_by(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<bool, sqlx::Error>;
+    /// Anyone's stack, since anyone may review it
+    async fn get_stack(&self, stack_uri: &str) -> Result<Option<StackDetails>, sqlx::Error>;
+    /// Whichever of the stacks at `stack_uris` are indexed, in no particular order
+    async fn get_stacks(&self, stack_uris: &[String]) -> Result<Vec<StackDetails>, sqlx::Error>;
+    async fn get_owned_stack(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<Option<StackDetails>, sqlx::Error>;
+    async fn stack_clone_data(
+        &self,
+        stack_uri: &str,
+    ) -> Result<Option<StackCloneData>, sqlx::Error>;
+    async fn update_owned_stack(
+        &self,
+        args: &StackUpdateArgs,
+    ) -> Result<Option<StackDetails>, sqlx::Error>;
+    /// `did`'s stacks, by label
+    async fn user_stacks(&self, did: &str) -> Result<Vec<StackDetails>, sqlx::Error>;
+    /// Every DID with at least one stack or collection indexed
+    async fn indexed_authors(&self) -> Result<Vec<String>, sqlx::Error>;
+    async fn stack_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error>;
+    /// Whether one of `author_did`'s stacks has their blob `cid` for a cover
+    async fn stack_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error>;
+    /// Up to `limit` stacks matching `search`, most recently indexed first
+    async fn search_stacks(
+        &self,
+        search: &StackSearch,
+        limit: i64,
+    ) -> Result<Vec<StackDetails>, sqlx::Error>;
+    /// Every tag on `author_did`'s stacks and cards, alphabetically
+    async fn user_tags(&self, author_did: &str) -> Result<Vec<String>, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait CardRepo: Send + Sync {
+    async fn save_card(&self, card: &DbCard) -> Result<(), sqlx::Error>;
+    /// Same rules as [`StackRepo::upsert_stack`]
+    async fn upsert_card(&self, card: &DbCard) -> Result<(), sqlx::Error>;
+    /// Same rules as [`StackRepo::delete_stack`]
+    async fn delete_card(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error>;
+    async fn card_is_owned_by(&self, author_did: &str, card_uri: &str)
+    -> Result<bool, sqlx::Error>;
+    async fn get_owned_card(
+        &self,
+        author_did: &str,
+        card_uri: &str,
+    ) -> Result<Option<DbCard>, sqlx::Error>;
+    /// What copying the stack's cards takes, in the stack's order
+    async fn card_clone_data(&self, stack_uri: &str) -> Result<Vec<CardCloneData>, sqlx::Error>;
+    async fn update_owned_card(
+        &self,
+        args: &CardUpdateArgs,
+    ) -> Result<Option<DisplayCard>, sqlx::Error>;
+    /// The stack's cards in order: by position, then any without one oldest first
+    async fn stack_cards(&self, stack_uri: &str) -> Result<Vec<DisplayCard>, sqlx::Error>;
+    /// Up to `limit` cards from `front_lang` to `back_lang`, from any stack, in no
+    /// particular order
+    async fn lang_pair_cards(
+        &self,
+        front_lang: &str,
+        back_lang: &str,
+        limit: i64,
+    ) -> Result<Vec<DisplayCard>, sqlx::Error>;
+    async fn card_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error>;
+    /// Whether one of `author_did`'s cards uses their blob `cid`
+    async fn card_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error>;
+    /// Up to `limit` languages from either side of `author_did`'s cards, most used first
+    async fn most_used_langs(
+        &self,
+        author_did: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait CollectionRepo: Send + Sync {
+    /// Saves the collection's stacks along with it, in order
+    async fn save_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error>;
+    /// Same rules as [`StackRepo::upsert_stack`]
+    async fn upsert_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error>;
+    /// Same rules as [`StackRepo::delete_stack`]
+    async fn delete_collection(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error>;
+    async fn collection_is_owned_by(
+        &self,
+        author_did: &str,
+        collection_uri: &str,
+    ) -> Result<bool, sqlx::Error>;
+    /// Anyone's collection, since anyone may study it
+    async fn get_collection(
+        &self,
+        collection_uri: &str,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error>;
+    async fn update_owned_collection(
+        &self,
+        args: &CollectionUpdateArgs,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error>;
+    /// `did`'s collections, by label
+    async fn user_collections(&self, did: &str) -> Result<Vec<CollectionDetails>, sqlx::Error>;
+    async fn collection_versions(
+        &self,
+        author_did: &str,
+    ) -> Result<Vec<RecordVersion>, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait ReviewRepo: Send + Sync {
+    /// `reviewer_did`'s schedules for the items of the cards in `stack_uri`
+    async fn stack_review_states(
+        &self,
+        reviewer_did: &str,
+        stack_uri: &str,
+    ) -> Result<Vec<ReviewState>, sqlx::Error>;
+    /// Saves the schedule a review left an item with, and the review itself
+    async fn save_review(&self, state: &ReviewState, review: &Review) -> Result<(), sqlx::Error>;
+    /// `reviewer_did`'s reviews since `since`, oldest first
+    async fn reviewer_history(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StackedReview>, sqlx::Error>;
+    /// All of `reviewer_did`'s schedules, in any stack
+    async fn reviewer_states(
+        &self,
+        reviewer_did: &str,
+    ) -> Result<Vec<StackedReviewState>, sqlx::Error>;
+    /// Every stack `reviewer_did` has reviewed something in
+    async fn reviewed_stacks(&self, reviewer_did: &str) -> Result<Vec<StackDetails>, sqlx::Error>;
+    /// What `reviewer_did` has reviewed since `since`
+    async fn review_counts(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<ReviewCounts, sqlx::Error>;
+    /// Nothing if they've never set any
+    async fn review_limits(&self, reviewer_did: &str) -> Result<Option<ReviewLimits>, sqlx::Error>;
+    async fn save_review_limits(&self, limits: &ReviewLimits) -> Result<(), sqlx::Error>;
+}
+
+#[async_trait]
+pub trait GameRepo: Send + Sync {
+    /// `player_did`'s best time matching `pairs` pairs from `stack_uri`
+    async fn best_match(
+        &self,
+        player_did: &str,
+        stack_uri: &str,
+        pairs: i32,
+    ) -> Result<Option<MatchScore>, sqlx::Error>;
+    /// Keeps `score` if it beats the player's best, and says whether it did
+    async fn save_match(&self, score: &MatchScore) -> Result<bool, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait AuthRepo: Send + Sync {
+    async fn get_session(&self, did: &str) -> Result<Option<AuthSession>, sqlx::Error>;
+    async fn save_session(&self, session: &AuthSession) -> Result<(), sqlx::Error>;
+    async fn delete_session(&self, did: &str) -> Result<(), sqlx::Error>;
+    async fn clear_sessions(&self) -> Result<(), sqlx::Error>;
+    async fn get_state(&self, key: &str) -> Result<Option<AuthState>, sqlx::Error>;
+    async fn save_state(&self, state: &AuthState) -> Result<(), sqlx::Error>;
+    async fn delete_state(&self, key: &str) -> Result<(), sqlx::Error>;
+    async fn clear_states(&self) -> Result<(), sqlx::Error>;
+    async fn save_api_token(&self, token: &ApiToken) -> Result<(), sqlx::Error>;
+    async fn api_token_did(&self, token_hash: &str) -> Result<Option<String>, sqlx::Error>;
+    async fn delete_api_token(&self, token_hash: &str) -> Result<(), sqlx::Error>;
+}
+
+/// Everything the app stores, as one object to hand to routes, stores and ingesters
+pub trait Repo: StackRepo + CardRepo + CollectionRepo + ReviewRepo + GameRepo + AuthRepo {}
+
+impl<T: StackRepo + CardRepo + CollectionRepo + ReviewRepo + GameRepo + AuthRepo> Repo for T {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbStack {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub label: String,
+    pub directions: String,
+    /// What the stack is for; this and the rest are described in [`crate::stacks`]
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub difficulty: Option<String>,
+    #[sqlx(json(nullable))]
+    pub cover: Option<BlobRef>,
+    /// Kept in their own table, see [`StackRepo::save_stack`]
+    #[sqlx(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub indexed_at: DateTime<Utc>,
+    /// The repo revision and record cid this row was ingested at. Rows we wrote ourselves
+    /// have neither until the firehose catches up.
+    pub rev: Option<String>,
+    pub cid: Option<String>,
+}
+
+impl DbStack {
+    pub fn new(
+        StackArgs {
+            uri,
+            author_did,
+            back_lang,
+            front_lang,
+            label,
+            directions,
+            description,
+            source,
+            license,
+            difficulty,
+            cover,
+            tags,
+            indexed_at,
+        }: StackArgs,
+    ) -> Self {
+        let ia = indexed_at.unwrap_or_else(Utc::now);
+        Self {
+            uri,
+            author_did,
+            back_lang,
+            front_lang,
+            label,
+            directions,
+            description,
+            source,
+            license,
+            difficulty,
+            cover,
+            tags,
+            created_at: ia,
+            indexed_at: ia,
+            rev: None,
+            cid: None,
+        }
+    }
+}
+
+impl From<DbStack> for StackDetails {
+    fn from(val: DbStack) -> Self {
+        Self {
+            uri: val.uri,
+            back_lang: val.back_lang,
+            front_lang: val.front_lang,
+            label: val.label,
+            directions: val.directions,
+            description: val.description,
+            source: val.source,
+            license: val.license,
+            difficulty: val.difficulty,
+            cover: val.cover,
+            tags: val.tags,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackArgs {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub label: String,
+    pub directions: String,
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub difficulty: Option<String>,
+    pub cover: Option<BlobRef>,
+    pub tags: Vec<String>,
+    pub indexed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackUpdateArgs {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub label: String,
+    pub directions: String,
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub difficulty: Option<String>,
+    pub cover: Option<BlobRef>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StackDetails {
+    pub uri: String,
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub label: String,
+    pub directions: String,
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub difficulty: Option<String>,
+    #[sqlx(json(nullable))]
+    pub cover: Option<BlobRef>,
+    pub tags: Vec<String>,
+}
+
+impl StackDetails {
+    /// The languages as the pickers take them, with `""` for none
+    pub fn back_lang_or_none(&self) -> &str {
+        self.back_lang.as_deref().unwrap_or_default()
+    }
+    pub fn front_lang_or_none(&self) -> &str {
+        self.front_lang.as_deref().unwrap_or_default()
+    }
+    /// How to lay out text on each side of the stack's cards
+    pub fn back_layout(&self) -> TextLayout {
+        text_layout(self.back_lang_or_none())
+    }
+    pub fn front_layout(&self) -> TextLayout {
+        text_layout(self.front_lang_or_none())
+    }
+    pub fn is_directions(&self, directions: &str) -> bool {
+        self.directions == directions
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        has_tag(&self.tags, tag)
+    }
+    /// The tags as the editor shows them
+    pub fn tags_text(&self) -> String {
+        tags_text(&self.tags)
+    }
+    /// The text fields as the editor shows them, with `""` for none
+    pub fn description_or_empty(&self) -> &str {
+        self.description.as_deref().unwrap_or_default()
+    }
+    pub fn source_or_empty(&self) -> &str {
+        self.source.as_deref().unwrap_or_default()
+    }
+    pub fn license_or_empty(&self) -> &str {
+        self.license.as_deref().unwrap_or_default()
+    }
+    pub fn difficulty_or_empty(&self) -> &str {
+        self.difficulty.as_deref().unwrap_or_default()
+    }
+    /// The stack's license, if it's one of [`stacks::LICENSES`]
+    pub fn known_license(&self) -> Option<&'static stacks::License> {
+        self.license.as_deref().and_then(stacks::license)
+    }
+    pub fn is_all_rights_reserved(&self) -> bool {
+        self.license_or_empty() == stacks::ALL_RIGHTS_RESERVED
+    }
+    /// The did of the stack's author, whose repo it's in
+    pub fn author_did(&self) -> &str {
+        uri_did(&self.uri)
+    }
+    /// The message naming the stack's difficulty, if it's one of [`stacks::DIFFICULTIES`]
+    pub fn difficulty_message(&self) -> Option<String> {
+        self.difficulty
+            .as_deref()
+            .filter(|d| stacks::is_difficulty(d))
+            .map(|d| format!("stack-difficulty-{d}"))
+    }
+    /// Where the cover is served from, if there is one
+    pub fn cover_url(&self) -> Option<String> {
+        self.cover.as_ref().map(|b| blob_url(self.author_did(), b))
+    }
+    /// The cover's ref, for the editor to send back if it's kept
+    pub fn cover_json(&self) -> String {
+        blob_json(&self.cover)
+    }
+}
+
+/// What to look for in [`StackRepo::search_stacks`]. Empty fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct StackSearch {
+    /// Text anywhere in the label, in any case
+    pub text: String,
+    /// A tag on the stack or on one of its cards
+    pub tag: Option<String>,
+    /// Only this author's stacks
+    pub author_did: Option<String>,
+}
+
+impl StackSearch {
+    /// A search for what was typed into a search form
+    pub fn new(text: &str, tag: &str) -> Self {
+        Self {
+            text: text.trim().to_string(),
+            tag: normalize_tag(tag),
+            author_did: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StackCloneData {
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub label: String,
+    pub directions: String,
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub difficulty: Option<String>,
+    #[sqlx(json(nullable))]
+    pub cover: Option<BlobRef>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbCard {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: String,
+    pub back_text: String,
+    pub front_lang: String,
+    pub front_text: String,
+    pub kind: String,
+    pub format: String,
+    #[sqlx(json(nullable))]
+    pub front_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub back_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub audio: Option<BlobRef>,
+    /// Kept in their own table, like [`DbStack::tags`]
+    #[sqlx(default)]
+    pub tags: Vec<String>,
+    /// Where the card goes in its stack. Cards from before stacks were ordered have none,
+    /// and go last, oldest first.
+    pub position: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub indexed_at: DateTime<Utc>,
+    pub stack_id: String,
+    pub rev: Option<String>,
+    pub cid: Option<String>,
+}
+
+impl DbCard {
+    pub fn new(
+        CardArgs {
+            uri,
+            author_did,
+            back_lang,
+            back_text,
+            front_lang,
+            front_text,
+            kind,
+            format,
+            front_image,
+            back_image,
+            audio,
+            tags,
+            position,
+            indexed_at,
+            stack_id,
+        }: CardArgs,
+    ) -> Self {
+        let ia = indexed_at.unwrap_or_else(Utc::now);
+        Self {
+            uri,
+            author_did,
+            back_lang,
+            back_text,
+            front_lang,
+            front_text,
+            kind,
+            format,
+            front_image,
+            back_image,
+            audio,
+            tags,
+            position,
+            created_at: ia,
+            indexed_at: ia,
+            stack_id,
+            rev: None,
+            cid: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardArgs {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: String,
+    pub back_text: String,
+    pub front_lang: String,
+    pub front_text: String,
+    pub kind: String,
+    pub format: String,
+    pub front_image: Option<BlobRef>,
+    pub back_image: Option<BlobRef>,
+    pub audio: Option<BlobRef>,
+    pub tags: Vec<String>,
+    pub position: Option<i32>,
+    pub indexed_at: Option<DateTime<Utc>>,
+    pub stack_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardUpdateArgs {
+    pub uri: String,
+    pub author_did: String,
+    pub back_lang: String,
+    pub back_text: String,
+    pub front_lang: String,
+    pub front_text: String,
+    pub kind: String,
+    pub format: String,
+    pub front_image: Option<BlobRef>,
+    pub back_image: Option<BlobRef>,
+    pub audio: Option<BlobRef>,
+    pub tags: Vec<String>,
+    pub position: Option<i32>,
+    pub stack_id: String,
+    /// Not updated, only carried along to the record
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<CardUpdateArgs> for DisplayCard {
+    fn from(val: CardUpdateArgs) -> Self {
+        Self {
+            uri: val.uri,
+            back_lang: val.back_lang,
+            back_text: val.back_text,
+            front_lang: val.front_lang,
+            front_text: val.front_text,
+            kind: val.kind,
+            format: val.format,
+            front_image: val.front_image,
+            back_image: val.back_image,
+            audio: val.audio,
+            tags: val.tags,
+            position: val.position,
+            created_at: val.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DisplayCard {
+    pub uri: String,
+    pub back_lang: String,
+    pub back_text: String,
+    pub front_lang: String,
+    pub front_text: String,
+    pub kind: String,
+    pub format: String,
+    #[sqlx(json(nullable))]
+    pub front_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub back_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub audio: Option<BlobRef>,
+    pub tags: Vec<String>,
+    pub position: Option<i32>,
+    /// When the card was first written, which its record keeps through every edit
+    pub created_at: DateTime<Utc>,
+}
+
+impl DisplayCard {
+    /// How to lay out each side's text
+    pub fn back_layout(&self) -> TextLayout {
+        text_layout(&self.back_lang)
+    }
+    pub fn front_layout(&self) -> TextLayout {
+        text_layout(&self.front_lang)
+    }
+    pub fn is_cloze(&self) -> bool {
+        self.kind == KIND_CLOZE
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        has_tag(&self.tags, tag)
+    }
+    /// The tags as the editor shows them
+    pub fn tags_text(&self) -> String {
+        tags_text(&self.tags)
+    }
+    /// What reviewing a cloze card means going through; nothing for other cards
+    pub fn cloze_items(&self) -> Vec<ClozeItem> {
+        if self.is_cloze() {
+            review_items(&self.front_text).unwrap_or_default()
+        } else {
+            Vec::new()
+        }
+    }
+    pub fn is_markdown(&self) -> bool {
+        self.format == FORMAT_MARKDOWN
+    }
+    /// Each side's text as sanitized HTML
+    pub fn back_html(&self) -> String {
+        to_html(&self.back_text, &self.format)
+    }
+    pub fn front_html(&self) -> String {
+        to_html(&self.front_text, &self.format)
+    }
+    /// Where each side's image is served from, if it has one
+    pub fn back_image_url(&self) -> Option<String> {
+        self.back_image
+            .as_ref()
+            .map(|b| blob_url(self.author_did(), b))
+    }
+    pub fn front_image_url(&self) -> Option<String> {
+        self.front_image
+            .as_ref()
+            .map(|b| blob_url(self.author_did(), b))
+    }
+    /// Each side's image ref, for the editor to send back if it's kept
+    pub fn back_image_json(&self) -> String {
+        blob_json(&self.back_image)
+    }
+    pub fn front_image_json(&self) -> String {
+        blob_json(&self.front_image)
+    }
+    /// Where the card's recording is served from, if it has one
+    pub fn audio_url(&self) -> Option<String> {
+        self.audio.as_ref().map(|b| blob_url(self.author_did(), b))
+    }
+    pub fn audio_json(&self) -> String {
+        blob_json(&self.audio)
+    }
+    fn author_did(&self) -> &str {
+        uri_did(&self.uri)
+    }
+}
+
+/// The did of the repo the record at `uri` is in
+fn uri_did(uri: &str) -> &str {
+    uri.strip_prefix("at://")
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default()
+}
+
+fn blob_json(blob: &Option<BlobRef>) -> String {
+    blob.as_ref()
+        .and_then(|b| serde_json::to_string(b).ok())
+        .unwrap_or_default()
+}
+
+impl From<DbCard> for DisplayCard {
+    fn from(val: DbCard) -> Self {
+        Self {
+            uri: val.uri,
+            back_lang: val.back_lang,
+            back_text: val.back_text,
+            front_lang: val.front_lang,
+            front_text: val.front_text,
+            kind: val.kind,
+            format: val.format,
+            front_image: val.front_image,
+            back_image: val.back_image,
+            audio: val.audio,
+            tags: val.tags,
+            position: val.position,
+            created_at: val.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CardCloneData {
+    pub back_lang: String,
+    pub back_text: String,
+    pub front_lang: String,
+    pub front_text: String,
+    pub kind: String,
+    pub format: String,
+    #[sqlx(json(nullable))]
+    pub front_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub back_image: Option<BlobRef>,
+    #[sqlx(json(nullable))]
+    pub audio: Option<BlobRef>,
+    pub tags: Vec<String>,
+}
+
+impl From<&DbCard> for CardCloneData {
+    fn from(val: &DbCard) -> Self {
+        Self {
+            back_lang: val.back_lang.clone(),
+            back_text: val.back_text.clone(),
+            front_lang: val.front_lang.clone(),
+            front_text: val.front_text.clone(),
+            kind: val.kind.clone(),
+            format: val.format.clone(),
+            front_image: val.front_image.clone(),
+            back_image: val.back_image.clone(),
+            audio: val.audio.clone(),
+            tags: val.tags.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbCollection {
+    pub uri: String,
+    pub author_did: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// The stacks' uris in order, kept in their own table. They needn't be indexed, or
+    /// still exist.
+    #[sqlx(default)]
+    pub stacks: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub indexed_at: DateTime<Utc>,
+    pub rev: Option<String>,
+    pub cid: Option<String>,
+}
+
+impl DbCollection {
+    pub fn new(
+        CollectionArgs {
+            uri,
+            author_did,
+            label,
+            description,
+            stacks,
+        }: CollectionArgs,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            uri,
+            author_did,
+            label,
+            description,
+            stacks,
+            created_at: now,
+            indexed_at: now,
+            rev: None,
+            cid: None,
+        }
+    }
+}
+
+impl From<DbCollection> for CollectionDetails {
+    fn from(val: DbCollection) -> Self {
+        Self {
+            uri: val.uri,
+            author_did: val.author_did,
+            label: val.label,
+            description: val.description,
+            stacks: val.stacks,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionArgs {
+    pub uri: String,
+    pub author_did: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub stacks: Vec<String>,
+}
+
+/// Same fields, but only written if `author_did` owns the collection
+pub type CollectionUpdateArgs = CollectionArgs;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CollectionDetails {
+    pub uri: String,
+    pub author_did: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub stacks: Vec<String>,
+}
+
+/// A reviewer's schedule for one review item of a card. `ordinal` is the deletion number
+/// for cloze cards, and 0 for anything else.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReviewState {
+    pub reviewer_did: String,
+    pub card_uri: String,
+    pub direction: String,
+    pub ordinal: i32,
+    pub ease: f64,
+    pub interval_days: i32,
+    pub reps: i32,
+    pub lapses: i32,
+    pub due_at: DateTime<Utc>,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+impl ReviewState {
+    pub fn schedule(&self) -> Schedule {
+        Schedule {
+            ease: self.ease,
+            interval_days: self.interval_days,
+            reps: self.reps,
+            lapses: self.lapses,
+            due_at: self.due_at,
+        }
+    }
+}
+
+/// One review of an item, kept as history
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Review {
+    pub reviewer_did: String,
+    pub card_uri: String,
+    pub direction: String,
+    pub ordinal: i32,
+    pub grade: String,
+    /// The interval the review scheduled
+    pub interval_days: i32,
+    /// How long the item was looked at before being graded
+    pub duration_ms: i64,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+/// How many new items and reviews of items already seen a reviewer allows themself a day
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReviewLimits {
+    pub reviewer_did: String,
+    pub new_per_day: i32,
+    pub reviews_per_day: i32,
+}
+
+/// What a reviewer has done so far in some stretch of time
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct ReviewCounts {
+    /// Items first reviewed in it
+    pub new_items: i64,
+    /// Reviews of items first reviewed before it
+    pub reviews: i64,
+}
+
+/// A review with the stack its card is in, or nothing once the card's been deleted
+#[derive(Debug, Clone, FromRow)]
+pub struct StackedReview {
+    #[sqlx(flatten)]
+    pub review: Review,
+    pub stack_uri: Option<String>,
+}
+
+/// A schedule with the stack its card is in
+#[derive(Debug, Clone, FromRow)]
+pub struct StackedReviewState {
+    #[sqlx(flatten)]
+    pub state: ReviewState,
+    pub stack_uri: String,
+}
+
+/// A player's best time matching the pairs of a stack, for a number of pairs
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MatchScore {
+    pub player_did: String,
+    pub stack_uri: String,
+    pub pairs: i32,
+    pub duration_ms: i64,
+    pub played_at: DateTime<Utc>,
+}
+
+/// Just enough of a stored record to compare it with the copy on the author's PDS
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecordVersion {
+    pub uri: String,
+    pub cid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuthSession {
+    pub key: String,
+    pub session: String,
+}
+
+impl AuthSession {
+    pub fn new<V: Serialize>(key: String, session: V) -> Self {
+        let session = serde_json::to_string(&session).unwrap();
+        Self { key, session }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuthState {
+    pub key: String,
+    pub state: String,
+}
+
+impl AuthState {
+    pub fn new<V: Serialize>(key: String, state: V) -> Self {
+        let state = serde_json::to_string(&state).unwrap();
+        Self { key, state }
+    }
+}
+
+/// A bearer token for the JSON API, bound to the DID that created it.
+/// Only the SHA-256 hash of the token is stored.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiToken {
+    pub token_hash: String,
+    pub did: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn new(token_hash: String, did: String) -> Self {
+        Self {
+            token_hash,
+            did,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// The same checks against every backend, through the traits alone. The Postgres run needs a
+/// database to write to, named by `TEST_DB_URL`, and is skipped without one.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPool;
+
+    /// Authors unique to this run, so a shared database needn't be emptied first
+    struct Authors {
+        alice: String,
+        bob: String,
+    }
+
+    impl Authors {
+        fn new() -> Self {
+            let run: u64 = rand::random();
+            Self {
+                alice: format!("did:plc:alice{run:x}"),
+                bob: format!("did:plc:bob{run:x}"),
+            }
+        }
+    }
+
+    fn stack(author_did: &str, rkey: &str, label: &str) -> DbStack {
+        DbStack::new(StackArgs {
+            uri: format!("at://{author_did}/xyz.flatshcards.stack/{rkey}"),
+            author_did: author_did.to_string(),
+            back_lang: Some("en".to_string()),
+            front_lang: Some("ja".to_string()),
+            label: label.to_string(),
+            directions: "forward".to_string(),
+            description: None,
+            source: None,
+            license: None,
+            difficulty: None,
+            cover: None,
+            tags: vec!["kitchen".to_string()],
+            indexed_at: None,
+        })
+    }
+
+    fn card(stack: &DbStack, rkey: &str, front_image: Option<BlobRef>) -> DbCard {
+        DbCard::new(CardArgs {
+            uri: format!("at://{}/xyz.flatshcards.card/{rkey}", stack.author_did),
+            author_did: stack.author_did.clone(),
+            back_lang: "en".to_string(),
+            back_text: "knife".to_string(),
+            front_lang: "ja".to_string(),
+            front_text: "包丁".to_string(),
+            kind: "basic".to_string(),
+            format: "plain".to_string(),
+            front_image,
+            back_image: None,
+            audio: None,
+            tags: Vec::new(),
+            position: None,
+            indexed_at: None,
+            stack_id: stack.uri.clone(),
+        })
+    }
+
+    fn blob(cid: &str) -> BlobRef {
+        serde_json::from_value(serde_json::json!({
+            "$type": "blob",
+            "ref": { "$link": cid },
+            "mimeType": "image/png",
+            "size": 68,
+        }))
+        .unwrap()
+    }
+
+    async fn ownership(repo: &dyn Repo) {
+        let Authors { alice, bob } = Authors::new();
+        let kitchen = stack(&alice, "3lbhtdjy7ws2a", "Kitchen");
+        repo.save_stack(&kitchen).await.unwrap();
+        assert!(repo.stack_is_owned_by(&alice, &kitchen.uri).await.unwrap());
+        assert!(!repo.stack_is_owned_by(&bob, &kitchen.uri).await.unwrap());
+        let elsewhere = format!("at://{alice}/xyz.flatshcards.stack/3lbhtzzzzzz2a");
+        assert!(!repo.stack_is_owned_by(&alice, &elsewhere).await.unwrap());
+
+        let cid = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+        let knife = card(&kitchen, "3lbhtdk2aqs2b", Some(blob(cid)));
+        repo.save_card(&knife).await.unwrap();
+        assert!(repo.card_is_owned_by(&alice, &knife.uri).await.unwrap());
+        assert!(!repo.card_is_owned_by(&bob, &knife.uri).await.unwrap());
+        assert!(repo.card_uses_blob(&alice, cid).await.unwrap());
+        assert!(!repo.card_uses_blob(&bob, cid).await.unwrap());
+        assert!(
+            !repo
+                .card_uses_blob(
+                    &alice,
+                    "bafkreiaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+                )
+                .await
+                .unwrap()
+        );
+        assert!(!repo.stack_uses_blob(&alice, cid).await.unwrap());
+    }
+
+    async fn stacks_and_cards(repo: &dyn Repo) {
+        let Authors { alice, bob } = Authors::new();
+        let kitchen = stack(&alice, "3lbhtdjy7ws2a", "Kitchen");
+        repo.save_stack(&kitchen).await.unwrap();
+        assert!(repo.save_stack(&kitchen).await.is_err());
+        let saved = repo.get_stack(&kitchen.uri).await.unwrap().unwrap();
+        assert_eq!(saved.label, "Kitchen");
+        assert_eq!(saved.tags, ["kitchen"]);
+        assert_eq!(repo.user_stacks(&alice).await.unwrap().len(), 1);
+
+        let knife = card(&kitchen, "3lbhtdk2aqs2b", None);
+        repo.save_card(&knife).await.unwrap();
+        let cards = repo.stack_cards(&kitchen.uri).await.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].uri, knife.uri);
+        assert_eq!(cards[0].back_text, "knife");
+        assert_eq!(
+            cards[0].created_at.timestamp_micros(),
+            knife.created_at.timestamp_micros()
+        );
+        let owned = repo.get_owned_card(&alice, &knife.uri).await.unwrap();
+        assert_eq!(owned.unwrap().tags, knife.tags);
+        assert!(
+            repo.get_owned_card(&bob, &knife.uri)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Deleting the stack takes its cards with it
+        repo.delete_stack(&kitchen.uri, None).await.unwrap();
+        assert!(repo.get_stack(&kitchen.uri).await.unwrap().is_none());
+        assert!(!repo.card_is_owned_by(&alice, &knife.uri).await.unwrap());
+        assert!(repo.stack_cards(&kitchen.uri).await.unwrap().is_empty());
+    }
+
+    async fn upserts_keep_the_latest(repo: &dyn Repo) {
+        let Authors { alice, .. } = Authors::new();
+        let version = |label: &str, rev: &str, cid: &str| DbStack {
+            rev: Some(rev.to_string()),
+            cid: Some(cid.to_string()),
+            ..stack(&alice, "3lbhtdjy7ws2a", label)
+        };
+        let label = |stack: Option<StackDetails>| stack.unwrap().label;
+        let uri = version("", "", "").uri;
+        repo.upsert_stack(&version("Kitchen", "3lbhu2aaaab2a", "bafyreib"))
+            .await
+            .unwrap();
+        // An earlier rev, as after a rewound cursor, is ignored
+        repo.upsert_stack(&version("Kitchen?", "3lbhu2aaaaa2a", "bafyreia"))
+            .await
+            .unwrap();
+        assert_eq!(label(repo.get_stack(&uri).await.unwrap()), "Kitchen");
+        // So is the same record again
+        repo.upsert_stack(&version("Kitchen!", "3lbhu2aaaac2a", "bafyreib"))
+            .await
+            .unwrap();
+        assert_eq!(label(repo.get_stack(&uri).await.unwrap()), "Kitchen");
+        repo.upsert_stack(&version("Kitchen things", "3lbhu2aaaac2a", "bafyreic"))
+            .await
+            .unwrap();
+        assert_eq!(label(repo.get_stack(&uri).await.unwrap()), "Kitchen things");
+        assert_eq!(
+            repo.stack_versions(&alice).await.unwrap()[0].cid.as_deref(),
+            Some("bafyreic")
+        );
+    }
+
+    async fn labels_can_repeat(repo: &dyn Repo) {
+        let Authors { alice, .. } = Authors::new();
+        repo.save_stack(&stack(&alice, "3lbhtdjy7ws2a", "Kitchen"))
+            .await
+            .unwrap();
+        repo.save_stack(&stack(&alice, "3lbhtdjy7ws2b", "Kitchen"))
+            .await
+            .unwrap();
+        let ingested = DbStack {
+            rev: Some("3lbhu2aaaab2a".to_string()),
+            cid: Some("bafyreib".to_string()),
+            ..stack(&alice, "3lbhtdjy7ws2c", "Kitchen")
+        };
+        repo.upsert_stack(&ingested).await.unwrap();
+        let stacks = repo.user_stacks(&alice).await.unwrap();
+        assert_eq!(stacks.len(), 3);
+        assert!(stacks.iter().all(|stack| stack.label == "Kitchen"));
+    }
+
+    async fn auth(repo: &dyn Repo) {
+        let Authors { alice, .. } = Authors::new();
+        repo.save_session(&AuthSession::new(alice.clone(), "session"))
+            .await
+            .unwrap();
+        let session = repo.get_session(&alice).await.unwrap().unwrap();
+        assert_eq!(session.session, "\"session\"");
+        repo.delete_session(&alice).await.unwrap();
+        assert!(repo.get_session(&alice).await.unwrap().is_none());
+
+        repo.save_state(&AuthState::new(alice.clone(), "state"))
+            .await
+            .unwrap();
+        assert!(repo.get_state(&alice).await.unwrap().is_some());
+        repo.delete_state(&alice).await.unwrap();
+        assert!(repo.get_state(&alice).await.unwrap().is_none());
+
+        let hash = format!("{alice}-token");
+        repo.save_api_token(&ApiToken::new(hash.clone(), alice.clone()))
+            .await
+            .unwrap();
+        assert_eq!(repo.api_token_did(&hash).await.unwrap(), Some(alice));
+        repo.delete_api_token(&hash).await.unwrap();
+        assert!(repo.api_token_did(&hash).await.unwrap().is_none());
+    }
+
+    /// Every order three versions of a card can arrive in, as when a rewound cursor or a
+    /// backfill interleaves with the live stream, ends at the latest
+    async fn versions_converge(repo: &dyn Repo) {
+        let Authors { alice, .. } = Authors::new();
+        let kitchen = stack(&alice, "3lbhtdjy7ws2a", "Kitchen");
+        repo.save_stack(&kitchen).await.unwrap();
+        let versions = [
+            ("knife", "3lbhu2aaaaa2a", "bafyreia"),
+            ("kitchen knife", "3lbhu2aaaab2a", "bafyreib"),
+            ("chef's knife", "3lbhu2aaaac2a", "bafyreic"),
+        ];
+        let orders = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        for (n, order) in orders.into_iter().enumerate() {
+            let rkey = format!("3lbhtdk2aqs{}b", n + 2);
+            for i in order {
+                let (back_text, rev, cid) = versions[i];
+                let version = DbCard {
+                    back_text: back_text.to_string(),
+                    rev: Some(rev.to_string()),
+                    cid: Some(cid.to_string()),
+                    ..card(&kitchen, &rkey, None)
+                };
+                repo.upsert_card(&version).await.unwrap();
+            }
+        }
+        let cards = repo.stack_cards(&kitchen.uri).await.unwrap();
+        assert_eq!(cards.len(), orders.len());
+        assert!(cards.iter().all(|card| card.back_text == "chef's knife"));
+    }
+
+    /// A create or update from before a delete, arriving after it, leaves the record
+    /// deleted, but a later one brings it back
+    async fn deletes_stick(repo: &dyn Repo) {
+        let Authors { alice, .. } = Authors::new();
+        let version = |label: &str, rev: &str, cid: &str| DbStack {
+            rev: Some(rev.to_string()),
+            cid: Some(cid.to_string()),
+            ..stack(&alice, "3lbhtdjy7ws2a", label)
+        };
+        let uri = version("", "", "").uri;
+        repo.upsert_stack(&version("Kitchen", "3lbhu2aaaaa2a", "bafyreia"))
+            .await
+            .unwrap();
+        repo.delete_stack(&uri, Some("3lbhu2aaaac2a"))
+            .await
+            .unwrap();
+        repo.upsert_stack(&version("Kitchen", "3lbhu2aaaaa2a", "bafyreia"))
+            .await
+            .unwrap();
+        repo.upsert_stack(&version("Kitchen things", "3lbhu2aaaab2a", "bafyreib"))
+            .await
+            .unwrap();
+        assert!(repo.get_stack(&uri).await.unwrap().is_none());
+        // An earlier delete arriving late doesn't move the tombstone back
+        repo.delete_stack(&uri, Some("3lbhu2aaaab2a"))
+            .await
+            .unwrap();
+        repo.upsert_stack(&version("Kitchen things", "3lbhu2aaaab2a", "bafyreib"))
+            .await
+            .unwrap();
+        assert!(repo.get_stack(&uri).await.unwrap().is_none());
+        repo.upsert_stack(&version("Kitchen again", "3lbhu2aaaad2a", "bafyreid"))
+            .await
+            .unwrap();
+        let stack = repo.get_stack(&uri).await.unwrap().unwrap();
+        assert_eq!(stack.label, "Kitchen again");
+
+        let knife = DbCard {
+            rev: Some("3lbhu2aaaae2a".to_string()),
+            cid: Some("bafyreie".to_string()),
+            ..card(&version("", "", ""), "3lbhtdk2aqs2b", None)
+        };
+        repo.delete_card(&knife.uri, Some("3lbhu2aaaaf2a"))
+            .await
+            .unwrap();
+        repo.upsert_card(&knife).await.unwrap();
+        assert!(!repo.card_is_owned_by(&alice, &knife.uri).await.unwrap());
+
+        let collection = DbCollection {
+            rev: Some("3lbhu2aaaae2a".to_string()),
+            cid: Some("bafyreie".to_string()),
+            ..DbCollection::new(CollectionArgs {
+                uri: format!("at://{alice}/xyz.flatshcards.collection/3lbhtdkcbyk2c"),
+                author_did: alice.clone(),
+                label: "Japanese".to_string(),
+                description: None,
+                stacks: vec![uri.clone()],
+            })
+        };
+        repo.delete_collection(&collection.uri, Some("3lbhu2aaaaf2a"))
+            .await
+            .unwrap();
+        repo.upsert_collection(&collection).await.unwrap();
+        assert!(
+            !repo
+                .collection_is_owned_by(&alice, &collection.uri)
+                .await
+                .unwrap()
+        );
+        // Our own deletes have no rev, so don't stop anything coming back
+        repo.delete_stack(&uri, None).await.unwrap();
+        repo.upsert_stack(&version("Kitchen", "3lbhu2aaaae2a", "bafyreie"))
+            .await
+            .unwrap();
+        assert!(repo.get_stack(&uri).await.unwrap().is_some());
+    }
+
+    async fn all(repo: &dyn Repo) {
+        ownership(repo).await;
+        stacks_and_cards(repo).await;
+        upserts_keep_the_latest(repo).await;
+        labels_can_repeat(repo).await;
+        versions_converge(repo).await;
+        deletes_stick(repo).await;
+        auth(repo).await;
+    }
+
+    #[tokio::test]
+    async fn memory() {
+        all(&MemoryRepo::default()).await;
+    }
+
+    #[tokio::test]
+    async fn postgres() {
+        let Ok(url) = std::env::var("TEST_DB_URL") else {
+            eprintln!("TEST_DB_URL isn't set, skipping the Postgres repo tests");
+            return;
+        };
+        let pool = PgPool::connect(&url).await.unwrap();
+        create_tables_in_database(&pool).await.unwrap();
+        all(&pool).await;
+    }
+}
@@ -0,0 +1,1393 @@
+use super::{
+    ApiToken, AuthRepo, AuthSession, AuthState, CardCloneData, CardRepo, CardUpdateArgs,
+    CollectionDetails, CollectionRepo, CollectionUpdateArgs, DbCard, DbCollection, DbStack,
+    DisplayCard, GameRepo, MatchScore, RecordVersion, Review, ReviewCounts, ReviewLimits,
+    ReviewRepo, ReviewState, StackCloneData, StackDetails, StackRepo, StackSearch, StackUpdateArgs,
+    StackedReview, StackedReviewState,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Row, postgres::PgPool, types::Json};
+
+/// A stack's tags, as a column of a query on `stack`
+const STACK_TAGS: &str = "ARRAY(
+  SELECT t.name FROM stack_tag st JOIN tag t ON t.id = st.tag_id
+  WHERE st.stack_uri = stack.uri ORDER BY lower(t.name)
+) AS tags";
+/// A card's tags, as a column of a query on `card`
+const CARD_TAGS: &str = "ARRAY(
+  SELECT t.name FROM card_tag ct JOIN tag t ON t.id = ct.tag_id
+  WHERE ct.card_uri = card.uri ORDER BY lower(t.name)
+) AS tags";
+/// When a card was written, as a column of a query on `card`. It's stored without a zone,
+/// in UTC.
+const CARD_CREATED_AT: &str = "created_at AT TIME ZONE 'UTC' AS created_at";
+/// A collection's stacks in order, as a column of a query on `collection`
+const COLLECTION_STACKS: &str = "ARRAY(
+  SELECT cs.stack_uri FROM collection_stack cs
+  WHERE cs.collection_uri = collection.uri ORDER BY cs.position
+) AS stacks";
+
+/// Replaces the tags on the `stack` or `card` at `uri`, adding any tags not seen before
+async fn set_tags(
+    conn: &mut PgConnection,
+    table: &str,
+    uri: &str,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DELETE FROM {table}_tag WHERE {table}_uri = $1"))
+        .bind(uri)
+        .execute(&mut *conn)
+        .await?;
+    if tags.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        "
+INSERT INTO tag (name) SELECT DISTINCT ON (lower(name)) name FROM unnest($1::TEXT[]) AS name
+ON CONFLICT ((lower(name))) DO NOTHING
+",
+    )
+    .bind(tags)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(&format!(
+        "
+INSERT INTO {table}_tag ({table}_uri, tag_id)
+SELECT $1, id FROM tag WHERE lower(name) IN (SELECT lower(unnest($2::TEXT[])))
+ON CONFLICT DO NOTHING
+"
+    ))
+    .bind(uri)
+    .bind(tags)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Replaces the stacks in the collection at `uri`, keeping their order
+async fn set_collection_stacks(
+    conn: &mut PgConnection,
+    uri: &str,
+    stacks: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM collection_stack WHERE collection_uri = $1")
+        .bind(uri)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "
+INSERT INTO collection_stack (collection_uri, position, stack_uri)
+SELECT $1, position, stack_uri FROM unnest($2::TEXT[]) WITH ORDINALITY AS s(stack_uri, position)
+",
+    )
+    .bind(uri)
+    .bind(stacks)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn create_tables_in_database(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS stack (
+  uri TEXT PRIMARY KEY,
+  author_did TEXT NOT NULL,
+  back_lang TEXT,
+  front_lang TEXT,
+  label VARCHAR(100),
+  directions TEXT NOT NULL DEFAULT 'forward',
+  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  indexed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  rev TEXT,
+  cid TEXT
+)",
+    )
+    .execute(pool)
+    .await?;
+    // Languages used to be two-letter ISO 639-1 codes, now they're BCP-47 tags
+    sqlx::query(
+        "ALTER TABLE stack ALTER COLUMN back_lang TYPE TEXT, ALTER COLUMN front_lang TYPE TEXT",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE stack ADD COLUMN IF NOT EXISTS rev TEXT, ADD COLUMN IF NOT EXISTS cid TEXT",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE stack ADD COLUMN IF NOT EXISTS directions TEXT NOT NULL DEFAULT 'forward'",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "
+ALTER TABLE stack
+  ADD COLUMN IF NOT EXISTS description TEXT,
+  ADD COLUMN IF NOT EXISTS source TEXT,
+  ADD COLUMN IF NOT EXISTS license TEXT,
+  ADD COLUMN IF NOT EXISTS difficulty TEXT,
+  ADD COLUMN IF NOT EXISTS cover JSONB
+",
+    )
+    .execute(pool)
+    .await?;
+    // Like the cards' image cids, to look up which stack a cover belongs to
+    sqlx::query(
+        "
+ALTER TABLE stack
+  ADD COLUMN IF NOT EXISTS cover_cid TEXT
+    GENERATED ALWAYS AS (COALESCE(cover->'ref'->>'$link', cover->>'cid')) STORED
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS stack_cover ON stack (cover_cid)")
+        .execute(pool)
+        .await?;
+
+    // Labels needn't be unique: records are written by any client, and one that couldn't
+    // be indexed for its label would never be
+    sqlx::query("DROP INDEX IF EXISTS stack_author_label")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS stack_author ON stack (author_did)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS card (
+  uri TEXT PRIMARY KEY,
+  author_did TEXT NOT NULL,
+  back_lang TEXT NOT NULL,
+  back_text TEXT NOT NULL,
+  front_lang TEXT NOT NULL,
+  front_text TEXT NOT NULL,
+  kind TEXT NOT NULL DEFAULT 'basic',
+  format TEXT NOT NULL DEFAULT 'plain',
+  front_image JSONB,
+  back_image JSONB,
+  audio JSONB,
+  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  indexed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  stack_id TEXT REFERENCES stack(uri) ON DELETE CASCADE,
+  rev TEXT,
+  cid TEXT
+)
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE card ALTER COLUMN back_lang TYPE TEXT, ALTER COLUMN front_lang TYPE TEXT",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE card ADD COLUMN IF NOT EXISTS rev TEXT, ADD COLUMN IF NOT EXISTS cid TEXT",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE card ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'basic'")
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE card ADD COLUMN IF NOT EXISTS format TEXT NOT NULL DEFAULT 'plain'")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "ALTER TABLE card ADD COLUMN IF NOT EXISTS front_image JSONB, ADD COLUMN IF NOT EXISTS back_image JSONB",
+    )
+    .execute(pool)
+    .await?;
+    // The blob refs' cids, to look up which card a blob belongs to. Old records may still
+    // hold untyped refs, with the cid at the top level.
+    sqlx::query(
+        "
+ALTER TABLE card
+  ADD COLUMN IF NOT EXISTS front_image_cid TEXT
+    GENERATED ALWAYS AS (COALESCE(front_image->'ref'->>'$link', front_image->>'cid')) STORED,
+  ADD COLUMN IF NOT EXISTS back_image_cid TEXT
+    GENERATED ALWAYS AS (COALESCE(back_image->'ref'->>'$link', back_image->>'cid')) STORED
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_front_image ON card (front_image_cid)")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_back_image ON card (back_image_cid)")
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE card ADD COLUMN IF NOT EXISTS audio JSONB")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+ALTER TABLE card
+  ADD COLUMN IF NOT EXISTS audio_cid TEXT
+    GENERATED ALWAYS AS (COALESCE(audio->'ref'->>'$link', audio->>'cid')) STORED
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_audio ON card (audio_cid)")
+        .execute(pool)
+        .await?;
+    // This allowed one card per language pair across everyone's stacks
+    sqlx::query("DROP INDEX IF EXISTS card_langs")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_stack ON card (stack_id)")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_lang_pair ON card (front_lang, back_lang)")
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE card ADD COLUMN IF NOT EXISTS position INTEGER")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS auth_state (
+  key TEXT PRIMARY KEY,
+  state TEXT NOT NULL
+)
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS auth_session (
+  key TEXT PRIMARY KEY,
+  session TEXT NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS api_token (
+  token_hash TEXT PRIMARY KEY,
+  did TEXT NOT NULL,
+  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS api_token_did ON api_token (did)")
+        .execute(pool)
+        .await?;
+    // Review times are read back, so unlike the other tables' they have a time zone, which
+    // is what DateTime<Utc> decodes from
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS review_state (
+  reviewer_did TEXT NOT NULL,
+  card_uri TEXT NOT NULL REFERENCES card(uri) ON DELETE CASCADE,
+  direction TEXT NOT NULL,
+  ordinal INTEGER NOT NULL,
+  ease DOUBLE PRECISION NOT NULL,
+  interval_days INTEGER NOT NULL,
+  reps INTEGER NOT NULL,
+  lapses INTEGER NOT NULL,
+  due_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  reviewed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  PRIMARY KEY (reviewer_did, card_uri, direction, ordinal)
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS review_state_card ON review_state (card_uri)")
+        .execute(pool)
+        .await?;
+    // Kept when cards go, as history
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS review (
+  id BIGSERIAL PRIMARY KEY,
+  reviewer_did TEXT NOT NULL,
+  card_uri TEXT NOT NULL,
+  direction TEXT NOT NULL,
+  ordinal INTEGER NOT NULL,
+  grade TEXT NOT NULL,
+  interval_days INTEGER NOT NULL,
+  duration_ms BIGINT NOT NULL,
+  reviewed_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS review_reviewer ON review (reviewer_did, reviewed_at)")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS review_limits (
+  reviewer_did TEXT PRIMARY KEY,
+  new_per_day INTEGER NOT NULL,
+  reviews_per_day INTEGER NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS match_score (
+  player_did TEXT NOT NULL,
+  stack_uri TEXT NOT NULL REFERENCES stack(uri) ON DELETE CASCADE,
+  pairs INTEGER NOT NULL,
+  duration_ms BIGINT NOT NULL,
+  played_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  PRIMARY KEY (player_did, stack_uri, pairs)
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    // Tags are told apart by lowercase, and spelled however they were first used
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS tag (
+  id BIGSERIAL PRIMARY KEY,
+  name TEXT NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS tag_name ON tag (lower(name))")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS stack_tag (
+  stack_uri TEXT NOT NULL REFERENCES stack(uri) ON DELETE CASCADE,
+  tag_id BIGINT NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+  PRIMARY KEY (stack_uri, tag_id)
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS stack_tag_tag ON stack_tag (tag_id)")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS card_tag (
+  card_uri TEXT NOT NULL REFERENCES card(uri) ON DELETE CASCADE,
+  tag_id BIGINT NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+  PRIMARY KEY (card_uri, tag_id)
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS card_tag_tag ON card_tag (tag_id)")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS collection (
+  uri TEXT PRIMARY KEY,
+  author_did TEXT NOT NULL,
+  label VARCHAR(100) NOT NULL,
+  description TEXT,
+  created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  indexed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+  rev TEXT,
+  cid TEXT
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS collection_author ON collection (author_did)")
+        .execute(pool)
+        .await?;
+    // Stacks can be anyone's, and may not be indexed yet or any more, so there's no
+    // foreign key to them
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS collection_stack (
+  collection_uri TEXT NOT NULL REFERENCES collection(uri) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  stack_uri TEXT NOT NULL,
+  PRIMARY KEY (collection_uri, position)
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    // The latest rev each deleted stack, card or collection was deleted at, so a late
+    // create or update from before then isn't ingested
+    sqlx::query(
+        "
+CREATE TABLE IF NOT EXISTS deleted_record (
+  uri TEXT PRIMARY KEY,
+  rev TEXT NOT NULL
+);
+",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Deletes the record at `uri` from `table`, remembering `rev` as when, if it's known
+async fn delete_record(
+    pool: &PgPool,
+    table: &str,
+    uri: &str,
+    rev: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!("DELETE FROM {table} WHERE uri = $1"))
+        .bind(uri)
+        .execute(&mut *tx)
+        .await?;
+    if let Some(rev) = rev {
+        sqlx::query(
+            r#"
+      INSERT INTO deleted_record (uri, rev) VALUES ($1, $2)
+      ON CONFLICT (uri) DO UPDATE SET rev = EXCLUDED.rev
+      WHERE EXCLUDED.rev COLLATE "C" > deleted_record.rev COLLATE "C";
+    "#,
+        )
+        .bind(uri)
+        .bind(rev)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+#[async_trait]
+impl StackRepo for PgPool {
+    async fn save_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "
+      INSERT INTO stack (uri, author_did, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, created_at, indexed_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13);
+    ",
+        )
+        .bind(&stack.uri)
+        .bind(&stack.author_did)
+        .bind(&stack.back_lang)
+        .bind(&stack.front_lang)
+        .bind(&stack.label)
+        .bind(&stack.directions)
+        .bind(&stack.description)
+        .bind(&stack.source)
+        .bind(&stack.license)
+        .bind(&stack.difficulty)
+        .bind(stack.cover.as_ref().map(Json))
+        .bind(stack.created_at)
+        .bind(stack.indexed_at)
+        .execute(&mut *tx)
+        .await?;
+        set_tags(&mut tx, "stack", &stack.uri, &stack.tags).await?;
+        tx.commit().await
+    }
+    async fn upsert_stack(&self, stack: &DbStack) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        // revs are TIDs, which only sort as strings byte by byte, hence COLLATE "C"
+        let res = sqlx::query(
+            r#"
+      INSERT INTO stack (uri, author_did, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, created_at, indexed_at, rev, cid)
+      SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15
+      WHERE NOT EXISTS (SELECT 1 FROM deleted_record
+                        WHERE uri = $1 AND $14 COLLATE "C" <= rev COLLATE "C")
+      ON CONFLICT (uri) DO UPDATE SET
+        back_lang = EXCLUDED.back_lang,
+        front_lang = EXCLUDED.front_lang,
+        label = EXCLUDED.label,
+        directions = EXCLUDED.directions,
+        description = EXCLUDED.description,
+        source = EXCLUDED.source,
+        license = EXCLUDED.license,
+        difficulty = EXCLUDED.difficulty,
+        cover = EXCLUDED.cover,
+        created_at = EXCLUDED.created_at,
+        indexed_at = EXCLUDED.indexed_at,
+        rev = EXCLUDED.rev,
+        cid = EXCLUDED.cid
+      WHERE NOT (stack.rev IS NOT NULL AND EXCLUDED.rev IS NOT NULL
+                 AND EXCLUDED.rev COLLATE "C" < stack.rev COLLATE "C")
+        AND (stack.cid IS NULL OR stack.cid IS DISTINCT FROM EXCLUDED.cid);
+    "#,
+        )
+        .bind(&stack.uri)
+        .bind(&stack.author_did)
+        .bind(&stack.back_lang)
+        .bind(&stack.front_lang)
+        .bind(&stack.label)
+        .bind(&stack.directions)
+        .bind(&stack.description)
+        .bind(&stack.source)
+        .bind(&stack.license)
+        .bind(&stack.difficulty)
+        .bind(stack.cover.as_ref().map(Json))
+        .bind(stack.created_at)
+        .bind(stack.indexed_at)
+        .bind(&stack.rev)
+        .bind(&stack.cid)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() > 0 {
+            set_tags(&mut tx, "stack", &stack.uri, &stack.tags).await?;
+        }
+        tx.commit().await
+    }
+    async fn delete_stack(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        delete_record(self, "stack", uri, rev).await
+    }
+    async fn stack_is_owned_by(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"SELECT EXISTS(SELECT 1 FROM stack WHERE author_did = $1 AND uri = $2) AS "exists""#,
+        )
+        .bind(author_did)
+        .bind(stack_uri)
+        .fetch_one(self)
+        .await
+        .map(|r| r.get("exists"))
+    }
+    async fn get_stack(&self, stack_uri: &str) -> Result<Option<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack WHERE uri = $1 LIMIT 1",
+        ))
+        .bind(stack_uri)
+        .fetch_optional(self)
+        .await
+    }
+    async fn get_stacks(&self, stack_uris: &[String]) -> Result<Vec<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack WHERE uri = ANY($1)",
+        ))
+        .bind(stack_uris)
+        .fetch_all(self)
+        .await
+    }
+    async fn get_owned_stack(
+        &self,
+        author_did: &str,
+        stack_uri: &str,
+    ) -> Result<Option<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+        "SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack WHERE author_did = $1 AND uri = $2 LIMIT 1"
+        )).bind(author_did).bind(stack_uri).fetch_optional(self).await
+    }
+    async fn stack_clone_data(
+        &self,
+        stack_uri: &str,
+    ) -> Result<Option<StackCloneData>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+        SELECT back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack WHERE uri = $1 LIMIT 1
+        ",
+        ))
+        .bind(stack_uri)
+        .fetch_optional(self)
+        .await
+    }
+    async fn update_owned_stack(
+        &self,
+        args: &StackUpdateArgs,
+    ) -> Result<Option<StackDetails>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let updated: Option<String> = sqlx::query_scalar(
+            "
+    UPDATE stack SET back_lang = $3, front_lang = $4, label = $5, directions = $6,
+      description = $7, source = $8, license = $9, difficulty = $10, cover = $11
+    WHERE uri = $1 AND author_did = $2
+    RETURNING uri",
+        )
+        .bind(&args.uri)
+        .bind(&args.author_did)
+        .bind(&args.back_lang)
+        .bind(&args.front_lang)
+        .bind(&args.label)
+        .bind(&args.directions)
+        .bind(&args.description)
+        .bind(&args.source)
+        .bind(&args.license)
+        .bind(&args.difficulty)
+        .bind(args.cover.as_ref().map(Json))
+        .fetch_optional(&mut *tx)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+        set_tags(&mut tx, "stack", &args.uri, &args.tags).await?;
+        tx.commit().await?;
+        self.get_stack(&args.uri).await
+    }
+    async fn user_stacks(&self, did: &str) -> Result<Vec<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack
+WHERE author_did = $1 ORDER BY lower(label)",
+        ))
+        .bind(did)
+        .fetch_all(self)
+        .await
+    }
+    async fn indexed_authors(&self) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query("SELECT author_did FROM stack UNION SELECT author_did FROM collection")
+            .fetch_all(self)
+            .await
+            .map(|rows| rows.into_iter().map(|r| r.get("author_did")).collect())
+    }
+    async fn stack_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        sqlx::query_as("SELECT uri, cid FROM stack WHERE author_did = $1")
+            .bind(author_did)
+            .fetch_all(self)
+            .await
+    }
+    async fn stack_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"SELECT EXISTS(SELECT 1 FROM stack WHERE author_did = $1 AND cover_cid = $2) AS "exists""#,
+        )
+        .bind(author_did)
+        .bind(cid)
+        .fetch_one(self)
+        .await
+        .map(|r| r.get("exists"))
+    }
+    async fn search_stacks(
+        &self,
+        search: &StackSearch,
+        limit: i64,
+    ) -> Result<Vec<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS} FROM stack
+WHERE strpos(lower(label), lower($1)) > 0
+  AND ($2::TEXT IS NULL OR EXISTS (
+    SELECT 1 FROM stack_tag st JOIN tag t ON t.id = st.tag_id
+    WHERE st.stack_uri = stack.uri AND lower(t.name) = lower($2)
+  ) OR EXISTS (
+    SELECT 1 FROM card c JOIN card_tag ct ON ct.card_uri = c.uri JOIN tag t ON t.id = ct.tag_id
+    WHERE c.stack_id = stack.uri AND lower(t.name) = lower($2)
+  ))
+  AND ($3::TEXT IS NULL OR author_did = $3)
+ORDER BY indexed_at DESC
+LIMIT $4
+"
+        ))
+        .bind(&search.text)
+        .bind(&search.tag)
+        .bind(&search.author_did)
+        .bind(limit)
+        .fetch_all(self)
+        .await
+    }
+    async fn user_tags(&self, author_did: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "
+SELECT name FROM tag
+WHERE id IN (
+  SELECT st.tag_id FROM stack_tag st JOIN stack s ON s.uri = st.stack_uri WHERE s.author_did = $1
+  UNION
+  SELECT ct.tag_id FROM card_tag ct JOIN card c ON c.uri = ct.card_uri WHERE c.author_did = $1
+)
+ORDER BY lower(name)
+",
+        )
+        .bind(author_did)
+        .fetch_all(self)
+        .await
+    }
+}
+
+#[async_trait]
+impl CardRepo for PgPool {
+    async fn save_card(&self, card: &DbCard) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "
+      INSERT INTO card (uri, author_did, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, created_at, indexed_at, stack_id, position)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15);
+    ",
+        )
+        .bind(&card.uri)
+        .bind(&card.author_did)
+        .bind(&card.back_lang)
+        .bind(&card.back_text)
+        .bind(&card.front_lang)
+        .bind(&card.front_text)
+        .bind(&card.kind)
+        .bind(&card.format)
+        .bind(card.front_image.as_ref().map(Json))
+        .bind(card.back_image.as_ref().map(Json))
+        .bind(card.audio.as_ref().map(Json))
+        .bind(card.created_at)
+        .bind(card.indexed_at)
+        .bind(&card.stack_id)
+        .bind(card.position)
+        .execute(&mut *tx)
+        .await?;
+        set_tags(&mut tx, "card", &card.uri, &card.tags).await?;
+        tx.commit().await
+    }
+    async fn upsert_card(&self, card: &DbCard) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let res = sqlx::query(
+            r#"
+      INSERT INTO card (uri, author_did, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, created_at, indexed_at, stack_id, rev, cid, position)
+      SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17
+      WHERE NOT EXISTS (SELECT 1 FROM deleted_record
+                        WHERE uri = $1 AND $15 COLLATE "C" <= rev COLLATE "C")
+      ON CONFLICT (uri) DO UPDATE SET
+        back_lang = EXCLUDED.back_lang,
+        back_text = EXCLUDED.back_text,
+        front_lang = EXCLUDED.front_lang,
+        front_text = EXCLUDED.front_text,
+        kind = EXCLUDED.kind,
+        format = EXCLUDED.format,
+        front_image = EXCLUDED.front_image,
+        back_image = EXCLUDED.back_image,
+        audio = EXCLUDED.audio,
+        created_at = EXCLUDED.created_at,
+        indexed_at = EXCLUDED.indexed_at,
+        stack_id = EXCLUDED.stack_id,
+        rev = EXCLUDED.rev,
+        cid = EXCLUDED.cid,
+        position = EXCLUDED.position
+      WHERE NOT (card.rev IS NOT NULL AND EXCLUDED.rev IS NOT NULL
+                 AND EXCLUDED.rev COLLATE "C" < card.rev COLLATE "C")
+        AND (card.cid IS NULL OR card.cid IS DISTINCT FROM EXCLUDED.cid);
+    "#,
+        )
+        .bind(&card.uri)
+        .bind(&card.author_did)
+        .bind(&card.back_lang)
+        .bind(&card.back_text)
+        .bind(&card.front_lang)
+        .bind(&card.front_text)
+        .bind(&card.kind)
+        .bind(&card.format)
+        .bind(card.front_image.as_ref().map(Json))
+        .bind(card.back_image.as_ref().map(Json))
+        .bind(card.audio.as_ref().map(Json))
+        .bind(card.created_at)
+        .bind(card.indexed_at)
+        .bind(&card.stack_id)
+        .bind(&card.rev)
+        .bind(&card.cid)
+        .bind(card.position)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() > 0 {
+            set_tags(&mut tx, "card", &card.uri, &card.tags).await?;
+        }
+        tx.commit().await
+    }
+    async fn delete_card(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        delete_record(self, "card", uri, rev).await
+    }
+    async fn card_is_owned_by(
+        &self,
+        author_did: &str,
+        card_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"SELECT EXISTS(SELECT 1 FROM card WHERE author_did = $1 AND uri = $2) AS "exists""#,
+        )
+        .bind(author_did)
+        .bind(card_uri)
+        .fetch_one(self)
+        .await
+        .map(|r| r.get("exists"))
+    }
+    async fn get_owned_card(
+        &self,
+        author_did: &str,
+        card_uri: &str,
+    ) -> Result<Option<DbCard>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, author_did, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, position, {CARD_CREATED_AT}, indexed_at AT TIME ZONE 'UTC' AS indexed_at, stack_id, rev, cid, {CARD_TAGS}
+FROM card WHERE author_did = $1 AND uri = $2 LIMIT 1
+"
+        ))
+        .bind(author_did)
+        .bind(card_uri)
+        .fetch_optional(self)
+        .await
+    }
+    async fn card_clone_data(&self, stack_uri: &str) -> Result<Vec<CardCloneData>, sqlx::Error> {
+        let res = sqlx::query_as(&format!(
+            "
+        SELECT back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, {CARD_TAGS}
+        FROM card WHERE stack_id = $1
+        ORDER BY position NULLS LAST, created_at, uri
+        ",
+        ))
+        .bind(stack_uri)
+        .fetch_all(self)
+        .await?;
+        Ok(res)
+    }
+    async fn update_owned_card(
+        &self,
+        args: &CardUpdateArgs,
+    ) -> Result<Option<DisplayCard>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let updated: Option<String> = sqlx::query_scalar(
+            "
+    UPDATE card SET back_lang = $3, back_text = $4, front_lang = $5, front_text = $6, kind = $7, format = $8,
+      front_image = $9, back_image = $10, audio = $11, stack_id = $12, position = $13
+    WHERE uri = $1 AND author_did = $2
+    RETURNING uri",
+        )
+        .bind(&args.uri)
+        .bind(&args.author_did)
+        .bind(&args.back_lang)
+        .bind(&args.back_text)
+        .bind(&args.front_lang)
+        .bind(&args.front_text)
+        .bind(&args.kind)
+        .bind(&args.format)
+        .bind(args.front_image.as_ref().map(Json))
+        .bind(args.back_image.as_ref().map(Json))
+        .bind(args.audio.as_ref().map(Json))
+        .bind(&args.stack_id)
+        .bind(args.position)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+        set_tags(&mut tx, "card", &args.uri, &args.tags).await?;
+        tx.commit().await?;
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, position, {CARD_CREATED_AT}, {CARD_TAGS}
+FROM card WHERE uri = $1
+"
+        ))
+        .bind(&args.uri)
+        .fetch_optional(self)
+        .await
+    }
+    async fn stack_cards(&self, stack_uri: &str) -> Result<Vec<DisplayCard>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, position, {CARD_CREATED_AT}, {CARD_TAGS}
+FROM card WHERE stack_id = $1
+ORDER BY position NULLS LAST, created_at, uri
+",
+        ))
+        .bind(stack_uri)
+        .fetch_all(self)
+        .await
+    }
+    async fn lang_pair_cards(
+        &self,
+        front_lang: &str,
+        back_lang: &str,
+        limit: i64,
+    ) -> Result<Vec<DisplayCard>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, back_text, front_lang, front_text, kind, format, front_image, back_image, audio, position, {CARD_CREATED_AT}, {CARD_TAGS}
+FROM card
+WHERE front_lang = $1 AND back_lang = $2
+ORDER BY random() LIMIT $3
+",
+        ))
+        .bind(front_lang)
+        .bind(back_lang)
+        .bind(limit)
+        .fetch_all(self)
+        .await
+    }
+    async fn card_versions(&self, author_did: &str) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        sqlx::query_as("SELECT uri, cid FROM card WHERE author_did = $1")
+            .bind(author_did)
+            .fetch_all(self)
+            .await
+    }
+    async fn card_uses_blob(&self, author_did: &str, cid: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"SELECT EXISTS(SELECT 1 FROM card WHERE author_did = $1 AND (front_image_cid = $2 OR back_image_cid = $2 OR audio_cid = $2)) AS "exists""#,
+        )
+        .bind(author_did)
+        .bind(cid)
+        .fetch_one(self)
+        .await
+        .map(|r| r.get("exists"))
+    }
+    async fn most_used_langs(
+        &self,
+        author_did: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "
+SELECT lang FROM (
+  SELECT front_lang AS lang FROM card WHERE author_did = $1
+  UNION ALL
+  SELECT back_lang AS lang FROM card WHERE author_did = $1
+) AS used
+GROUP BY lang
+ORDER BY COUNT(*) DESC, lang
+LIMIT $2
+",
+        )
+        .bind(author_did)
+        .bind(limit)
+        .fetch_all(self)
+        .await
+    }
+}
+
+#[async_trait]
+impl CollectionRepo for PgPool {
+    async fn save_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "
+      INSERT INTO collection (uri, author_did, label, description, created_at, indexed_at)
+      VALUES ($1, $2, $3, $4, $5, $6);
+    ",
+        )
+        .bind(&collection.uri)
+        .bind(&collection.author_did)
+        .bind(&collection.label)
+        .bind(&collection.description)
+        .bind(collection.created_at)
+        .bind(collection.indexed_at)
+        .execute(&mut *tx)
+        .await?;
+        set_collection_stacks(&mut tx, &collection.uri, &collection.stacks).await?;
+        tx.commit().await
+    }
+    async fn upsert_collection(&self, collection: &DbCollection) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let res = sqlx::query(
+            r#"
+      INSERT INTO collection (uri, author_did, label, description, created_at, indexed_at, rev, cid)
+      SELECT $1, $2, $3, $4, $5, $6, $7, $8
+      WHERE NOT EXISTS (SELECT 1 FROM deleted_record
+                        WHERE uri = $1 AND $7 COLLATE "C" <= rev COLLATE "C")
+      ON CONFLICT (uri) DO UPDATE SET
+        label = EXCLUDED.label,
+        description = EXCLUDED.description,
+        created_at = EXCLUDED.created_at,
+        indexed_at = EXCLUDED.indexed_at,
+        rev = EXCLUDED.rev,
+        cid = EXCLUDED.cid
+      WHERE NOT (collection.rev IS NOT NULL AND EXCLUDED.rev IS NOT NULL
+                 AND EXCLUDED.rev COLLATE "C" < collection.rev COLLATE "C")
+        AND (collection.cid IS NULL OR collection.cid IS DISTINCT FROM EXCLUDED.cid);
+    "#,
+        )
+        .bind(&collection.uri)
+        .bind(&collection.author_did)
+        .bind(&collection.label)
+        .bind(&collection.description)
+        .bind(collection.created_at)
+        .bind(collection.indexed_at)
+        .bind(&collection.rev)
+        .bind(&collection.cid)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() > 0 {
+            set_collection_stacks(&mut tx, &collection.uri, &collection.stacks).await?;
+        }
+        tx.commit().await
+    }
+    async fn delete_collection(&self, uri: &str, rev: Option<&str>) -> Result<(), sqlx::Error> {
+        delete_record(self, "collection", uri, rev).await
+    }
+    async fn collection_is_owned_by(
+        &self,
+        author_did: &str,
+        collection_uri: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"SELECT EXISTS(SELECT 1 FROM collection WHERE author_did = $1 AND uri = $2) AS "exists""#,
+        )
+        .bind(author_did)
+        .bind(collection_uri)
+        .fetch_one(self)
+        .await
+        .map(|r| r.get("exists"))
+    }
+    async fn get_collection(
+        &self,
+        collection_uri: &str,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT uri, author_did, label, description, {COLLECTION_STACKS} FROM collection WHERE uri = $1",
+        ))
+        .bind(collection_uri)
+        .fetch_optional(self)
+        .await
+    }
+    async fn update_owned_collection(
+        &self,
+        args: &CollectionUpdateArgs,
+    ) -> Result<Option<CollectionDetails>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let updated: Option<String> = sqlx::query_scalar(
+            "
+    UPDATE collection SET label = $3, description = $4
+    WHERE uri = $1 AND author_did = $2
+    RETURNING uri",
+        )
+        .bind(&args.uri)
+        .bind(&args.author_did)
+        .bind(&args.label)
+        .bind(&args.description)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+        set_collection_stacks(&mut tx, &args.uri, &args.stacks).await?;
+        tx.commit().await?;
+        self.get_collection(&args.uri).await
+    }
+    async fn user_collections(&self, did: &str) -> Result<Vec<CollectionDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, author_did, label, description, {COLLECTION_STACKS} FROM collection
+WHERE author_did = $1 ORDER BY lower(label)",
+        ))
+        .bind(did)
+        .fetch_all(self)
+        .await
+    }
+    async fn collection_versions(
+        &self,
+        author_did: &str,
+    ) -> Result<Vec<RecordVersion>, sqlx::Error> {
+        sqlx::query_as("SELECT uri, cid FROM collection WHERE author_did = $1")
+            .bind(author_did)
+            .fetch_all(self)
+            .await
+    }
+}
+
+#[async_trait]
+impl ReviewRepo for PgPool {
+    async fn stack_review_states(
+        &self,
+        reviewer_did: &str,
+        stack_uri: &str,
+    ) -> Result<Vec<ReviewState>, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT r.reviewer_did, r.card_uri, r.direction, r.ordinal, r.ease, r.interval_days, r.reps, r.lapses,
+  r.due_at, r.reviewed_at
+FROM review_state r JOIN card c ON c.uri = r.card_uri
+WHERE r.reviewer_did = $1 AND c.stack_id = $2
+",
+        )
+        .bind(reviewer_did)
+        .bind(stack_uri)
+        .fetch_all(self)
+        .await
+    }
+    async fn save_review(&self, state: &ReviewState, review: &Review) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "
+      INSERT INTO review_state (reviewer_did, card_uri, direction, ordinal, ease, interval_days, reps, lapses, due_at, reviewed_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+      ON CONFLICT (reviewer_did, card_uri, direction, ordinal) DO UPDATE SET
+        ease = EXCLUDED.ease,
+        interval_days = EXCLUDED.interval_days,
+        reps = EXCLUDED.reps,
+        lapses = EXCLUDED.lapses,
+        due_at = EXCLUDED.due_at,
+        reviewed_at = EXCLUDED.reviewed_at
+    ",
+        )
+        .bind(&state.reviewer_did)
+        .bind(&state.card_uri)
+        .bind(&state.direction)
+        .bind(state.ordinal)
+        .bind(state.ease)
+        .bind(state.interval_days)
+        .bind(state.reps)
+        .bind(state.lapses)
+        .bind(state.due_at)
+        .bind(state.reviewed_at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "
+      INSERT INTO review (reviewer_did, card_uri, direction, ordinal, grade, interval_days, duration_ms, reviewed_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    ",
+        )
+        .bind(&review.reviewer_did)
+        .bind(&review.card_uri)
+        .bind(&review.direction)
+        .bind(review.ordinal)
+        .bind(&review.grade)
+        .bind(review.interval_days)
+        .bind(review.duration_ms)
+        .bind(review.reviewed_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    async fn reviewer_history(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StackedReview>, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT r.reviewer_did, r.card_uri, r.direction, r.ordinal, r.grade, r.interval_days, r.duration_ms,
+  r.reviewed_at, c.stack_id AS stack_uri
+FROM review r LEFT JOIN card c ON c.uri = r.card_uri
+WHERE r.reviewer_did = $1 AND r.reviewed_at >= $2
+ORDER BY r.reviewed_at
+",
+        )
+        .bind(reviewer_did)
+        .bind(since)
+        .fetch_all(self)
+        .await
+    }
+    async fn reviewer_states(
+        &self,
+        reviewer_did: &str,
+    ) -> Result<Vec<StackedReviewState>, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT r.reviewer_did, r.card_uri, r.direction, r.ordinal, r.ease, r.interval_days, r.reps, r.lapses,
+  r.due_at, r.reviewed_at, c.stack_id AS stack_uri
+FROM review_state r JOIN card c ON c.uri = r.card_uri
+WHERE r.reviewer_did = $1 AND c.stack_id IS NOT NULL
+",
+        )
+        .bind(reviewer_did)
+        .fetch_all(self)
+        .await
+    }
+    async fn reviewed_stacks(&self, reviewer_did: &str) -> Result<Vec<StackDetails>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "
+SELECT uri, back_lang, front_lang, label, directions, description, source, license, difficulty, cover, {STACK_TAGS}
+FROM stack
+WHERE EXISTS (
+  SELECT 1 FROM review_state r JOIN card c ON c.uri = r.card_uri
+  WHERE r.reviewer_did = $1 AND c.stack_id = stack.uri
+)
+",
+        ))
+        .bind(reviewer_did)
+        .fetch_all(self)
+        .await
+    }
+    async fn review_counts(
+        &self,
+        reviewer_did: &str,
+        since: DateTime<Utc>,
+    ) -> Result<ReviewCounts, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT
+  COUNT(DISTINCT (card_uri, direction, ordinal)) FILTER (WHERE first_at >= $2) AS new_items,
+  COUNT(*) FILTER (WHERE first_at < $2) AS reviews
+FROM (
+  SELECT card_uri, direction, ordinal, reviewed_at,
+    MIN(reviewed_at) OVER (PARTITION BY card_uri, direction, ordinal) AS first_at
+  FROM review
+  WHERE reviewer_did = $1
+) r
+WHERE reviewed_at >= $2
+",
+        )
+        .bind(reviewer_did)
+        .bind(since)
+        .fetch_one(self)
+        .await
+    }
+    async fn review_limits(&self, reviewer_did: &str) -> Result<Option<ReviewLimits>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT reviewer_did, new_per_day, reviews_per_day FROM review_limits WHERE reviewer_did = $1",
+        )
+        .bind(reviewer_did)
+        .fetch_optional(self)
+        .await
+    }
+    async fn save_review_limits(&self, limits: &ReviewLimits) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "
+INSERT INTO review_limits (reviewer_did, new_per_day, reviews_per_day)
+VALUES ($1, $2, $3)
+ON CONFLICT (reviewer_did) DO UPDATE SET
+  new_per_day = EXCLUDED.new_per_day,
+  reviews_per_day = EXCLUDED.reviews_per_day
+",
+        )
+        .bind(&limits.reviewer_did)
+        .bind(limits.new_per_day)
+        .bind(limits.reviews_per_day)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameRepo for PgPool {
+    async fn best_match(
+        &self,
+        player_did: &str,
+        stack_uri: &str,
+        pairs: i32,
+    ) -> Result<Option<MatchScore>, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT player_did, stack_uri, pairs, duration_ms, played_at FROM match_score
+WHERE player_did = $1 AND stack_uri = $2 AND pairs = $3
+",
+        )
+        .bind(player_did)
+        .bind(stack_uri)
+        .bind(pairs)
+        .fetch_optional(self)
+        .await
+    }
+    async fn save_match(&self, score: &MatchScore) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            "
+      INSERT INTO match_score (player_did, stack_uri, pairs, duration_ms, played_at)
+      VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (player_did, stack_uri, pairs) DO UPDATE SET
+        duration_ms = EXCLUDED.duration_ms,
+        played_at = EXCLUDED.played_at
+      WHERE match_score.duration_ms > EXCLUDED.duration_ms
+    ",
+        )
+        .bind(&score.player_did)
+        .bind(&score.stack_uri)
+        .bind(score.pairs)
+        .bind(score.duration_ms)
+        .bind(score.played_at)
+        .execute(self)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl AuthRepo for PgPool {
+    async fn get_session(&self, did: &str) -> Result<Option<AuthSession>, sqlx::Error> {
+        sqlx::query_as(
+            "
+SELECT key, session FROM auth_session WHERE key = $1 LIMIT 1
+",
+        )
+        .bind(did)
+        .fetch_optional(self)
+        .await
+    }
+    async fn save_session(&self, session: &AuthSession) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO auth_session (key, session) VALUES ($1, $2)
+            ON CONFLICT (key) DO UPDATE SET session = EXCLUDED.session",
+        )
+        .bind(&session.key)
+        .bind(&session.session)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+    async fn delete_session(&self, did: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM auth_session WHERE key = $1")
+            .bind(did)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+    async fn clear_sessions(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM auth_session")
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+    async fn get_state(&self, key: &str) -> Result<Option<AuthState>, sqlx::Error> {
+        let res = sqlx::query_as(
+            "
+        SELECT key, state FROM auth_state WHERE key = $1 LIMIT 1
+                ",
+        )
+        .bind(key)
+        .fetch_optional(self)
+        .await?;
+        Ok(res)
+    }
+    async fn save_state(&self, state: &AuthState) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO auth_state (key, state) VALUES ($1, $2)
+            ON CONFLICT (key) DO UPDATE SET state = EXCLUDED.state",
+        )
+        .bind(&state.key)
+        .bind(&state.state)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+    async fn delete_state(&self, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "
+DELETE FROM auth_state WHERE key = $1
+",
+        )
+        .bind(key)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+    async fn clear_states(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM auth_state").execute(self).await?;
+        Ok(())
+    }
+    async fn save_api_token(&self, token: &ApiToken) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO api_token (token_hash, did, created_at) VALUES ($1, $2, $3)")
+            .bind(&token.token_hash)
+            .bind(&token.did)
+            .bind(token.created_at)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+    async fn api_token_did(&self, token_hash: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query("SELECT did FROM api_token WHERE token_hash = $1 LIMIT 1")
+            .bind(token_hash)
+            .fetch_optional(self)
+            .await
+            .map(|r| r.map(|r| r.get("did")))
+    }
+    async fn delete_api_token(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM api_token WHERE token_hash = $1")
+            .bind(token_hash)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+//! Interface translations. Messages are Fluent files under `locales/`, one directory per
+//! locale; anything missing from a catalog falls back to English.
+//!
+//! Handlers take a [`Locale`] like any other extractor and hand it to their templates,
+//! which look messages up with [`Locale::t`].
+use actix_session::SessionExt;
+use actix_web::{FromRequest, HttpRequest, dev::Payload, http::header::ACCEPT_LANGUAGE};
+use fluent_templates::{LanguageIdentifier, Loader, fluent_bundle::FluentValue, static_loader};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::future::{Ready, ready};
+use std::sync::LazyLock;
+
+static_loader! {
+    static CATALOGS = {
+        locales: "./locales",
+        fallback_language: "en",
+    };
+}
+
+/// The locales there's a catalog for, each named in itself. The first is the default.
+pub const LOCALES_OFFERED: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("es", "español"),
+    ("fr", "français"),
+    ("de", "Deutsch"),
+    ("ja", "日本語"),
+    ("pt-BR", "português (Brasil)"),
+];
+
+/// Session key of the locale someone picked, which wins over `Accept-Language`
+pub const LOCALE_KEY: &str = "locale";
+
+static LOCALE_IDS: LazyLock<Vec<LanguageIdentifier>> = LazyLock::new(|| {
+    LOCALES_OFFERED
+        .iter()
+        .map(|(tag, _)| tag.parse().expect("offered locales should be valid"))
+        .collect()
+});
+
+/// The locale a request is answered in
+#[derive(Debug, Clone, Copy)]
+pub struct Locale(&'static LanguageIdentifier);
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self(&LOCALE_IDS[0])
+    }
+}
+
+impl Locale {
+    /// The offered locale for `tag`: exactly, or else by language alone, so `pt` and
+    /// `pt-PT` get `pt-BR`
+    pub fn matching(tag: &str) -> Option<Self> {
+        let language = |tag: &str| tag.split('-').next().unwrap_or(tag).to_ascii_lowercase();
+        let offered = || LOCALES_OFFERED.iter().map(|(tag, _)| *tag).enumerate();
+        offered()
+            .find(|(_, offered)| offered.eq_ignore_ascii_case(tag))
+            .or_else(|| offered().find(|(_, offered)| language(offered) == language(tag)))
+            .map(|(i, _)| Self(&LOCALE_IDS[i]))
+    }
+
+    /// Picks the locale for someone who chose `preference`, if anyone did, and whose
+    /// browser sent `accept_language`
+    pub fn negotiate(preference: Option<&str>, accept_language: Option<&str>) -> Self {
+        let mut ranges: Vec<(f32, &str)> = accept_language
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';');
+                let tag = params.next()?.trim();
+                let quality = match params.find_map(|p| p.trim().strip_prefix("q=")) {
+                    Some(q) => q.parse().ok()?,
+                    None => 1.0,
+                };
+                (!tag.is_empty() && quality > 0.0).then_some((quality, tag))
+            })
+            .collect();
+        // Stable, so equal weights keep the browser's order
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        preference
+            .into_iter()
+            .chain(ranges.into_iter().map(|(_, tag)| tag))
+            .find_map(Self::matching)
+            .unwrap_or_default()
+    }
+
+    pub fn tag(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn is(&self, tag: &str) -> bool {
+        self.tag() == tag
+    }
+
+    pub fn offered(&self) -> &'static [(&'static str, &'static str)] {
+        LOCALES_OFFERED
+    }
+
+    /// The message `id`
+    pub fn t(&self, id: &str) -> String {
+        CATALOGS.lookup(self.0, id)
+    }
+
+    /// The message `id`, with `value` for its variable `arg`
+    pub fn t_arg(&self, id: &str, arg: &'static str, value: impl AsRef<str>) -> String {
+        let args = HashMap::from([(Cow::Borrowed(arg), FluentValue::from(value.as_ref()))]);
+        CATALOGS.lookup_with_args(self.0, id, &args)
+    }
+
+    /// The message `id`, with values for several of its variables
+    pub fn t_args(&self, id: &str, args: &[(&'static str, String)]) -> String {
+        let args = args
+            .iter()
+            .map(|(arg, value)| (Cow::Borrowed(*arg), FluentValue::from(value.as_str())))
+            .collect();
+        CATALOGS.lookup_with_args(self.0, id, &args)
+    }
+}
+
+/// A message that's translated where it's shown rather than where it's made, like why a
+/// form didn't validate: its id, and values for its variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub id: &'static str,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Message {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            args: Vec::new(),
+        }
+    }
+
+    /// The message with `value` for its variable `arg`
+    pub fn arg(mut self, arg: &'static str, value: impl ToString) -> Self {
+        self.args.push((arg, value.to_string()));
+        self
+    }
+
+    pub fn text(&self, locale: Locale) -> String {
+        locale.t_args(self.id, &self.args)
+    }
+}
+
+/// In the default locale, for logs and the JSON API. Those aren't laid out in HTML, so
+/// there's no need for the marks Fluent isolates each variable's value with.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            &self
+                .text(Locale::default())
+                .replace(['\u{2068}', '\u{2069}'], ""),
+        )
+    }
+}
+
+impl FromRequest for Locale {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let preference = req.get_session().get::<String>(LOCALE_KEY).unwrap_or(None);
+        let accept_language = req
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok());
+        ready(Ok(Self::negotiate(preference.as_deref(), accept_language)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn ids(tag: &str) -> BTreeSet<String> {
+        std::fs::read_to_string(format!("locales/{tag}/main.ftl"))
+            .expect("catalog should be readable")
+            .lines()
+            .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+            .filter(|id| !id.is_empty() && !id.starts_with([' ', '#', '.']))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn every_catalog_has_every_message() {
+        let en = ids("en");
+        for (tag, _) in LOCALES_OFFERED {
+            assert_eq!(ids(tag), en, "{tag}");
+        }
+    }
+
+    #[test]
+    fn messages_translate_with_their_args() {
+        let message = Message::new("invalid-tag-count").arg("max", 10);
+        assert_eq!(message.to_string(), "At most 10 tags are allowed");
+        let de = Locale::matching("de").unwrap();
+        assert!(message.text(de).contains("Höchstens"));
+        assert!(
+            Message::new("invalid-cloze-none")
+                .to_string()
+                .contains("{{c1::answer}}")
+        );
+    }
+}
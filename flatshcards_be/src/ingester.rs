@@ -1,5 +1,11 @@
-use crate::db;
-use crate::lexicons::xyz::flatshcards::{self, card, stack};
+use crate::cloze::KIND_BASIC;
+use crate::collections;
+use crate::db::{self, Repo};
+use crate::lexicons::xyz::flatshcards::{self, card, collection, stack};
+use crate::review::DIRECTION_FORWARD;
+use crate::rich::FORMAT_PLAIN;
+use crate::stacks;
+use crate::tags;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use atrium_api::types::Collection;
@@ -12,14 +18,16 @@ use rocketman::{
     types::event::{Event, Operation},
 };
 use serde_json::Value;
-use sqlx::postgres::PgPool;
 use std::{
     collections::HashMap,
+    io::BufRead,
     sync::{Arc, Mutex},
 };
 
+type Ingesters = HashMap<String, Box<dyn LexiconIngestor + Send + Sync>>;
+
 pub struct FlatshcardsStackIngester {
-    db_pool: PgPool,
+    repo: Arc<dyn Repo>,
 }
 #[async_trait]
 impl LexiconIngestor for FlatshcardsStackIngester {
@@ -38,32 +46,59 @@ impl LexiconIngestor for FlatshcardsStackIngester {
                                     back_lang,
                                     front_lang,
                                     label,
+                                    directions,
+                                    description,
+                                    source,
+                                    license,
+                                    difficulty,
+                                    cover,
+                                    tags,
                                 },
                             ..
                         } = serde_json::from_value::<stack::StackRecord>(record.clone())?;
 
-                        if let Some(ref _cid) = commit.cid {
+                        if let Some(ref cid) = commit.cid {
                             // Although esquema does not have full validation yet,
                             // if you get to this point,
                             // You know the data structure is the same
                             let created_at = created_at.as_ref().to_utc();
                             let right_now = chrono::Utc::now();
-                            // We save or update the record in the db
-                            db::DbStack {
+                            // We save or update the record in the db, unless we've
+                            // already seen this or a later version of it
+                            let stack = db::DbStack {
                                 uri: record_uri,
                                 author_did: message.did.clone(),
                                 back_lang,
                                 front_lang,
                                 label,
+                                directions: directions
+                                    .unwrap_or_else(|| DIRECTION_FORWARD.to_string()),
+                                description: description.and_then(|d| {
+                                    stacks::truncated(&d, stacks::MAX_DESCRIPTION_LENGTH)
+                                }),
+                                source: source
+                                    .and_then(|s| stacks::truncated(&s, stacks::MAX_SOURCE_LENGTH)),
+                                // Licenses are open-ended, and shown as they are if unknown
+                                license: license.and_then(|l| {
+                                    stacks::truncated(&l, stacks::MAX_LICENSE_LENGTH)
+                                }),
+                                difficulty: difficulty.filter(|d| stacks::is_difficulty(d)),
+                                cover,
+                                tags: tags::normalize(&tags.unwrap_or_default()),
                                 created_at,
                                 indexed_at: right_now,
-                            }
-                            .upsert(&self.db_pool)
-                            .await?;
+                                rev: Some(commit.rev.clone()),
+                                cid: Some(cid.clone()),
+                            };
+                            self.repo.upsert_stack(&stack).await?;
                         }
                     }
                 }
-                Operation::Delete => db::DbStack::delete_by_uri(&record_uri, &self.db_pool).await?,
+                Operation::Delete => {
+                    self.repo
+                        .delete_stack(&record_uri, Some(&commit.rev))
+                        .await?
+                }
             }
         } else {
             return Err(anyhow!("Message has no commit"));
@@ -73,7 +108,7 @@ impl LexiconIngestor for FlatshcardsStackIngester {
 }
 
 pub struct FlatshcardsCardIngester {
-    db_pool: PgPool,
+    repo: Arc<dyn Repo>,
 }
 #[async_trait]
 impl LexiconIngestor for FlatshcardsCardIngester {
@@ -93,35 +128,67 @@ impl LexiconIngestor for FlatshcardsCardIngester {
                                     back_text,
                                     front_lang,
                                     front_text,
+                                    kind,
+                                    format,
+                                    front_image,
+                                    back_image,
+                                    audio,
+                                    position,
                                     stack_id,
+                                    tags,
                                 },
                             ..
                         } = serde_json::from_value::<card::CardRecord>(record.clone())?;
 
-                        if let Some(ref _cid) = commit.cid {
+                        if let Some(ref cid) = commit.cid {
                             // Although esquema does not have full validation yet,
                             // if you get to this point,
                             // You know the data structure is the same
                             let created_at = created_at.as_ref().to_utc();
                             let right_now = chrono::Utc::now();
-                            // We save or update the record in the db
-                            db::DbCard {
+                            // The record only has the stack's rkey, which is in the
+                            // same repo as the card
+                            let stack_uri = format!(
+                                "at://{}/{}/{}",
+                                message.did,
+                                flatshcards::Stack::NSID,
+                                stack_id.as_str()
+                            );
+                            // We save or update the record in the db, unless we've
+                            // already seen this or a later version of it
+                            let card = db::DbCard {
                                 uri: record_uri,
                                 author_did: message.did.clone(),
                                 back_lang,
                                 back_text,
                                 front_lang,
                                 front_text,
-                                stack_id: stack_id.into(),
+                                kind: kind.unwrap_or_else(|| KIND_BASIC.to_string()),
+                                format: format.unwrap_or_else(|| FORMAT_PLAIN.to_string()),
+                                front_image,
+                                back_image,
+                                audio,
+                                tags: tags::normalize(&tags.unwrap_or_default()),
+                                // Records from elsewhere can break the rules, and then
+                                // go last like cards from before there were positions
+                                position: position
+                                    .and_then(|p| i32::try_from(p).ok())
+                                    .filter(|p| *p >= 0),
+                                stack_id: stack_uri,
                                 created_at,
                                 indexed_at: right_now,
-                            }
-                            .upsert(&self.db_pool)
-                            .await?;
+                                rev: Some(commit.rev.clone()),
+                                cid: Some(cid.clone()),
+                            };
+                            self.repo.upsert_card(&card).await?;
                         }
                     }
                 }
-                Operation::Delete => db::DbCard::delete_by_uri(&record_uri, &self.db_pool).await?,
+                Operation::Delete => {
+                    self.repo
+                        .delete_card(&record_uri, Some(&commit.rev))
+                        .await?
+                }
             }
         } else {
             return Err(anyhow!("Message has no commit"));
@@ -129,7 +196,76 @@ impl LexiconIngestor for FlatshcardsCardIngester {
         Ok(())
     }
 }
-pub async fn start_ingester(db_pool: PgPool) {
+pub struct FlatshcardsCollectionIngester {
+    repo: Arc<dyn Repo>,
+}
+#[async_trait]
+impl LexiconIngestor for FlatshcardsCollectionIngester {
+    async fn ingest(&self, message: Event<Value>) -> anyhow::Result<()> {
+        if let Some(commit) = &message.commit {
+            let record_uri = format!("at://{}/{}/{}", message.did, commit.collection, commit.rkey);
+            match commit.operation {
+                Operation::Create | Operation::Update => {
+                    if let Some(record) = &commit.record {
+                        let collection::CollectionRecord {
+                            data:
+                                collection::Collection {
+                                    created_at,
+                                    label,
+                                    description,
+                                    stacks,
+                                },
+                            ..
+                        } = serde_json::from_value::<collection::CollectionRecord>(record.clone())?;
+
+                        if let Some(ref cid) = commit.cid {
+                            let collection = db::DbCollection {
+                                uri: record_uri,
+                                author_did: message.did.clone(),
+                                label,
+                                description: description.and_then(|d| collections::description(&d)),
+                                stacks: collections::normalize(&stacks),
+                                created_at: created_at.as_ref().to_utc(),
+                                indexed_at: chrono::Utc::now(),
+                                rev: Some(commit.rev.clone()),
+                                cid: Some(cid.clone()),
+                            };
+                            self.repo.upsert_collection(&collection).await?;
+                        }
+                    }
+                }
+                Operation::Delete => {
+                    self.repo
+                        .delete_collection(&record_uri, Some(&commit.rev))
+                        .await?
+                }
+            }
+        } else {
+            return Err(anyhow!("Message has no commit"));
+        }
+        Ok(())
+    }
+}
+
+/// One ingester per collection we index, keyed by its EXACT nsid
+fn ingesters(repo: Arc<dyn Repo>) -> Ingesters {
+    let mut ingesters: Ingesters = HashMap::new();
+    ingesters.insert(
+        flatshcards::Stack::NSID.parse().unwrap(),
+        Box::new(FlatshcardsStackIngester { repo: repo.clone() }),
+    );
+    ingesters.insert(
+        flatshcards::Card::NSID.parse().unwrap(),
+        Box::new(FlatshcardsCardIngester { repo: repo.clone() }),
+    );
+    ingesters.insert(
+        flatshcards::Collection::NSID.parse().unwrap(),
+        Box::new(FlatshcardsCollectionIngester { repo }),
+    );
+    ingesters
+}
+
+pub async fn start_ingester(repo: Arc<dyn Repo>) {
     // init the builder
     let opts = JetstreamOptions::builder()
         // your EXACT nsids
@@ -137,27 +273,14 @@ pub async fn start_ingester(db_pool: PgPool) {
         .wanted_collections(vec![
             flatshcards::Stack::NSID.parse().unwrap(),
             flatshcards::Card::NSID.parse().unwrap(),
+            flatshcards::Collection::NSID.parse().unwrap(),
         ])
         .build();
     // create the jetstream connector
     let jetstream = JetstreamConnection::new(opts);
 
     // create your ingesters
-    // Which in this case is xyz.statusphere.status
-    let mut ingesters: HashMap<String, Box<dyn LexiconIngestor + Send + Sync>> = HashMap::new();
-    ingesters.insert(
-        // your EXACT nsid
-        flatshcards::Stack::NSID.parse().unwrap(),
-        Box::new(FlatshcardsStackIngester {
-            db_pool: db_pool.clone(),
-        }),
-    );
-    ingesters.insert(
-        flatshcards::Card::NSID.parse().unwrap(),
-        Box::new(FlatshcardsCardIngester {
-            db_pool: db_pool.clone(),
-        }),
-    );
+    let ingesters = ingesters(repo);
 
     // tracks the last message we've processed
     let cursor: Arc<Mutex<Option<u64>>> = Arc::new(Mutex::new(None));
@@ -187,3 +310,253 @@ pub async fn start_ingester(db_pool: PgPool) {
         std::process::exit(1);
     }
 }
+
+/// What happened to each line of a replay
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub ingested: usize,
+    /// At or before the starting cursor
+    pub skipped: usize,
+    /// Not a commit, or not one of our collections
+    pub ignored: usize,
+    pub failed: usize,
+}
+
+/// Feeds recorded Jetstream events, one JSON object per line, through the same ingesters as
+/// the live connection. Events at or before `cursor` are skipped, as Jetstream does when you
+/// reconnect from one, and `cursor` is left at the last event replayed.
+pub async fn replay_events(
+    repo: Arc<dyn Repo>,
+    events: impl BufRead,
+    cursor: Arc<Mutex<Option<u64>>>,
+) -> std::io::Result<ReplaySummary> {
+    let ingesters = ingesters(repo);
+    let start = *cursor.lock().unwrap();
+    let mut summary = ReplaySummary::default();
+    for (n, line) in events.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = match serde_json::from_str::<Value>(&line) {
+            Ok(event) => event,
+            Err(e) => {
+                error!("Replay line {}: not JSON: {e}", n + 1);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let time_us = event.get("time_us").and_then(Value::as_u64);
+        if time_us.zip(start).is_some_and(|(t, s)| t <= s) {
+            summary.skipped += 1;
+            continue;
+        }
+        let message = match serde_json::from_value::<Event<Value>>(event) {
+            Ok(message) => message,
+            Err(e) => {
+                error!("Replay line {}: not a Jetstream event: {e}", n + 1);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let ingester = message
+            .commit
+            .as_ref()
+            .and_then(|commit| ingesters.get(commit.collection.as_str()));
+        match ingester {
+            Some(ingester) => match ingester.ingest(message).await {
+                Ok(()) => summary.ingested += 1,
+                Err(e) => {
+                    error!("Replay line {}: error ingesting: {e}", n + 1);
+                    summary.failed += 1;
+                }
+            },
+            None => summary.ignored += 1,
+        }
+        if let Some(time_us) = time_us {
+            cursor.lock().unwrap().replace(time_us);
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CardRepo, MemoryRepo, StackRepo};
+
+    const AUTHOR: &str = "did:plc:replayfixtureauthor2222";
+
+    fn uri(collection: &str, rkey: &str) -> String {
+        format!("at://{AUTHOR}/{collection}/{rkey}")
+    }
+
+    async fn replay(
+        repo: &Arc<MemoryRepo>,
+        fixture: &str,
+        start: Option<u64>,
+    ) -> (ReplaySummary, Option<u64>) {
+        let cursor = Arc::new(Mutex::new(start));
+        let summary = replay_events(repo.clone(), fixture.as_bytes(), cursor.clone())
+            .await
+            .unwrap();
+        let end = *cursor.lock().unwrap();
+        (summary, end)
+    }
+
+    async fn cards(repo: &MemoryRepo, stack_rkey: &str) -> Vec<(String, String)> {
+        repo.stack_cards(&uri(flatshcards::Stack::NSID, stack_rkey))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.front_text, c.back_text))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replays_creates_updates_and_deletes_in_order() {
+        let repo = Arc::new(MemoryRepo::default());
+        let (summary, cursor) = replay(
+            &repo,
+            include_str!("../fixtures/jetstream/ordering.jsonl"),
+            None,
+        )
+        .await;
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                ingested: 9,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cursor, Some(1732125731131000));
+        let stacks = repo.user_stacks(AUTHOR).await.unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].label, "Kitchen things");
+        assert_eq!(
+            cards(&repo, "3lbhtdjy7ws2a").await,
+            [("包丁".to_string(), "kitchen knife".to_string())]
+        );
+        assert!(cards(&repo, "3lbhtfq2xcc2a").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stale_events_change_nothing() {
+        let repo = Arc::new(MemoryRepo::default());
+        let fixture = include_str!("../fixtures/jetstream/ordering.jsonl");
+        replay(&repo, fixture, None).await;
+        // The creates and first updates again, out of order, as from a backfill running
+        // alongside the live stream, including the create of the card since deleted
+        let stale: Vec<&str> = fixture.lines().take(5).collect();
+        let stale = stale.into_iter().rev().collect::<Vec<_>>().join("\n");
+        let (summary, _) = replay(&repo, &stale, None).await;
+        assert_eq!(summary.ingested, 5);
+        assert_eq!(
+            repo.user_stacks(AUTHOR).await.unwrap()[0].label,
+            "Kitchen things"
+        );
+        assert_eq!(
+            cards(&repo, "3lbhtdjy7ws2a").await,
+            [("包丁".to_string(), "kitchen knife".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn ingests_past_malformed_events() {
+        let repo = Arc::new(MemoryRepo::default());
+        let (summary, cursor) = replay(
+            &repo,
+            include_str!("../fixtures/jetstream/malformed.jsonl"),
+            None,
+        )
+        .await;
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                ingested: 1,
+                skipped: 0,
+                ignored: 3,
+                failed: 4,
+            }
+        );
+        assert_eq!(cursor, Some(1732125731229000));
+        let stacks = repo.user_stacks(AUTHOR).await.unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].label, "Still ingested after the bad lines");
+    }
+
+    #[tokio::test]
+    async fn repeated_events_are_idempotent() {
+        let repo = Arc::new(MemoryRepo::default());
+        let (summary, cursor) = replay(
+            &repo,
+            include_str!("../fixtures/jetstream/resume.jsonl"),
+            None,
+        )
+        .await;
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                ingested: 7,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cursor, Some(1732125731327000));
+        let stacks = repo.user_stacks(AUTHOR).await.unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].label, "Resumed twice");
+        let mut cards = cards(&repo, "3lbhuzzzzzz2a").await;
+        cards.sort();
+        assert_eq!(
+            cards,
+            [
+                ("猫".to_string(), "cat (animal)".to_string()),
+                ("鳥".to_string(), "bird".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_cursor() {
+        let repo = Arc::new(MemoryRepo::default());
+        let fixture = include_str!("../fixtures/jetstream/resume.jsonl");
+        // Everything the cursor covers was seen before the reconnect
+        let (summary, _) = replay(&repo, fixture, None).await;
+        assert_eq!(summary.ingested, 7);
+        let (summary, cursor) = replay(&repo, fixture, Some(1732125731324000)).await;
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                ingested: 5,
+                skipped: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cursor, Some(1732125731327000));
+        assert_eq!(
+            repo.user_stacks(AUTHOR).await.unwrap()[0].label,
+            "Resumed twice"
+        );
+        assert_eq!(cards(&repo, "3lbhuzzzzzz2a").await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn resuming_from_the_end_replays_nothing() {
+        let repo = Arc::new(MemoryRepo::default());
+        let (summary, cursor) = replay(
+            &repo,
+            include_str!("../fixtures/jetstream/resume.jsonl"),
+            Some(1732125731327000),
+        )
+        .await;
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                skipped: 7,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cursor, Some(1732125731327000));
+        assert!(repo.user_stacks(AUTHOR).await.unwrap().is_empty());
+    }
+}
@@ -1,24 +1,536 @@
 use codes_iso_639::part_1::ALL_CODES;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 
+/// Tags offered in the pickers on top of the ISO 639-1 codes: regional and script
+/// variants people actually study, and languages that only have a 639-2/3 code
+const EXTRA_LANGS: &[(&str, &str)] = &[
+    ("akk", "Akkadian"),
+    ("ang", "Old English"),
+    ("ar-EG", "Arabic (Egypt)"),
+    ("ase", "American Sign Language"),
+    ("de-AT", "German (Austria)"),
+    ("de-CH", "German (Switzerland)"),
+    ("egy", "Egyptian (Ancient)"),
+    ("en-AU", "English (Australia)"),
+    ("en-GB", "English (United Kingdom)"),
+    ("en-US", "English (United States)"),
+    ("es-419", "Spanish (Latin America)"),
+    ("es-ES", "Spanish (Spain)"),
+    ("es-MX", "Spanish (Mexico)"),
+    ("fr-CA", "French (Canada)"),
+    ("fr-FR", "French (France)"),
+    ("got", "Gothic"),
+    ("grc", "Ancient Greek"),
+    ("haw", "Hawaiian"),
+    ("hbo", "Ancient Hebrew"),
+    ("ja-Latn", "Japanese (Romaji)"),
+    ("jbo", "Lojban"),
+    ("nan", "Min Nan Chinese"),
+    ("non", "Old Norse"),
+    ("pt-BR", "Portuguese (Brazil)"),
+    ("pt-PT", "Portuguese (Portugal)"),
+    ("sr-Cyrl", "Serbian (Cyrillic)"),
+    ("sr-Latn", "Serbian (Latin)"),
+    ("sux", "Sumerian"),
+    ("tlh", "Klingon"),
+    ("tok", "Toki Pona"),
+    ("yue", "Cantonese"),
+    ("yue-Hans", "Cantonese (Simplified)"),
+    ("yue-Hant", "Cantonese (Traditional)"),
+    ("zh-Hans", "Chinese (Simplified)"),
+    ("zh-Hant", "Chinese (Traditional)"),
+];
+
+/// Names in the language itself, for the languages people are most likely to be studying.
+/// Tags not in here fall back to their base language's entry, e.g. `ar-EG` to `ar`.
+const NATIVE_NAMES: &[(&str, &str)] = &[
+    ("ar", "العربية"),
+    ("bn", "বাংলা"),
+    ("cs", "čeština"),
+    ("cy", "Cymraeg"),
+    ("da", "dansk"),
+    ("de", "Deutsch"),
+    ("de-AT", "Deutsch (Österreich)"),
+    ("de-CH", "Deutsch (Schweiz)"),
+    ("el", "Ελληνικά"),
+    ("es", "español"),
+    ("es-419", "español (Latinoamérica)"),
+    ("es-ES", "español (España)"),
+    ("es-MX", "español (México)"),
+    ("fa", "فارسی"),
+    ("fi", "suomi"),
+    ("fr", "français"),
+    ("ga", "Gaeilge"),
+    ("grc", "Ἀρχαία Ἑλληνική"),
+    ("haw", "ʻŌlelo Hawaiʻi"),
+    ("he", "עברית"),
+    ("hi", "हिन्दी"),
+    ("hu", "magyar"),
+    ("hy", "Հայերեն"),
+    ("id", "Bahasa Indonesia"),
+    ("is", "íslenska"),
+    ("it", "italiano"),
+    ("ja", "日本語"),
+    ("ja-Latn", "rōmaji"),
+    ("ka", "ქართული"),
+    ("ko", "한국어"),
+    ("la", "Latina"),
+    ("mn", "монгол"),
+    ("ms", "Bahasa Melayu"),
+    ("nb", "norsk bokmål"),
+    ("nl", "Nederlands"),
+    ("nn", "nynorsk"),
+    ("no", "norsk"),
+    ("pl", "polski"),
+    ("pt", "português"),
+    ("pt-BR", "português (Brasil)"),
+    ("pt-PT", "português (Portugal)"),
+    ("ro", "română"),
+    ("ru", "русский"),
+    ("sa", "संस्कृतम्"),
+    ("sr", "српски"),
+    ("sr-Latn", "srpski"),
+    ("sv", "svenska"),
+    ("sw", "Kiswahili"),
+    ("ta", "தமிழ்"),
+    ("te", "తెలుగు"),
+    ("th", "ไทย"),
+    ("tl", "Tagalog"),
+    ("tr", "Türkçe"),
+    ("uk", "українська"),
+    ("ur", "اردو"),
+    ("vi", "Tiếng Việt"),
+    ("yue", "粵語"),
+    ("yue-Hans", "粤语"),
+    ("zh", "中文"),
+    ("zh-Hans", "简体中文"),
+    ("zh-Hant", "繁體中文"),
+];
+
+/// What a picker leads with for someone who hasn't made any cards yet
+const COMMON_LANGS: &[&str] = &[
+    "en", "es", "fr", "de", "ja", "zh-Hans", "ko", "it", "pt-BR", "ru",
+];
+
+/// How many of someone's own languages a picker leads with
+pub const FAVOURITE_LANGS: i64 = 8;
+
+/// Search results are cut off here; a more specific query finds the rest
+const MAX_MATCHES: usize = 40;
+
 static LANG_OPTIONS: LazyLock<Vec<(&'static str, &'static str)>> = LazyLock::new(|| {
-    ALL_CODES
+    let mut options: Vec<(&'static str, &'static str)> = ALL_CODES
         .iter()
         .map(|lc| {
             let ln = lc.language_name();
             (lc.code(), ln.split_once(" ;").map(|v| v.0).unwrap_or(ln))
         })
-        .collect()
+        .chain(EXTRA_LANGS.iter().copied())
+        .collect();
+    // By tag, so variants sit right after their language
+    options.sort_by_key(|(code, _)| *code);
+    options
 });
 
 static LANG_SET: LazyLock<HashSet<&'static str>> =
     LazyLock::new(|| ALL_CODES.iter().map(|lc| lc.code()).collect());
 
+static LANG_NAMES: LazyLock<HashMap<&'static str, &'static str>> =
+    LazyLock::new(|| LANG_OPTIONS.iter().copied().collect());
+
+static NATIVE_NAME_MAP: LazyLock<HashMap<&'static str, &'static str>> =
+    LazyLock::new(|| NATIVE_NAMES.iter().copied().collect());
+
+fn alpha(subtag: &str) -> bool {
+    subtag.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Checks `tag` is a well-formed BCP-47 language tag (RFC 5646) and returns it in canonical
+/// case, e.g. `ZH-hant-tw` becomes `zh-Hant-TW`. Two-letter languages have to be real
+/// ISO 639-1 codes; three-letter ones can be any 639-2/3/5 code, so they're taken on trust.
+/// Grandfathered tags like `i-klingon` aren't accepted.
+pub fn normalize_lang(tag: &str) -> Option<String> {
+    let subtags: Vec<&str> = tag.split('-').collect();
+    if subtags
+        .iter()
+        .any(|s| s.is_empty() || s.len() > 8 || !s.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return None;
+    }
+    let mut rest = subtags.into_iter().peekable();
+    let language = rest.next()?.to_ascii_lowercase();
+    match language.len() {
+        2 if LANG_SET.contains(language.as_str()) => {}
+        3 if alpha(&language) => {}
+        _ => return None,
+    }
+    let mut out = vec![language];
+    for _ in 0..3 {
+        match rest.next_if(|s| s.len() == 3 && alpha(s)) {
+            Some(extlang) => out.push(extlang.to_ascii_lowercase()),
+            None => break,
+        }
+    }
+    if let Some(script) = rest.next_if(|s| s.len() == 4 && alpha(s)) {
+        let mut script = script.to_ascii_lowercase();
+        script[..1].make_ascii_uppercase();
+        out.push(script);
+    }
+    if let Some(region) = rest.next_if(|s| {
+        (s.len() == 2 && alpha(s)) || (s.len() == 3 && s.chars().all(|c| c.is_ascii_digit()))
+    }) {
+        out.push(region.to_ascii_uppercase());
+    }
+    while let Some(variant) = rest
+        .next_if(|s| s.len() >= 5 || (s.len() == 4 && s.starts_with(|c: char| c.is_ascii_digit())))
+    {
+        out.push(variant.to_ascii_lowercase());
+    }
+    // Extensions, then private use, which swallows everything after it
+    while let Some(singleton) = rest.next() {
+        if singleton.len() != 1 {
+            return None;
+        }
+        let singleton = singleton.to_ascii_lowercase();
+        let private = singleton == "x";
+        out.push(singleton);
+        let before = out.len();
+        while let Some(subtag) = rest.next_if(|s| private || s.len() >= 2) {
+            out.push(subtag.to_ascii_lowercase());
+        }
+        if out.len() == before {
+            return None;
+        }
+    }
+    Some(out.join("-"))
+}
+
 pub fn is_lang(lang: &str) -> bool {
-    LANG_SET.contains(lang)
+    normalize_lang(lang).is_some()
+}
+
+/// Scripts written right to left
+const RTL_SCRIPTS: &[&str] = &[
+    "Adlm", "Arab", "Hebr", "Mand", "Nkoo", "Samr", "Syrc", "Thaa",
+];
+
+/// Scripts we know anything about, for matching a tag's script subtag
+const KNOWN_SCRIPTS: &[&str] = &[
+    "Adlm", "Arab", "Beng", "Cyrl", "Deva", "Grek", "Hang", "Hans", "Hant", "Hebr", "Hira", "Jpan",
+    "Kana", "Kore", "Latn", "Mand", "Nkoo", "Samr", "Syrc", "Thaa", "Thai",
+];
+
+/// The script a language is written in when its tag doesn't say, for the languages where
+/// that changes the layout or the font
+const DEFAULT_SCRIPTS: &[(&str, &str)] = &[
+    ("ar", "Arab"),
+    ("arc", "Syrc"),
+    ("ckb", "Arab"),
+    ("dv", "Thaa"),
+    ("fa", "Arab"),
+    ("hbo", "Hebr"),
+    ("he", "Hebr"),
+    ("hi", "Deva"),
+    ("ja", "Jpan"),
+    ("ko", "Kore"),
+    ("mr", "Deva"),
+    ("ne", "Deva"),
+    ("ps", "Arab"),
+    ("sa", "Deva"),
+    ("sd", "Arab"),
+    ("syr", "Syrc"),
+    ("th", "Thai"),
+    ("ug", "Arab"),
+    ("ur", "Arab"),
+    ("yi", "Hebr"),
+    ("yue", "Hant"),
+    ("zh", "Hans"),
+];
+
+/// The script `tag` is written in: its script subtag, or else its language's usual one
+fn script_of(tag: &str) -> Option<&'static str> {
+    let mut subtags = tag.split('-');
+    let language = subtags.next()?.to_ascii_lowercase();
+    let mut region = None;
+    for subtag in subtags.take_while(|s| s.len() > 1) {
+        if subtag.len() == 4 && alpha(subtag) {
+            return KNOWN_SCRIPTS
+                .iter()
+                .find(|s| s.eq_ignore_ascii_case(subtag))
+                .copied();
+        }
+        if (subtag.len() == 2 && alpha(subtag)) || subtag.chars().all(|c| c.is_ascii_digit()) {
+            region = Some(subtag.to_ascii_uppercase());
+        }
+    }
+    // Chinese as written in Taiwan, Hong Kong and Macau is Traditional
+    if language == "zh" && matches!(region.as_deref(), Some("TW" | "HK" | "MO")) {
+        return Some("Hant");
+    }
+    DEFAULT_SCRIPTS
+        .iter()
+        .find(|(l, _)| *l == language)
+        .map(|(_, script)| *script)
 }
 
-pub fn lang_choices() -> Vec<(&'static str, &'static str)> {
-    LANG_OPTIONS.clone()
+/// How to lay out text in some language: the `dir` for it, and a `script-*` class that
+/// picks a font for the scripts that need one, e.g. so Japanese isn't drawn with Chinese
+/// glyph variants
+#[derive(Debug, Clone, Copy)]
+pub struct TextLayout {
+    pub dir: &'static str,
+    pub class: &'static str,
+}
+
+/// The [`TextLayout`] for text tagged `tag`; `""` for an unknown language leaves the
+/// direction to the browser
+pub fn text_layout(tag: &str) -> TextLayout {
+    let script = script_of(tag);
+    let dir = match script {
+        Some(script) if RTL_SCRIPTS.contains(&script) => "rtl",
+        _ if tag.is_empty() => "auto",
+        _ => "ltr",
+    };
+    let class = match script {
+        Some("Arab") => "script-arab",
+        Some("Deva") => "script-deva",
+        Some("Hans") => "script-hans",
+        Some("Hant") => "script-hant",
+        Some("Hebr") => "script-hebr",
+        Some("Jpan" | "Hira" | "Kana") => "script-jpan",
+        Some("Kore" | "Hang") => "script-kore",
+        Some("Thai") => "script-thai",
+        _ => "",
+    };
+    TextLayout { dir, class }
+}
+
+/// One option in a language picker
+#[derive(Debug, Clone)]
+pub struct LangChoice {
+    pub tag: String,
+    /// English name; `None` for tags that aren't offered, which are shown as they are
+    pub name: Option<&'static str>,
+    pub native: Option<&'static str>,
+}
+
+impl LangChoice {
+    fn new(tag: &str) -> Self {
+        let base = tag.split('-').next().unwrap_or(tag);
+        Self {
+            tag: tag.to_string(),
+            name: LANG_NAMES.get(tag).copied(),
+            native: NATIVE_NAME_MAP
+                .get(tag)
+                .or_else(|| NATIVE_NAME_MAP.get(base))
+                .copied(),
+        }
+    }
+
+    pub fn is(&self, tag: &str) -> bool {
+        self.tag == tag
+    }
+
+    /// E.g. `Japanese · 日本語`
+    pub fn label(&self) -> String {
+        match (self.name, self.native) {
+            (Some(name), Some(native)) => format!("{name} · {native}"),
+            (Some(name), None) => name.to_string(),
+            (None, _) => self.tag.clone(),
+        }
+    }
+
+    /// How well this matches a lowercased `query`: 0 for the tag or the start of a name,
+    /// 1 for anywhere in a name
+    fn rank(&self, query: &str) -> Option<u8> {
+        let names = [self.name, self.native].map(|n| n.map(str::to_lowercase));
+        if self.tag.to_lowercase() == query || names.iter().flatten().any(|n| n.starts_with(query))
+        {
+            Some(0)
+        } else if names.iter().flatten().any(|n| n.contains(query)) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+/// An `<optgroup>` of [`LangChoice`]s
+#[derive(Debug, Clone)]
+pub struct LangGroup {
+    /// A message id
+    pub label: &'static str,
+    pub langs: Vec<LangChoice>,
+}
+
+/// The options for one user's language pickers. Rather than every language, a picker
+/// starts with the ones they've used most in their cards, and finds the rest by searching.
+#[derive(Debug, Clone)]
+pub struct LangPicker {
+    favourites: Vec<LangChoice>,
+    own: bool,
+}
+
+impl LangPicker {
+    /// `most_used` as from [`CardRepo::most_used_langs`](crate::db::CardRepo::most_used_langs);
+    /// if it's empty the picker leads with some common languages instead
+    pub fn new(most_used: Vec<String>) -> Self {
+        let own = !most_used.is_empty();
+        let favourites = if own {
+            most_used.iter().map(|tag| LangChoice::new(tag)).collect()
+        } else {
+            COMMON_LANGS
+                .iter()
+                .map(|tag| LangChoice::new(tag))
+                .collect()
+        };
+        Self { favourites, own }
+    }
+
+    /// What a picker shows before anything's been searched for
+    pub fn groups(&self, selected: &str) -> Vec<LangGroup> {
+        self.search("", selected)
+    }
+
+    /// The favourites and up to [`MAX_MATCHES`] other languages matching `query` by tag,
+    /// English or native name. `selected` is always kept, so searching never changes
+    /// the value on its own.
+    pub fn search(&self, query: &str, selected: &str) -> Vec<LangGroup> {
+        let query = query.trim().to_lowercase();
+        let is_favourite = |tag: &str| self.favourites.iter().any(|l| l.is(tag));
+        let favourites: Vec<LangChoice> = self
+            .favourites
+            .iter()
+            .filter(|l| query.is_empty() || l.rank(&query).is_some())
+            .cloned()
+            .collect();
+        let mut groups = Vec::new();
+        if !selected.is_empty() && !favourites.iter().any(|l| l.is(selected)) {
+            groups.push(LangGroup {
+                label: "lang-group-selected",
+                langs: vec![LangChoice::new(selected)],
+            });
+        }
+        if !favourites.is_empty() {
+            groups.push(LangGroup {
+                label: if self.own {
+                    "lang-group-yours"
+                } else {
+                    "lang-group-common"
+                },
+                langs: favourites,
+            });
+        }
+        if !query.is_empty() {
+            let mut matches: Vec<(u8, LangChoice)> = LANG_OPTIONS
+                .iter()
+                .filter(|(tag, _)| *tag != selected && !is_favourite(tag))
+                .filter_map(|(tag, _)| {
+                    let choice = LangChoice::new(tag);
+                    choice.rank(&query).map(|rank| (rank, choice))
+                })
+                .collect();
+            matches.sort_by_key(|(rank, _)| *rank);
+            if !matches.is_empty() {
+                groups.push(LangGroup {
+                    label: "lang-group-other",
+                    langs: matches
+                        .into_iter()
+                        .take(MAX_MATCHES)
+                        .map(|(_, choice)| choice)
+                        .collect(),
+                });
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_put_in_canonical_case() {
+        for (tag, canonical) in [
+            ("en", "en"),
+            ("EN", "en"),
+            ("PT-br", "pt-BR"),
+            ("zh-hant-tw", "zh-Hant-TW"),
+            ("ZH-HANT", "zh-Hant"),
+            ("es-419", "es-419"),
+            ("grc", "grc"),
+            ("zh-YUE-hk", "zh-yue-HK"),
+            ("sl-ROZAJ-biske", "sl-rozaj-biske"),
+            ("de-DE-1996", "de-DE-1996"),
+            ("en-US-U-CA-GREGORY", "en-US-u-ca-gregory"),
+            ("en-x-Twain-B", "en-x-twain-b"),
+        ] {
+            assert_eq!(normalize_lang(tag).as_deref(), Some(canonical), "{tag}");
+        }
+    }
+
+    #[test]
+    fn malformed_tags_are_rejected() {
+        for tag in [
+            "",
+            "-",
+            "en-",
+            "-en",
+            "en--US",
+            "en_US",
+            "en US",
+            "e",
+            "xx",
+            "english",
+            "en-toolongsubtag",
+            "en-US-u",
+            "en-a-b-c",
+            "en-US-US",
+            "i-klingon",
+            "日本語",
+        ] {
+            assert_eq!(normalize_lang(tag), None, "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn scripts_come_from_the_tag_or_the_language() {
+        for (tag, script) in [
+            ("sr-Latn", Some("Latn")),
+            ("sr-latn-RS", Some("Latn")),
+            ("ja", Some("Jpan")),
+            ("ja-Latn", Some("Latn")),
+            ("zh", Some("Hans")),
+            ("zh-TW", Some("Hant")),
+            ("zh-HK", Some("Hant")),
+            ("zh-CN", Some("Hans")),
+            ("yue-Hans", Some("Hans")),
+            ("en", None),
+            ("en-Zzzz", None),
+            ("", None),
+        ] {
+            assert_eq!(script_of(tag), script, "{tag}");
+        }
+    }
+
+    #[test]
+    fn right_to_left_languages_are_detected() {
+        for tag in [
+            "ar", "ar-EG", "he", "he-IL", "fa", "fa-IR", "ur", "yi", "en-Arab",
+        ] {
+            assert_eq!(text_layout(tag).dir, "rtl", "{tag}");
+        }
+        for tag in ["en", "de-CH", "ja", "ar-Latn", "und"] {
+            assert_eq!(text_layout(tag).dir, "ltr", "{tag}");
+        }
+        assert_eq!(text_layout("").dir, "auto");
+        assert_eq!(text_layout("ar").class, "script-arab");
+        assert_eq!(text_layout("he").class, "script-hebr");
+        assert_eq!(text_layout("fa").class, "script-arab");
+        assert_eq!(text_layout("ja").class, "script-jpan");
+        assert_eq!(text_layout("zh-TW").class, "script-hant");
+        assert_eq!(text_layout("en").class, "");
+    }
 }
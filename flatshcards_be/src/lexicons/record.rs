@@ -1,12 +1,15 @@
-use super::xyz::flatshcards::{card, stack};
+use super::xyz::flatshcards::{card, collection, stack};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "$type")]
+#[allow(clippy::enum_variant_names)]
 pub enum KnownRecord {
     #[serde(rename = "xyz.flatshcards.cards#stack")]
     LexiconXyzFlatshcardsCardsStack(Box<stack::StackRecord>),
     #[serde(rename = "xyz.flatshcards.cards#card")]
     LexiconXyzFlatshcardsCardsCard(Box<card::CardRecord>),
+    #[serde(rename = "xyz.flatshcards.cards#collection")]
+    LexiconXyzFlatshcardsCardsCollection(Box<collection::CollectionRecord>),
 }
 
 impl From<stack::StackRecord> for KnownRecord {
@@ -29,6 +32,16 @@ impl From<card::Card> for KnownRecord {
         KnownRecord::LexiconXyzFlatshcardsCardsCard(Box::new(record.into()))
     }
 }
+impl From<collection::CollectionRecord> for KnownRecord {
+    fn from(record: collection::CollectionRecord) -> Self {
+        KnownRecord::LexiconXyzFlatshcardsCardsCollection(Box::new(record))
+    }
+}
+impl From<collection::Collection> for KnownRecord {
+    fn from(record: collection::Collection) -> Self {
+        KnownRecord::LexiconXyzFlatshcardsCardsCollection(Box::new(record.into()))
+    }
+}
 #[allow(clippy::from_over_into)]
 impl Into<atrium_api::types::Unknown> for KnownRecord {
     fn into(self) -> atrium_api::types::Unknown {
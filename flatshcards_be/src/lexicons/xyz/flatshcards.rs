@@ -1,17 +1,24 @@
-use atrium_api::types::Collection;
 pub mod card;
+pub mod collection;
 pub mod stack;
 
 #[derive(Debug)]
 pub struct Stack;
-impl Collection for Stack {
+impl atrium_api::types::Collection for Stack {
     const NSID: &'static str = "xyz.flatshcards.stack";
     type Record = stack::StackRecord;
 }
 
 #[derive(Debug)]
 pub struct Card;
-impl Collection for Card {
+impl atrium_api::types::Collection for Card {
     const NSID: &'static str = "xyz.flatshcards.card";
     type Record = card::CardRecord;
 }
+
+#[derive(Debug)]
+pub struct Collection;
+impl atrium_api::types::Collection for Collection {
+    const NSID: &'static str = "xyz.flatshcards.collection";
+    type Record = collection::CollectionRecord;
+}
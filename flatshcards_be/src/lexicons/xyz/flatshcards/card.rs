@@ -3,12 +3,31 @@ use atrium_api::types::TryFromUnknown;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Card {
+    ///A recording of the card, e.g. of frontText being said
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub audio: core::option::Option<atrium_api::types::BlobRef>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub back_image: core::option::Option<atrium_api::types::BlobRef>,
     pub back_lang: String,
     pub back_text: String,
     pub created_at: atrium_api::types::string::Datetime,
+    ///How frontText and backText are written. markdown allows bold, italics, lists and line breaks, and ruby like 漢字[かんじ].
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub format: core::option::Option<String>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub front_image: core::option::Option<atrium_api::types::BlobRef>,
     pub front_lang: String,
     pub front_text: String,
+    ///basic cards are a front and a back. For cloze cards, frontText marks deletions like {{c1::answer}} or {{c1::answer::hint}}, each number being one review item, and backText is shown with the answer, e.g. a translation.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub kind: core::option::Option<String>,
+    ///Where the card goes in its stack, counting from 0. Cards without one go after those with one, oldest first.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub position: core::option::Option<i64>,
     pub stack_id: atrium_api::types::string::RecordKey,
+    ///Free-form labels for finding and grouping cards, across stacks
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub tags: core::option::Option<Vec<String>>,
 }
 pub type CardRecord = atrium_api::types::Object<Card>;
 impl From<atrium_api::types::Unknown> for Card {
@@ -0,0 +1,18 @@
+use atrium_api::types::TryFromUnknown;
+///an ordered collection of stacks, like a textbook with one stack per chapter
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    pub created_at: atrium_api::types::string::Datetime,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub description: core::option::Option<String>,
+    pub label: String,
+    ///xyz.flatshcards.stack records, in the order they're meant to be studied. They may be from any repo.
+    pub stacks: Vec<String>,
+}
+pub type CollectionRecord = atrium_api::types::Object<Collection>;
+impl From<atrium_api::types::Unknown> for Collection {
+    fn from(value: atrium_api::types::Unknown) -> Self {
+        Self::try_from_unknown(value).unwrap()
+    }
+}
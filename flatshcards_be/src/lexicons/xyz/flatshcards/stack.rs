@@ -5,10 +5,31 @@ use atrium_api::types::TryFromUnknown;
 pub struct Stack {
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub back_lang: core::option::Option<String>,
+    ///A picture to show with the stack
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub cover: core::option::Option<atrium_api::types::BlobRef>,
     pub created_at: atrium_api::types::string::Datetime,
+    ///What the stack is for, such as who it's meant for or what it covers
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub description: core::option::Option<String>,
+    ///Who the stack is pitched at
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub difficulty: core::option::Option<String>,
+    ///Which ways the stack's cards are studied: forward is front to back, reverse back to front. Cloze cards are only ever studied forward.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub directions: core::option::Option<String>,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub front_lang: core::option::Option<String>,
     pub label: String,
+    ///What others may do with the cards: an SPDX license identifier, or all-rights-reserved
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub license: core::option::Option<String>,
+    ///Where the cards came from, to credit, such as a textbook or a word list
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub source: core::option::Option<String>,
+    ///Free-form labels for finding and grouping stacks
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub tags: core::option::Option<Vec<String>>,
 }
 pub type StackRecord = atrium_api::types::Object<Stack>;
 impl From<atrium_api::types::Unknown> for Stack {
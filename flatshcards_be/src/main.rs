@@ -1,17 +1,51 @@
 use crate::{
-    db::create_tables_in_database,
-    ingester::start_ingester,
+    blobs::BlobCache,
+    crosscheck::{CrossCheck, start_cross_check},
+    db::{MemoryRepo, Repo, create_tables_in_database},
+    ingester::{replay_events, start_ingester},
     routes::{
+        AppOAuthClient, AuthClient,
+        api::{
+            self,
+            cards::{
+                api_create_card, api_delete_card, api_put_card, api_speak, api_stack_cards,
+                api_upload_audio, api_upload_image,
+            },
+            collections::{
+                api_clone_collection, api_create_collection, api_delete_collection,
+                api_get_collection, api_put_collection,
+            },
+            stacks::{
+                api_clone_stack, api_create_stack, api_delete_stack, api_put_stack,
+                api_reorder_cards, api_search_stacks, api_speak_stack,
+            },
+            tokens::{create_api_token, revoke_api_token},
+        },
+        blobs::get_blob,
         cards::{create_card, delete_card, put_card},
+        collections::{
+            clone_collection, collection_page, create_collection, create_collection_page,
+            delete_collection, edit_collection_page, put_collection,
+        },
+        games::{finish_match, match_page, match_pair},
         home,
+        langs::search_langs,
+        quizzes::{quiz_page, score_quiz},
+        reviews::{
+            check_review, grade_review, review_page, review_session_page, save_review_limits,
+        },
         stacks::{
             clone_stack, create_stack, create_stack_page, delete_stack, edit_stack_page, put_stack,
+            reorder_cards, search_page, speak_stack, stack_page,
         },
-        user_management::{login, login_post, logout, oauth_callback},
+        stats::stats_page,
+        user_management::{login, login_post, logout, oauth_callback, set_locale},
     },
     storage::{DbSessionStore, DbStateStore},
+    tts::{Espeak, SpeechCache},
 };
 use actix_files::Files;
+use actix_multipart::form::MultipartFormConfig;
 use actix_session::{SessionMiddleware, config::PersistentSession, storage::CookieSessionStore};
 use actix_web::{
     App, HttpServer,
@@ -29,18 +63,40 @@ use atrium_oauth::{
 use dotenv::dotenv;
 use resolver::HickoryDnsTxtResolver;
 use sqlx::postgres::PgPool;
-use std::{io::Error, sync::Arc};
+use std::{
+    fs::File,
+    io::{BufReader, Error},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 extern crate dotenv;
 
+mod answer;
+mod blobs;
+mod cloze;
+mod collections;
+mod crosscheck;
 mod db;
+mod i18n;
 mod ingester;
 mod lang;
 mod lexicons;
+mod matching;
+#[cfg(any(test, feature = "mock-pds"))]
+mod mock_pds;
+mod quiz;
 mod resolver;
+mod review;
+mod rich;
 mod routes;
+mod services;
+mod stacks;
+mod stats;
 mod storage;
+mod tags;
 mod templates;
+mod tts;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -52,22 +108,28 @@ async fn main() -> std::io::Result<()> {
         .parse::<u16>()
         .unwrap_or(8080);
 
-    //Uses a default sqlite db path or use the one from env
+    //Uses the postgres db from env, or keeps everything in memory if DB_URL=memory
     let db_connection_string = std::env::var("DB_URL").unwrap();
 
-    //Crates a db pool to share resources to the db
-    let pool = match PgPool::connect(&db_connection_string).await {
-        Ok(pool) => pool,
-        Err(err) => {
-            log::error!("Error creating the db pool: {err}");
-            return Err(Error::other("db pool could not be created."));
-        }
-    };
+    let repo: Arc<dyn Repo> = if db_connection_string == "memory" {
+        log::warn!("using the in-memory repository, nothing will be persisted");
+        Arc::new(MemoryRepo::default())
+    } else {
+        //Crates a db pool to share resources to the db
+        let pool = match PgPool::connect(&db_connection_string).await {
+            Ok(pool) => pool,
+            Err(err) => {
+                log::error!("Error creating the db pool: {err}");
+                return Err(Error::other("db pool could not be created."));
+            }
+        };
 
-    //Creates the DB and tables
-    create_tables_in_database(&pool)
-        .await
-        .expect("Could not create the database");
+        //Creates the DB and tables
+        create_tables_in_database(&pool)
+            .await
+            .expect("Could not create the database");
+        Arc::new(pool)
+    };
 
     //Create a new handle resolver for the home page
     let http_client = Arc::new(DefaultHttpClient::default());
@@ -78,7 +140,145 @@ async fn main() -> std::io::Result<()> {
     });
     let handle_resolver = Arc::new(handle_resolver);
 
-    // Create a new OAuth client
+    //Writes go to an in-memory stand-in PDS, and logging in always yields MOCK_PDS_DID, if it's set
+    //and the mock-pds feature is on
+    #[cfg(any(test, feature = "mock-pds"))]
+    let mock = match std::env::var("MOCK_PDS_DID") {
+        Ok(did) => {
+            let did = atrium_api::types::string::Did::new(did)
+                .expect("MOCK_PDS_DID should be a valid DID");
+            let mock_port = std::env::var("MOCK_PDS_PORT")
+                .ok()
+                .and_then(|p| p.parse::<u16>().ok())
+                .unwrap_or(2583);
+            let (base_uri, _) = mock_pds::spawn(
+                web::Data::new(mock_pds::MockPds::default()),
+                ("127.0.0.1", mock_port),
+            )?;
+            log::warn!(
+                "using the mock PDS at {base_uri}, logins will be as {}",
+                did.as_str()
+            );
+            Some(mock_pds::MockLogin { did, base_uri })
+        }
+        Err(_) => None,
+    };
+    #[cfg(any(test, feature = "mock-pds"))]
+    let blob_pds = mock.as_ref().map(|m| m.base_uri.clone());
+    #[cfg(not(any(test, feature = "mock-pds")))]
+    let blob_pds = None;
+    //Card images are fetched from their authors' PDSes, and kept in BLOB_CACHE_DIR
+    let blob_cache_dir =
+        std::env::var("BLOB_CACHE_DIR").unwrap_or_else(|_| "blob_cache".to_string());
+    let blobs = web::Data::new(BlobCache::new(
+        blob_cache_dir.into(),
+        handle_resolver.clone(),
+        blob_pds,
+    ));
+    //Audio can be generated from card text if TTS names an engine, run as TTS_PROGRAM,
+    //and what it says is kept in TTS_CACHE_DIR, up to TTS_CACHE_MAX_MB
+    let speech = match std::env::var("TTS").as_deref() {
+        Ok("espeak-ng") => {
+            let program = std::env::var("TTS_PROGRAM").unwrap_or_else(|_| "espeak-ng".to_string());
+            let dir = std::env::var("TTS_CACHE_DIR").unwrap_or_else(|_| "tts_cache".to_string());
+            let max_mb: u64 = std::env::var("TTS_CACHE_MAX_MB")
+                .ok()
+                .and_then(|mb| mb.parse().ok())
+                .unwrap_or(512);
+            Some(web::Data::new(SpeechCache::new(
+                dir.into(),
+                Box::new(Espeak::new(program)),
+                max_mb * 1024 * 1024,
+            )))
+        }
+        Ok(engine) => {
+            log::warn!("unknown TTS engine {engine}, audio won't be generated");
+            None
+        }
+        Err(_) => None,
+    };
+    let client = Arc::new(AuthClient {
+        oauth: oauth_client(&host, port, &repo),
+        #[cfg(any(test, feature = "mock-pds"))]
+        mock,
+    });
+    //Spawns the ingester that listens for other's Statusphere updates,
+    //or replays recorded events from JETSTREAM_REPLAY instead if it's set
+    let ingester_repo = repo.clone();
+    match std::env::var("JETSTREAM_REPLAY") {
+        Ok(path) => {
+            let events = BufReader::new(File::open(&path)?);
+            let cursor = std::env::var("JETSTREAM_REPLAY_CURSOR")
+                .ok()
+                .and_then(|c| c.parse::<u64>().ok());
+            tokio::spawn(async move {
+                let cursor = Arc::new(Mutex::new(cursor));
+                match replay_events(ingester_repo, events, cursor.clone()).await {
+                    Ok(summary) => log::info!(
+                        "replayed {path}: {summary:?}, cursor now {:?}",
+                        *cursor.lock().unwrap()
+                    ),
+                    Err(err) => log::error!("Error replaying {path}: {err}"),
+                }
+            });
+        }
+        Err(_) => {
+            tokio::spawn(async move {
+                start_ingester(ingester_repo).await;
+            });
+        }
+    }
+    //Periodically compares what we've indexed with each author's PDS, if CROSS_CHECK_INTERVAL_SECS is set
+    if let Some(secs) = std::env::var("CROSS_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+    {
+        let check = CrossCheck::new(repo.clone(), handle_resolver.clone());
+        tokio::spawn(start_cross_check(check, Duration::from_secs(secs)));
+    }
+    log::info!("starting HTTP server at http://{host}:{port}");
+    HttpServer::new(move || {
+        App::new()
+            .wrap(middleware::Logger::default())
+            .app_data(web::Data::new(client.clone()))
+            .app_data(web::Data::from(repo.clone()))
+            .app_data(web::Data::new(handle_resolver.clone()))
+            .app_data(blobs.clone())
+            .configure(|cfg| {
+                if let Some(speech) = &speech {
+                    cfg.app_data(speech.clone());
+                }
+            })
+            // Room for a card's two images and its audio, and for audio uploaded through
+            // the API
+            .app_data(MultipartFormConfig::default().memory_limit(10 * 1024 * 1024))
+            .app_data(web::PayloadConfig::new(6 * 1024 * 1024))
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _req| api::ApiError::Invalid(err.to_string()).into()),
+            )
+            .wrap(session_middleware())
+            .configure(routes)
+    })
+    .bind(("127.0.0.1", port))?
+    .run()
+    .await
+}
+
+/// Where sessions are kept: in the cookie
+fn session_middleware() -> SessionMiddleware<CookieSessionStore> {
+    SessionMiddleware::builder(CookieSessionStore::default(), Key::from(&[0; 64]))
+        //TODO will need to set to true in production
+        .cookie_secure(false)
+        // customize session and cookie expiration
+        .session_lifecycle(
+            PersistentSession::default().session_ttl(cookie::time::Duration::days(14)),
+        )
+        .build()
+}
+
+/// The OAuth client, for an app listening on `host`:`port`
+fn oauth_client(host: &str, port: u16, repo: &Arc<dyn Repo>) -> AppOAuthClient {
     let http_client = Arc::new(DefaultHttpClient::default());
     let config = OAuthClientConfig {
         client_metadata: AtprotoLocalhostClientMetadata {
@@ -104,49 +304,74 @@ async fn main() -> std::io::Result<()> {
             authorization_server_metadata: Default::default(),
             protected_resource_metadata: Default::default(),
         },
-        state_store: DbStateStore::new(pool.clone()),
-        session_store: DbSessionStore::new(pool.clone()),
+        state_store: DbStateStore::new(repo.clone()),
+        session_store: DbSessionStore::new(repo.clone()),
     };
-    let client = Arc::new(OAuthClient::new(config).expect("failed to create OAuth client"));
-    //Spawns the ingester that listens for other's Statusphere updates
-    let ingester_pool = pool.clone();
-    tokio::spawn(async move {
-        start_ingester(ingester_pool).await;
-    });
-    log::info!("starting HTTP server at http://{host}:{port}");
-    HttpServer::new(move || {
-        App::new()
-            .wrap(middleware::Logger::default())
-            .app_data(web::Data::new(client.clone()))
-            .app_data(web::ThinData(pool.clone()))
-            .app_data(web::Data::new(handle_resolver.clone()))
-            .wrap(
-                SessionMiddleware::builder(CookieSessionStore::default(), Key::from(&[0; 64]))
-                    //TODO will need to set to true in production
-                    .cookie_secure(false)
-                    // customize session and cookie expiration
-                    .session_lifecycle(
-                        PersistentSession::default().session_ttl(cookie::time::Duration::days(14)),
-                    )
-                    .build(),
-            )
-            .service(Files::new("/css", "public/css").show_files_listing())
-            .service(oauth_callback)
-            .service(login)
-            .service(login_post)
-            .service(logout)
-            .service(home)
-            .service(create_card)
-            .service(delete_card)
-            .service(put_card)
-            .service(clone_stack)
-            .service(create_stack)
-            .service(create_stack_page)
-            .service(delete_stack)
-            .service(edit_stack_page)
-            .service(put_stack)
-    })
-    .bind(("127.0.0.1", port))?
-    .run()
-    .await
+    OAuthClient::new(config).expect("failed to create OAuth client")
+}
+
+/// Every page and endpoint, and the static files they use
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(Files::new("/css", "public/css").show_files_listing())
+        .service(Files::new("/js", "public/js"))
+        .service(oauth_callback)
+        .service(login)
+        .service(login_post)
+        .service(logout)
+        .service(home)
+        .service(create_card)
+        .service(delete_card)
+        .service(put_card)
+        .service(get_blob)
+        .service(clone_stack)
+        .service(create_stack)
+        .service(create_stack_page)
+        .service(delete_stack)
+        .service(edit_stack_page)
+        .service(put_stack)
+        .service(speak_stack)
+        .service(reorder_cards)
+        .service(stack_page)
+        .service(search_page)
+        .service(clone_collection)
+        .service(collection_page)
+        .service(create_collection)
+        .service(create_collection_page)
+        .service(delete_collection)
+        .service(edit_collection_page)
+        .service(put_collection)
+        .service(review_page)
+        .service(grade_review)
+        .service(check_review)
+        .service(review_session_page)
+        .service(save_review_limits)
+        .service(quiz_page)
+        .service(score_quiz)
+        .service(match_page)
+        .service(finish_match)
+        .service(match_pair)
+        .service(stats_page)
+        .service(search_langs)
+        .service(set_locale)
+        .service(create_api_token)
+        .service(revoke_api_token)
+        .service(api_stack_cards)
+        .service(api_create_card)
+        .service(api_put_card)
+        .service(api_delete_card)
+        .service(api_upload_image)
+        .service(api_upload_audio)
+        .service(api_speak)
+        .service(api_clone_stack)
+        .service(api_search_stacks)
+        .service(api_clone_collection)
+        .service(api_get_collection)
+        .service(api_create_collection)
+        .service(api_put_collection)
+        .service(api_delete_collection)
+        .service(api_create_stack)
+        .service(api_put_stack)
+        .service(api_delete_stack)
+        .service(api_speak_stack)
+        .service(api_reorder_cards);
 }
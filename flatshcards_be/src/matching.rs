@@ -0,0 +1,98 @@
+//! Match the pairs: a few of a stack's fronts and backs, shuffled separately, to be
+//! paired up against the clock. Only cards whose front and back are both unlike the
+//! others' can be told apart, and cloze cards have no back, so the rest are left out.
+use crate::db::DisplayCard;
+use crate::lang::{TextLayout, text_layout};
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+pub const DEFAULT_PAIRS: usize = 6;
+pub const MIN_PAIRS: usize = 2;
+pub const MAX_PAIRS: usize = 10;
+
+/// One side of one pair
+pub struct Tile {
+    /// Which pair it's part of, the same for the front and the back. It's kept from the
+    /// page, so the pairs can only be told by matching them.
+    pub pair: usize,
+    /// Sanitized, see [`crate::rich::to_html`]
+    pub html: String,
+    pub lang: String,
+}
+
+impl Tile {
+    pub fn layout(&self) -> TextLayout {
+        text_layout(&self.lang)
+    }
+}
+
+pub struct MatchBoard {
+    pub fronts: Vec<Tile>,
+    pub backs: Vec<Tile>,
+}
+
+impl MatchBoard {
+    pub fn pairs(&self) -> usize {
+        self.fronts.len()
+    }
+
+    /// For each front, where its back is in [`MatchBoard::backs`]
+    pub fn answers(&self) -> Vec<usize> {
+        self.fronts
+            .iter()
+            .map(|front| {
+                self.backs
+                    .iter()
+                    .position(|back| back.pair == front.pair)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+/// Lays out up to `pairs` random pairs from `cards`, or none if there aren't
+/// [`MIN_PAIRS`] to be had
+pub fn board(cards: &[DisplayCard], pairs: usize) -> MatchBoard {
+    let mut rng = rand::rng();
+    let mut cards: Vec<&DisplayCard> = cards.iter().filter(|c| !c.is_cloze()).collect();
+    cards.shuffle(&mut rng);
+    let mut fronts_seen = HashSet::new();
+    let mut backs_seen = HashSet::new();
+    let chosen: Vec<&DisplayCard> = cards
+        .into_iter()
+        .filter(|c| fronts_seen.insert(&c.front_text) && backs_seen.insert(&c.back_text))
+        .take(pairs.clamp(MIN_PAIRS, MAX_PAIRS))
+        .collect();
+    if chosen.len() < MIN_PAIRS {
+        return MatchBoard {
+            fronts: Vec::new(),
+            backs: Vec::new(),
+        };
+    }
+    let mut fronts: Vec<Tile> = chosen
+        .iter()
+        .enumerate()
+        .map(|(pair, c)| Tile {
+            pair,
+            html: c.front_html(),
+            lang: c.front_lang.clone(),
+        })
+        .collect();
+    let mut backs: Vec<Tile> = chosen
+        .iter()
+        .enumerate()
+        .map(|(pair, c)| Tile {
+            pair,
+            html: c.back_html(),
+            lang: c.back_lang.clone(),
+        })
+        .collect();
+    fronts.shuffle(&mut rng);
+    backs.shuffle(&mut rng);
+    MatchBoard { fronts, backs }
+}
+
+/// A time in milliseconds as seconds, to the tenth
+pub fn seconds(ms: i64) -> String {
+    format!("{:.1}", ms as f64 / 1000.0)
+}
@@ -0,0 +1,1306 @@
+//! A stand-in PDS that keeps records in memory, so the app can run (and be driven end to end)
+//! without a real account. It serves the slice of `com.atproto.repo` we write through,
+//! plus `listRecords` for checking what landed and `getBlob` for uploaded blobs, and [`MockSession`] talks to it on behalf of a
+//! single fixed DID in place of an OAuth session.
+//!
+//! Anyone can log in as that DID, so it's only built with the `mock-pds` feature, and for
+//! the tests.
+use crate::blobs::{CODEC_RAW, cid, media_type};
+use actix_web::{HttpResponse, ResponseError, dev::ServerHandle, get, http::StatusCode, post, web};
+use atrium_api::{
+    agent::SessionManager,
+    types::string::Did,
+    xrpc::{
+        HttpClient, XrpcClient,
+        http::{Request, Response},
+    },
+};
+use atrium_oauth::DefaultHttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Clone, Serialize)]
+struct StoredRecord {
+    uri: String,
+    cid: String,
+    value: Value,
+}
+
+#[derive(Clone)]
+struct StoredBlob {
+    mime_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// Every record written to the mock, keyed by `(repo, collection, rkey)`, and every blob
+/// uploaded to it, keyed by cid
+#[derive(Default)]
+pub struct MockPds {
+    records: Mutex<BTreeMap<(String, String, String), StoredRecord>>,
+    blobs: Mutex<HashMap<String, StoredBlob>>,
+    last_tid: Mutex<u64>,
+}
+
+impl MockPds {
+    fn records(&self) -> MutexGuard<'_, BTreeMap<(String, String, String), StoredRecord>> {
+        self.records.lock().unwrap()
+    }
+
+    /// A TID rkey, bumped past the last one so records created in the same microsecond
+    /// still get distinct, ordered keys
+    fn next_tid(&self) -> String {
+        const ALPHABET: &[u8] = b"234567abcdefghijklmnopqrstuvwxyz";
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let mut last = self.last_tid.lock().unwrap();
+        *last = now.max(*last + 1);
+        let tid = *last << 10;
+        (0..13)
+            .rev()
+            .map(|i| ALPHABET[((tid >> (i * 5)) & 31) as usize] as char)
+            .collect()
+    }
+
+    fn create(
+        &self,
+        repo: &str,
+        collection: &str,
+        rkey: Option<String>,
+        value: Value,
+    ) -> Result<StoredRecord, XrpcError> {
+        let rkey = rkey.unwrap_or_else(|| self.next_tid());
+        let key = (repo.to_string(), collection.to_string(), rkey);
+        let mut records = self.records();
+        if records.contains_key(&key) {
+            return Err(XrpcError::InvalidRequest(format!(
+                "Record already exists at {}",
+                at_uri(&key)
+            )));
+        }
+        let record = StoredRecord {
+            uri: at_uri(&key),
+            cid: record_cid(&value),
+            value,
+        };
+        records.insert(key, record.clone());
+        Ok(record)
+    }
+
+    fn put(&self, repo: &str, collection: &str, rkey: String, value: Value) -> StoredRecord {
+        let key = (repo.to_string(), collection.to_string(), rkey);
+        let record = StoredRecord {
+            uri: at_uri(&key),
+            cid: record_cid(&value),
+            value,
+        };
+        self.records().insert(key, record.clone());
+        record
+    }
+
+    fn delete(&self, repo: &str, collection: &str, rkey: String) {
+        self.records()
+            .remove(&(repo.to_string(), collection.to_string(), rkey));
+    }
+
+    /// Stores `data`, returning its blob ref. Like a real PDS it goes by what the bytes look
+    /// like, not what the uploader said they were.
+    fn upload(&self, data: Vec<u8>) -> Result<Value, XrpcError> {
+        let mime_type = media_type(&data)
+            .ok_or_else(|| XrpcError::InvalidRequest("Unsupported blob type".into()))?;
+        let cid = cid(CODEC_RAW, &data);
+        let blob = serde_json::json!({
+            "$type": "blob",
+            "ref": { "$link": cid },
+            "mimeType": mime_type,
+            "size": data.len(),
+        });
+        self.blobs
+            .lock()
+            .unwrap()
+            .insert(cid, StoredBlob { mime_type, data });
+        Ok(blob)
+    }
+
+    /// The records in one collection of one repo, in rkey order
+    fn list(&self, repo: &str, collection: &str) -> Vec<StoredRecord> {
+        self.records()
+            .iter()
+            .filter(|((r, c, _), _)| r == repo && c == collection)
+            .map(|(_, record)| record.clone())
+            .collect()
+    }
+}
+
+fn at_uri((repo, collection, rkey): &(String, String, String)) -> String {
+    format!("at://{repo}/{collection}/{rkey}")
+}
+
+/// A CIDv1 (dag-cbor, sha2-256) over the record's JSON. A real PDS hashes the CBOR encoding,
+/// but nothing here checks a cid against its content, it only has to parse.
+fn record_cid(value: &Value) -> String {
+    cid(0x71, value.to_string().as_bytes())
+}
+
+#[derive(Error, Debug)]
+enum XrpcError {
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("Could not locate record: {0}")]
+    RecordNotFound(String),
+    #[error("Blob not found: {0}")]
+    BlobNotFound(String),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+}
+
+impl ResponseError for XrpcError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+    fn error_response(&self) -> HttpResponse {
+        let error = match self {
+            XrpcError::InvalidRequest(_) => "InvalidRequest",
+            XrpcError::RecordNotFound(_) => "RecordNotFound",
+            XrpcError::BlobNotFound(_) => "BlobNotFound",
+        };
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            error,
+            message: self.to_string(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateRecordInput {
+    repo: String,
+    collection: String,
+    rkey: Option<String>,
+    record: Value,
+}
+
+#[derive(Deserialize)]
+struct PutRecordInput {
+    repo: String,
+    collection: String,
+    rkey: String,
+    record: Value,
+}
+
+#[derive(Deserialize)]
+struct DeleteRecordInput {
+    repo: String,
+    collection: String,
+    rkey: String,
+}
+
+#[derive(Serialize)]
+struct RecordRef {
+    uri: String,
+    cid: String,
+}
+
+impl From<StoredRecord> for RecordRef {
+    fn from(record: StoredRecord) -> Self {
+        Self {
+            uri: record.uri,
+            cid: record.cid,
+        }
+    }
+}
+
+#[post("/xrpc/com.atproto.repo.createRecord")]
+async fn create_record(
+    pds: web::Data<MockPds>,
+    input: web::Json<CreateRecordInput>,
+) -> Result<HttpResponse, XrpcError> {
+    let CreateRecordInput {
+        repo,
+        collection,
+        rkey,
+        record,
+    } = input.into_inner();
+    let created = pds.create(&repo, &collection, rkey, record)?;
+    Ok(HttpResponse::Ok().json(RecordRef::from(created)))
+}
+
+#[post("/xrpc/com.atproto.repo.putRecord")]
+async fn put_record(pds: web::Data<MockPds>, input: web::Json<PutRecordInput>) -> HttpResponse {
+    let PutRecordInput {
+        repo,
+        collection,
+        rkey,
+        record,
+    } = input.into_inner();
+    HttpResponse::Ok().json(RecordRef::from(pds.put(&repo, &collection, rkey, record)))
+}
+
+#[post("/xrpc/com.atproto.repo.deleteRecord")]
+async fn delete_record(
+    pds: web::Data<MockPds>,
+    input: web::Json<DeleteRecordInput>,
+) -> HttpResponse {
+    let DeleteRecordInput {
+        repo,
+        collection,
+        rkey,
+    } = input.into_inner();
+    pds.delete(&repo, &collection, rkey);
+    HttpResponse::Ok().json(serde_json::json!({}))
+}
+
+#[derive(Deserialize)]
+struct ListRecordsParams {
+    repo: String,
+    collection: String,
+    limit: Option<usize>,
+    cursor: Option<String>,
+    reverse: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ListRecordsOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
+    records: Vec<StoredRecord>,
+}
+
+/// Pages by rkey; the cursor is the last rkey of the previous page
+#[get("/xrpc/com.atproto.repo.listRecords")]
+async fn list_records(
+    pds: web::Data<MockPds>,
+    params: web::Query<ListRecordsParams>,
+) -> HttpResponse {
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let mut records = pds.list(&params.repo, &params.collection);
+    if params.reverse.unwrap_or(false) {
+        records.reverse();
+    }
+    let rkey = |record: &StoredRecord| record.uri.rsplit('/').next().unwrap_or_default().to_owned();
+    let start = params
+        .cursor
+        .as_ref()
+        .and_then(|cursor| records.iter().position(|r| &rkey(r) == cursor))
+        .map_or(0, |i| i + 1);
+    let page: Vec<StoredRecord> = records.into_iter().skip(start).take(limit).collect();
+    let cursor = if page.len() == limit {
+        page.last().map(rkey)
+    } else {
+        None
+    };
+    HttpResponse::Ok().json(ListRecordsOutput {
+        cursor,
+        records: page,
+    })
+}
+
+#[post("/xrpc/com.atproto.repo.uploadBlob")]
+async fn upload_blob(pds: web::Data<MockPds>, body: web::Bytes) -> Result<HttpResponse, XrpcError> {
+    let blob = pds.upload(body.to_vec())?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "blob": blob })))
+}
+
+#[derive(Deserialize)]
+struct GetBlobParams {
+    cid: String,
+}
+
+/// Blobs aren't kept per repo, so `did` is ignored
+#[get("/xrpc/com.atproto.sync.getBlob")]
+async fn get_blob(
+    pds: web::Data<MockPds>,
+    params: web::Query<GetBlobParams>,
+) -> Result<HttpResponse, XrpcError> {
+    let blob = pds
+        .blobs
+        .lock()
+        .unwrap()
+        .get(&params.cid)
+        .cloned()
+        .ok_or_else(|| XrpcError::BlobNotFound(params.cid.clone()))?;
+    Ok(HttpResponse::Ok()
+        .content_type(blob.mime_type)
+        .body(blob.data))
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "$type")]
+enum Write {
+    #[serde(rename = "com.atproto.repo.applyWrites#create")]
+    Create {
+        collection: String,
+        rkey: Option<String>,
+        value: Value,
+    },
+    #[serde(rename = "com.atproto.repo.applyWrites#update")]
+    Update {
+        collection: String,
+        rkey: String,
+        value: Value,
+    },
+    #[serde(rename = "com.atproto.repo.applyWrites#delete")]
+    Delete { collection: String, rkey: String },
+}
+
+#[derive(Deserialize)]
+struct ApplyWritesInput {
+    repo: String,
+    writes: Vec<Write>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "$type")]
+enum WriteResult {
+    #[serde(rename = "com.atproto.repo.applyWrites#createResult")]
+    Create { uri: String, cid: String },
+    #[serde(rename = "com.atproto.repo.applyWrites#updateResult")]
+    Update { uri: String, cid: String },
+    #[serde(rename = "com.atproto.repo.applyWrites#deleteResult")]
+    Delete {},
+}
+
+/// Applies the writes in order. Unlike a real PDS a failed write doesn't roll back the ones
+/// before it, which is fine for a single test client.
+#[post("/xrpc/com.atproto.repo.applyWrites")]
+async fn apply_writes(
+    pds: web::Data<MockPds>,
+    input: web::Json<ApplyWritesInput>,
+) -> Result<HttpResponse, XrpcError> {
+    let ApplyWritesInput { repo, writes } = input.into_inner();
+    let mut results = Vec::with_capacity(writes.len());
+    for write in writes {
+        results.push(match write {
+            Write::Create {
+                collection,
+                rkey,
+                value,
+            } => {
+                let StoredRecord { uri, cid, .. } = pds.create(&repo, &collection, rkey, value)?;
+                WriteResult::Create { uri, cid }
+            }
+            Write::Update {
+                collection,
+                rkey,
+                value,
+            } => {
+                let key = (repo.clone(), collection.clone(), rkey.clone());
+                if !pds.records().contains_key(&key) {
+                    return Err(XrpcError::RecordNotFound(at_uri(&key)));
+                }
+                let StoredRecord { uri, cid, .. } = pds.put(&repo, &collection, rkey, value);
+                WriteResult::Update { uri, cid }
+            }
+            Write::Delete { collection, rkey } => {
+                pds.delete(&repo, &collection, rkey);
+                WriteResult::Delete {}
+            }
+        });
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "results": results })))
+}
+
+/// Mounts the mock's XRPC endpoints, sharing `pds` as their store
+pub fn configure(pds: web::Data<MockPds>) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| {
+        cfg.app_data(pds)
+            // Blobs come in as raw bodies, bigger than the default limit
+            .app_data(web::PayloadConfig::new(10 * 1024 * 1024))
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _req| XrpcError::InvalidRequest(err.to_string()).into()),
+            )
+            .service(create_record)
+            .service(put_record)
+            .service(delete_record)
+            .service(list_records)
+            .service(apply_writes)
+            .service(upload_blob)
+            .service(get_blob);
+    }
+}
+
+/// Starts the mock on `addr` in the background, returning its base url (`addr` may use port 0)
+/// and a handle to stop it
+pub fn spawn(
+    pds: web::Data<MockPds>,
+    addr: (&str, u16),
+) -> std::io::Result<(String, ServerHandle)> {
+    let server =
+        actix_web::HttpServer::new(move || actix_web::App::new().configure(configure(pds.clone())))
+            .workers(1)
+            .bind(addr)?;
+    let bound: SocketAddr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    tokio::spawn(server);
+    Ok((format!("http://{bound}"), handle))
+}
+
+/// Stands in for an OAuth session: unauthenticated XRPC calls to the mock, as `did`
+pub struct MockSession {
+    did: Did,
+    base_uri: String,
+    http_client: DefaultHttpClient,
+}
+
+impl MockSession {
+    pub fn new(did: Did, base_uri: String) -> Self {
+        Self {
+            did,
+            base_uri,
+            http_client: DefaultHttpClient::default(),
+        }
+    }
+}
+
+impl HttpClient for MockSession {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.http_client.send_http(request).await
+    }
+}
+
+impl XrpcClient for MockSession {
+    fn base_uri(&self) -> String {
+        self.base_uri.clone()
+    }
+}
+
+impl SessionManager for MockSession {
+    async fn did(&self) -> Option<Did> {
+        Some(self.did.clone())
+    }
+}
+
+/// Whose session the login bypass hands out, and where their mock PDS is listening
+#[derive(Clone)]
+pub struct MockLogin {
+    pub did: Did,
+    pub base_uri: String,
+}
+
+impl MockLogin {
+    pub fn session(&self) -> MockSession {
+        MockSession::new(self.did.clone(), self.base_uri.clone())
+    }
+}
+
+/// The services, and the whole app logged in through the bypass, writing through a real XRPC
+/// client to the mock, as they do when the app runs against it
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobs::{BlobCache, BlobError, MAX_BLOB_SIZE, blob_cid};
+    use crate::db::{
+        CardArgs, CardRepo, CardUpdateArgs, CollectionRepo, DbCard, MemoryRepo, Repo, StackRepo,
+    };
+    use crate::lexicons::record::KnownRecord;
+    use crate::routes::AuthClient;
+    use crate::services::pds::MAX_WRITES;
+    use crate::services::stacks::CLONE_ATTEMPTS;
+    use crate::services::{
+        CardInput, CardService, CollectionInput, CollectionService, Pds, PdsError, ServiceError,
+        StackInput, StackService,
+    };
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{App, cookie::Cookie, http::header, test};
+    use async_trait::async_trait;
+    use atrium_api::agent::Agent;
+    use atrium_api::types::{BlobRef, string::RecordKey};
+    use atrium_identity::did::{CommonDidResolver, CommonDidResolverConfig};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nnot much of a picture";
+
+    struct Mock {
+        pds: web::Data<MockPds>,
+        base_uri: String,
+        handle: ServerHandle,
+        blob_dir: std::path::PathBuf,
+    }
+
+    impl Mock {
+        fn start() -> Self {
+            let pds = web::Data::new(MockPds::default());
+            let (base_uri, handle) = spawn(pds.clone(), ("127.0.0.1", 0)).unwrap();
+            let blob_dir =
+                std::env::temp_dir().join(format!("flatshcards-blobs-{:x}", rand::random::<u64>()));
+            Self {
+                pds,
+                base_uri,
+                handle,
+                blob_dir,
+            }
+        }
+
+        async fn stop(self) {
+            self.handle.stop(false).await;
+            let _ = std::fs::remove_dir_all(&self.blob_dir);
+        }
+
+        fn agent(&self, did: &Did) -> Agent<MockSession> {
+            Agent::new(MockSession::new(did.clone(), self.base_uri.clone()))
+        }
+
+        /// Fetching only ever from the mock, so the resolver goes unused
+        fn blobs(&self) -> BlobCache {
+            let resolver = CommonDidResolver::new(CommonDidResolverConfig {
+                plc_directory_url: "http://127.0.0.1:1".to_string(),
+                http_client: Arc::new(DefaultHttpClient::default()),
+            });
+            BlobCache::new(
+                self.blob_dir.clone(),
+                Arc::new(resolver),
+                Some(self.base_uri.clone()),
+            )
+        }
+
+        fn values(&self, did: &Did, collection: &str) -> Vec<Value> {
+            self.pds
+                .list(did.as_str(), collection)
+                .into_iter()
+                .map(|record| record.value)
+                .collect()
+        }
+    }
+
+    fn did(did: &str) -> Did {
+        Did::new(did.to_string()).unwrap()
+    }
+
+    fn stack_input(label: &str) -> StackInput {
+        serde_json::from_value(serde_json::json!({
+            "frontLang": "ja",
+            "backLang": "en",
+            "stackLabel": label,
+        }))
+        .unwrap()
+    }
+
+    fn card_input(stack_uri: &str, back_text: &str) -> CardInput {
+        serde_json::from_value(serde_json::json!({
+            "frontLang": "ja",
+            "frontText": "包丁",
+            "backLang": "en",
+            "backText": back_text,
+            "stackId": stack_uri,
+        }))
+        .unwrap()
+    }
+
+    /// The app as it's served, logging everyone in as `did` on the mock
+    fn app(
+        mock: &Mock,
+        repo: &Arc<dyn Repo>,
+        did: &Did,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody + use<>>,
+            Error = actix_web::Error,
+            InitError = (),
+        > + use<>,
+    > {
+        let client = Arc::new(AuthClient {
+            oauth: crate::oauth_client("127.0.0.1", 8080, repo),
+            mock: Some(MockLogin {
+                did: did.clone(),
+                base_uri: mock.base_uri.clone(),
+            }),
+        });
+        App::new()
+            .app_data(web::Data::new(client))
+            .app_data(web::Data::from(repo.clone()))
+            .app_data(web::Data::new(mock.blobs()))
+            .wrap(crate::session_middleware())
+            .configure(crate::routes)
+    }
+
+    /// A form as the editors send it
+    fn multipart(fields: &[(&str, &str)]) -> (String, String) {
+        let boundary = "flatshcards-form";
+        let mut body = String::new();
+        for (name, value) in fields {
+            body += &format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            );
+        }
+        body += &format!("--{boundary}--\r\n");
+        (format!("multipart/form-data; boundary={boundary}"), body)
+    }
+
+    /// `uri` kept to one path segment, as the pages link to it
+    fn encode_path(uri: &str) -> String {
+        askama::filters::urlencode_strict(uri).unwrap().to_string()
+    }
+
+    /// A request to log in, which the bypass lets through whatever the handle
+    fn log_in() -> actix_web::test::TestRequest {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_form([("handle", "anyone.test")])
+    }
+
+    /// The session cookie `response` to logging in set
+    fn session_cookie<B>(response: &ServiceResponse<B>) -> Cookie<'static> {
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        response
+            .response()
+            .cookies()
+            .next()
+            .expect("logging in should set the session cookie")
+            .into_owned()
+    }
+
+    #[actix_web::test]
+    async fn drives_the_app_through_the_login_bypass() {
+        let mock = Mock::start();
+        let repo: Arc<dyn Repo> = Arc::new(MemoryRepo::default());
+        let alice = did("did:plc:alice");
+        let alice_app = test::init_service(app(&mock, &repo, &alice)).await;
+        let cookie = session_cookie(&test::call_service(&alice_app, log_in().to_request()).await);
+
+        let (content_type, body) = multipart(&[
+            ("stackLabel", "Kitchen"),
+            ("frontLang", "ja"),
+            ("backLang", "en"),
+        ]);
+        let create_stack = test::TestRequest::post()
+            .uri("/stacks/create")
+            .cookie(cookie.clone())
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .set_payload(body)
+            .to_request();
+        let response = test::call_service(&alice_app, create_stack).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let stacks = repo.user_stacks(alice.as_str()).await.unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].label, "Kitchen");
+        let stack_uri = stacks[0].uri.clone();
+
+        for back_text in ["knife", "cutting board"] {
+            let (content_type, body) = multipart(&[
+                ("frontLang", "ja"),
+                ("frontText", "包丁"),
+                ("backLang", "en"),
+                ("backText", back_text),
+                ("kind", "basic"),
+                ("format", "plain"),
+                ("stackId", &stack_uri),
+            ]);
+            let create_card = test::TestRequest::post()
+                .uri("/cards/create")
+                .cookie(cookie.clone())
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload(body)
+                .to_request();
+            let response = test::call_service(&alice_app, create_card).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let written = mock.values(&alice, "xyz.flatshcards.card");
+        assert_eq!(written.len(), 2);
+        assert_eq!(repo.stack_cards(&stack_uri).await.unwrap().len(), 2);
+
+        // Someone else's session can't write to alice's stack
+        let bob = did("did:plc:bob");
+        let bob_app = test::init_service(app(&mock, &repo, &bob)).await;
+        let bob_cookie = session_cookie(&test::call_service(&bob_app, log_in().to_request()).await);
+        let (content_type, body) = multipart(&[
+            ("frontLang", "ja"),
+            ("frontText", "鍋"),
+            ("backLang", "en"),
+            ("backText", "pot"),
+            ("kind", "basic"),
+            ("format", "plain"),
+            ("stackId", &stack_uri),
+        ]);
+        let create_card = test::TestRequest::post()
+            .uri("/cards/create")
+            .cookie(bob_cookie.clone())
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .set_payload(body)
+            .to_request();
+        let response = test::call_service(&bob_app, create_card).await;
+        assert!(!response.status().is_success());
+        assert_eq!(repo.stack_cards(&stack_uri).await.unwrap().len(), 2);
+
+        let clone = test::TestRequest::post()
+            .uri(&format!("/stacks/clone/{}", encode_path(&stack_uri)))
+            .cookie(bob_cookie)
+            .to_request();
+        let response = test::call_service(&bob_app, clone).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let copies = repo.user_stacks(bob.as_str()).await.unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].label, "Kitchen");
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(location.contains("/stacks/edit/"));
+        let cloned = repo.stack_cards(&copies[0].uri).await.unwrap();
+        assert_eq!(
+            cloned
+                .iter()
+                .map(|card| card.back_text.as_str())
+                .collect::<Vec<_>>(),
+            ["knife", "cutting board"]
+        );
+        assert_eq!(mock.values(&bob, "xyz.flatshcards.card").len(), 2);
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn writes_cards_through_the_mock() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let agent = mock.agent(&alice);
+        let stacks = StackService::new(&agent, &repo, &alice);
+        let cards = CardService::new(&agent, &repo, &alice);
+
+        let stack = stacks.create(&stack_input("Kitchen")).await.unwrap();
+        let rkey = stack.uri.rsplit('/').next().unwrap();
+        let image = cards.upload_image(PNG.to_vec()).await.unwrap();
+        let card = cards
+            .create(&CardInput {
+                front_image: Some(image.clone()),
+                ..card_input(&stack.uri, "knife")
+            })
+            .await
+            .unwrap();
+        let renamed = CardInput {
+            front_image: Some(image.clone()),
+            ..card_input(&stack.uri, "kitchen knife")
+        };
+        cards.update(card.uri.clone(), &renamed).await.unwrap();
+
+        let written = mock.values(&alice, "xyz.flatshcards.card");
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0]["stackId"], rkey);
+        assert_eq!(written[0]["backText"], "kitchen knife");
+        assert_eq!(
+            mock.values(&alice, "xyz.flatshcards.stack")[0]["label"],
+            "Kitchen"
+        );
+        let mirrored = repo.stack_cards(&stack.uri).await.unwrap();
+        assert_eq!(mirrored.len(), 1);
+        assert_eq!(mirrored[0].back_text, "kitchen knife");
+        assert!(
+            repo.card_uses_blob(alice.as_str(), &blob_cid(&image))
+                .await
+                .unwrap()
+        );
+
+        // Nobody else's stack can take the card, nor can they edit it
+        let bob = did("did:plc:bob");
+        let bob_agent = mock.agent(&bob);
+        let bobs = StackService::new(&bob_agent, &repo, &bob)
+            .create(&stack_input("Bob's"))
+            .await
+            .unwrap();
+        let moved = card_input(&bobs.uri, "knife");
+        let moved = cards.update(card.uri.clone(), &moved).await;
+        assert!(matches!(moved, Err(ServiceError::Forbidden)));
+        let taken = card_input(&stack.uri, "knife");
+        let taken = CardService::new(&bob_agent, &repo, &bob)
+            .update(card.uri.clone(), &taken)
+            .await;
+        assert!(matches!(taken, Err(ServiceError::Forbidden)));
+        assert_eq!(
+            mock.values(&alice, "xyz.flatshcards.card")[0]["backText"],
+            "kitchen knife"
+        );
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn clones_someone_elses_stack_with_its_blobs() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let alice_agent = mock.agent(&alice);
+        let stack = StackService::new(&alice_agent, &repo, &alice)
+            .create(&stack_input("Kitchen"))
+            .await
+            .unwrap();
+        let cards = CardService::new(&alice_agent, &repo, &alice);
+        let image = cards.upload_image(PNG.to_vec()).await.unwrap();
+        for back_text in ["knife", "cutting board"] {
+            cards
+                .create(&CardInput {
+                    front_image: Some(image.clone()),
+                    ..card_input(&stack.uri, back_text)
+                })
+                .await
+                .unwrap();
+        }
+
+        let bob = did("did:plc:bob");
+        let bob_agent = mock.agent(&bob);
+        let blobs = mock.blobs();
+        let clone = StackService::new(&bob_agent, &repo, &bob)
+            .clone_from(&stack.uri, &blobs)
+            .await
+            .unwrap();
+        assert!(clone.failed.is_empty());
+        let clone_uri = clone.uri;
+
+        assert!(
+            repo.stack_is_owned_by(bob.as_str(), &clone_uri)
+                .await
+                .unwrap()
+        );
+        let written = mock.values(&bob, "xyz.flatshcards.card");
+        let clone_rkey = clone_uri.rsplit('/').next().unwrap();
+        assert_eq!(written.len(), 2);
+        assert!(written.iter().all(|card| card["stackId"] == clone_rkey));
+        assert_eq!(written[0]["backText"], "knife");
+        assert_eq!(written[1]["backText"], "cutting board");
+        let cloned = repo.stack_cards(&clone_uri).await.unwrap();
+        assert_eq!(cloned.len(), 2);
+        assert!(
+            repo.card_uses_blob(bob.as_str(), &blob_cid(&image))
+                .await
+                .unwrap()
+        );
+
+        mock.stop().await;
+    }
+
+    /// The mock, except that a card with the back text `failing`, or a stack with it as
+    /// its label, never gets created, counting the calls that matter to the tests
+    struct Flaky {
+        agent: Agent<MockSession>,
+        failing: &'static str,
+        /// Failed creates
+        tries: AtomicU32,
+        /// Creates of many records at once, failed or not
+        creates: AtomicU32,
+        puts: AtomicU32,
+        batches: AtomicU32,
+        uploads: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(agent: Agent<MockSession>, failing: &'static str) -> Self {
+            Self {
+                agent,
+                failing,
+                tries: AtomicU32::new(0),
+                creates: AtomicU32::new(0),
+                puts: AtomicU32::new(0),
+                batches: AtomicU32::new(0),
+                uploads: AtomicU32::new(0),
+            }
+        }
+
+        fn fails(&self, record: &KnownRecord) -> bool {
+            let fails = match record {
+                KnownRecord::LexiconXyzFlatshcardsCardsCard(card) => card.back_text == self.failing,
+                KnownRecord::LexiconXyzFlatshcardsCardsStack(stack) => stack.label == self.failing,
+                _ => false,
+            };
+            if fails {
+                self.tries.fetch_add(1, Ordering::Relaxed);
+            }
+            fails
+        }
+    }
+
+    #[async_trait]
+    impl Pds for Flaky {
+        async fn create_record(
+            &self,
+            repo: &Did,
+            collection: &str,
+            record: KnownRecord,
+        ) -> Result<String, PdsError> {
+            if self.fails(&record) {
+                return Err(PdsError("flaky".to_string()));
+            }
+            self.agent.create_record(repo, collection, record).await
+        }
+        async fn create_records(
+            &self,
+            repo: &Did,
+            collection: &str,
+            records: Vec<KnownRecord>,
+        ) -> Result<Vec<String>, PdsError> {
+            self.creates.fetch_add(1, Ordering::Relaxed);
+            // Like applyWrites, one failure fails them all
+            if records.iter().any(|record| self.fails(record)) {
+                return Err(PdsError("flaky".to_string()));
+            }
+            self.agent.create_records(repo, collection, records).await
+        }
+        async fn put_record(
+            &self,
+            repo: &Did,
+            collection: &str,
+            rkey: RecordKey,
+            record: KnownRecord,
+        ) -> Result<(), PdsError> {
+            self.puts.fetch_add(1, Ordering::Relaxed);
+            self.agent.put_record(repo, collection, rkey, record).await
+        }
+        async fn put_records(
+            &self,
+            repo: &Did,
+            collection: &str,
+            records: Vec<(RecordKey, KnownRecord)>,
+        ) -> Result<(), PdsError> {
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            self.agent.put_records(repo, collection, records).await
+        }
+        async fn delete_record(
+            &self,
+            repo: &Did,
+            collection: &str,
+            rkey: RecordKey,
+        ) -> Result<(), PdsError> {
+            self.agent.delete_record(repo, collection, rkey).await
+        }
+        async fn upload_blob(&self, data: Vec<u8>) -> Result<BlobRef, PdsError> {
+            self.uploads.fetch_add(1, Ordering::Relaxed);
+            self.agent.upload_blob(data).await
+        }
+    }
+
+    /// A card for `stack`, as indexed, without going through the mock
+    fn indexed_card(stack: &str, n: i32, front_text: &str, back_text: &str) -> DbCard {
+        DbCard::new(CardArgs {
+            uri: format!("{}/{n:013}", stack.replace("stack", "card")),
+            author_did: stack.split('/').nth(2).unwrap().to_string(),
+            back_lang: "en".to_string(),
+            back_text: back_text.to_string(),
+            front_lang: "ja".to_string(),
+            front_text: front_text.to_string(),
+            kind: "basic".to_string(),
+            format: "plain".to_string(),
+            front_image: None,
+            back_image: None,
+            audio: None,
+            tags: Vec::new(),
+            position: Some(n),
+            indexed_at: None,
+            stack_id: stack.to_string(),
+        })
+    }
+
+    #[actix_web::test]
+    async fn clones_in_batches_giving_up_on_ones_that_keep_failing() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let alice_agent = mock.agent(&alice);
+        let stack = StackService::new(&alice_agent, &repo, &alice)
+            .create(&stack_input("Kitchen"))
+            .await
+            .unwrap();
+        let image = CardService::new(&alice_agent, &repo, &alice)
+            .upload_image(PNG.to_vec())
+            .await
+            .unwrap();
+        // A batch's worth, and one more that goes in a batch of its own
+        let count = MAX_WRITES as i32;
+        for n in 0..count {
+            let card = indexed_card(&stack.uri, n, &n.to_string(), "spoon");
+            repo.save_card(&card).await.unwrap();
+        }
+        let knife = DbCard {
+            front_image: Some(image),
+            ..indexed_card(&stack.uri, count, "包丁", "knife")
+        };
+        repo.save_card(&knife).await.unwrap();
+
+        let bob = did("did:plc:bob");
+        let flaky = Flaky::new(mock.agent(&bob), "knife");
+        let blobs = mock.blobs();
+        let clone = StackService::new(&flaky, &repo, &bob)
+            .clone_from(&stack.uri, &blobs)
+            .await
+            .unwrap();
+
+        assert_eq!(clone.failed, ["包丁"]);
+        assert_eq!(flaky.tries.load(Ordering::Relaxed), CLONE_ATTEMPTS);
+        assert_eq!(flaky.creates.load(Ordering::Relaxed), 1 + CLONE_ATTEMPTS);
+        // The image is copied once, however many times its card is tried
+        assert_eq!(flaky.uploads.load(Ordering::Relaxed), 1);
+        let written = mock.values(&bob, "xyz.flatshcards.card");
+        assert_eq!(written.len(), MAX_WRITES);
+        assert!(written.iter().all(|card| card["backText"] == "spoon"));
+        let cloned = repo.stack_cards(&clone.uri).await.unwrap();
+        assert_eq!(cloned.len(), MAX_WRITES);
+        assert_eq!(cloned[0].position, Some(0));
+        assert_eq!(cloned[MAX_WRITES - 1].front_text, (count - 1).to_string());
+
+        mock.stop().await;
+    }
+
+    fn collection_input(label: &str, stacks: &[&str]) -> CollectionInput {
+        serde_json::from_value(serde_json::json!({
+            "collectionLabel": label,
+            "stacks": stacks,
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn clones_your_own_collection() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let agent = mock.agent(&alice);
+        let stacks = StackService::new(&agent, &repo, &alice);
+        let cards = CardService::new(&agent, &repo, &alice);
+        let mut uris = Vec::new();
+        for label in ["Kitchen", "Garden"] {
+            let stack = stacks.create(&stack_input(label)).await.unwrap();
+            cards
+                .create(&card_input(&stack.uri, "knife"))
+                .await
+                .unwrap();
+            uris.push(stack.uri);
+        }
+        let collections = CollectionService::new(&agent, &repo, &alice);
+        let uris: Vec<&str> = uris.iter().map(String::as_str).collect();
+        let src = collections
+            .create(&collection_input("House", &uris))
+            .await
+            .unwrap();
+
+        let clone = collections
+            .clone_from(&src.uri, &mock.blobs())
+            .await
+            .unwrap();
+        assert!(clone.failed.is_empty());
+        let copy = repo.get_collection(&clone.uri).await.unwrap().unwrap();
+        assert_eq!(copy.label, "House");
+        assert_eq!(copy.stacks.len(), 2);
+        assert!(copy.stacks.iter().all(|uri| !uris.contains(&uri.as_str())));
+        let copies = repo.get_stacks(&copy.stacks).await.unwrap();
+        assert_eq!(copies.len(), 2);
+        for uri in &copy.stacks {
+            assert_eq!(repo.stack_cards(uri).await.unwrap().len(), 1);
+        }
+        assert_eq!(repo.user_stacks(alice.as_str()).await.unwrap().len(), 4);
+        assert_eq!(mock.values(&alice, "xyz.flatshcards.collection").len(), 2);
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn collections_are_cloned_without_stacks_that_fail() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let agent = mock.agent(&alice);
+        let stacks = StackService::new(&agent, &repo, &alice);
+        let cards = CardService::new(&agent, &repo, &alice);
+        let mut uris = Vec::new();
+        for label in ["Kitchen", "Garden"] {
+            let stack = stacks.create(&stack_input(label)).await.unwrap();
+            cards
+                .create(&card_input(&stack.uri, "knife"))
+                .await
+                .unwrap();
+            uris.push(stack.uri);
+        }
+        let uris: Vec<&str> = uris.iter().map(String::as_str).collect();
+        let src = CollectionService::new(&agent, &repo, &alice)
+            .create(&collection_input("House", &uris))
+            .await
+            .unwrap();
+
+        let bob = did("did:plc:bob");
+        let flaky = Flaky::new(mock.agent(&bob), "Kitchen");
+        let clone = CollectionService::new(&flaky, &repo, &bob)
+            .clone_from(&src.uri, &mock.blobs())
+            .await
+            .unwrap();
+        assert_eq!(clone.failed, ["包丁"]);
+        let copy = repo.get_collection(&clone.uri).await.unwrap().unwrap();
+        let copies = repo.get_stacks(&copy.stacks).await.unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].label, "Garden");
+        assert_eq!(mock.values(&bob, "xyz.flatshcards.card").len(), 1);
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn reorders_in_one_commit_keeping_when_cards_were_made() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let pds = Flaky::new(mock.agent(&alice), "");
+        let stack = StackService::new(&pds, &repo, &alice)
+            .create(&stack_input("Kitchen"))
+            .await
+            .unwrap();
+        let cards = CardService::new(&pds, &repo, &alice);
+        let mut uris = Vec::new();
+        for back_text in ["knife", "cutting board", "pot"] {
+            let card = card_input(&stack.uri, back_text);
+            uris.push(cards.create(&card).await.unwrap().uri);
+        }
+        let created_at = |written: &[Value]| -> Vec<Value> {
+            written
+                .iter()
+                .map(|card| card["createdAt"].clone())
+                .collect()
+        };
+        let made = created_at(&mock.values(&alice, "xyz.flatshcards.card"));
+
+        let order = [uris[2].clone(), uris[0].clone(), uris[1].clone()];
+        let ordered = cards.reorder(&stack.uri, &order).await.unwrap();
+        assert_eq!(
+            ordered
+                .iter()
+                .map(|card| &card.back_text)
+                .collect::<Vec<_>>(),
+            ["pot", "knife", "cutting board"]
+        );
+        assert_eq!(pds.batches.load(Ordering::Relaxed), 1);
+        assert_eq!(pds.puts.load(Ordering::Relaxed), 0);
+        let written = mock.values(&alice, "xyz.flatshcards.card");
+        assert_eq!(
+            written
+                .iter()
+                .map(|card| card["position"].clone())
+                .collect::<Vec<_>>(),
+            [1, 2, 0]
+        );
+        assert_eq!(created_at(&written), made);
+        let stored = repo.stack_cards(&stack.uri).await.unwrap();
+        assert_eq!(
+            stored.iter().map(|card| &card.uri).collect::<Vec<_>>(),
+            order.iter().collect::<Vec<_>>()
+        );
+
+        let renamed = card_input(&stack.uri, "kitchen knife");
+        cards.update(uris[0].clone(), &renamed).await.unwrap();
+        let written = mock.values(&alice, "xyz.flatshcards.card");
+        assert_eq!(written[0]["backText"], "kitchen knife");
+        assert_eq!(written[0]["position"], 1);
+        assert_eq!(created_at(&written), made);
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn cards_without_positions_get_them_before_a_card_is_added() {
+        let mock = Mock::start();
+        let repo = MemoryRepo::default();
+        let alice = did("did:plc:alice");
+        let pds = Flaky::new(mock.agent(&alice), "");
+        let stack = StackService::new(&pds, &repo, &alice)
+            .create(&stack_input("Kitchen"))
+            .await
+            .unwrap();
+        let cards = CardService::new(&pds, &repo, &alice);
+        let mut made = Vec::new();
+        for back_text in ["knife", "cutting board"] {
+            let card = card_input(&stack.uri, back_text);
+            made.push(cards.create(&card).await.unwrap());
+        }
+        // As cards from before stacks were ordered look once they're indexed
+        for card in made {
+            repo.update_owned_card(&CardUpdateArgs {
+                uri: card.uri,
+                author_did: card.author_did,
+                back_lang: card.back_lang,
+                back_text: card.back_text,
+                front_lang: card.front_lang,
+                front_text: card.front_text,
+                kind: card.kind,
+                format: card.format,
+                front_image: card.front_image,
+                back_image: card.back_image,
+                audio: card.audio,
+                tags: card.tags,
+                position: None,
+                stack_id: card.stack_id,
+                created_at: card.created_at,
+            })
+            .await
+            .unwrap();
+        }
+
+        cards.create(&card_input(&stack.uri, "pot")).await.unwrap();
+        let stored = repo.stack_cards(&stack.uri).await.unwrap();
+        assert_eq!(
+            stored
+                .iter()
+                .map(|card| (card.back_text.as_str(), card.position))
+                .collect::<Vec<_>>(),
+            [
+                ("knife", Some(0)),
+                ("cutting board", Some(1)),
+                ("pot", Some(2))
+            ]
+        );
+        assert_eq!(pds.batches.load(Ordering::Relaxed), 1);
+        let written = mock.values(&alice, "xyz.flatshcards.card");
+        assert!(written.iter().all(|card| card["position"].is_number()));
+
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn caches_only_blobs_that_match_their_cid() {
+        let mock = Mock::start();
+        let blobs = mock.blobs();
+        let alice = "did:plc:alice";
+        let blob = mock.pds.upload(PNG.to_vec()).unwrap();
+        let good = blob["ref"]["$link"].as_str().unwrap();
+        assert_eq!(blobs.get(alice, good).await.unwrap(), PNG);
+        assert!(mock.blob_dir.join(good).exists());
+
+        // A PDS serving other bytes under a cid can't get them cached as that cid
+        let forged = cid(CODEC_RAW, b"something else");
+        let huge = cid(CODEC_RAW, b"something huge");
+        let mut png = PNG.to_vec();
+        png.resize(MAX_BLOB_SIZE + 1, 0);
+        for (cid, data) in [(&forged, PNG.to_vec()), (&huge, png)] {
+            mock.pds.blobs.lock().unwrap().insert(
+                cid.clone(),
+                StoredBlob {
+                    mime_type: "image/png",
+                    data,
+                },
+            );
+        }
+        assert!(matches!(
+            blobs.get(alice, &forged).await,
+            Err(BlobError::Mismatch(_))
+        ));
+        assert!(matches!(
+            blobs.get(alice, &huge).await,
+            Err(BlobError::TooLarge(_))
+        ));
+        assert!(!mock.blob_dir.join(&forged).exists());
+        assert!(!mock.blob_dir.join(&huge).exists());
+        mock.stop().await;
+    }
+}
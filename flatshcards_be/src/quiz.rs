@@ -0,0 +1,113 @@
+//! Multiple-choice quizzes: each card's front, with its back to pick out from among the
+//! backs of other cards. Quizzes aren't scheduled or kept, so they're a gentler way in
+//! than reviewing. Cloze cards have no back to choose, so they're left out.
+use crate::db::DisplayCard;
+use crate::lang::{TextLayout, text_layout};
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+/// Questions in one quiz, at most
+pub const QUIZ_LENGTH: usize = 10;
+/// Choices per question, the answer included, when there are enough backs to go round
+pub const CHOICES: usize = 4;
+
+/// One of a question's choices: a back from some card
+#[derive(Clone, Debug)]
+pub struct Choice {
+    /// The back as written, which is what's submitted and compared
+    pub text: String,
+    /// Sanitized, see [`crate::rich::to_html`]
+    pub html: String,
+    pub lang: String,
+}
+
+impl Choice {
+    fn of(card: &DisplayCard) -> Self {
+        Self {
+            text: card.back_text.clone(),
+            html: card.back_html(),
+            lang: card.back_lang.clone(),
+        }
+    }
+
+    pub fn layout(&self) -> TextLayout {
+        text_layout(&self.lang)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Question {
+    pub card: DisplayCard,
+    pub choices: Vec<Choice>,
+}
+
+/// Up to [`QUIZ_LENGTH`] questions on `cards`, in random order. Distractors come from
+/// `cards` first, then from `pool`, other cards with the same languages.
+pub fn questions(cards: &[DisplayCard], pool: &[DisplayCard]) -> Vec<Question> {
+    let mut rng = rand::rng();
+    let mut asked: Vec<&DisplayCard> = cards.iter().filter(|c| !c.is_cloze()).collect();
+    asked.shuffle(&mut rng);
+    asked.truncate(QUIZ_LENGTH);
+    asked
+        .into_iter()
+        .map(|card| {
+            let mut seen = HashSet::from([card.back_text.clone()]);
+            let mut others = |from: &[DisplayCard]| -> Vec<Choice> {
+                let mut backs: Vec<&DisplayCard> = from
+                    .iter()
+                    .filter(|c| !c.is_cloze() && c.back_lang == card.back_lang)
+                    .filter(|c| seen.insert(c.back_text.clone()))
+                    .collect();
+                backs.shuffle(&mut rand::rng());
+                backs.into_iter().map(Choice::of).collect()
+            };
+            let mut choices = others(cards);
+            if choices.len() < CHOICES - 1 {
+                choices.extend(others(pool));
+            }
+            choices.truncate(CHOICES - 1);
+            choices.push(Choice::of(card));
+            choices.shuffle(&mut rng);
+            Question {
+                card: card.clone(),
+                choices,
+            }
+        })
+        .collect()
+}
+
+/// How one question was answered
+#[derive(Clone, Debug)]
+pub struct QuizResult {
+    pub card: DisplayCard,
+    /// The back that was picked, if any was
+    pub chosen: Option<String>,
+}
+
+impl QuizResult {
+    pub fn is_correct(&self) -> bool {
+        self.chosen.as_deref() == Some(&self.card.back_text)
+    }
+}
+
+/// A finished quiz
+pub struct QuizScore {
+    pub results: Vec<QuizResult>,
+}
+
+impl QuizScore {
+    pub fn correct(&self) -> usize {
+        self.results.iter().filter(|r| r.is_correct()).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Rounded down, so a perfect score is only ever a perfect score
+    pub fn percent(&self) -> usize {
+        (self.correct() * 100)
+            .checked_div(self.total())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,408 @@
+//! Spaced repetition. A card is reviewed as one or more items: one per direction its
+//! stack is studied in, or for a cloze card one per deletion number. Every reviewer has
+//! their own schedule for each item. Schedules are private, so they're only kept here
+//! rather than as records.
+use crate::cloze::{filled, prompt_in};
+use crate::db::{DisplayCard, ReviewState, StackDetails};
+use crate::lang::{TextLayout, text_layout};
+use crate::rich::{to_html, to_plain};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Front to back, the only way stacks were studied before they had directions
+pub const DIRECTION_FORWARD: &str = "forward";
+/// Back to front
+pub const DIRECTION_REVERSE: &str = "reverse";
+/// The `directions` of a stack studied both ways
+pub const DIRECTIONS_BOTH: &str = "both";
+
+pub fn is_directions(directions: &str) -> bool {
+    [DIRECTION_FORWARD, DIRECTION_REVERSE, DIRECTIONS_BOTH].contains(&directions)
+}
+
+/// The directions a stack with `directions` is studied in, each one an item per card
+pub fn directions_of(directions: &str) -> &'static [&'static str] {
+    match directions {
+        DIRECTION_REVERSE => &[DIRECTION_REVERSE],
+        DIRECTIONS_BOTH => &[DIRECTION_FORWARD, DIRECTION_REVERSE],
+        _ => &[DIRECTION_FORWARD],
+    }
+}
+
+/// How well a review item was remembered
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+}
+
+const START_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+/// How soon something forgotten comes back, within the same session
+const RELEARN_MINUTES: i64 = 10;
+/// The longest an item's put off for, about a century. Without it, enough easy reviews
+/// would grow the interval past what a date can hold.
+pub const MAX_INTERVAL_DAYS: i32 = 36500;
+
+/// Where an item is in its schedule, after some reviews
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Schedule {
+    /// How much the interval grows after a good review
+    pub ease: f64,
+    pub interval_days: i32,
+    /// Good reviews in a row
+    pub reps: i32,
+    /// Times it was forgotten after having been learned
+    pub lapses: i32,
+    pub due_at: DateTime<Utc>,
+}
+
+impl Schedule {
+    /// The schedule after reviewing an item with schedule `prev`, or a new item if
+    /// there's none, at `now`. This is SM-2, give or take.
+    pub fn next(prev: Option<&Schedule>, grade: Grade, now: DateTime<Utc>) -> Schedule {
+        let (ease, interval, reps, lapses) = match prev {
+            Some(s) => (s.ease, s.interval_days, s.reps, s.lapses),
+            None => (START_EASE, 0, 0, 0),
+        };
+        let grown = |factor: f64| {
+            ((interval.max(1) as f64) * factor)
+                .round()
+                .min(MAX_INTERVAL_DAYS.into()) as i32
+        };
+        let (ease, interval_days, reps, lapses) = match grade {
+            Grade::Again => (
+                (ease - 0.2).max(MIN_EASE),
+                0,
+                0,
+                lapses + i32::from(reps > 0),
+            ),
+            Grade::Hard if reps == 0 => ((ease - 0.15).max(MIN_EASE), 1, 1, lapses),
+            Grade::Hard => ((ease - 0.15).max(MIN_EASE), grown(1.2), reps + 1, lapses),
+            Grade::Good if reps == 0 => (ease, 1, 1, lapses),
+            Grade::Good if reps == 1 => (ease, 3, 2, lapses),
+            Grade::Good => (ease, grown(ease), reps + 1, lapses),
+            Grade::Easy if reps == 0 => (ease + 0.15, 4, 1, lapses),
+            Grade::Easy => (ease + 0.15, grown(ease * 1.3), reps + 1, lapses),
+        };
+        let due_at = if interval_days == 0 {
+            now + Duration::minutes(RELEARN_MINUTES)
+        } else {
+            now.checked_add_signed(Duration::days(interval_days.into()))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC)
+        };
+        Schedule {
+            ease,
+            interval_days,
+            reps,
+            lapses,
+            due_at,
+        }
+    }
+}
+
+/// One thing to review: a card in one direction, or one of a cloze card's deletions
+#[derive(Clone, Debug)]
+pub struct ReviewItem {
+    /// The stack the card's in, which says the directions it's studied in
+    pub stack_uri: String,
+    pub card: DisplayCard,
+    pub direction: String,
+    pub ordinal: i32,
+    /// Nothing for an item that's never been reviewed
+    pub state: Option<ReviewState>,
+}
+
+/// A side of a card as it's shown in review
+pub struct Face {
+    /// Sanitized, see [`to_html`]
+    pub html: String,
+    pub lang: String,
+    pub layout: TextLayout,
+    pub image_url: Option<String>,
+    pub audio_url: Option<String>,
+}
+
+impl ReviewItem {
+    pub fn is_new(&self) -> bool {
+        self.state.is_none()
+    }
+
+    /// What's shown to be answered. The card's audio goes with its front, so it's held
+    /// back wherever it would give the answer away.
+    pub fn prompt(&self) -> Vec<Face> {
+        if self.card.is_cloze() {
+            let prompt = prompt_in(
+                &self.card.front_text,
+                self.ordinal as u32,
+                &self.card.format,
+            );
+            vec![Face {
+                html: to_html(&prompt, &self.card.format),
+                audio_url: None,
+                ..self.front()
+            }]
+        } else if self.direction == DIRECTION_REVERSE {
+            vec![self.back()]
+        } else {
+            vec![self.front()]
+        }
+    }
+
+    /// What's revealed once it has been
+    pub fn answer(&self) -> Vec<Face> {
+        if self.card.is_cloze() {
+            vec![self.front(), self.back()]
+        } else if self.direction == DIRECTION_REVERSE {
+            vec![self.front()]
+        } else {
+            vec![self.back()]
+        }
+    }
+
+    /// What a typed answer is checked against
+    pub fn expected(&self) -> String {
+        let card = &self.card;
+        if card.is_cloze() {
+            let answer = card
+                .cloze_items()
+                .into_iter()
+                .find(|item| item.ordinal as i32 == self.ordinal)
+                .map(|item| item.answer)
+                .unwrap_or_default();
+            to_plain(&answer, &card.format)
+        } else if self.direction == DIRECTION_REVERSE {
+            to_plain(&card.front_text, &card.format)
+        } else {
+            to_plain(&card.back_text, &card.format)
+        }
+    }
+
+    /// The language of the [`ReviewItem::expected`] answer
+    pub fn answer_lang(&self) -> &str {
+        if self.direction == DIRECTION_REVERSE || self.card.is_cloze() {
+            &self.card.front_lang
+        } else {
+            &self.card.back_lang
+        }
+    }
+
+    pub fn answer_layout(&self) -> TextLayout {
+        text_layout(self.answer_lang())
+    }
+
+    fn front(&self) -> Face {
+        let text = if self.card.is_cloze() {
+            filled(&self.card.front_text)
+        } else {
+            self.card.front_text.clone()
+        };
+        Face {
+            html: to_html(&text, &self.card.format),
+            lang: self.card.front_lang.clone(),
+            layout: self.card.front_layout(),
+            image_url: self.card.front_image_url(),
+            audio_url: self.card.audio_url(),
+        }
+    }
+
+    fn back(&self) -> Face {
+        Face {
+            html: self.card.back_html(),
+            lang: self.card.back_lang.clone(),
+            layout: self.card.back_layout(),
+            image_url: self.card.back_image_url(),
+            audio_url: None,
+        }
+    }
+}
+
+/// The items of `cards` in `stack` that are due at `now`, given the reviewer's `states`:
+/// those due for review first, most overdue first, then new ones
+pub fn due_items(
+    stack: &StackDetails,
+    cards: Vec<DisplayCard>,
+    states: Vec<ReviewState>,
+    now: DateTime<Utc>,
+) -> Vec<ReviewItem> {
+    let mut states: std::collections::HashMap<(String, String, i32), ReviewState> = states
+        .into_iter()
+        .map(|s| ((s.card_uri.clone(), s.direction.clone(), s.ordinal), s))
+        .collect();
+    let mut items: Vec<ReviewItem> = cards
+        .into_iter()
+        .flat_map(|card| {
+            let keys: Vec<(&str, i32)> = if card.is_cloze() {
+                card.cloze_items()
+                    .iter()
+                    .map(|item| (DIRECTION_FORWARD, item.ordinal as i32))
+                    .collect()
+            } else {
+                directions_of(&stack.directions)
+                    .iter()
+                    .map(|d| (*d, 0))
+                    .collect()
+            };
+            keys.into_iter()
+                .map(|(direction, ordinal)| ReviewItem {
+                    stack_uri: stack.uri.clone(),
+                    state: states.remove(&(card.uri.clone(), direction.to_string(), ordinal)),
+                    card: card.clone(),
+                    direction: direction.to_string(),
+                    ordinal,
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|item| item.state.as_ref().is_none_or(|s| s.due_at <= now))
+        .collect();
+    sort_items(&mut items);
+    items
+}
+
+/// Due items first, most overdue first, then new ones. Stable, so new items stay in
+/// card order.
+pub fn sort_items(items: &mut [ReviewItem]) {
+    items.sort_by_key(|item| (item.is_new(), item.state.as_ref().map(|s| s.due_at)));
+}
+
+/// Shuffles the new items at the end of `items`, as [`sort_items`] leaves them. The same
+/// `seed` always shuffles them the same way, so they don't jump about between items.
+pub fn shuffle_new(items: &mut [ReviewItem], seed: &str) {
+    let first_new = items.partition_point(|item| !item.is_new());
+    items[first_new..].sort_by_cached_key(|item| {
+        let mut hasher = DefaultHasher::new();
+        (seed, &item.card.uri, &item.direction, item.ordinal).hash(&mut hasher);
+        hasher.finish()
+    });
+}
+
+/// New items a reviewer sees in a day, unless they say otherwise
+pub const DEFAULT_NEW_PER_DAY: i32 = 20;
+/// Reviews of items already seen a reviewer does in a day, unless they say otherwise
+pub const DEFAULT_REVIEWS_PER_DAY: i32 = 200;
+/// The most either limit can be set to
+pub const MAX_PER_DAY: i32 = 9999;
+
+/// Whether an item's still being learned, or relearned after being forgotten. Those
+/// come back within minutes, and are never held back for the day's limits.
+fn is_learning(item: &ReviewItem) -> bool {
+    item.state.as_ref().is_some_and(|s| s.interval_days == 0)
+}
+
+/// `items` in order, less any beyond the `new_left` new items and `reviews_left` reviews
+/// still allowed today. Returns those kept, and how many were held back.
+pub fn within_limits(
+    items: Vec<ReviewItem>,
+    new_left: i64,
+    reviews_left: i64,
+) -> (Vec<ReviewItem>, usize) {
+    let (mut new, mut reviews) = (0, 0);
+    let total = items.len();
+    let kept: Vec<ReviewItem> = items
+        .into_iter()
+        .filter(|item| {
+            if item.is_new() {
+                new += 1;
+                new <= new_left
+            } else if is_learning(item) {
+                true
+            } else {
+                reviews += 1;
+                reviews <= reviews_left
+            }
+        })
+        .collect();
+    let held_back = total - kept.len();
+    (kept, held_back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        "2025-04-01T09:00:00Z".parse().unwrap()
+    }
+
+    fn after(grades: &[Grade]) -> Schedule {
+        let mut schedule: Option<Schedule> = None;
+        for grade in grades {
+            schedule = Some(Schedule::next(schedule.as_ref(), *grade, now()));
+        }
+        schedule.unwrap()
+    }
+
+    #[test]
+    fn new_items_are_scheduled_by_grade() {
+        use Grade::*;
+        for (grade, ease, interval_days) in [
+            (Again, 2.3, 0),
+            (Hard, 2.35, 1),
+            (Good, 2.5, 1),
+            (Easy, 2.65, 4),
+        ] {
+            let schedule = Schedule::next(None, grade, now());
+            assert!((schedule.ease - ease).abs() < 1e-9, "{grade:?}");
+            assert_eq!(schedule.interval_days, interval_days, "{grade:?}");
+            assert_eq!(schedule.lapses, 0, "{grade:?}");
+        }
+        assert_eq!(
+            Schedule::next(None, Again, now()).due_at,
+            now() + Duration::minutes(RELEARN_MINUTES)
+        );
+        assert_eq!(
+            Schedule::next(None, Easy, now()).due_at,
+            now() + Duration::days(4)
+        );
+    }
+
+    #[test]
+    fn learned_items_grow_or_lapse() {
+        use Grade::*;
+        assert_eq!(after(&[Good, Good]).interval_days, 3);
+        assert_eq!(after(&[Good, Good, Good]).interval_days, 8);
+        assert_eq!(after(&[Good, Good, Hard]).interval_days, 4);
+        assert_eq!(after(&[Good, Good, Easy]).interval_days, 10);
+        let forgotten = after(&[Good, Good, Good, Again]);
+        assert_eq!(forgotten.interval_days, 0);
+        assert_eq!(forgotten.reps, 0);
+        assert_eq!(forgotten.lapses, 1);
+        // Forgetting while still learning isn't a lapse
+        assert_eq!(after(&[Again, Again]).lapses, 0);
+    }
+
+    #[test]
+    fn ease_stops_falling() {
+        assert_eq!(after(&[Grade::Again; 20]).ease, MIN_EASE);
+        let mut grades = vec![Grade::Good];
+        grades.extend([Grade::Hard; 20]);
+        assert_eq!(after(&grades).ease, MIN_EASE);
+    }
+
+    #[test]
+    fn intervals_stop_growing() {
+        let schedule = after(&[Grade::Easy; 40]);
+        assert_eq!(schedule.interval_days, MAX_INTERVAL_DAYS);
+        assert_eq!(
+            schedule.due_at,
+            now() + Duration::days(MAX_INTERVAL_DAYS.into())
+        );
+        let later = Schedule::next(Some(&schedule), Grade::Easy, DateTime::<Utc>::MAX_UTC);
+        assert_eq!(later.due_at, DateTime::<Utc>::MAX_UTC);
+    }
+}
@@ -0,0 +1,270 @@
+//! Card text formats. Plain text is shown as it is; Markdown text may use a small subset
+//! of Markdown, bold, italics, lists and line breaks, plus ruby like `漢字[かんじ]`, where
+//! `\[` is a bracket rather than the start of a reading.
+//! Anything outside the subset, raw HTML included, is kept as its text, so rendering is
+//! also how card text is sanitized.
+use pulldown_cmark::{CowStr, Event, Parser, Tag, TagEnd, html};
+
+/// The `format` of card text that's shown as it is, and of every card from before there
+/// were formats
+pub const FORMAT_PLAIN: &str = "plain";
+/// The `format` of card text in the Markdown subset
+pub const FORMAT_MARKDOWN: &str = "markdown";
+
+pub fn is_format(format: &str) -> bool {
+    format == FORMAT_PLAIN || format == FORMAT_MARKDOWN
+}
+
+/// Card `text` in `format` as HTML that's safe to put in a page
+pub fn to_html(text: &str, format: &str) -> String {
+    let mut out = String::new();
+    if format == FORMAT_MARKDOWN {
+        html::push_html(&mut out, markdown_events(text).into_iter());
+    } else {
+        html::push_html(&mut out, std::iter::once(Event::Text(text.into())));
+    }
+    out
+}
+
+/// Card `text` in `format` with the markup gone, as it would be read out. Ruby is read as
+/// its reading rather than its base.
+pub fn to_plain(text: &str, format: &str) -> String {
+    if format != FORMAT_MARKDOWN {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut in_base = false;
+    for event in markdown_events(text) {
+        match event {
+            Event::InlineHtml(html) => in_base = &*html == "<ruby>",
+            Event::Text(text) if !in_base => out.push_str(&text),
+            Event::HardBreak | Event::End(TagEnd::Paragraph | TagEnd::Item) => out.push('\n'),
+            _ => {}
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn markdown_events(text: &str) -> Vec<Event<'_>> {
+    let mut events = Vec::new();
+    let mut pending = String::new();
+    for (event, range) in Parser::new(text).into_offset_iter() {
+        let event = match event {
+            // An escaped bracket ends the text a base could come from
+            Event::Text(escaped)
+                if escaped.starts_with('[') && text[..range.start].ends_with('\\') =>
+            {
+                ruby_events(&std::mem::take(&mut pending), &mut events);
+                pending.push_str(&escaped);
+                continue;
+            }
+            Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                pending.push_str(&text);
+                continue;
+            }
+            // Card text is short, so a new line is meant as one
+            Event::SoftBreak | Event::HardBreak => Event::HardBreak,
+            Event::Start(
+                tag @ (Tag::Paragraph | Tag::Emphasis | Tag::Strong | Tag::List(_) | Tag::Item),
+            ) => Event::Start(tag),
+            Event::End(
+                tag @ (TagEnd::Paragraph
+                | TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::List(_)
+                | TagEnd::Item),
+            ) => Event::End(tag),
+            // Other blocks that only hold text are kept apart as paragraphs
+            Event::Start(Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::HtmlBlock) => {
+                Event::Start(Tag::Paragraph)
+            }
+            Event::End(TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::HtmlBlock) => {
+                Event::End(TagEnd::Paragraph)
+            }
+            // Links, quotes and the rest keep their text but not their markup
+            _ => continue,
+        };
+        ruby_events(&std::mem::take(&mut pending), &mut events);
+        events.push(event);
+    }
+    ruby_events(&pending, &mut events);
+    events
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3005}' | '\u{3007}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{3134F}')
+}
+
+/// `text` with each `base[reading]` as ruby. The base is the word before the brackets,
+/// or if that ends in kanji or hanzi just those, so `日本語[にほんご]の漢字[かんじ]` needs
+/// no spaces. A `|` or `｜` marks where a base starts, for when neither guess is right.
+fn ruby_events(text: &str, events: &mut Vec<Event<'_>>) {
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let before = &rest[..open];
+        let after = &rest[open + 1..];
+        let reading = after
+            .find(']')
+            .map(|close| &after[..close])
+            .filter(|r| !r.trim().is_empty() && !r.contains('['));
+        let bar = before
+            .rfind(['|', '｜'])
+            .filter(|bar| !before[*bar..].contains(char::is_whitespace));
+        let (kept, base) = match bar {
+            Some(bar) => (&before[..bar], &before[next_char(before, bar)..]),
+            None if before.ends_with(is_han) => {
+                before.split_at(before.trim_end_matches(is_han).len())
+            }
+            None => before.split_at(
+                before
+                    .rfind(char::is_whitespace)
+                    .map_or(0, |ws| next_char(before, ws)),
+            ),
+        };
+        match reading {
+            Some(reading) if !base.is_empty() => {
+                plain.push_str(kept);
+                push_text(&mut plain, events);
+                events.push(Event::InlineHtml("<ruby>".into()));
+                events.push(Event::Text(CowStr::from(base.to_string())));
+                events.push(Event::InlineHtml("<rp>(</rp><rt>".into()));
+                events.push(Event::Text(CowStr::from(reading.to_string())));
+                events.push(Event::InlineHtml("</rt><rp>)</rp></ruby>".into()));
+                rest = &after[reading.len() + 1..];
+            }
+            _ => {
+                plain.push_str(&rest[..open + 1]);
+                rest = after;
+            }
+        }
+    }
+    plain.push_str(rest);
+    push_text(&mut plain, events);
+}
+
+/// Where the character after the one at `i` starts
+fn next_char(text: &str, i: usize) -> usize {
+    i + text[i..].chars().next().map_or(0, char::len_utf8)
+}
+
+fn push_text(plain: &mut String, events: &mut Vec<Event<'_>>) {
+    if !plain.is_empty() {
+        events.push(Event::Text(CowStr::from(std::mem::take(plain))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md(text: &str) -> String {
+        to_html(text, FORMAT_MARKDOWN)
+    }
+
+    #[test]
+    fn plain_text_is_escaped() {
+        assert_eq!(
+            to_html("<b>a</b> & *b*", FORMAT_PLAIN),
+            "&lt;b&gt;a&lt;/b&gt; &amp; *b*"
+        );
+    }
+
+    #[test]
+    fn the_subset_is_rendered() {
+        assert_eq!(
+            md("**bold** and *italics*"),
+            "<p><strong>bold</strong> and <em>italics</em></p>\n"
+        );
+        assert_eq!(md("one\ntwo"), "<p>one<br />\ntwo</p>\n");
+        assert_eq!(md("- a\n- b"), "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n");
+    }
+
+    #[test]
+    fn raw_html_is_escaped() {
+        assert_eq!(
+            md("a <img src=x onerror=alert(1)> b"),
+            "<p>a &lt;img src=x onerror=alert(1)&gt; b</p>\n"
+        );
+        let block = md("<script>alert(1)</script>\n\n<div onclick=\"x()\">hi</div>");
+        assert!(!block.contains("<script"), "{block}");
+        assert!(!block.contains("<div"), "{block}");
+        assert!(
+            block.contains("&lt;script&gt;alert(1)&lt;/script&gt;"),
+            "{block}"
+        );
+        assert!(
+            block.contains("&lt;div onclick=\"x()\"&gt;hi&lt;/div&gt;"),
+            "{block}"
+        );
+    }
+
+    #[test]
+    fn links_and_images_are_dropped() {
+        for text in [
+            "[click](javascript:alert(1))",
+            "<javascript:alert(1)>",
+            "[click][x]\n\n[x]: javascript:alert(1)",
+            "![click](javascript:alert(1))",
+        ] {
+            let html = md(text);
+            assert!(!html.contains("href"), "{text}: {html}");
+            assert!(!html.contains("src"), "{text}: {html}");
+            assert!(!html.contains("<a"), "{text}: {html}");
+        }
+        assert_eq!(md("[click](javascript:alert(1))"), "<p>click</p>\n");
+    }
+
+    #[test]
+    fn readings_become_ruby() {
+        assert_eq!(
+            md("漢字[かんじ]"),
+            "<p><ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby></p>\n"
+        );
+        // Only the kanji before the brackets are the base
+        assert_eq!(
+            md("日本語[にほんご]の漢字[かんじ]"),
+            "<p><ruby>日本語<rp>(</rp><rt>にほんご</rt><rp>)</rp></ruby>の\
+             <ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby></p>\n"
+        );
+        // Otherwise the word before them, or from a bar
+        assert_eq!(
+            md("a word[reading]"),
+            "<p>a <ruby>word<rp>(</rp><rt>reading</rt><rp>)</rp></ruby></p>\n"
+        );
+        assert_eq!(
+            md("お｜土産[みやげ]"),
+            "<p>お<ruby>土産<rp>(</rp><rt>みやげ</rt><rp>)</rp></ruby></p>\n"
+        );
+        // Readings are escaped too
+        assert_eq!(
+            md("字[<b>]"),
+            "<p><ruby>字<rp>(</rp><rt>&lt;b&gt;</rt><rp>)</rp></ruby></p>\n"
+        );
+    }
+
+    #[test]
+    fn brackets_without_a_reading_are_text() {
+        assert_eq!(md("[ ]"), "<p>[ ]</p>\n");
+        assert_eq!(md("[only]"), "<p>[only]</p>\n");
+        assert_eq!(md("漢字[かんじ"), "<p>漢字[かんじ</p>\n");
+    }
+
+    #[test]
+    fn escaped_brackets_are_not_ruby() {
+        assert_eq!(md(r"漢字\[かんじ]"), "<p>漢字[かんじ]</p>\n");
+        assert_eq!(md(r"the \[blank] here"), "<p>the [blank] here</p>\n");
+    }
+
+    #[test]
+    fn plain_text_reads_ruby_as_its_reading() {
+        assert_eq!(
+            to_plain("**日本[にほん]の**\n勉強[べんきょう]", FORMAT_MARKDOWN),
+            "にほんの\nべんきょう"
+        );
+        assert_eq!(to_plain(r"漢字\[かんじ]", FORMAT_MARKDOWN), "漢字[かんじ]");
+        assert_eq!(to_plain("**a**", FORMAT_PLAIN), "**a**");
+    }
+}
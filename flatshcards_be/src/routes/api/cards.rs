@@ -0,0 +1,201 @@
+use super::{ApiError, get_api_agent_and_did};
+use crate::{
+    db::{self, Repo},
+    lexicons::xyz::flatshcards::Card,
+    routes::{AtS, OAuthClientType},
+    services::{CardInput, CardService, ServiceError},
+    tts::SpeechCache,
+};
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, put, web};
+use atrium_api::types::{BlobRef, Collection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ApiCard {
+    uri: String,
+    front_lang: String,
+    front_text: String,
+    back_lang: String,
+    back_text: String,
+    kind: String,
+    format: String,
+    front_image: Option<BlobRef>,
+    back_image: Option<BlobRef>,
+    audio: Option<BlobRef>,
+    tags: Vec<String>,
+    position: Option<i32>,
+    stack_id: String,
+}
+
+impl ApiCard {
+    pub(super) fn from_display(card: db::DisplayCard, stack_id: String) -> Self {
+        Self {
+            uri: card.uri,
+            front_lang: card.front_lang,
+            front_text: card.front_text,
+            back_lang: card.back_lang,
+            back_text: card.back_text,
+            kind: card.kind,
+            format: card.format,
+            front_image: card.front_image,
+            back_image: card.back_image,
+            audio: card.audio,
+            tags: card.tags,
+            position: card.position,
+            stack_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CardKeyPath {
+    rkey: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StackCardsQuery {
+    stack: String,
+}
+
+/// Every card in anyone's stack, by the stack's uri, in the stack's order
+#[get("/api/cards")]
+pub(crate) async fn api_stack_cards(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<StackCardsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    repo.get_stack(&query.stack)
+        .await
+        .map_err(ServiceError::from)?
+        .ok_or(ApiError::NotFound)?;
+    let cards: Vec<ApiCard> = repo
+        .stack_cards(&query.stack)
+        .await
+        .map_err(ServiceError::from)?
+        .into_iter()
+        .map(|card| ApiCard::from_display(card, query.stack.clone()))
+        .collect();
+    Ok(HttpResponse::Ok().json(cards))
+}
+
+#[post("/api/cards")]
+pub(crate) async fn api_create_card(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    body: web::Json<CardInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let card = CardService::new(&agent, repo.get_ref(), &did)
+        .create(&body)
+        .await?;
+    let stack_id = card.stack_id.clone();
+    Ok(HttpResponse::Created().json(ApiCard::from_display(card.into(), stack_id)))
+}
+
+#[put("/api/cards/{rkey}")]
+pub(crate) async fn api_put_card(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CardKeyPath>,
+    body: web::Json<CardInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let card_uri = format!("at://{}/{}/{}", did.as_str(), Card::NSID, path.rkey);
+    let card = CardService::new(&agent, repo.get_ref(), &did)
+        .update(card_uri, &body)
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiCard::from_display(card, body.stack_id.clone())))
+}
+
+#[derive(Serialize)]
+struct UploadedBlob {
+    blob: BlobRef,
+}
+
+/// Uploads the request body as an image, for a card's `frontImage` or `backImage` or a
+/// stack's `cover`
+#[post("/api/images")]
+pub(crate) async fn api_upload_image(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let blob = CardService::new(&agent, repo.get_ref(), &did)
+        .upload_image(body.to_vec())
+        .await?;
+    Ok(HttpResponse::Created().json(UploadedBlob { blob }))
+}
+
+/// Uploads the request body as a recording, for a card's `audio`
+#[post("/api/audio")]
+pub(crate) async fn api_upload_audio(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let blob = CardService::new(&agent, repo.get_ref(), &did)
+        .upload_audio(body.to_vec())
+        .await?;
+    Ok(HttpResponse::Created().json(UploadedBlob { blob }))
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpeechBody {
+    text: String,
+    lang: String,
+}
+
+/// Generates `text` said in `lang` and uploads it, for a card's `audio`
+#[post("/api/speech")]
+pub(crate) async fn api_speak(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
+    body: web::Json<SpeechBody>,
+) -> Result<HttpResponse, ApiError> {
+    let speech = speech.ok_or(ApiError::NotFound)?;
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let blob = CardService::new(&agent, repo.get_ref(), &did)
+        .speak(&speech, &body.text, &body.lang)
+        .await?;
+    Ok(HttpResponse::Created().json(UploadedBlob { blob }))
+}
+
+#[delete("/api/cards/{rkey}")]
+pub(crate) async fn api_delete_card(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CardKeyPath>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let card_uri = format!("at://{}/{}/{}", did.as_str(), Card::NSID, path.rkey);
+    CardService::new(&agent, repo.get_ref(), &did)
+        .delete(&card_uri)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,153 @@
+use super::{ApiError, get_api_agent_and_did};
+use crate::{
+    blobs::BlobCache,
+    db::{self, Repo},
+    lexicons::xyz::flatshcards::Collection,
+    routes::{AtS, OAuthClientType},
+    services::{Cloned, CollectionInput, CollectionService, ServiceError},
+};
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, put, web};
+use atrium_api::types::Collection as _;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiCollection {
+    uri: String,
+    label: String,
+    description: Option<String>,
+    stacks: Vec<String>,
+}
+
+impl From<db::CollectionDetails> for ApiCollection {
+    fn from(collection: db::CollectionDetails) -> Self {
+        Self {
+            uri: collection.uri,
+            label: collection.label,
+            description: collection.description,
+            stacks: collection.stacks,
+        }
+    }
+}
+
+impl From<db::DbCollection> for ApiCollection {
+    fn from(collection: db::DbCollection) -> Self {
+        db::CollectionDetails::from(collection).into()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CollectionUriQuery {
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CollectionKeyPath {
+    rkey: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CloneCollectionBody {
+    src_uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClonedCollection {
+    uri: String,
+    /// The front text of each card the copy went without
+    failed_cards: Vec<String>,
+}
+
+/// Anyone's collection, by uri
+#[get("/api/collections")]
+pub(crate) async fn api_get_collection(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<CollectionUriQuery>,
+) -> Result<HttpResponse, ApiError> {
+    get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let collection = repo
+        .get_collection(&query.uri)
+        .await
+        .map_err(ServiceError::from)?
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(ApiCollection::from(collection)))
+}
+
+#[post("/api/collections")]
+pub(crate) async fn api_create_collection(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    body: web::Json<CollectionInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let collection = CollectionService::new(&agent, repo.get_ref(), &did)
+        .create(&body)
+        .await?;
+    Ok(HttpResponse::Created().json(ApiCollection::from(collection)))
+}
+
+#[put("/api/collections/{rkey}")]
+pub(crate) async fn api_put_collection(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionKeyPath>,
+    body: web::Json<CollectionInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let collection_uri = format!("at://{}/{}/{}", did.as_str(), Collection::NSID, path.rkey);
+    let collection = CollectionService::new(&agent, repo.get_ref(), &did)
+        .update(collection_uri, &body)
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiCollection::from(collection)))
+}
+
+/// Deletes the collection, but none of its stacks
+#[delete("/api/collections/{rkey}")]
+pub(crate) async fn api_delete_collection(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionKeyPath>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let collection_uri = format!("at://{}/{}/{}", did.as_str(), Collection::NSID, path.rkey);
+    CollectionService::new(&agent, repo.get_ref(), &did)
+        .delete(&collection_uri)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Clones any user's collection, by uri, and every stack in it into the caller's repo
+#[post("/api/collections/clone")]
+pub(crate) async fn api_clone_collection(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    blobs: web::Data<BlobCache>,
+    body: web::Json<CloneCollectionBody>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let Cloned { uri, failed } = CollectionService::new(&agent, repo.get_ref(), &did)
+        .clone_from(&body.src_uri, &blobs)
+        .await?;
+    Ok(HttpResponse::Created().json(ClonedCollection {
+        uri,
+        failed_cards: failed,
+    }))
+}
@@ -0,0 +1,140 @@
+//! JSON equivalents of the card, stack and collection form handlers, for clients other than the browser.
+//! Requests are authenticated with the OAuth session cookie or an `Authorization: Bearer`
+//! token minted at `POST /api/tokens`.
+pub(crate) mod cards;
+pub(crate) mod collections;
+pub(crate) mod stacks;
+pub(crate) mod tokens;
+
+use super::{
+    AtS, OAuthClientType,
+    atproto_agent::{get_session_agent_and_did, restore_agent},
+};
+use crate::{db::Repo, services::ServiceError};
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, ResponseError, http::StatusCode, http::header};
+use atrium_api::types::string::Did;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub(crate) enum ApiError {
+    #[error("Authentication required")]
+    Unauthorized,
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Not found")]
+    NotFound,
+    #[error("You do not have permission to perform this action")]
+    Forbidden,
+    #[error("Error writing to PDS")]
+    Upstream,
+    #[error("Internal server error")]
+    Internal,
+}
+
+/// The body of every error response, in the same shape as XRPC errors
+#[derive(Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized => "AuthRequired",
+            ApiError::Invalid(_) => "InvalidRequest",
+            ApiError::NotFound => "NotFound",
+            ApiError::Forbidden => "Forbidden",
+            ApiError::Upstream => "UpstreamFailure",
+            ApiError::Internal => "InternalServerError",
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Invalid(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::Upstream => StatusCode::BAD_GATEWAY,
+            ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            error: self.code(),
+            message: self.to_string(),
+        })
+    }
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(err: ServiceError) -> Self {
+        match err {
+            ServiceError::Invalid(msg) => ApiError::Invalid(msg.to_string()),
+            ServiceError::NotFound => ApiError::NotFound,
+            ServiceError::Forbidden => ApiError::Forbidden,
+            ServiceError::Pds(err) => {
+                log::error!("api error writing to PDS {err}");
+                ApiError::Upstream
+            }
+            ServiceError::Db(err) => {
+                log::error!("api database error {err}");
+                ApiError::Internal
+            }
+            ServiceError::Speech(err) => {
+                log::error!("api error generating speech {err}");
+                ApiError::Internal
+            }
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of a bearer token, which is what gets stored
+pub(super) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub(super) fn bearer_token(request: &HttpRequest) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Resolves the caller from a bearer token if one was sent, falling back to the session cookie
+pub(super) async fn get_api_agent_and_did(
+    request: &HttpRequest,
+    session: &Session,
+    oauth_client: &OAuthClientType,
+    repo: &dyn Repo,
+) -> Result<AtS, ApiError> {
+    let Some(token) = bearer_token(request) else {
+        return get_session_agent_and_did(oauth_client, session)
+            .await
+            .ok_or(ApiError::Unauthorized);
+    };
+    let did = repo
+        .api_token_did(&hash_token(token))
+        .await
+        .map_err(|err| {
+            log::error!("error looking up api token {err}");
+            ApiError::Internal
+        })?
+        .and_then(|did| Did::new(did).ok())
+        .ok_or(ApiError::Unauthorized)?;
+    match restore_agent(oauth_client, &did).await {
+        Ok(agent) => Ok(AtS { agent, did }),
+        Err(err) => {
+            log::error!("error restoring session for api token {err}");
+            Err(ApiError::Unauthorized)
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use super::{ApiError, cards::ApiCard, get_api_agent_and_did};
+use crate::{
+    blobs::BlobCache,
+    db::{self, Repo},
+    lexicons::xyz::flatshcards::Stack,
+    routes::{
+        AtS, OAuthClientType,
+        stacks::{SEARCH_LIMIT, SearchQuery},
+    },
+    services::{CardOrder, CardService, Cloned, ServiceError, Side, StackInput, StackService},
+    tts::SpeechCache,
+};
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, put, web};
+use atrium_api::types::{BlobRef, Collection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiStack {
+    uri: String,
+    label: String,
+    front_lang: Option<String>,
+    back_lang: Option<String>,
+    directions: String,
+    tags: Vec<String>,
+    description: Option<String>,
+    source: Option<String>,
+    license: Option<String>,
+    difficulty: Option<String>,
+    cover: Option<BlobRef>,
+}
+
+impl From<db::StackDetails> for ApiStack {
+    fn from(stack: db::StackDetails) -> Self {
+        Self {
+            uri: stack.uri,
+            label: stack.label,
+            front_lang: stack.front_lang,
+            back_lang: stack.back_lang,
+            directions: stack.directions,
+            tags: stack.tags,
+            description: stack.description,
+            source: stack.source,
+            license: stack.license,
+            difficulty: stack.difficulty,
+            cover: stack.cover,
+        }
+    }
+}
+
+impl From<db::DbStack> for ApiStack {
+    fn from(stack: db::DbStack) -> Self {
+        Self {
+            uri: stack.uri,
+            label: stack.label,
+            front_lang: stack.front_lang,
+            back_lang: stack.back_lang,
+            directions: stack.directions,
+            tags: stack.tags,
+            description: stack.description,
+            source: stack.source,
+            license: stack.license,
+            difficulty: stack.difficulty,
+            cover: stack.cover,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StackKeyPath {
+    rkey: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CloneStackBody {
+    src_uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClonedStack {
+    uri: String,
+    /// The front text of each card the copy went without
+    failed_cards: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpeakStackBody {
+    side: Side,
+}
+
+#[derive(Serialize)]
+struct SpokenCards {
+    spoken: usize,
+}
+
+/// Anyone's stacks, searched like the search page, by `q` in the label and `tag`
+#[get("/api/stacks")]
+pub(crate) async fn api_search_stacks(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stacks = repo
+        .search_stacks(&query.search(), SEARCH_LIMIT)
+        .await
+        .map_err(ServiceError::from)?;
+    let stacks: Vec<ApiStack> = stacks.into_iter().map(ApiStack::from).collect();
+    Ok(HttpResponse::Ok().json(stacks))
+}
+
+#[post("/api/stacks")]
+pub(crate) async fn api_create_stack(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    body: web::Json<StackInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stack = StackService::new(&agent, repo.get_ref(), &did)
+        .create(&body)
+        .await?;
+    Ok(HttpResponse::Created().json(ApiStack::from(stack)))
+}
+
+#[put("/api/stacks/{rkey}")]
+pub(crate) async fn api_put_stack(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<StackKeyPath>,
+    body: web::Json<StackInput>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stack_uri = format!("at://{}/{}/{}", did.as_str(), Stack::NSID, path.rkey);
+    let stack = StackService::new(&agent, repo.get_ref(), &did)
+        .update(stack_uri, &body)
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiStack::from(stack)))
+}
+
+#[delete("/api/stacks/{rkey}")]
+pub(crate) async fn api_delete_stack(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<StackKeyPath>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stack_uri = format!("at://{}/{}/{}", did.as_str(), Stack::NSID, path.rkey);
+    StackService::new(&agent, repo.get_ref(), &did)
+        .delete(&stack_uri)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Generates audio for each of the stack's cards that has none, from `side`
+#[post("/api/stacks/{rkey}/speech")]
+pub(crate) async fn api_speak_stack(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
+    path: web::Path<StackKeyPath>,
+    body: web::Json<SpeakStackBody>,
+) -> Result<HttpResponse, ApiError> {
+    let speech = speech.ok_or(ApiError::NotFound)?;
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stack_uri = format!("at://{}/{}/{}", did.as_str(), Stack::NSID, path.rkey);
+    let spoken = CardService::new(&agent, repo.get_ref(), &did)
+        .speak_stack(&speech, &stack_uri, body.side)
+        .await?;
+    Ok(HttpResponse::Ok().json(SpokenCards { spoken }))
+}
+
+/// Puts the stack's cards in the order given by their uris, and returns them in it
+#[put("/api/stacks/{rkey}/order")]
+pub(crate) async fn api_reorder_cards(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<StackKeyPath>,
+    body: web::Json<CardOrder>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let stack_uri = format!("at://{}/{}/{}", did.as_str(), Stack::NSID, path.rkey);
+    let cards: Vec<ApiCard> = CardService::new(&agent, repo.get_ref(), &did)
+        .reorder(&stack_uri, &body.cards)
+        .await?
+        .into_iter()
+        .map(|card| ApiCard::from_display(card, stack_uri.clone()))
+        .collect();
+    Ok(HttpResponse::Ok().json(cards))
+}
+
+/// Clones any user's stack, by uri, into the caller's repo
+#[post("/api/stacks/clone")]
+pub(crate) async fn api_clone_stack(
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    blobs: web::Data<BlobCache>,
+    body: web::Json<CloneStackBody>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { agent, did } =
+        get_api_agent_and_did(&request, &session, &oauth_client, repo.get_ref()).await?;
+    let Cloned { uri, failed } = StackService::new(&agent, repo.get_ref(), &did)
+        .clone_from(&body.src_uri, &blobs)
+        .await?;
+    Ok(HttpResponse::Created().json(ClonedStack {
+        uri,
+        failed_cards: failed,
+    }))
+}
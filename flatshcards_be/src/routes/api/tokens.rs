@@ -0,0 +1,54 @@
+use super::{ApiError, bearer_token, hash_token};
+use crate::{
+    db::{self, Repo},
+    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+};
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, delete, post, web};
+use rand::Rng;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct NewToken {
+    token: String,
+    did: String,
+}
+
+/// Mints a bearer token for the logged-in user. The token is only ever returned here.
+#[post("/api/tokens")]
+pub(crate) async fn create_api_token(
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+) -> Result<HttpResponse, ApiError> {
+    let AtS { did, .. } = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let token = hex::encode(rand::rng().random::<[u8; 32]>());
+    repo.save_api_token(&db::ApiToken::new(hash_token(&token), did.to_string()))
+        .await
+        .map_err(|err| {
+            log::error!("error saving api token {err}");
+            ApiError::Internal
+        })?;
+    Ok(HttpResponse::Created().json(NewToken {
+        token,
+        did: did.to_string(),
+    }))
+}
+
+/// Revokes the bearer token the request was made with
+#[delete("/api/tokens")]
+pub(crate) async fn revoke_api_token(
+    request: HttpRequest,
+    repo: web::Data<dyn Repo>,
+) -> Result<HttpResponse, ApiError> {
+    let token = bearer_token(&request).ok_or(ApiError::Unauthorized)?;
+    repo.delete_api_token(&hash_token(token))
+        .await
+        .map_err(|err| {
+            log::error!("error revoking api token {err}");
+            ApiError::Internal
+        })?;
+    Ok(HttpResponse::NoContent().finish())
+}
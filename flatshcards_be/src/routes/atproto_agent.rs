@@ -1,36 +1,117 @@
 use super::OAuthClientType;
+#[cfg(any(test, feature = "mock-pds"))]
+use crate::mock_pds::MockSession;
 use crate::{resolver::HickoryDnsTxtResolver, storage::DbSessionStore};
 use actix_session::Session;
-use atrium_api::{agent, types::string::Did};
+use atrium_api::{
+    agent::{self, SessionManager},
+    types::string::Did,
+    xrpc::{
+        self, HttpClient, OutputDataOrBytes, XrpcClient, XrpcRequest,
+        http::{Request, Response},
+    },
+};
 use atrium_identity::{did::CommonDidResolver, handle::AtprotoHandleResolver};
 use atrium_oauth::DefaultHttpClient;
 use atrium_oauth::OAuthSession;
+use serde::{Serialize, de::DeserializeOwned};
+use std::fmt::Debug;
 
-pub(super) type Agent = agent::Agent<
-    OAuthSession<
-        DefaultHttpClient,
-        CommonDidResolver<DefaultHttpClient>,
-        AtprotoHandleResolver<HickoryDnsTxtResolver, DefaultHttpClient>,
-        DbSessionStore,
-    >,
+pub(super) type OAuthSessionType = OAuthSession<
+    DefaultHttpClient,
+    CommonDidResolver<DefaultHttpClient>,
+    AtprotoHandleResolver<HickoryDnsTxtResolver, DefaultHttpClient>,
+    DbSessionStore,
 >;
 
+/// Either a real OAuth session, or the fixed-DID session of the mock PDS
+pub(super) enum AppSession {
+    OAuth(OAuthSessionType),
+    #[cfg(any(test, feature = "mock-pds"))]
+    Mock(MockSession),
+}
+
+impl HttpClient for AppSession {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        match self {
+            AppSession::OAuth(session) => session.send_http(request).await,
+            #[cfg(any(test, feature = "mock-pds"))]
+            AppSession::Mock(session) => session.send_http(request).await,
+        }
+    }
+}
+
+impl XrpcClient for AppSession {
+    fn base_uri(&self) -> String {
+        match self {
+            AppSession::OAuth(session) => session.base_uri(),
+            #[cfg(any(test, feature = "mock-pds"))]
+            AppSession::Mock(session) => session.base_uri(),
+        }
+    }
+    async fn send_xrpc<P, I, O, E>(
+        &self,
+        request: &XrpcRequest<P, I>,
+    ) -> xrpc::Result<OutputDataOrBytes<O>, E>
+    where
+        P: Serialize + Send + Sync,
+        I: Serialize + Send + Sync,
+        O: DeserializeOwned + Send + Sync,
+        E: DeserializeOwned + Send + Sync + Debug,
+    {
+        match self {
+            AppSession::OAuth(session) => session.send_xrpc(request).await,
+            #[cfg(any(test, feature = "mock-pds"))]
+            AppSession::Mock(session) => session.send_xrpc(request).await,
+        }
+    }
+}
+
+impl SessionManager for AppSession {
+    async fn did(&self) -> Option<Did> {
+        match self {
+            AppSession::OAuth(session) => session.did().await,
+            #[cfg(any(test, feature = "mock-pds"))]
+            AppSession::Mock(session) => session.did().await,
+        }
+    }
+}
+
+pub(super) type Agent = agent::Agent<AppSession>;
+
 pub(super) struct AtS {
     pub(super) agent: Agent,
     pub(super) did: Did,
 }
 
+/// Restores the stored OAuth session for `did` and wraps it in an [`Agent`].
+/// The mock login's DID gets a session on the mock PDS instead.
+pub(super) async fn restore_agent(
+    oauth_client: &OAuthClientType,
+    did: &Did,
+) -> atrium_oauth::Result<Agent> {
+    #[cfg(any(test, feature = "mock-pds"))]
+    if let Some(mock) = oauth_client.mock.as_ref().filter(|mock| &mock.did == did) {
+        return Ok(Agent::new(AppSession::Mock(mock.session())));
+    }
+    oauth_client
+        .oauth
+        .restore(did)
+        .await
+        .map(|session| Agent::new(AppSession::OAuth(session)))
+}
+
 pub(super) async fn get_session_agent_and_did(
     oauth_client: &OAuthClientType,
     session: &Session,
 ) -> Option<AtS> {
     if let Some(did_string) = session.get::<String>("did").unwrap_or(None) {
         let did = Did::new(did_string).expect("invalid did");
-        match oauth_client.restore(&did).await {
-            Ok(s) => {
-                let agent = Agent::new(s);
-                Some(AtS { agent, did })
-            }
+        match restore_agent(oauth_client, &did).await {
+            Ok(agent) => Some(AtS { agent, did }),
             Err(err) => {
                 log::error!(
                     "Error restoring session, we are removing the session from the cookie: {err}"
@@ -0,0 +1,52 @@
+use crate::{
+    blobs::{BlobCache, media_type},
+    db::Repo,
+};
+use actix_web::{HttpResponse, get, http::header, web};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BlobPath {
+    did: String,
+    cid: String,
+}
+
+/// Serves a blob that one of `did`'s cards or stack covers uses, from their PDS by way of
+/// the cache. Only indexed blobs are served, so this can't be used to proxy anything else.
+#[get("/blobs/{did}/{cid}")]
+pub(crate) async fn get_blob(
+    repo: web::Data<dyn Repo>,
+    blobs: web::Data<BlobCache>,
+    path: web::Path<BlobPath>,
+) -> HttpResponse {
+    let BlobPath { did, cid } = path.into_inner();
+    let used = match repo.card_uses_blob(&did, &cid).await {
+        Ok(true) => Ok(true),
+        Ok(false) => repo.stack_uses_blob(&did, &cid).await,
+        Err(err) => Err(err),
+    };
+    match used {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::NotFound().finish(),
+        Err(err) => {
+            log::error!("error looking up blob {did}/{cid} {err}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+    match blobs.get(&did, &cid).await {
+        // Served as what the bytes are, whatever the record said they'd be
+        Ok(data) => match media_type(&data) {
+            Some(mime_type) => HttpResponse::Ok()
+                .content_type(mime_type)
+                // A cid always names the same bytes
+                .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+                .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+                .body(data),
+            None => HttpResponse::NotFound().finish(),
+        },
+        Err(err) => {
+            log::error!("error fetching blob {did}/{cid} {err}");
+            HttpResponse::BadGateway().finish()
+        }
+    }
+}
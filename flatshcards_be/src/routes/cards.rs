@@ -1,79 +1,205 @@
 use crate::{
-    db,
-    lang::{is_lang, lang_choices},
-    lexicons::{
-        record::KnownRecord,
-        xyz::flatshcards::{Card, card},
-    },
-    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+    db::Repo,
+    i18n::{Locale, Message},
+    routes::{AtS, OAuthClientType, get_session_agent_and_did, langs::user_lang_picker},
+    services::{CardInput, CardService, ServiceError, Side},
+    tags::split_tags,
     templates,
+    tts::SpeechCache,
 };
+use actix_multipart::form::{MultipartForm, bytes::Bytes, text::Text};
 use actix_session::Session;
 use actix_web::{
     HttpRequest, HttpResponse, Responder, delete, post, put,
     web::{self, Redirect},
 };
 use askama::Template;
-use atrium_api::com::atproto::repo::{create_record, delete_record, put_record};
-use atrium_api::types::{
-    Collection,
-    string::{Datetime, RecordKey},
-};
+use atrium_api::types::BlobRef;
 use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgPool;
+
+/// The card editor's form. It's multipart so it can carry images and audio; a blob the
+/// card already has comes back as its ref, in `frontImage`, `backImage` or `audio`.
+#[derive(MultipartForm)]
+pub(crate) struct CardForm {
+    #[multipart(rename = "frontLang")]
+    front_lang: Text<String>,
+    #[multipart(rename = "frontText")]
+    front_text: Text<String>,
+    #[multipart(rename = "backLang")]
+    back_lang: Text<String>,
+    #[multipart(rename = "backText")]
+    back_text: Text<String>,
+    kind: Text<String>,
+    format: Text<String>,
+    #[multipart(rename = "stackId")]
+    stack_id: Text<String>,
+    #[multipart(rename = "frontImage")]
+    front_image: Option<Text<String>>,
+    #[multipart(rename = "backImage")]
+    back_image: Option<Text<String>>,
+    /// Too big is reported by [`CardService::upload_image`], this is just a backstop
+    #[multipart(rename = "frontImageFile", limit = "2MiB")]
+    front_image_file: Option<Bytes>,
+    #[multipart(rename = "backImageFile", limit = "2MiB")]
+    back_image_file: Option<Bytes>,
+    #[multipart(rename = "frontImageRemove")]
+    remove_front_image: Option<Text<String>>,
+    #[multipart(rename = "backImageRemove")]
+    remove_back_image: Option<Text<String>>,
+    audio: Option<Text<String>>,
+    /// Either a chosen file or one recorded in the browser
+    #[multipart(rename = "audioFile", limit = "6MiB")]
+    audio_file: Option<Bytes>,
+    #[multipart(rename = "audioRemove")]
+    remove_audio: Option<Text<String>>,
+    /// `front` or `back`, to generate the audio from that side's text
+    #[multipart(rename = "audioSpeak")]
+    speak_audio: Option<Text<String>>,
+    /// Separated by commas
+    tags: Option<Text<String>>,
+}
+
+impl CardForm {
+    /// Uploads any new images and audio, so the input can refer to them. Audio is a
+    /// new file, or else generated with `speech` if that was asked for. Nothing is
+    /// uploaded or generated until the card, as a new one or over `card_uri`, is known to
+    /// be one that can be written.
+    async fn into_input(
+        self,
+        service: &CardService<'_>,
+        card_uri: Option<&str>,
+        speech: Option<&SpeechCache>,
+    ) -> Result<CardInput, ServiceError> {
+        let front_file = CardForm::file(self.front_image_file);
+        let back_file = CardForm::file(self.back_image_file);
+        let audio_file = CardForm::file(self.audio_file);
+        let speak = match self.speak_audio.as_deref().map(String::as_str) {
+            Some("front") => Some(Side::Front),
+            Some("back") => Some(Side::Back),
+            _ => None,
+        };
+        // A new file wins, then removing, then the blob the card had
+        let front_image = match front_file {
+            Some(_) => None,
+            None => CardForm::kept(self.front_image, self.remove_front_image.is_some())?,
+        };
+        let back_image = match back_file {
+            Some(_) => None,
+            None => CardForm::kept(self.back_image, self.remove_back_image.is_some())?,
+        };
+        let audio = match (&audio_file, speak, speech) {
+            (None, Some(_), None) => {
+                return Err(ServiceError::Invalid(Message::new(
+                    "error-speech-unavailable",
+                )));
+            }
+            (None, None, _) => CardForm::kept(self.audio, self.remove_audio.is_some())?,
+            _ => None,
+        };
+        let mut input = CardInput {
+            front_lang: self.front_lang.into_inner(),
+            front_text: self.front_text.into_inner(),
+            back_lang: self.back_lang.into_inner(),
+            back_text: self.back_text.into_inner(),
+            kind: self.kind.into_inner(),
+            format: self.format.into_inner(),
+            front_image,
+            back_image,
+            audio,
+            tags: self.tags.map(|t| split_tags(&t)).unwrap_or_default(),
+            position: None,
+            stack_id: self.stack_id.into_inner(),
+        };
+        service.check(card_uri, &input).await?;
+        for data in [&front_file, &back_file].into_iter().flatten() {
+            CardService::check_image(data)?;
+        }
+        if let Some(data) = &audio_file {
+            CardService::check_audio(data)?;
+        }
+        if let Some(data) = front_file {
+            input.front_image = Some(service.upload_image(data).await?);
+        }
+        if let Some(data) = back_file {
+            input.back_image = Some(service.upload_image(data).await?);
+        }
+        match (audio_file, speak, speech) {
+            (Some(data), _, _) => input.audio = Some(service.upload_audio(data).await?),
+            (None, Some(side), Some(speech)) => {
+                let (text, lang) = input.speech(side);
+                input.audio = Some(service.speak(speech, &text, &lang).await?);
+            }
+            _ => {}
+        }
+        Ok(input)
+    }
+
+    /// An empty file input still sends a part, with nothing in it
+    pub(super) fn file(file: Option<Bytes>) -> Option<Vec<u8>> {
+        file.filter(|f| !f.data.is_empty()).map(|f| f.data.to_vec())
+    }
+
+    /// The blob the card had, unless it's being removed. Stack covers come back the same way.
+    pub(super) fn kept(
+        current: Option<Text<String>>,
+        remove: bool,
+    ) -> Result<Option<BlobRef>, ServiceError> {
+        if remove {
+            return Ok(None);
+        }
+        current
+            .map(Text::into_inner)
+            .filter(|json| !json.is_empty())
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|_| ServiceError::Invalid(Message::new("invalid-blob")))
+    }
+}
 
 #[post("/cards/create")]
 pub(crate) async fn create_card(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
-    form: web::Form<CardForm>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
+    MultipartForm(form): MultipartForm<CardForm>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let form = form.clone();
-        if let Some(ref error) = form.validate() {
-            let error_html = templates::FormError { error }.render().unwrap();
-            return HttpResponse::BadRequest().body(error_html);
+        let service = CardService::new(&agent, repo.get_ref(), &did);
+        let created = match form
+            .into_input(&service, None, speech.as_ref().map(|s| s.get_ref()))
+            .await
+        {
+            Ok(form) => service.create(&form).await.map(|card| (card, form)),
+            Err(err) => Err(err),
         };
-        let card = form.as_record();
-        let db_did = did.clone().to_string();
-        let create_result = agent
-            .api
-            .com
-            .atproto
-            .repo
-            .create_record(
-                create_record::InputData {
-                    collection: Card::NSID.parse().unwrap(),
-                    repo: did.into(),
-                    rkey: None,
-                    record: card.into(),
-                    swap_commit: None,
-                    validate: None,
-                }
-                .into(),
-            )
-            .await;
-        match create_result {
-            Ok(record) => {
-                let stack_id = form.stack_id.clone();
-                let args = form.as_args(record.uri.clone(), db_did);
-                let card = db::DbCard::new(args);
-                let _ = card.save(&db_pool).await;
+        match created {
+            Ok((card, form)) => {
                 let html = templates::EditSingleCardTemplate {
-                    lang_choices: lang_choices(),
+                    locale,
+                    langs: user_lang_picker(repo.get_ref(), &did).await,
                     card: card.into(),
-                    stack_id,
+                    stack_id: form.stack_id,
+                    speech: speech.is_some(),
                 }
                 .render()
                 .unwrap();
                 HttpResponse::Created().body(html)
             }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = templates::FormError {
+                    error: &error.text(locale),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
             Err(err) => {
                 log::error!("error creating card in atmosphere {err}");
                 let error_html = templates::FormError {
-                    error: "An unknown error has occurred.",
+                    error: &locale.t("error-unknown"),
                 }
                 .render()
                 .unwrap();
@@ -88,62 +214,6 @@ pub(crate) async fn create_card(
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub(crate) struct CardForm {
-    front_lang: String,
-    front_text: String,
-    back_lang: String,
-    back_text: String,
-    stack_id: String,
-}
-
-impl CardForm {
-    fn validate(&self) -> Option<String> {
-        if !is_lang(&self.front_lang) {
-            let l = &self.front_lang;
-            Some(format!("Invalid front language {l}"))
-        } else if !is_lang(&self.back_lang) {
-            let l = &self.back_lang;
-            Some(format!("Invalid back langauge {l}"))
-        } else {
-            None
-        }
-    }
-    fn as_record(&self) -> KnownRecord {
-        card::Card {
-            back_lang: self.back_lang.clone(),
-            back_text: self.back_text.clone(),
-            front_lang: self.front_lang.clone(),
-            front_text: self.front_text.clone(),
-            stack_id: RecordKey::new(self.stack_id.clone()).unwrap(),
-            created_at: Datetime::now(),
-        }
-        .into()
-    }
-    fn as_args(&self, uri: String, author_did: String) -> db::CardArgs {
-        db::CardArgs {
-            uri,
-            author_did,
-            back_lang: self.back_lang.clone(),
-            back_text: self.back_text.clone(),
-            front_lang: self.front_lang.clone(),
-            front_text: self.front_text.clone(),
-            indexed_at: None,
-            stack_id: self.stack_id.clone(),
-        }
-    }
-    fn as_display(&self, uri: String) -> db::DisplayCard {
-        db::DisplayCard {
-            uri,
-            back_lang: self.back_lang.clone(),
-            back_text: self.back_text.clone(),
-            front_lang: self.front_lang.clone(),
-            front_text: self.front_text.clone(),
-        }
-    }
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct CardUriPath {
     card_uri: String,
@@ -151,58 +221,32 @@ pub(crate) struct CardUriPath {
 
 #[delete("/cards/edit/{card_uri}")]
 pub(crate) async fn delete_card(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
     card_uri: web::Path<CardUriPath>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
         let CardUriPath { card_uri } = card_uri.into_inner();
-        match db::DbCard::is_owned_by(&did, &card_uri, &db_pool).await {
-            Err(err) => {
-                log::error!("error checking card ownership {err}");
+        match CardService::new(&agent, repo.get_ref(), &did)
+            .delete(&card_uri)
+            .await
+        {
+            // Not 204, which htmx wouldn't swap, so the card's editor can be removed
+            Ok(()) => HttpResponse::Ok().finish(),
+            Err(ServiceError::Forbidden) => {
                 Redirect::to("/").respond_to(&request).map_into_boxed_body()
             }
-            Ok(false) => Redirect::to("/").respond_to(&request).map_into_boxed_body(),
-            Ok(true) => {
-                let db_uri = card_uri.clone();
-                let rkey = RecordKey::new(card_uri).unwrap();
-                let at_result = agent
-                    .api
-                    .com
-                    .atproto
-                    .repo
-                    .delete_record(
-                        delete_record::InputData {
-                            collection: Card::NSID.parse().unwrap(),
-                            repo: did.into(),
-                            rkey,
-                            swap_commit: None,
-                            swap_record: None,
-                        }
-                        .into(),
-                    )
-                    .await;
-                if let Err(err) = at_result {
-                    log::error!("error deleting card in atmosphere {err}");
-                    let error_html = templates::FormError {
-                        error: "Error deleting card.",
-                    }
-                    .render()
-                    .unwrap();
-                    return HttpResponse::InternalServerError().body(error_html);
-                };
-                if let Err(err) = db::DbCard::delete_by_uri(&db_uri, &db_pool).await {
-                    log::error!("error deleting card from db {err}");
-                    let error_html = templates::FormError {
-                        error: "Error deleting card.",
-                    }
-                    .render()
-                    .unwrap();
-                    return HttpResponse::InternalServerError().body(error_html);
-                };
-                HttpResponse::NoContent().into()
+            Err(err) => {
+                log::error!("error deleting card {err}");
+                let error_html = templates::FormError {
+                    error: &locale.t("error-delete-card"),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
@@ -211,69 +255,176 @@ pub(crate) async fn delete_card(
 }
 
 #[put("/cards/edit/{card_uri}")]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn put_card(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
     card_uri: web::Path<CardUriPath>,
-    form: web::Form<CardForm>,
+    speech: Option<web::Data<SpeechCache>>,
+    MultipartForm(form): MultipartForm<CardForm>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
         let CardUriPath { card_uri } = card_uri.into_inner();
-        let form = form.clone();
-        match db::DbCard::is_owned_by(&did, &card_uri, &db_pool).await {
-            Err(err) => {
-                log::error!("error checking card ownership {err}");
+        let service = CardService::new(&agent, repo.get_ref(), &did);
+        let updated = match form
+            .into_input(
+                &service,
+                Some(&card_uri),
+                speech.as_ref().map(|s| s.get_ref()),
+            )
+            .await
+        {
+            Ok(form) => service
+                .update(card_uri, &form)
+                .await
+                .map(|card| (card, form)),
+            Err(err) => Err(err),
+        };
+        match updated {
+            Ok((card, form)) => {
+                let html = templates::EditSingleCardTemplate {
+                    locale,
+                    langs: user_lang_picker(repo.get_ref(), &did).await,
+                    card,
+                    stack_id: form.stack_id,
+                    speech: speech.is_some(),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::Ok().body(html)
+            }
+            Err(ServiceError::Forbidden) => {
                 Redirect::to("/").respond_to(&request).map_into_boxed_body()
             }
-            Ok(false) => Redirect::to("/").respond_to(&request).map_into_boxed_body(),
-            Ok(true) => {
-                let db_uri = card_uri.clone();
-                let rkey = RecordKey::new(card_uri).unwrap();
-                let at_result = agent
-                    .api
-                    .com
-                    .atproto
-                    .repo
-                    .put_record(
-                        put_record::InputData {
-                            collection: Card::NSID.parse().unwrap(),
-                            record: form.as_record().into(),
-                            repo: did.into(),
-                            rkey,
-                            swap_commit: None,
-                            swap_record: None,
-                            validate: None,
-                        }
-                        .into(),
-                    )
-                    .await;
-                match at_result {
-                    Err(err) => {
-                        log::error!("error editing card in atmosphere {err}");
-                        let error_html = templates::FormError {
-                            error: "Error editing card",
-                        }
-                        .render()
-                        .unwrap();
-                        HttpResponse::InternalServerError().body(error_html)
-                    }
-                    Ok(_record) => {
-                        let card = form.as_display(db_uri);
-                        let html = templates::EditSingleCardTemplate {
-                            lang_choices: lang_choices(),
-                            card,
-                            stack_id: form.stack_id.clone(),
-                        }
-                        .render()
-                        .unwrap();
-                        HttpResponse::Ok().body(html)
-                    }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = templates::FormError {
+                    error: &error.text(locale),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error editing card in atmosphere {err}");
+                let error_html = templates::FormError {
+                    error: &locale.t("error-edit-card"),
                 }
+                .render()
+                .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
         Redirect::to("/").respond_to(&request).map_into_boxed_body()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryRepo;
+    use crate::lexicons::record::KnownRecord;
+    use crate::services::{Pds, PdsError};
+    use async_trait::async_trait;
+    use atrium_api::types::string::{Did, RecordKey};
+
+    /// A PDS that fails the test if anything is written to it
+    struct NoWrites;
+
+    #[async_trait]
+    impl Pds for NoWrites {
+        async fn create_record(
+            &self,
+            _: &Did,
+            _: &str,
+            _: KnownRecord,
+        ) -> Result<String, PdsError> {
+            panic!("created a record")
+        }
+        async fn put_record(
+            &self,
+            _: &Did,
+            _: &str,
+            _: RecordKey,
+            _: KnownRecord,
+        ) -> Result<(), PdsError> {
+            panic!("put a record")
+        }
+        async fn delete_record(&self, _: &Did, _: &str, _: RecordKey) -> Result<(), PdsError> {
+            panic!("deleted a record")
+        }
+        async fn create_records(
+            &self,
+            _: &Did,
+            _: &str,
+            _: Vec<KnownRecord>,
+        ) -> Result<Vec<String>, PdsError> {
+            panic!("created records")
+        }
+        async fn put_records(
+            &self,
+            _: &Did,
+            _: &str,
+            _: Vec<(RecordKey, KnownRecord)>,
+        ) -> Result<(), PdsError> {
+            panic!("put records")
+        }
+        async fn upload_blob(&self, _: Vec<u8>) -> Result<BlobRef, PdsError> {
+            panic!("uploaded a blob")
+        }
+    }
+
+    fn form(front_lang: &str) -> CardForm {
+        let text = |text: &str| Text(text.to_string());
+        let file = |data: &'static [u8]| {
+            Some(Bytes {
+                data: data.into(),
+                content_type: None,
+                file_name: None,
+            })
+        };
+        CardForm {
+            front_lang: text(front_lang),
+            front_text: text("猫"),
+            back_lang: text("en"),
+            back_text: text("cat"),
+            kind: text("basic"),
+            format: text("plain"),
+            stack_id: text("at://did:plc:bob/xyz.flatshcards.stack/3lbfjti"),
+            front_image: None,
+            back_image: None,
+            front_image_file: file(b"\x89PNG\r\n\x1a\nnot much of a picture"),
+            back_image_file: None,
+            remove_front_image: None,
+            remove_back_image: None,
+            audio: None,
+            audio_file: file(b"OggS not much of a recording"),
+            remove_audio: None,
+            speak_audio: None,
+            tags: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_is_uploaded_for_a_card_that_would_be_turned_away() {
+        let repo = MemoryRepo::default();
+        let alice = Did::new("did:plc:alice".to_string()).unwrap();
+        let service = CardService::new(&NoWrites, &repo, &alice);
+        assert!(matches!(
+            form("xx").into_input(&service, None, None).await,
+            Err(ServiceError::Invalid(_))
+        ));
+        assert!(matches!(
+            form("ja").into_input(&service, None, None).await,
+            Err(ServiceError::Forbidden)
+        ));
+        let card_uri = "at://did:plc:bob/xyz.flatshcards.card/3lbfjtj";
+        assert!(matches!(
+            form("ja").into_input(&service, Some(card_uri), None).await,
+            Err(ServiceError::Forbidden)
+        ));
+    }
+}
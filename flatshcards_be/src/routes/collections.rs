@@ -0,0 +1,342 @@
+use crate::{
+    blobs::BlobCache,
+    db::{self, Repo},
+    i18n::Locale,
+    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+    services::{CollectionInput, CollectionService, ServiceError, collection_stacks},
+    templates::{self, ErrorTemplate},
+};
+use actix_session::Session;
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, delete, get, post, put,
+    web::{self, Redirect},
+};
+use askama::Template;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CollectionUriPath {
+    collection_uri: String,
+}
+
+/// Where the page of the collection at `uri` is. The uri has slashes in it, so it's
+/// encoded to stay in one path segment.
+fn collection_page_path(uri: &str) -> String {
+    let uri = askama::filters::urlencode_strict(uri).map(|uri| uri.to_string());
+    format!("/collections/view/{}", uri.unwrap_or_default())
+}
+
+/// Renders the editor for `collection`, or for a new one if there's none
+async fn edit_collection_html(
+    locale: Locale,
+    collection: Option<db::CollectionDetails>,
+    repo: &dyn Repo,
+    did: &str,
+) -> HttpResponse {
+    let title = if collection.is_some() {
+        "title-edit-collection"
+    } else {
+        "title-create-collection"
+    };
+    let stacks = match &collection {
+        Some(collection) => collection_stacks(repo, collection).await,
+        None => Ok(Vec::new()),
+    };
+    let user_stacks = repo.user_stacks(did).await;
+    let (stacks, user_stacks, error) = match (stacks, user_stacks) {
+        (Ok(stacks), Ok(user_stacks)) => (stacks, user_stacks, None),
+        (Err(err), _) | (_, Err(err)) => {
+            log::error!("error retrieving stacks for collection editor {err}");
+            (Vec::new(), Vec::new(), Some("error-retrieve-stack"))
+        }
+    };
+    let html = templates::EditCollectionTemplate {
+        locale,
+        title,
+        collection,
+        stacks,
+        user_stacks,
+        error,
+    }
+    .render()
+    .unwrap();
+    if error.is_some() {
+        HttpResponse::InternalServerError().body(html)
+    } else {
+        HttpResponse::Ok().body(html)
+    }
+}
+
+#[get("/collections/create")]
+pub(crate) async fn create_collection_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+) -> HttpResponse {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
+        edit_collection_html(locale, None, repo.get_ref(), &did).await
+    } else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-edit-collections")
+            .render()
+            .unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+/// Creates a new collection, and goes to its page
+#[post("/collections/create")]
+pub(crate) async fn create_collection(
+    locale: Locale,
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    form: web::Form<CollectionInput>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        match CollectionService::new(&agent, repo.get_ref(), &did)
+            .create(&form)
+            .await
+        {
+            Ok(collection) => Redirect::to(collection_page_path(&collection.uri))
+                .see_other()
+                .respond_to(&request)
+                .map_into_boxed_body(),
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = ErrorTemplate::invalid(locale, error).render().unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
+            Err(err) => {
+                log::error!("Error creating collection: {err}");
+                let error_html = ErrorTemplate::new(locale, "error-create-collection")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-edit-collections")
+            .render()
+            .unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+/// Anyone's collection, with its stacks in order
+#[get("/collections/view/{collection_uri}")]
+pub(crate) async fn collection_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionUriPath>,
+) -> HttpResponse {
+    let did = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .map(|AtS { did, .. }| did);
+    let collection = match repo.get_collection(&path.collection_uri).await {
+        Ok(Some(collection)) => collection,
+        Ok(None) => {
+            let error_html = ErrorTemplate::new(locale, "error-collection-not-found")
+                .render()
+                .unwrap();
+            return HttpResponse::NotFound().body(error_html);
+        }
+        Err(err) => {
+            log::error!("error retrieving collection {err}");
+            let error_html = ErrorTemplate::new(locale, "error-retrieve-collection")
+                .render()
+                .unwrap();
+            return HttpResponse::InternalServerError().body(error_html);
+        }
+    };
+    match collection_stacks(repo.get_ref(), &collection).await {
+        Ok(stacks) => {
+            let html = templates::CollectionTemplate {
+                locale,
+                title: "title-collection",
+                logged_in: did.is_some(),
+                owned: did.is_some_and(|did| did.as_str() == collection.author_did),
+                collection,
+                stacks,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(err) => {
+            log::error!("error retrieving collection's stacks {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
+
+#[get("/collections/edit/{collection_uri}")]
+pub(crate) async fn edit_collection_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionUriPath>,
+) -> HttpResponse {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
+        match repo.get_collection(&path.collection_uri).await {
+            Ok(Some(collection)) if collection.author_did == did.as_str() => {
+                edit_collection_html(locale, Some(collection), repo.get_ref(), &did).await
+            }
+            Ok(_) => {
+                let error_html = ErrorTemplate::new(locale, "error-collection-not-found")
+                    .render()
+                    .unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error retrieving collection {err}");
+                let error_html = ErrorTemplate::new(locale, "error-retrieve-collection")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-edit-collections")
+            .render()
+            .unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+#[put("/collections/edit/{collection_uri}")]
+pub(crate) async fn put_collection(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionUriPath>,
+    form: web::Form<CollectionInput>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let CollectionUriPath { collection_uri } = path.into_inner();
+        match CollectionService::new(&agent, repo.get_ref(), &did)
+            .update(collection_uri, &form)
+            .await
+        {
+            Ok(updated) => edit_collection_html(locale, Some(updated), repo.get_ref(), &did).await,
+            Err(ServiceError::Forbidden) => {
+                let error_html = ErrorTemplate::forbidden(locale).render().unwrap();
+                HttpResponse::Forbidden().body(error_html)
+            }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = ErrorTemplate::invalid(locale, error).render().unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error updating collection {err}");
+                let error_html = ErrorTemplate::new(locale, "error-update-collection")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+/// Deletes the collection, leaving its stacks be
+#[delete("/collections/edit/{collection_uri}")]
+pub(crate) async fn delete_collection(
+    locale: Locale,
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<CollectionUriPath>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        match CollectionService::new(&agent, repo.get_ref(), &did)
+            .delete(&path.collection_uri)
+            .await
+        {
+            Ok(()) => Redirect::to("/")
+                .see_other()
+                .respond_to(&request)
+                .map_into_boxed_body(),
+            Err(ServiceError::Forbidden) => {
+                let error_html = ErrorTemplate::forbidden(locale).render().unwrap();
+                HttpResponse::Forbidden().body(error_html)
+            }
+            Err(err) => {
+                log::error!("Error deleting collection: {err}");
+                let error_html = ErrorTemplate::new(locale, "error-delete-collection")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CloneCollectionPath {
+    src_uri: String,
+}
+
+/// Copies anyone's collection, and every stack in it, into the caller's repo
+#[post("/collections/clone/{src_uri}")]
+pub(crate) async fn clone_collection(
+    locale: Locale,
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    blobs: web::Data<BlobCache>,
+    path: web::Path<CloneCollectionPath>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        match CollectionService::new(&agent, repo.get_ref(), &did)
+            .clone_from(&path.src_uri, &blobs)
+            .await
+        {
+            Ok(cloned) if cloned.failed.is_empty() => {
+                Redirect::to(collection_page_path(&cloned.uri))
+                    .see_other()
+                    .respond_to(&request)
+                    .map_into_boxed_body()
+            }
+            Ok(cloned) => {
+                let error_html = ErrorTemplate::clone_incomplete(locale, &cloned.failed)
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+            Err(ServiceError::NotFound) => {
+                let error_html = ErrorTemplate::new(locale, "error-collection-not-found")
+                    .render()
+                    .unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(ServiceError::Db(err)) => {
+                log::error!("error getting collection clone data {err}");
+                let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error cloning collection in atmosphere {err}");
+                let error_html = ErrorTemplate::new(locale, "error-unknown")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
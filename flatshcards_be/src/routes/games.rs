@@ -0,0 +1,175 @@
+use crate::{
+    db::Repo,
+    i18n::Locale,
+    matching::DEFAULT_PAIRS,
+    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+    services::{GameService, MatchStart, ServiceError},
+    templates::{self, ErrorTemplate},
+};
+use actix_session::Session;
+use actix_web::{HttpResponse, get, post, web};
+use askama::Template;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Where the matching game in progress is kept in the session
+const MATCH_KEY: &str = "match";
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StackUriPath {
+    stack_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MatchQuery {
+    pairs: Option<usize>,
+}
+
+/// Tiles by where they are in their columns
+#[derive(Debug, Deserialize)]
+pub(crate) struct PairForm {
+    front: usize,
+    back: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Paired {
+    matched: bool,
+    /// Every pair's matched, and the game can be finished
+    cleared: bool,
+}
+
+fn not_started(locale: &Locale) -> HttpResponse {
+    let error_html = templates::FormError {
+        error: &locale.t("error-match-not-started"),
+    }
+    .render()
+    .unwrap();
+    HttpResponse::BadRequest().body(error_html)
+}
+
+/// A matching game on a stack, timed from when the board's sent
+#[get("/stacks/match/{stack_uri}")]
+pub(crate) async fn match_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+    query: web::Query<MatchQuery>,
+) -> HttpResponse {
+    let did = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .map(|AtS { did, .. }| did);
+    let StackUriPath { stack_uri } = stack_uri.into_inner();
+    match GameService::new(repo.get_ref(), did.as_ref())
+        .match_board(&stack_uri, query.pairs.unwrap_or(DEFAULT_PAIRS), Utc::now())
+        .await
+    {
+        Ok((stack, board, start, best)) => {
+            if let Err(err) = session.insert(MATCH_KEY, &start) {
+                log::error!("error starting matching game {err}");
+            }
+            let html = templates::MatchTemplate {
+                locale,
+                title: "title-match",
+                stack,
+                board,
+                best,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(ServiceError::NotFound) => {
+            let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+            HttpResponse::NotFound().body(error_html)
+        }
+        Err(err) => {
+            log::error!("error making matching game {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
+
+/// Tries a front and a back as a pair in the matching game in progress on a stack
+#[post("/stacks/match/{stack_uri}/pair")]
+pub(crate) async fn match_pair(
+    locale: Locale,
+    session: Session,
+    stack_uri: web::Path<StackUriPath>,
+    form: web::Form<PairForm>,
+) -> HttpResponse {
+    let StackUriPath { stack_uri } = stack_uri.into_inner();
+    let start = session
+        .get::<MatchStart>(MATCH_KEY)
+        .ok()
+        .flatten()
+        .filter(|start| start.stack_uri == stack_uri);
+    let Some(mut start) = start else {
+        return not_started(&locale);
+    };
+    let matched = start.pair(form.front, form.back);
+    if let Err(err) = session.insert(MATCH_KEY, &start) {
+        log::error!("error saving matching game {err}");
+    }
+    HttpResponse::Ok().json(Paired {
+        matched,
+        cleared: start.is_cleared(),
+    })
+}
+
+/// Ends the matching game in progress on a stack, once the player's matched every pair
+#[post("/stacks/match/{stack_uri}")]
+pub(crate) async fn finish_match(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+) -> HttpResponse {
+    let StackUriPath { stack_uri } = stack_uri.into_inner();
+    let start = session
+        .remove_as::<MatchStart>(MATCH_KEY)
+        .and_then(Result::ok)
+        .filter(|start| start.stack_uri == stack_uri);
+    let Some(start) = start else {
+        return not_started(&locale);
+    };
+    let did = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .map(|AtS { did, .. }| did);
+    match GameService::new(repo.get_ref(), did.as_ref())
+        .finish_match(&start, Utc::now())
+        .await
+    {
+        Ok(finish) => {
+            let html = templates::MatchResultTemplate {
+                locale,
+                finish,
+                logged_in: did.is_some(),
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(ServiceError::Invalid(ref error)) => {
+            let error_html = templates::FormError {
+                error: &error.text(locale),
+            }
+            .render()
+            .unwrap();
+            HttpResponse::BadRequest().body(error_html)
+        }
+        Err(err) => {
+            log::error!("error saving matching game {err}");
+            let error_html = templates::FormError {
+                error: &locale.t("error-match"),
+            }
+            .render()
+            .unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
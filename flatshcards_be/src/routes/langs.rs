@@ -0,0 +1,55 @@
+use crate::{
+    db::Repo,
+    i18n::Locale,
+    lang::{FAVOURITE_LANGS, LangPicker},
+    templates::LangOptionsTemplate,
+};
+use actix_session::Session;
+use actix_web::{HttpResponse, get, web};
+use askama::Template;
+use std::collections::HashMap;
+
+/// The language pickers for `did`, leading with the languages of their cards
+pub(crate) async fn user_lang_picker(repo: &dyn Repo, did: &str) -> LangPicker {
+    let most_used = repo
+        .most_used_langs(did, FAVOURITE_LANGS)
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("Error loading most used languages: {err}");
+            Vec::new()
+        });
+    LangPicker::new(most_used)
+}
+
+/// Searches languages for a picker. Takes the search as `q`, the name of the picker's
+/// select as `field` and its current value under that name, and the message id of its
+/// empty option's label, if it has one, as `none`.
+#[get("/langs")]
+pub(crate) async fn search_langs(
+    locale: Locale,
+    session: Session,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    fn param<'a>(query: &'a HashMap<String, String>, key: &str) -> &'a str {
+        query.get(key).map(String::as_str).unwrap_or_default()
+    }
+    let langs = match session.get::<String>("did").unwrap_or(None) {
+        Some(did) => user_lang_picker(repo.get_ref(), &did).await,
+        None => LangPicker::new(Vec::new()),
+    };
+    let selected = param(&query, param(&query, "field"));
+    let none_label = match param(&query, "none") {
+        label @ ("lang-none" | "lang-choose") => label,
+        _ => "",
+    };
+    let html = LangOptionsTemplate {
+        locale,
+        selected,
+        none_label,
+        groups: langs.search(param(&query, "q"), selected),
+    }
+    .render()
+    .expect("template should be valid");
+    HttpResponse::Ok().body(html)
+}
@@ -1,35 +1,82 @@
+pub(crate) mod api;
 mod atproto_agent;
+pub(crate) mod blobs;
 pub(crate) mod cards;
+pub(crate) mod collections;
+pub(crate) mod games;
+pub(crate) mod langs;
+pub(crate) mod quizzes;
+pub(crate) mod reviews;
 pub(crate) mod stacks;
+pub(crate) mod stats;
 pub(crate) mod user_management;
 
-pub(crate) use user_management::OAuthClientType;
+pub(crate) use user_management::{AppOAuthClient, AuthClient, OAuthClientType};
 
 use crate::routes::atproto_agent::{AtS, get_session_agent_and_did};
 use crate::{
-    db,
+    db::{Repo, StackSearch},
+    i18n::Locale,
     templates::{HomeTemplate, Profile},
 };
 use actix_session::Session;
-use actix_web::{Responder, Result, get, web};
+use actix_web::{HttpRequest, Responder, Result, get, http::header, web};
 use askama::Template;
-use sqlx::postgres::PgPool;
+use serde::Deserialize;
+
+/// The path of the page a form was submitted from, to go back to. Only the path, so this
+/// can't be used to send anyone off-site.
+pub(crate) fn referer_path(request: &HttpRequest) -> String {
+    request
+        .headers()
+        .get(header::REFERER)
+        .and_then(|r| r.to_str().ok())
+        .and_then(|r| r.split_once("://"))
+        .and_then(|(_, r)| r.find('/').map(|i| &r[i..]))
+        .filter(|path| !path.starts_with("//") && !path.starts_with("/\\"))
+        .unwrap_or("/")
+        .to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct HomeQuery {
+    /// Only stacks with this tag, on them or on one of their cards
+    #[serde(default)]
+    tag: String,
+}
 
 #[get("/")]
 pub(crate) async fn home(
+    locale: Locale,
     session: Session,
     oauth_client: web::Data<user_management::OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<HomeQuery>,
 ) -> Result<impl Responder> {
-    const TITLE: &str = "Home";
+    const TITLE: &str = "title-home";
 
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let stacks = db::StackDetails::user_stacks(&did, &db_pool)
-            .await
-            .unwrap_or_else(|err| {
-                log::error!("Error loading stacks: {err}");
-                vec![]
-            });
+        let search = StackSearch {
+            author_did: Some(did.to_string()),
+            ..StackSearch::new("", &query.tag)
+        };
+        let stacks = if search.tag.is_some() {
+            repo.search_stacks(&search, i64::MAX).await
+        } else {
+            repo.user_stacks(&did).await
+        };
+        let stacks = stacks.unwrap_or_else(|err| {
+            log::error!("Error loading stacks: {err}");
+            vec![]
+        });
+        let collections = repo.user_collections(&did).await.unwrap_or_else(|err| {
+            log::error!("Error loading collections: {err}");
+            vec![]
+        });
+        let tags = repo.user_tags(&did).await.unwrap_or_else(|err| {
+            log::error!("Error loading tags: {err}");
+            vec![]
+        });
         // Fetch additional information about the logged-in user
         let profile = agent
             .api
@@ -59,9 +106,13 @@ pub(crate) async fn home(
             }
         }
         let html = HomeTemplate {
+            locale,
             title: TITLE,
             stacks,
+            collections,
             profile: pr,
+            tags,
+            tag: search.tag,
         }
         .render()
         .expect("template should be valid");
@@ -69,9 +120,13 @@ pub(crate) async fn home(
         Ok(web::Html::new(html))
     } else {
         let html = HomeTemplate {
+            locale,
             title: TITLE,
             profile: None,
             stacks: Vec::new(),
+            collections: Vec::new(),
+            tags: Vec::new(),
+            tag: None,
         }
         .render()
         .expect("template should be valid");
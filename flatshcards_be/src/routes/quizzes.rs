@@ -0,0 +1,82 @@
+use crate::{
+    db::Repo,
+    i18n::Locale,
+    services::{QuizService, ServiceError},
+    templates::{self, ErrorTemplate},
+};
+use actix_web::{HttpResponse, get, post, web};
+use askama::Template;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StackUriPath {
+    stack_uri: String,
+}
+
+/// A multiple-choice quiz on a stack. There's nothing to keep, so no need to log in.
+#[get("/stacks/quiz/{stack_uri}")]
+pub(crate) async fn quiz_page(
+    locale: Locale,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+) -> HttpResponse {
+    let StackUriPath { stack_uri } = stack_uri.into_inner();
+    match QuizService::new(repo.get_ref()).quiz(&stack_uri).await {
+        Ok((stack, questions)) => {
+            let html = templates::QuizTemplate {
+                locale,
+                title: "title-quiz",
+                stack,
+                questions,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(ServiceError::NotFound) => {
+            let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+            HttpResponse::NotFound().body(error_html)
+        }
+        Err(err) => {
+            log::error!("error making quiz {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
+
+/// Scores a quiz, see [`QuizService::score`] for its form
+#[post("/stacks/quiz/{stack_uri}")]
+pub(crate) async fn score_quiz(
+    locale: Locale,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+    form: web::Form<Vec<(String, String)>>,
+) -> HttpResponse {
+    let StackUriPath { stack_uri } = stack_uri.into_inner();
+    match QuizService::new(repo.get_ref())
+        .score(&stack_uri, &form)
+        .await
+    {
+        Ok((stack, score)) => {
+            let html = templates::QuizScoreTemplate {
+                locale,
+                title: "title-quiz",
+                stack,
+                score,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(ServiceError::NotFound) => {
+            let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+            HttpResponse::NotFound().body(error_html)
+        }
+        Err(err) => {
+            log::error!("error scoring quiz {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
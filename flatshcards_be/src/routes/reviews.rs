@@ -0,0 +1,272 @@
+use crate::{
+    db::Repo,
+    i18n::Locale,
+    routes::{AtS, OAuthClientType, get_session_agent_and_did, referer_path},
+    services::{
+        LimitsInput, ReviewAnswer, ReviewFilter, ReviewOptions, ReviewService, ServiceError,
+        TypedAnswer,
+    },
+    templates::{self, ErrorTemplate},
+};
+use actix_session::Session;
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, get, post,
+    web::{self, Redirect},
+};
+use askama::Template;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StackUriPath {
+    stack_uri: String,
+}
+
+/// Reviews whatever's due in a stack, one item at a time
+#[get("/stacks/review/{stack_uri}")]
+pub(crate) async fn review_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+    options: web::Query<ReviewOptions>,
+) -> HttpResponse {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let StackUriPath { stack_uri } = stack_uri.into_inner();
+        let now = Utc::now();
+        match ReviewService::new(repo.get_ref(), &did)
+            .queue(&stack_uri, options.new_order, now)
+            .await
+        {
+            Ok(queue) => {
+                let html = templates::ReviewTemplate::new(
+                    locale,
+                    queue,
+                    *options,
+                    ReviewFilter::default(),
+                    now,
+                )
+                .render()
+                .unwrap();
+                HttpResponse::Ok().body(html)
+            }
+            Err(ServiceError::NotFound) => {
+                let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error loading review queue {err}");
+                let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-review")
+            .render()
+            .unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+/// Reviews whatever's due across the reviewer's stacks, or the ones in the languages
+/// asked for
+#[get("/review/session")]
+pub(crate) async fn review_session_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    options: web::Query<ReviewOptions>,
+    filter: web::Query<ReviewFilter>,
+) -> HttpResponse {
+    let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-review")
+            .render()
+            .unwrap();
+        return HttpResponse::Unauthorized().body(error_html);
+    };
+    let filter = ReviewFilter {
+        session: true,
+        ..filter.into_inner()
+    };
+    let now = Utc::now();
+    match ReviewService::new(repo.get_ref(), &did)
+        .session(&filter, options.new_order, now)
+        .await
+    {
+        Ok(queue) => {
+            let html = templates::ReviewTemplate::new(locale, queue, *options, filter, now)
+                .render()
+                .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(err) => {
+            log::error!("error loading review session {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
+
+/// Sets the reviewer's daily limits, and goes back to the review they were set from
+#[post("/review/limits")]
+pub(crate) async fn save_review_limits(
+    request: HttpRequest,
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    form: web::Form<LimitsInput>,
+) -> HttpResponse {
+    let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        return HttpResponse::Unauthorized().body(error_html);
+    };
+    match ReviewService::new(repo.get_ref(), &did)
+        .save_limits(&form)
+        .await
+    {
+        Ok(()) => Redirect::to(referer_path(&request))
+            .see_other()
+            .respond_to(&request)
+            .map_into_boxed_body(),
+        Err(ServiceError::Invalid(ref error)) => {
+            let error_html = ErrorTemplate::invalid(locale, error).render().unwrap();
+            HttpResponse::BadRequest().body(error_html)
+        }
+        Err(err) => {
+            log::error!("error saving review limits {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
+
+/// Grades the item on the review page, and swaps in the next one
+#[post("/review")]
+pub(crate) async fn grade_review(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    options: web::Query<ReviewOptions>,
+    filter: web::Query<ReviewFilter>,
+    form: web::Form<ReviewAnswer>,
+) -> HttpResponse {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let now = Utc::now();
+        match ReviewService::new(repo.get_ref(), &did)
+            .grade(&form, &filter, options.new_order, now)
+            .await
+        {
+            Ok(queue) => {
+                let html = templates::ReviewItemTemplate::new(
+                    locale,
+                    &queue,
+                    *options,
+                    filter.into_inner(),
+                    now,
+                )
+                .render()
+                .unwrap();
+                HttpResponse::Ok().body(html)
+            }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = templates::FormError {
+                    error: &error.text(locale),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
+            Err(ServiceError::NotFound) => {
+                let error_html = templates::FormError {
+                    error: &locale.t("error-card-not-found"),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error saving review {err}");
+                let error_html = templates::FormError {
+                    error: &locale.t("error-review"),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
+
+/// Checks what was typed on the review page, and swaps the item back in with the answer
+/// and a suggested grade
+#[post("/review/check")]
+pub(crate) async fn check_review(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    options: web::Query<ReviewOptions>,
+    filter: web::Query<ReviewFilter>,
+    form: web::Form<TypedAnswer>,
+) -> HttpResponse {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let now = Utc::now();
+        match ReviewService::new(repo.get_ref(), &did)
+            .check(&form, &filter, *options, now)
+            .await
+        {
+            Ok((queue, item, checked)) => {
+                let html = templates::ReviewItemTemplate {
+                    item: Some(item),
+                    shown_at: form.shown_at.unwrap_or(now).to_rfc3339(),
+                    typed: Some((form.typed.clone(), checked)),
+                    ..templates::ReviewItemTemplate::new(
+                        locale,
+                        &queue,
+                        *options,
+                        filter.into_inner(),
+                        now,
+                    )
+                }
+                .render()
+                .unwrap();
+                HttpResponse::Ok().body(html)
+            }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = templates::FormError {
+                    error: &error.text(locale),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
+            Err(ServiceError::NotFound) => {
+                let error_html = templates::FormError {
+                    error: &locale.t("error-card-not-found"),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error checking answer {err}");
+                let error_html = templates::FormError {
+                    error: &locale.t("error-unknown"),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
+    }
+}
@@ -1,46 +1,220 @@
 use crate::{
-    db,
-    lang::{is_lang, lang_choices},
-    lexicons::{
-        record::KnownRecord,
-        xyz::flatshcards::{Card, Stack, card, stack},
+    blobs::BlobCache,
+    db::{self, Repo},
+    i18n::Locale,
+    review::DIRECTION_FORWARD,
+    routes::{
+        AtS, OAuthClientType, cards::CardForm, get_session_agent_and_did, langs::user_lang_picker,
     },
-    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+    services::{CardOrder, CardService, ServiceError, Side, StackInput, StackService},
+    tags::split_tags,
     templates::{self, ErrorTemplate},
+    tts::SpeechCache,
 };
+use actix_multipart::form::{MultipartForm, bytes::Bytes, text::Text};
 use actix_session::Session;
 use actix_web::{
     HttpRequest, HttpResponse, Responder, delete, get, post, put,
     web::{self, Redirect},
 };
 use askama::Template;
-use atrium_api::com::atproto::repo::{create_record, delete_record, put_record};
-use atrium_api::types::{
-    Collection, Object,
-    string::{Datetime, Did, RecordKey},
-};
 use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgPool;
+
+/// Stacks shown for a search, at most
+pub(crate) const SEARCH_LIMIT: i64 = 50;
+
+/// A stack search, from the search form or the JSON API
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SearchQuery {
+    /// Text in the label
+    #[serde(default)]
+    pub(crate) q: String,
+    /// A tag on the stack or one of its cards
+    #[serde(default)]
+    pub(crate) tag: String,
+}
+
+impl SearchQuery {
+    pub(crate) fn search(&self) -> db::StackSearch {
+        db::StackSearch::new(&self.q, &self.tag)
+    }
+    fn is_empty(&self) -> bool {
+        self.q.trim().is_empty() && self.tag.trim().is_empty()
+    }
+}
+
+/// Searches everyone's stacks by label and tag
+#[get("/search")]
+pub(crate) async fn search_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    query: web::Query<SearchQuery>,
+) -> HttpResponse {
+    let logged_in = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .is_some();
+    let query = query.into_inner();
+    let stacks = if query.is_empty() {
+        Vec::new()
+    } else {
+        match repo.search_stacks(&query.search(), SEARCH_LIMIT).await {
+            Ok(stacks) => stacks,
+            Err(err) => {
+                log::error!("error searching stacks {err}");
+                let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+                return HttpResponse::InternalServerError().body(error_html);
+            }
+        }
+    };
+    let html = templates::SearchTemplate {
+        locale,
+        title: "title-search",
+        searched: !query.is_empty(),
+        q: query.q,
+        tag: query.tag,
+        stacks,
+        logged_in,
+    }
+    .render()
+    .unwrap();
+    HttpResponse::Ok().body(html)
+}
+
+/// The stack editor's form. It's multipart so it can carry a cover; a cover the stack
+/// already has comes back as its ref, in `coverImage`.
+#[derive(MultipartForm)]
+pub(crate) struct StackForm {
+    #[multipart(rename = "stackLabel")]
+    stack_label: Text<String>,
+    #[multipart(rename = "frontLang")]
+    front_lang: Option<Text<String>>,
+    #[multipart(rename = "backLang")]
+    back_lang: Option<Text<String>>,
+    directions: Option<Text<String>>,
+    /// Separated by commas
+    tags: Option<Text<String>>,
+    description: Option<Text<String>>,
+    source: Option<Text<String>>,
+    license: Option<Text<String>>,
+    difficulty: Option<Text<String>>,
+    #[multipart(rename = "coverImage")]
+    cover: Option<Text<String>>,
+    /// Too big is reported by [`StackService::upload_cover`], this is just a backstop
+    #[multipart(rename = "coverImageFile", limit = "2MiB")]
+    cover_file: Option<Bytes>,
+    #[multipart(rename = "coverImageRemove")]
+    remove_cover: Option<Text<String>>,
+}
+
+impl StackForm {
+    /// Uploads any new cover, so the input can refer to it, once the stack, as a new one or
+    /// over `stack_uri`, is known to be one that can be written. A new file wins, then
+    /// removing, then the cover the stack had.
+    async fn into_input(
+        self,
+        service: &StackService<'_>,
+        stack_uri: Option<&str>,
+    ) -> Result<StackInput, ServiceError> {
+        let cover_file = CardForm::file(self.cover_file);
+        let cover = match cover_file {
+            Some(_) => None,
+            None => CardForm::kept(self.cover, self.remove_cover.is_some())?,
+        };
+        let text = |field: Option<Text<String>>| field.map(Text::into_inner).unwrap_or_default();
+        let mut input = StackInput {
+            back_lang: self.back_lang.map(Text::into_inner),
+            front_lang: self.front_lang.map(Text::into_inner),
+            stack_label: self.stack_label.into_inner(),
+            directions: self
+                .directions
+                .map(Text::into_inner)
+                .unwrap_or_else(|| DIRECTION_FORWARD.to_string()),
+            tags: self.tags.map(|t| split_tags(&t)).unwrap_or_default(),
+            description: text(self.description),
+            source: text(self.source),
+            license: text(self.license),
+            difficulty: text(self.difficulty),
+            cover,
+        };
+        service.check(stack_uri, &input).await?;
+        if let Some(data) = cover_file {
+            input.cover = Some(service.upload_cover(data).await?);
+        }
+        Ok(input)
+    }
+}
+
+/// Anyone's stack, with what it says about itself
+#[get("/stacks/view/{stack_uri}")]
+pub(crate) async fn stack_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    path: web::Path<StackUriPath>,
+) -> HttpResponse {
+    let did = get_session_agent_and_did(&oauth_client, &session)
+        .await
+        .map(|AtS { did, .. }| did);
+    let stack = match repo.get_stack(&path.stack_uri).await {
+        Ok(Some(stack)) => stack,
+        Ok(None) => {
+            let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+            return HttpResponse::NotFound().body(error_html);
+        }
+        Err(err) => {
+            log::error!("error retrieving stack {err}");
+            let error_html = ErrorTemplate::new(locale, "error-retrieve-stack")
+                .render()
+                .unwrap();
+            return HttpResponse::InternalServerError().body(error_html);
+        }
+    };
+    match repo.stack_cards(&stack.uri).await {
+        Ok(cards) => {
+            let html = templates::StackTemplate {
+                locale,
+                title: "title-stack",
+                logged_in: did.is_some(),
+                owned: did.is_some_and(|did| did.as_str() == stack.author_did()),
+                card_count: cards.len(),
+                stack,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(err) => {
+            log::error!("error retrieving stack's cards {err}");
+            let error_html = ErrorTemplate::new(locale, "error-retrieve-cards")
+                .render()
+                .unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
 
 #[get("/stacks/create")]
 pub(crate) async fn create_stack_page(
+    locale: Locale,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
 ) -> HttpResponse {
-    if get_session_agent_and_did(&oauth_client, &session)
-        .await
-        .is_some()
-    {
+    if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
         let html = templates::CreateStackTemplate {
-            title: "Create Stack",
-            lang_choices: lang_choices(),
+            locale,
+            title: "title-create-stack",
+            langs: user_lang_picker(repo.get_ref(), &did).await,
             error: None,
         }
         .render()
         .unwrap();
         HttpResponse::Ok().body(html)
     } else {
-        let error_html = ErrorTemplate::session_agent_did().render().unwrap();
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
         HttpResponse::Unauthorized().body(error_html)
     }
 }
@@ -48,614 +222,407 @@ pub(crate) async fn create_stack_page(
 /// Creates a new stack
 #[post("/stacks/create")]
 pub(crate) async fn create_stack(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
-    form: web::Form<StackForm>,
+    repo: web::Data<dyn Repo>,
+    MultipartForm(form): MultipartForm<StackForm>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let form = form.clone();
-        if !form.front_valid() {
-            let bad_lang = form.front_lang.unwrap(); // None is valid
-            let error_html = ErrorTemplate {
-                title: "Form Validation",
-                error: format!("Invalid front language {bad_lang}").as_ref(),
-            }
-            .render()
-            .expect("template should be valid");
-            return HttpResponse::BadRequest().body(error_html);
+        let service = StackService::new(&agent, repo.get_ref(), &did);
+        let created = match form.into_input(&service, None).await {
+            Ok(input) => service.create(&input).await,
+            Err(err) => Err(err),
         };
-        if !form.back_valid() {
-            let bad_lang = form.back_lang.unwrap(); // None is valid
-            let error_html = ErrorTemplate {
-                title: "Form Validation",
-                error: format!("Invalid back language {bad_lang}").as_ref(),
-            }
-            .render()
-            .expect("template should be valid");
-            return HttpResponse::Ok().body(error_html);
-        };
-        let stack = form.to_record();
-        let db_did = did.clone().to_string();
-
-        let create_result = agent
-            .api
-            .com
-            .atproto
-            .repo
-            .create_record(
-                create_record::InputData {
-                    collection: Stack::NSID.parse().unwrap(),
-                    repo: did.into(),
-                    rkey: None,
-                    record: stack.into(),
-                    swap_commit: None,
-                    validate: None,
-                }
-                .into(),
-            )
-            .await;
-
-        match create_result {
-            Ok(record) => {
-                let args = form.to_args(record.uri.clone(), db_did);
-                let stack = db::DbStack::new(args);
-                let _ = stack.save(&db_pool).await;
-                Redirect::to("/")
-                    .see_other()
-                    .respond_to(&request)
-                    .map_into_boxed_body()
+        match created {
+            Ok(_) => Redirect::to("/")
+                .see_other()
+                .respond_to(&request)
+                .map_into_boxed_body(),
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = ErrorTemplate::invalid(locale, error)
+                    .render()
+                    .expect("template should be valid");
+                HttpResponse::BadRequest().body(error_html)
             }
             Err(err) => {
-                log::error!("Error creating status: {err}");
-                let error_html = ErrorTemplate {
-                    title: "Error",
-                    error: "There was an error creating the stack",
-                }
-                .render()
-                .expect("template should be valid");
+                log::error!("Error creating stack: {err}");
+                let error_html = ErrorTemplate::new(locale, "error-create-stack")
+                    .render()
+                    .expect("template should be valid");
                 HttpResponse::Ok().body(error_html)
             }
         }
     } else {
-        let error_template = ErrorTemplate {
-            title: "Error",
-            error: "You must be logged in to create a stack.",
-        }
-        .render()
-        .expect("template should be valid");
+        let error_template = ErrorTemplate::new(locale, "error-login-to-create-stack")
+            .render()
+            .expect("template should be valid");
         HttpResponse::Ok().body(error_template)
     }
 }
 
-/// The post body for creating a new stack
-#[derive(Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct StackForm {
-    back_lang: Option<String>,
-    front_lang: Option<String>,
-    stack_label: String,
+#[derive(Debug, Deserialize, Serialize)]
+struct StackUriPath {
+    stack_uri: String,
 }
-impl StackForm {
-    fn lang_valid(lang: &str) -> bool {
-        is_lang(lang)
-    }
-    fn front_valid(&self) -> bool {
-        if let Some(ref l) = self.front_lang {
-            StackForm::lang_valid(l)
-        } else {
-            true
-        }
-    }
-    fn back_valid(&self) -> bool {
-        if let Some(ref l) = self.back_lang {
-            StackForm::lang_valid(l)
-        } else {
-            true
-        }
-    }
-    fn to_args(&self, uri: String, author_did: String) -> db::StackArgs {
-        db::StackArgs {
-            uri,
-            author_did,
-            back_lang: self.back_lang.clone(),
-            front_lang: self.front_lang.clone(),
-            label: self.stack_label.clone(),
-            indexed_at: None,
-        }
-    }
-    fn to_record(&self) -> KnownRecord {
-        stack::Stack {
-            back_lang: self.back_lang.clone(),
-            front_lang: self.front_lang.clone(),
-            label: self.stack_label.clone(),
-            created_at: Datetime::now(),
+
+/// Where the editor of the stack at `uri` is, with the uri encoded to stay in one path
+/// segment
+fn edit_stack_path(uri: &str) -> String {
+    let uri = askama::filters::urlencode_strict(uri).map(|uri| uri.to_string());
+    format!("/stacks/edit/{}", uri.unwrap_or_default())
+}
+
+/// Renders the full edit page for `stack`, including its cards
+async fn edit_stack_html(
+    locale: Locale,
+    stack: db::StackDetails,
+    repo: &dyn Repo,
+    did: &str,
+    speech: bool,
+) -> HttpResponse {
+    let langs = user_lang_picker(repo, did).await;
+    let stack_id = stack.uri.clone();
+    let add_card = templates::AddCardTemplate {
+        locale,
+        langs: langs.clone(),
+        stack: stack.clone(),
+        error: None,
+        speech,
+    };
+    match repo.stack_cards(&stack_id).await {
+        Err(err) => {
+            log::error!("error retrieving cards from db {err}");
+            let html = templates::EditStackTemplate {
+                locale,
+                title: "title-edit-stack",
+                langs: langs.clone(),
+                stack,
+                error: Some("error-retrieve-cards"),
+                add_card,
+                edit_cards: templates::EditCardsTemplate {
+                    locale,
+                    langs,
+                    cards: Vec::new(),
+                    stack_id,
+                    speech,
+                },
+                speech,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::BadRequest().body(html)
         }
-        .into()
-    }
-    fn to_update_args(&self, uri: String, author_did: String) -> db::StackUpdateArgs {
-        db::StackUpdateArgs {
-            uri,
-            author_did,
-            back_lang: self.back_lang.clone(),
-            front_lang: self.front_lang.clone(),
-            label: self.stack_label.clone(),
+        Ok(cards) => {
+            let html = templates::EditStackTemplate {
+                locale,
+                title: "title-edit-stack",
+                langs: langs.clone(),
+                stack,
+                error: None,
+                add_card,
+                edit_cards: templates::EditCardsTemplate {
+                    locale,
+                    langs,
+                    cards,
+                    stack_id,
+                    speech,
+                },
+                speech,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct StackUriPath {
-    stack_uri: String,
-}
-
 #[get("/stacks/edit/{stack_uri}")]
 pub(crate) async fn edit_stack_page(
+    locale: Locale,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
     stack_uri: web::Path<StackUriPath>,
 ) -> HttpResponse {
     if let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await {
         let StackUriPath { stack_uri } = stack_uri.into_inner();
-        match db::DbStack::get_owned_by(&did, &stack_uri, &db_pool).await {
+        match repo.get_owned_stack(&did, &stack_uri).await {
             Ok(Some(stack)) => {
-                let lc = lang_choices();
-                let st = stack.clone();
-                let stack_id = st.uri.clone();
-                let add_card = templates::AddCardTemplate {
-                    lang_choices: lc.clone(),
-                    stack: st,
-                    error: None,
-                };
-                match db::DisplayCard::stack_cards(&stack_uri, &db_pool).await {
-                    Err(err) => {
-                        log::error!("error retrieving cards from db {err}");
-                        let html = templates::EditStackTemplate {
-                            title: "Edit Stack",
-                            lang_choices: lc.clone(),
-                            stack,
-                            error: Some(err.to_string().as_ref()),
-                            add_card,
-                            edit_cards: templates::EditCardsTemplate {
-                                lang_choices: lc.clone(),
-                                cards: Vec::new(),
-                                stack_id,
-                            },
-                        }
-                        .render()
-                        .unwrap();
-                        HttpResponse::BadRequest().body(html)
-                    }
-                    Ok(cards) => {
-                        let stack_id = stack.uri.clone();
-                        let html = templates::EditStackTemplate {
-                            title: "Edit Stack",
-                            lang_choices: lc.clone(),
-                            stack,
-                            error: None,
-                            add_card,
-                            edit_cards: templates::EditCardsTemplate {
-                                lang_choices: lc.clone(),
-                                cards,
-                                stack_id,
-                            },
-                        }
-                        .render()
-                        .unwrap();
-                        HttpResponse::Ok().body(html)
-                    }
-                }
+                edit_stack_html(locale, stack, repo.get_ref(), &did, speech.is_some()).await
             }
             Ok(None) => {
-                let error_html = ErrorTemplate::stack_not_found().render().unwrap();
+                let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
                 HttpResponse::NotFound().body(error_html)
             }
             Err(err) => {
                 log::error!("error retrieving stack {err}");
-                let error_html = ErrorTemplate {
-                    title: "Error",
-                    error: "Error retrieving stack",
-                }
-                .render()
-                .unwrap();
+                let error_html = ErrorTemplate::new(locale, "error-retrieve-stack")
+                    .render()
+                    .unwrap();
                 HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
-        let error_html = ErrorTemplate {
-            title: "Error",
-            error: "You must be logged in to edit stacks",
-        }
-        .render()
-        .unwrap();
+        let error_html = ErrorTemplate::new(locale, "error-login-to-edit-stacks")
+            .render()
+            .unwrap();
         HttpResponse::Unauthorized().body(error_html)
     }
 }
 
 #[delete("/stacks/edit/{stack_uri}")]
 pub(crate) async fn delete_stack(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
     stack_uri: web::Path<StackUriPath>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let db_did = did.clone();
         let StackUriPath { stack_uri } = stack_uri.into_inner();
-        match db::DbStack::is_owned_by(&db_did, &stack_uri, &db_pool).await {
-            Ok(true) => {
-                if let Err(err) = db::DbStack::delete_by_uri(&stack_uri, &db_pool).await {
-                    log::error!("Error deleting stack from db: {err}");
-                    let error_html = ErrorTemplate {
-                        title: "Error",
-                        error: "Error deleting stack from database.",
-                    }
-                    .render()
-                    .unwrap();
-                    HttpResponse::InternalServerError().body(error_html)
-                } else {
-                    let rkey = RecordKey::new(stack_uri).unwrap();
-                    let delete_result = agent
-                        .api
-                        .com
-                        .atproto
-                        .repo
-                        .delete_record(
-                            delete_record::InputData {
-                                collection: Stack::NSID.parse().unwrap(),
-                                repo: did.into(),
-                                rkey,
-                                swap_commit: None,
-                                swap_record: None,
-                            }
-                            .into(),
-                        )
-                        .await;
-                    if delete_result.is_err() {
-                        log::error!("error deleting stack from repo");
-                    };
-                    Redirect::to("/")
-                        .see_other()
-                        .respond_to(&request)
-                        .map_into_boxed_body()
-                }
-            }
-            Ok(false) => {
-                let error_html = ErrorTemplate::forbidden().render().unwrap();
+        match StackService::new(&agent, repo.get_ref(), &did)
+            .delete(&stack_uri)
+            .await
+        {
+            Ok(()) => Redirect::to("/")
+                .see_other()
+                .respond_to(&request)
+                .map_into_boxed_body(),
+            Err(ServiceError::Forbidden) => {
+                let error_html = ErrorTemplate::forbidden(locale).render().unwrap();
                 HttpResponse::Forbidden().body(error_html)
             }
+            Err(ServiceError::Db(err)) => {
+                log::error!("Error deleting stack from db: {err}");
+                let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
             Err(err) => {
-                log::error!("Error querying db: {err}");
-                let error_html = ErrorTemplate::db_query().render().unwrap();
+                log::error!("Error deleting stack: {err}");
+                let error_html = ErrorTemplate::new(locale, "error-delete-stack")
+                    .render()
+                    .unwrap();
                 HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
         log::error!("error retrieving did and agent");
-        let error_html = ErrorTemplate::session_agent_did().render().unwrap();
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
         HttpResponse::Unauthorized().body(error_html)
     }
 }
-#[put("/stacks/edit/{card_uri}")]
+
+#[put("/stacks/edit/{stack_uri}")]
 pub(crate) async fn put_stack(
+    locale: Locale,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
     stack_uri: web::Path<StackUriPath>,
-    form: web::Form<StackForm>,
+    MultipartForm(form): MultipartForm<StackForm>,
 ) -> HttpResponse {
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let form = form.clone();
-        let db_form = form.clone();
-        let db_did = did.clone();
         let StackUriPath { stack_uri } = stack_uri.into_inner();
-        let db_uri = stack_uri.clone();
-        match db::DbStack::is_owned_by(&db_did, &stack_uri, &db_pool).await {
-            Ok(true) => {
-                let update_result = agent
-                    .api
-                    .com
-                    .atproto
-                    .repo
-                    .put_record(
-                        put_record::InputData {
-                            collection: Stack::NSID.parse().unwrap(),
-                            record: form.to_record().into(),
-                            repo: did.into(),
-                            rkey: RecordKey::new(stack_uri.clone()).unwrap(),
-                            swap_commit: None,
-                            swap_record: None,
-                            validate: None,
-                        }
-                        .into(),
-                    )
-                    .await;
-                if let Err(err) = update_result {
-                    log::error!("error updating stack in atmosphere {err}");
-                    let error_html = ErrorTemplate {
-                        title: "Error",
-                        error: "Error updating stack",
-                    }
-                    .render()
-                    .unwrap();
-                    HttpResponse::InternalServerError().body(error_html)
-                } else {
-                    match db_form
-                        .to_update_args(db_uri, db_did.to_string())
-                        .update_owned(&db_pool)
-                        .await
-                    {
-                        Ok(Some(updated)) => {
-                            let lc = lang_choices();
-                            match db::DisplayCard::stack_cards(&stack_uri, &db_pool).await {
-                                Err(err) => {
-                                    log::error!("error retrieving post-update stack cards {err}");
-                                    let html = templates::EditStackTemplate {
-                                        title: "Edit Stack",
-                                        lang_choices: lc.clone(),
-                                        stack: updated.clone(),
-                                        error: Some("Could not retrieve cards, please try again."),
-                                        add_card: templates::AddCardTemplate {
-                                            lang_choices: lc.clone(),
-                                            stack: updated.clone(),
-                                            error: None,
-                                        },
-                                        edit_cards: templates::EditCardsTemplate {
-                                            lang_choices: lc.clone(),
-                                            cards: Vec::new(),
-                                            stack_id: updated.uri.clone(),
-                                        },
-                                    }
-                                    .render()
-                                    .unwrap();
-                                    HttpResponse::BadRequest().body(html)
-                                }
-                                Ok(cards) => {
-                                    let html = templates::EditStackTemplate {
-                                        title: "Edit Stack",
-                                        lang_choices: lc.clone(),
-                                        stack: updated.clone(),
-                                        error: None,
-                                        add_card: templates::AddCardTemplate {
-                                            lang_choices: lc.clone(),
-                                            stack: updated.clone(),
-                                            error: None,
-                                        },
-                                        edit_cards: templates::EditCardsTemplate {
-                                            lang_choices: lc.clone(),
-                                            cards,
-                                            stack_id: updated.uri.clone(),
-                                        },
-                                    }
-                                    .render()
-                                    .unwrap();
-                                    HttpResponse::Ok().body(html)
-                                }
-                            }
-                        }
-                        Ok(None) => {
-                            let error_html = ErrorTemplate::forbidden().render().unwrap();
-                            HttpResponse::Forbidden().body(error_html)
-                        }
-                        Err(err) => {
-                            log::error!("error updating stack {err}");
-                            let error_html = ErrorTemplate {
-                                title: "Error",
-                                error: "Error updating stack",
-                            }
-                            .render()
-                            .unwrap();
-                            HttpResponse::InternalServerError().body(error_html)
-                        }
-                    }
-                }
+        let service = StackService::new(&agent, repo.get_ref(), &did);
+        let updated = match form.into_input(&service, Some(&stack_uri)).await {
+            Ok(input) => service.update(stack_uri, &input).await,
+            Err(err) => Err(err),
+        };
+        match updated {
+            Ok(updated) => {
+                edit_stack_html(locale, updated, repo.get_ref(), &did, speech.is_some()).await
             }
-            Ok(false) => {
-                let error_html = ErrorTemplate::forbidden().render().unwrap();
+            Err(ServiceError::Forbidden) => {
+                let error_html = ErrorTemplate::forbidden(locale).render().unwrap();
                 HttpResponse::Forbidden().body(error_html)
             }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = ErrorTemplate::invalid(locale, error).render().unwrap();
+                HttpResponse::BadRequest().body(error_html)
+            }
             Err(err) => {
-                log::error!("error querying database {err}");
-                let error_html = ErrorTemplate::db_query().render().unwrap();
+                log::error!("error updating stack {err}");
+                let error_html = ErrorTemplate::new(locale, "error-update-stack")
+                    .render()
+                    .unwrap();
                 HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
-        let error_html = ErrorTemplate::session_agent_did().render().unwrap();
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
         HttpResponse::Unauthorized().body(error_html)
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct CloneStackPath {
-    src_uri: String,
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpeakStackForm {
+    side: Side,
 }
 
-#[post("/stacks/clone/{src_uri}")]
-pub(crate) async fn clone_stack(
+/// Generates audio for each of the stack's cards that has none, from `side`
+#[post("/stacks/speech/{stack_uri}")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn speak_stack(
+    locale: Locale,
     request: HttpRequest,
     session: Session,
     oauth_client: web::Data<OAuthClientType>,
-    db_pool: web::ThinData<PgPool>,
-    path: web::Path<CloneStackPath>,
+    repo: web::Data<dyn Repo>,
+    speech: Option<web::Data<SpeechCache>>,
+    stack_uri: web::Path<StackUriPath>,
+    form: web::Form<SpeakStackForm>,
 ) -> HttpResponse {
+    let Some(speech) = speech else {
+        let error_html = ErrorTemplate::new(locale, "error-speech-unavailable")
+            .render()
+            .unwrap();
+        return HttpResponse::NotFound().body(error_html);
+    };
     if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
-        let cl_did = did.clone();
-        let CloneStackPath { src_uri } = path.into_inner();
-        match db::DbStack::get_clone_data(&src_uri, &db_pool).await {
-            Ok(Some(db::StackCloneData {
-                back_lang,
-                front_lang,
-                label,
-            })) => {
-                let record: KnownRecord = stack::Stack {
-                    back_lang,
-                    front_lang,
-                    label,
-                    created_at: Datetime::now(),
-                }
-                .into();
-                let create_result = agent
-                    .api
-                    .com
-                    .atproto
-                    .repo
-                    .create_record(
-                        create_record::InputData {
-                            collection: Stack::NSID.parse().unwrap(),
-                            repo: did.into(),
-                            rkey: None,
-                            record: record.into(),
-                            swap_commit: None,
-                            validate: None,
-                        }
-                        .into(),
-                    )
-                    .await;
-                match create_result {
-                    Ok(Object {
-                        data:
-                            create_record::OutputData {
-                                uri: new_stack_uri, ..
-                            },
-                        ..
-                    }) => match db::DbCard::get_clone_data(&src_uri, &db_pool).await {
-                        Err(err) => {
-                            log::error!("error getting cards to clone {err}");
-                            let error_html = ErrorTemplate::db_query().render().unwrap();
-                            HttpResponse::InternalServerError().body(error_html)
-                        }
-                        Ok(cards) => {
-                            let nsu = new_stack_uri.clone();
-                            if let Err(err) =
-                                clone_stack_cards(cl_did, nsu, cards, &agent, &db_pool).await
-                            {
-                                log::error!("error cloning cards {err}");
-                                let error_html = ErrorTemplate {
-                                    title: "Error",
-                                    error: "An error has occurred.",
-                                }
-                                .render()
-                                .unwrap();
-                                HttpResponse::InternalServerError().body(error_html)
-                            } else {
-                                let url =
-                                    request.url_for("edit_stack_page", [new_stack_uri]).unwrap();
-                                Redirect::to(url.as_str().to_owned())
-                                    .see_other()
-                                    .respond_to(&request)
-                                    .map_into_boxed_body()
-                            }
-                        }
-                    },
-                    Err(err) => {
-                        log::error!("error cloning stack in atmosphere {err}");
-                        let error_html = ErrorTemplate {
-                            title: "Error",
-                            error: "An error has occurred.",
-                        }
-                        .render()
-                        .unwrap();
-                        HttpResponse::InternalServerError().body(error_html)
-                    }
-                }
+        let StackUriPath { stack_uri } = stack_uri.into_inner();
+        match CardService::new(&agent, repo.get_ref(), &did)
+            .speak_stack(&speech, &stack_uri, form.side)
+            .await
+        {
+            Ok(_) => {
+                let url = request.url_for("edit_stack_page", [stack_uri]).unwrap();
+                Redirect::to(url.as_str().to_owned())
+                    .see_other()
+                    .respond_to(&request)
+                    .map_into_boxed_body()
             }
-            Ok(None) => {
-                let error_html = ErrorTemplate::stack_not_found().render().unwrap();
-                HttpResponse::NotFound().body(error_html)
+            Err(ServiceError::Forbidden) => {
+                let error_html = ErrorTemplate::forbidden(locale).render().unwrap();
+                HttpResponse::Forbidden().body(error_html)
+            }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = ErrorTemplate::invalid(locale, error).render().unwrap();
+                HttpResponse::BadRequest().body(error_html)
             }
             Err(err) => {
-                log::error!("error getting clone data {err}");
-                let error_html = ErrorTemplate::db_query().render().unwrap();
+                log::error!("error generating audio for stack {err}");
+                let error_html = ErrorTemplate::new(locale, "error-speech").render().unwrap();
                 HttpResponse::InternalServerError().body(error_html)
             }
         }
     } else {
-        let error_html = ErrorTemplate::session_agent_did().render().unwrap();
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
         HttpResponse::Unauthorized().body(error_html)
     }
 }
 
-async fn clone_stack_cards(
-    did: Did,
-    new_stack_uri: String,
-    cards: Vec<db::CardCloneData>,
-    agent: &super::atproto_agent::Agent,
-    pool: &PgPool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut any_err: Option<Box<dyn std::error::Error>> = None;
-    let mut work: std::collections::VecDeque<db::CardCloneData> = cards.into();
-    while !work.is_empty() {
-        let clone_data = work.pop_front().unwrap();
-        let now = Datetime::now();
-        let db_now = now.clone();
-        let record_uri = new_stack_uri.clone();
-        let db_uri = new_stack_uri.clone();
-        let db_did = did.clone();
-        let rec: KnownRecord = card::Card {
-            back_lang: clone_data.back_lang.clone(),
-            back_text: clone_data.back_text.clone(),
-            created_at: now,
-            front_lang: clone_data.front_lang.clone(),
-            front_text: clone_data.front_text.clone(),
-            stack_id: RecordKey::new(record_uri).unwrap(),
-        }
-        .into();
-        let create_result = agent
-            .api
-            .com
-            .atproto
-            .repo
-            .create_record(
-                create_record::InputData {
-                    collection: Card::NSID.parse().unwrap(),
-                    repo: did.clone().into(),
-                    rkey: None,
-                    record: rec.into(),
-                    swap_commit: None,
-                    validate: None,
+/// Puts the stack's cards in the order they were dragged into, and goes back to its
+/// editor. Errors are for the editor to show beside the cards.
+#[post("/stacks/order/{stack_uri}")]
+pub(crate) async fn reorder_cards(
+    locale: Locale,
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    stack_uri: web::Path<StackUriPath>,
+    form: web::Form<CardOrder>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let StackUriPath { stack_uri } = stack_uri.into_inner();
+        match CardService::new(&agent, repo.get_ref(), &did)
+            .reorder(&stack_uri, &form.cards)
+            .await
+        {
+            Ok(_) => Redirect::to(edit_stack_path(&stack_uri))
+                .see_other()
+                .respond_to(&request)
+                .map_into_boxed_body(),
+            Err(ServiceError::Forbidden) => {
+                let error_html = templates::FormError {
+                    error: &locale.t("error-forbidden"),
                 }
-                .into(),
-            )
-            .await;
-        match create_result {
-            Ok(Object {
-                data: create_record::OutputData { uri, .. },
-                ..
-            }) => {
-                let indexed_at: Option<chrono::DateTime<chrono::Utc>> =
-                    Some(db_now.as_ref().to_utc());
-                if let Err(err) = db::DbCard::new(db::CardArgs {
-                    uri,
-                    author_did: db_did.into(),
-                    back_lang: clone_data.back_lang.clone(),
-                    back_text: clone_data.back_text.clone(),
-                    front_lang: clone_data.front_lang.clone(),
-                    front_text: clone_data.front_text.clone(),
-                    indexed_at,
-                    stack_id: db_uri.clone(),
-                })
-                .save(pool)
-                .await
-                {
-                    log::error!("error saving card in db, will ingest later {err}");
-                };
+                .render()
+                .unwrap();
+                HttpResponse::Forbidden().body(error_html)
+            }
+            Err(ServiceError::Invalid(ref error)) => {
+                let error_html = templates::FormError {
+                    error: &error.text(locale),
+                }
+                .render()
+                .unwrap();
+                HttpResponse::BadRequest().body(error_html)
             }
             Err(err) => {
-                log::error!("error saving card in atmosphere, will try again {err}");
-                if any_err.is_none() {
-                    any_err.replace(Box::new(err));
+                log::error!("error reordering cards {err}");
+                let error_html = templates::FormError {
+                    error: &locale.t("error-reorder-cards"),
                 }
-                work.push_back(clone_data)
+                .render()
+                .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
             }
         }
+    } else {
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
     }
-    if let Some(err) = any_err {
-        Err(err)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CloneStackPath {
+    src_uri: String,
+}
+
+#[post("/stacks/clone/{src_uri}")]
+pub(crate) async fn clone_stack(
+    locale: Locale,
+    request: HttpRequest,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+    blobs: web::Data<BlobCache>,
+    path: web::Path<CloneStackPath>,
+) -> HttpResponse {
+    if let Some(AtS { agent, did }) = get_session_agent_and_did(&oauth_client, &session).await {
+        let CloneStackPath { src_uri } = path.into_inner();
+        match StackService::new(&agent, repo.get_ref(), &did)
+            .clone_from(&src_uri, &blobs)
+            .await
+        {
+            Ok(cloned) if cloned.failed.is_empty() => {
+                let url = request.url_for("edit_stack_page", [cloned.uri]).unwrap();
+                Redirect::to(url.as_str().to_owned())
+                    .see_other()
+                    .respond_to(&request)
+                    .map_into_boxed_body()
+            }
+            Ok(cloned) => {
+                let error_html = ErrorTemplate::clone_incomplete(locale, &cloned.failed)
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+            Err(ServiceError::NotFound) => {
+                let error_html = ErrorTemplate::stack_not_found(locale).render().unwrap();
+                HttpResponse::NotFound().body(error_html)
+            }
+            Err(ServiceError::Db(err)) => {
+                log::error!("error getting clone data {err}");
+                let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+            Err(err) => {
+                log::error!("error cloning stack in atmosphere {err}");
+                let error_html = ErrorTemplate::new(locale, "error-unknown")
+                    .render()
+                    .unwrap();
+                HttpResponse::InternalServerError().body(error_html)
+            }
+        }
     } else {
-        Ok(())
+        let error_html = ErrorTemplate::session_agent_did(locale).render().unwrap();
+        HttpResponse::Unauthorized().body(error_html)
     }
 }
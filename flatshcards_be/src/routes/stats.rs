@@ -0,0 +1,47 @@
+use crate::{
+    db::Repo,
+    i18n::Locale,
+    routes::{AtS, OAuthClientType, get_session_agent_and_did},
+    services::StatsService,
+    templates::{self, ErrorTemplate},
+};
+use actix_session::Session;
+use actix_web::{HttpResponse, get, web};
+use askama::Template;
+use chrono::Utc;
+
+/// The logged-in user's study stats, from their reviews of anyone's stacks
+#[get("/stats")]
+pub(crate) async fn stats_page(
+    locale: Locale,
+    session: Session,
+    oauth_client: web::Data<OAuthClientType>,
+    repo: web::Data<dyn Repo>,
+) -> HttpResponse {
+    let Some(AtS { did, .. }) = get_session_agent_and_did(&oauth_client, &session).await else {
+        let error_html = ErrorTemplate::new(locale, "error-login-to-see-stats")
+            .render()
+            .unwrap();
+        return HttpResponse::Unauthorized().body(error_html);
+    };
+    match StatsService::new(repo.get_ref(), &did)
+        .stats(Utc::now())
+        .await
+    {
+        Ok(stats) => {
+            let html = templates::StatsTemplate {
+                locale,
+                title: "title-stats",
+                stats,
+            }
+            .render()
+            .unwrap();
+            HttpResponse::Ok().body(html)
+        }
+        Err(err) => {
+            log::error!("error working out stats {err}");
+            let error_html = ErrorTemplate::db_query(locale).render().unwrap();
+            HttpResponse::InternalServerError().body(error_html)
+        }
+    }
+}
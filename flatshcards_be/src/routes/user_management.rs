@@ -1,5 +1,7 @@
 use crate::{
+    i18n::{LOCALE_KEY, Locale},
     resolver::HickoryDnsTxtResolver,
+    routes::referer_path,
     storage,
     templates::{ErrorTemplate, LoginTemplate},
 };
@@ -17,19 +19,27 @@ use atrium_oauth::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-/// OAuthClientType to make it easier to access the OAuthClient in web requests
-pub(crate) type OAuthClientType = Arc<
-    OAuthClient<
-        storage::DbStateStore,
-        storage::DbSessionStore,
-        CommonDidResolver<DefaultHttpClient>,
-        AtprotoHandleResolver<HickoryDnsTxtResolver, DefaultHttpClient>,
-    >,
+pub(crate) type AppOAuthClient = OAuthClient<
+    storage::DbStateStore,
+    storage::DbSessionStore,
+    CommonDidResolver<DefaultHttpClient>,
+    AtprotoHandleResolver<HickoryDnsTxtResolver, DefaultHttpClient>,
 >;
 
+/// The OAuth client, plus the login bypass when running against the mock PDS
+pub(crate) struct AuthClient {
+    pub(crate) oauth: AppOAuthClient,
+    #[cfg(any(test, feature = "mock-pds"))]
+    pub(crate) mock: Option<crate::mock_pds::MockLogin>,
+}
+
+/// OAuthClientType to make it easier to access the OAuthClient in web requests
+pub(crate) type OAuthClientType = Arc<AuthClient>;
+
 /// OAuth callback endpoint to complete session creation
 #[get("/oauth/callback")]
 pub(crate) async fn oauth_callback(
+    locale: Locale,
     request: HttpRequest,
     params: web::Query<CallbackParams>,
     oauth_client: web::Data<OAuthClientType>,
@@ -37,7 +47,7 @@ pub(crate) async fn oauth_callback(
 ) -> HttpResponse {
     log::info!("oauth callback");
     //Processes the call back and parses out a session if found and valid
-    match oauth_client.callback(params.into_inner()).await {
+    match oauth_client.oauth.callback(params.into_inner()).await {
         Ok((bsky_session, _)) => {
             log::info!("got session");
             let agent = Agent::new(bsky_session);
@@ -50,20 +60,14 @@ pub(crate) async fn oauth_callback(
                         .map_into_boxed_body()
                 }
                 None => {
-                    let html = ErrorTemplate {
-                        title: "Error",
-                        error: "The OAuth agent did not return a DID. May try re-logging in.",
-                    };
+                    let html = ErrorTemplate::new(locale, "error-oauth-no-did");
                     HttpResponse::Ok().body(html.render().expect("template should be valid"))
                 }
             }
         }
         Err(err) => {
             log::error!("Error: {err}");
-            let html = ErrorTemplate {
-                title: "Error",
-                error: "OAuth error, check the logs",
-            };
+            let html = ErrorTemplate::new(locale, "error-oauth");
             HttpResponse::Ok().body(html.render().expect("template should be valid"))
         }
     }
@@ -71,9 +75,10 @@ pub(crate) async fn oauth_callback(
 
 /// Takes you to the login page
 #[get("/login")]
-async fn login() -> Result<impl Responder> {
+async fn login(locale: Locale) -> Result<impl Responder> {
     let html = LoginTemplate {
-        title: "Log in",
+        locale,
+        title: "title-login",
         error: None,
     };
     Ok(web::Html::new(
@@ -97,18 +102,45 @@ struct LoginForm {
     handle: String,
 }
 
+/// Against the mock PDS there's nobody to authorize with, so whatever the handle, you're
+/// logged in as the mock's DID. Returns whether you were.
+#[cfg(any(test, feature = "mock-pds"))]
+fn mock_login(oauth_client: &AuthClient, session: &Session) -> bool {
+    match &oauth_client.mock {
+        Some(mock) => {
+            session.insert("did", mock.did.as_str()).unwrap();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(not(any(test, feature = "mock-pds")))]
+fn mock_login(_: &AuthClient, _: &Session) -> bool {
+    false
+}
+
 /// Login endpoint
 #[post("/login")]
 async fn login_post(
+    locale: Locale,
     request: HttpRequest,
+    session: Session,
     params: web::Form<LoginForm>,
     oauth_client: web::Data<OAuthClientType>,
 ) -> HttpResponse {
+    if mock_login(&oauth_client, &session) {
+        return Redirect::to("/")
+            .see_other()
+            .respond_to(&request)
+            .map_into_boxed_body();
+    }
     // This will act the same as the js method isValidHandle to make sure it is valid
     match atrium_api::types::string::Handle::new(params.handle.clone()) {
         Ok(handle) => {
             //Creates the oauth url to redirect to for the user to log in with their credentials
             let oauth_url = oauth_client
+                .oauth
                 .authorize(
                     &handle,
                     AuthorizeOptions {
@@ -128,19 +160,48 @@ async fn login_post(
                 Err(err) => {
                     log::error!("Error: {err}");
                     let html = LoginTemplate {
-                        title: "Log in",
-                        error: Some("OAuth error"),
+                        locale,
+                        title: "title-login",
+                        error: Some("login-oauth-error"),
                     };
                     HttpResponse::Ok().body(html.render().expect("template should be valid"))
                 }
             }
         }
         Err(err) => {
-            let html: LoginTemplate<'_> = LoginTemplate {
-                title: "Log in",
-                error: Some(err),
+            log::info!("Invalid handle {}: {err}", params.handle);
+            let html = LoginTemplate {
+                locale,
+                title: "title-login",
+                error: Some("login-invalid-handle"),
             };
             HttpResponse::Ok().body(html.render().expect("template should be valid"))
         }
     }
 }
+
+/// The post body for picking the interface language
+#[derive(Serialize, Deserialize, Clone)]
+struct LocaleForm {
+    locale: String,
+}
+
+/// Sets the interface language, overriding the browser's, and goes back to the page
+/// it was set from
+#[post("/locale")]
+async fn set_locale(
+    request: HttpRequest,
+    session: Session,
+    params: web::Form<LocaleForm>,
+) -> HttpResponse {
+    match Locale::matching(&params.locale) {
+        Some(locale) => session.insert(LOCALE_KEY, locale.tag()).unwrap(),
+        None => {
+            session.remove(LOCALE_KEY);
+        }
+    }
+    Redirect::to(referer_path(&request))
+        .see_other()
+        .respond_to(&request)
+        .map_into_boxed_body()
+}
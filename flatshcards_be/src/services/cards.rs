@@ -0,0 +1,488 @@
+use super::{Pds, ServiceError, rkey_from_uri};
+use crate::{
+    blobs::{
+        AUDIO_TYPES, IMAGE_TYPES, MAX_AUDIO_SIZE, MAX_IMAGE_SIZE, audio_type, blob_mime_type,
+        image_type,
+    },
+    cloze::{KIND_BASIC, KIND_CLOZE, filled, review_items},
+    collections::deserialize_uris,
+    db::{self, Repo},
+    i18n::Message,
+    lang::{is_lang, normalize_lang},
+    lexicons::{
+        record::KnownRecord,
+        xyz::flatshcards::{Card, card},
+    },
+    rich::{FORMAT_PLAIN, is_format, to_plain},
+    tags::{self, deserialize_tags},
+    tts::{SpeechCache, TtsError},
+};
+use atrium_api::types::{
+    BlobRef, Collection,
+    string::{Datetime, Did},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Characters that can be spoken at once, as many as a side of a card holds
+const MAX_SPEECH_LENGTH: usize = 4096;
+
+/// One side of a card, to generate audio from
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Front,
+    Back,
+}
+
+/// A card as submitted by the editor or the JSON API
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardInput {
+    pub front_lang: String,
+    pub front_text: String,
+    pub back_lang: String,
+    pub back_text: String,
+    #[serde(default = "CardInput::basic")]
+    pub kind: String,
+    #[serde(default = "CardInput::plain")]
+    pub format: String,
+    /// Refs to blobs already uploaded with [`CardService::upload_image`]
+    #[serde(default)]
+    pub front_image: Option<BlobRef>,
+    #[serde(default)]
+    pub back_image: Option<BlobRef>,
+    /// A ref to a blob already uploaded with [`CardService::upload_audio`]
+    #[serde(default)]
+    pub audio: Option<BlobRef>,
+    /// Like [`super::StackInput::tags`]
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// Where the card goes in its stack. Left out, a new card goes last, as does one moved
+    /// to another stack, and an edited one stays where it was.
+    #[serde(default)]
+    pub position: Option<i32>,
+    pub stack_id: String,
+}
+
+impl CardInput {
+    /// `card` as it is, to write back changed
+    fn from_card(card: db::DisplayCard, stack_id: &str) -> Self {
+        Self {
+            front_lang: card.front_lang,
+            front_text: card.front_text,
+            back_lang: card.back_lang,
+            back_text: card.back_text,
+            kind: card.kind,
+            format: card.format,
+            front_image: card.front_image,
+            back_image: card.back_image,
+            audio: card.audio,
+            tags: card.tags,
+            position: card.position,
+            stack_id: stack_id.to_string(),
+        }
+    }
+    pub fn validate(&self) -> Option<Message> {
+        if !is_lang(&self.front_lang) {
+            let l = &self.front_lang;
+            Some(Message::new("invalid-front-lang").arg("lang", l))
+        } else if !is_lang(&self.back_lang) {
+            let l = &self.back_lang;
+            Some(Message::new("invalid-back-lang").arg("lang", l))
+        } else if !is_format(&self.format) {
+            let f = &self.format;
+            Some(Message::new("invalid-format").arg("format", f))
+        } else if let Some(blob) = [&self.front_image, &self.back_image]
+            .into_iter()
+            .flatten()
+            .find(|blob| !IMAGE_TYPES.contains(&blob_mime_type(blob)))
+        {
+            let t = blob_mime_type(blob);
+            Some(Message::new("invalid-image-type").arg("type", t))
+        } else if let Some(blob) = self
+            .audio
+            .as_ref()
+            .filter(|blob| !AUDIO_TYPES.contains(&blob_mime_type(blob)))
+        {
+            let t = blob_mime_type(blob);
+            Some(Message::new("invalid-audio-type").arg("type", t))
+        } else if self.kind == KIND_CLOZE {
+            review_items(&self.front_text).err().map(|e| e.message())
+        } else if self.kind != KIND_BASIC {
+            let k = &self.kind;
+            Some(Message::new("invalid-kind").arg("kind", k))
+        } else if let Some(p) = self.position.filter(|p| *p < 0) {
+            Some(Message::new("invalid-position").arg("position", p))
+        } else {
+            tags::validate(&self.tags)
+        }
+    }
+    /// What `side` says as it would be read out, and in which language, for
+    /// [`CardService::speak`]
+    pub fn speech(&self, side: Side) -> (String, String) {
+        let (text, lang) = match side {
+            Side::Front if self.kind == KIND_CLOZE => (filled(&self.front_text), &self.front_lang),
+            Side::Front => (self.front_text.clone(), &self.front_lang),
+            Side::Back => (self.back_text.clone(), &self.back_lang),
+        };
+        (to_plain(&text, &self.format), lang.clone())
+    }
+    fn basic() -> String {
+        KIND_BASIC.to_string()
+    }
+    fn plain() -> String {
+        FORMAT_PLAIN.to_string()
+    }
+    fn tags(&self) -> Vec<String> {
+        tags::normalize(&self.tags)
+    }
+    /// Language tags are stored in canonical case
+    fn lang(lang: &str) -> String {
+        normalize_lang(lang).unwrap_or_else(|| lang.to_string())
+    }
+    /// The card's record, which keeps `created_at` from when it was first written
+    fn as_record(&self, created_at: DateTime<Utc>) -> Result<KnownRecord, ServiceError> {
+        Ok(card::Card {
+            back_lang: CardInput::lang(&self.back_lang),
+            back_text: self.back_text.clone(),
+            front_lang: CardInput::lang(&self.front_lang),
+            front_text: self.front_text.clone(),
+            kind: Some(self.kind.clone()),
+            format: Some(self.format.clone()),
+            front_image: self.front_image.clone(),
+            back_image: self.back_image.clone(),
+            audio: self.audio.clone(),
+            tags: tags::record_tags(self.tags()),
+            position: self.position.map(i64::from),
+            stack_id: rkey_from_uri(&self.stack_id)?,
+            created_at: Datetime::new(created_at.fixed_offset()),
+        }
+        .into())
+    }
+    fn as_args(&self, uri: String, author_did: String, created_at: DateTime<Utc>) -> db::CardArgs {
+        db::CardArgs {
+            uri,
+            author_did,
+            back_lang: CardInput::lang(&self.back_lang),
+            back_text: self.back_text.clone(),
+            front_lang: CardInput::lang(&self.front_lang),
+            front_text: self.front_text.clone(),
+            kind: self.kind.clone(),
+            format: self.format.clone(),
+            front_image: self.front_image.clone(),
+            back_image: self.back_image.clone(),
+            audio: self.audio.clone(),
+            tags: self.tags(),
+            position: self.position,
+            indexed_at: Some(created_at),
+            stack_id: self.stack_id.clone(),
+        }
+    }
+    fn as_update_args(
+        &self,
+        uri: String,
+        author_did: String,
+        created_at: DateTime<Utc>,
+    ) -> db::CardUpdateArgs {
+        db::CardUpdateArgs {
+            uri,
+            author_did,
+            back_lang: CardInput::lang(&self.back_lang),
+            back_text: self.back_text.clone(),
+            front_lang: CardInput::lang(&self.front_lang),
+            front_text: self.front_text.clone(),
+            kind: self.kind.clone(),
+            format: self.format.clone(),
+            front_image: self.front_image.clone(),
+            back_image: self.back_image.clone(),
+            audio: self.audio.clone(),
+            tags: self.tags(),
+            position: self.position,
+            stack_id: self.stack_id.clone(),
+            created_at,
+        }
+    }
+}
+
+/// A stack's cards in a new order, as submitted by the editor or the JSON API
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CardOrder {
+    /// Card uris, one a line from the editor, or a list from the JSON API
+    #[serde(deserialize_with = "deserialize_uris")]
+    pub cards: Vec<String>,
+}
+
+/// Card writes on behalf of `did`
+pub struct CardService<'a> {
+    pds: &'a dyn Pds,
+    repo: &'a dyn Repo,
+    did: &'a Did,
+}
+
+impl<'a> CardService<'a> {
+    pub fn new(pds: &'a dyn Pds, repo: &'a dyn Repo, did: &'a Did) -> Self {
+        Self { pds, repo, did }
+    }
+
+    /// Whether `input` can be written, as a new card or over `card_uri`: it's valid, and
+    /// the card and the stack it's going in are ours. Worth asking before uploading
+    /// anything for it.
+    pub async fn check(
+        &self,
+        card_uri: Option<&str>,
+        input: &CardInput,
+    ) -> Result<(), ServiceError> {
+        if let Some(error) = input.validate() {
+            return Err(ServiceError::Invalid(error));
+        }
+        let card_is_ours = match card_uri {
+            Some(card_uri) => self.repo.card_is_owned_by(self.did, card_uri).await?,
+            None => true,
+        };
+        // Both the card and the stack it's moving to, or staying in, have to be ours
+        if !card_is_ours
+            || !self
+                .repo
+                .stack_is_owned_by(self.did, &input.stack_id)
+                .await?
+        {
+            return Err(ServiceError::Forbidden);
+        }
+        Ok(())
+    }
+
+    pub async fn create(&self, input: &CardInput) -> Result<db::DbCard, ServiceError> {
+        self.check(None, input).await?;
+        let mut input = input.clone();
+        if input.position.is_none() {
+            input.position = Some(self.next_position(&input.stack_id).await?);
+        }
+        let now = Utc::now();
+        let uri = self
+            .pds
+            .create_record(self.did, Card::NSID, input.as_record(now)?)
+            .await?;
+        let card = db::DbCard::new(input.as_args(uri, self.did.to_string(), now));
+        if let Err(err) = self.repo.save_card(&card).await {
+            log::error!("error saving card in db, will ingest later {err}");
+        }
+        Ok(card)
+    }
+
+    pub async fn update(
+        &self,
+        card_uri: String,
+        input: &CardInput,
+    ) -> Result<db::DisplayCard, ServiceError> {
+        self.check(Some(&card_uri), input).await?;
+        let current = self
+            .repo
+            .get_owned_card(self.did, &card_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let mut input = input.clone();
+        if input.position.is_none() {
+            input.position = if current.stack_id == input.stack_id {
+                current.position
+            } else {
+                Some(self.next_position(&input.stack_id).await?)
+            };
+        }
+        let rkey = rkey_from_uri(&card_uri)?;
+        self.pds
+            .put_record(
+                self.did,
+                Card::NSID,
+                rkey,
+                input.as_record(current.created_at)?,
+            )
+            .await?;
+        let args = input.as_update_args(card_uri, self.did.to_string(), current.created_at);
+        match self.repo.update_owned_card(&args).await {
+            Ok(Some(card)) => Ok(card),
+            Ok(None) => Err(ServiceError::NotFound),
+            Err(err) => {
+                log::error!("error updating card in db, will ingest later {err}");
+                Ok(args.into())
+            }
+        }
+    }
+
+    /// Where a card added to the stack goes: after the rest. Cards from before stacks were
+    /// ordered are given positions first, in the order they're shown in, so there's an
+    /// after for it to go.
+    async fn next_position(&self, stack_uri: &str) -> Result<i32, ServiceError> {
+        let mut cards = self.repo.stack_cards(stack_uri).await?;
+        if cards.iter().any(|card| card.position.is_none()) {
+            cards = self.reorder(stack_uri, &[]).await?;
+        }
+        Ok(cards
+            .iter()
+            .filter_map(|card| card.position)
+            .max()
+            .map_or(0, |p| p + 1))
+    }
+
+    /// Puts the stack's cards in the order of `card_uris`, rewriting the records of those
+    /// that move in as few commits as there can be. Any of its cards left out go after the
+    /// rest, in the order they were.
+    pub async fn reorder(
+        &self,
+        stack_uri: &str,
+        card_uris: &[String],
+    ) -> Result<Vec<db::DisplayCard>, ServiceError> {
+        if !self.repo.stack_is_owned_by(self.did, stack_uri).await? {
+            return Err(ServiceError::Forbidden);
+        }
+        let mut cards = self.repo.stack_cards(stack_uri).await?;
+        if let Some(uri) = card_uris
+            .iter()
+            .find(|uri| !cards.iter().any(|card| &card.uri == *uri))
+        {
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-stack-card").arg("uri", uri),
+            ));
+        }
+        cards.sort_by_key(|card| {
+            card_uris
+                .iter()
+                .position(|uri| uri == &card.uri)
+                .unwrap_or(card_uris.len())
+        });
+        let mut moved = Vec::new();
+        for (position, card) in (0..).zip(cards.iter_mut()) {
+            if card.position != Some(position) {
+                card.position = Some(position);
+                moved.push(card.clone());
+            }
+        }
+        let records = moved
+            .iter()
+            .map(|card| {
+                let input = CardInput::from_card(card.clone(), stack_uri);
+                Ok((rkey_from_uri(&card.uri)?, input.as_record(card.created_at)?))
+            })
+            .collect::<Result<Vec<_>, ServiceError>>()?;
+        self.pds.put_records(self.did, Card::NSID, records).await?;
+        for card in moved {
+            let (uri, created_at) = (card.uri.clone(), card.created_at);
+            let args = CardInput::from_card(card, stack_uri).as_update_args(
+                uri,
+                self.did.to_string(),
+                created_at,
+            );
+            if let Err(err) = self.repo.update_owned_card(&args).await {
+                log::error!("error reordering card in db, will ingest later {err}");
+            }
+        }
+        Ok(cards)
+    }
+
+    /// Uploads an image for a card to the author's repo, for a [`CardInput`] to refer to
+    pub async fn upload_image(&self, data: Vec<u8>) -> Result<BlobRef, ServiceError> {
+        CardService::check_image(&data)?;
+        Ok(self.pds.upload_blob(data).await?)
+    }
+
+    /// Whether `data` is an image a card can have
+    pub fn check_image(data: &[u8]) -> Result<(), ServiceError> {
+        if image_type(data).is_none() {
+            return Err(ServiceError::Invalid(Message::new("invalid-image-file")));
+        }
+        if data.len() > MAX_IMAGE_SIZE {
+            let max = MAX_IMAGE_SIZE / 1000;
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-image-size").arg("max", max),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Uploads a recording for a card to the author's repo, like [`CardService::upload_image`]
+    pub async fn upload_audio(&self, data: Vec<u8>) -> Result<BlobRef, ServiceError> {
+        CardService::check_audio(&data)?;
+        Ok(self.pds.upload_blob(data).await?)
+    }
+
+    /// Whether `data` is a recording a card can have
+    pub fn check_audio(data: &[u8]) -> Result<(), ServiceError> {
+        if audio_type(data).is_none() {
+            return Err(ServiceError::Invalid(Message::new("invalid-audio-file")));
+        }
+        if data.len() > MAX_AUDIO_SIZE {
+            let max = MAX_AUDIO_SIZE / 1_000_000;
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-audio-size").arg("max", max),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Generates `text` said in `lang` with `speech`, then uploads it like
+    /// [`CardService::upload_audio`]
+    pub async fn speak(
+        &self,
+        speech: &SpeechCache,
+        text: &str,
+        lang: &str,
+    ) -> Result<BlobRef, ServiceError> {
+        if text.trim().is_empty() {
+            return Err(ServiceError::Invalid(Message::new("invalid-speech-text")));
+        }
+        if text.chars().count() > MAX_SPEECH_LENGTH {
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-speech-length").arg("max", MAX_SPEECH_LENGTH),
+            ));
+        }
+        let Some(lang) = normalize_lang(lang) else {
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-speech-lang").arg("lang", lang),
+            ));
+        };
+        let data = speech.speak(text, &lang).await.map_err(|err| match err {
+            TtsError::NoVoice(lang) => {
+                ServiceError::Invalid(Message::new("invalid-speech-lang").arg("lang", lang))
+            }
+            err => err.into(),
+        })?;
+        self.upload_audio(data).await
+    }
+
+    /// Gives every card in the stack that has no audio some, spoken from `side`,
+    /// returning how many cards that was
+    pub async fn speak_stack(
+        &self,
+        speech: &SpeechCache,
+        stack_uri: &str,
+        side: Side,
+    ) -> Result<usize, ServiceError> {
+        if !self.repo.stack_is_owned_by(self.did, stack_uri).await? {
+            return Err(ServiceError::Forbidden);
+        }
+        let mut spoken = 0;
+        for card in self.repo.stack_cards(stack_uri).await? {
+            if card.audio.is_some() {
+                continue;
+            }
+            let uri = card.uri.clone();
+            let mut input = CardInput::from_card(card, stack_uri);
+            let (text, lang) = input.speech(side);
+            input.audio = Some(self.speak(speech, &text, &lang).await?);
+            self.update(uri, &input).await?;
+            spoken += 1;
+        }
+        Ok(spoken)
+    }
+
+    pub async fn delete(&self, card_uri: &str) -> Result<(), ServiceError> {
+        if !self.repo.card_is_owned_by(self.did, card_uri).await? {
+            return Err(ServiceError::Forbidden);
+        }
+        self.pds
+            .delete_record(self.did, Card::NSID, rkey_from_uri(card_uri)?)
+            .await?;
+        self.repo.delete_card(card_uri, None).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use super::{Cloned, Pds, ServiceError, StackService, rkey_from_uri};
+use crate::{
+    blobs::BlobCache,
+    collections::{self, MAX_DESCRIPTION_LENGTH, deserialize_uris},
+    db::{self, Repo},
+    i18n::Message,
+    lexicons::{
+        record::KnownRecord,
+        xyz::flatshcards::{self, collection},
+    },
+};
+use atrium_api::types::{
+    Collection as _,
+    string::{Datetime, Did},
+};
+use serde::{Deserialize, Serialize};
+
+/// Characters in a collection's label, at most
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// A collection as submitted by the editor or the JSON API
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionInput {
+    pub collection_label: String,
+    #[serde(default)]
+    pub description: String,
+    /// Stack uris in order, one a line from the editor, or a list from the JSON API
+    #[serde(default, deserialize_with = "deserialize_uris")]
+    pub stacks: Vec<String>,
+}
+
+impl CollectionInput {
+    pub fn validate(&self) -> Option<Message> {
+        let label = self.collection_label.trim();
+        if label.is_empty() {
+            Some(Message::new("invalid-collection-label"))
+        } else if label.chars().count() > MAX_LABEL_LENGTH {
+            Some(Message::new("invalid-label-length").arg("max", MAX_LABEL_LENGTH))
+        } else if self.description.trim().chars().count() > MAX_DESCRIPTION_LENGTH {
+            Some(Message::new("invalid-description-length").arg("max", MAX_DESCRIPTION_LENGTH))
+        } else {
+            collections::validate(&self.stacks)
+        }
+    }
+    fn to_args(&self, uri: String, author_did: String) -> db::CollectionArgs {
+        db::CollectionArgs {
+            uri,
+            author_did,
+            label: self.collection_label.trim().to_string(),
+            description: collections::description(&self.description),
+            stacks: collections::normalize(&self.stacks),
+        }
+    }
+    fn to_record(&self) -> KnownRecord {
+        collection::Collection {
+            label: self.collection_label.trim().to_string(),
+            description: collections::description(&self.description),
+            stacks: collections::normalize(&self.stacks),
+            created_at: Datetime::now(),
+        }
+        .into()
+    }
+}
+
+/// The indexed stacks of `collection`, in its order. Stacks that were deleted, or haven't
+/// been indexed yet, are left out.
+pub async fn collection_stacks(
+    repo: &dyn Repo,
+    collection: &db::CollectionDetails,
+) -> Result<Vec<db::StackDetails>, sqlx::Error> {
+    let mut stacks = repo.get_stacks(&collection.stacks).await?;
+    stacks.sort_by_key(|s| collection.stacks.iter().position(|uri| uri == &s.uri));
+    Ok(stacks)
+}
+
+/// Collection writes on behalf of `did`
+pub struct CollectionService<'a> {
+    pds: &'a dyn Pds,
+    repo: &'a dyn Repo,
+    did: &'a Did,
+}
+
+impl<'a> CollectionService<'a> {
+    pub fn new(pds: &'a dyn Pds, repo: &'a dyn Repo, did: &'a Did) -> Self {
+        Self { pds, repo, did }
+    }
+
+    pub async fn create(&self, input: &CollectionInput) -> Result<db::DbCollection, ServiceError> {
+        self.check(input).await?;
+        self.write(input).await
+    }
+
+    /// Writes `input` as a new collection, checked or not
+    async fn write(&self, input: &CollectionInput) -> Result<db::DbCollection, ServiceError> {
+        let uri = self
+            .pds
+            .create_record(self.did, flatshcards::Collection::NSID, input.to_record())
+            .await?;
+        let collection = db::DbCollection::new(input.to_args(uri, self.did.to_string()));
+        if let Err(err) = self.repo.save_collection(&collection).await {
+            log::error!("error saving collection in db, will ingest later {err}");
+        }
+        Ok(collection)
+    }
+
+    pub async fn update(
+        &self,
+        collection_uri: String,
+        input: &CollectionInput,
+    ) -> Result<db::CollectionDetails, ServiceError> {
+        self.check(input).await?;
+        if !self
+            .repo
+            .collection_is_owned_by(self.did, &collection_uri)
+            .await?
+        {
+            return Err(ServiceError::Forbidden);
+        }
+        let rkey = rkey_from_uri(&collection_uri)?;
+        self.pds
+            .put_record(
+                self.did,
+                flatshcards::Collection::NSID,
+                rkey,
+                input.to_record(),
+            )
+            .await?;
+        self.repo
+            .update_owned_collection(&input.to_args(collection_uri, self.did.to_string()))
+            .await?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    /// Deletes the collection, but none of its stacks
+    pub async fn delete(&self, collection_uri: &str) -> Result<(), ServiceError> {
+        if !self
+            .repo
+            .collection_is_owned_by(self.did, collection_uri)
+            .await?
+        {
+            return Err(ServiceError::Forbidden);
+        }
+        self.pds
+            .delete_record(
+                self.did,
+                flatshcards::Collection::NSID,
+                rkey_from_uri(collection_uri)?,
+            )
+            .await?;
+        self.repo.delete_collection(collection_uri, None).await?;
+        Ok(())
+    }
+
+    /// Copies any user's collection at `src_uri` into `did`'s repo, cloning each of its
+    /// stacks as [`StackService::clone_from`] does and keeping their order. The cards any
+    /// of them go without are all in what's returned. A stack that can't be copied at all
+    /// is left out, and all its cards counted as going without.
+    ///
+    /// The copies are only just written, so the collection isn't held up waiting for them
+    /// to be indexed as [`CollectionService::create`] would be.
+    pub async fn clone_from(
+        &self,
+        src_uri: &str,
+        blobs: &BlobCache,
+    ) -> Result<Cloned, ServiceError> {
+        let src = self
+            .repo
+            .get_collection(src_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let stack_service = StackService::new(self.pds, self.repo, self.did);
+        let mut stacks = Vec::new();
+        let mut failed = Vec::new();
+        for stack in collection_stacks(self.repo, &src).await? {
+            match stack_service.clone_from(&stack.uri, blobs).await {
+                Ok(cloned) => {
+                    stacks.push(cloned.uri);
+                    failed.extend(cloned.failed);
+                }
+                Err(err) => {
+                    log::error!("error cloning stack {}, leaving it out {err}", stack.uri);
+                    let cards = self.repo.stack_cards(&stack.uri).await?;
+                    failed.extend(cards.into_iter().map(|card| card.front_text));
+                }
+            }
+        }
+        let input = CollectionInput {
+            collection_label: src.label,
+            description: src.description.unwrap_or_default(),
+            stacks,
+        };
+        if let Some(error) = input.validate() {
+            return Err(ServiceError::Invalid(error));
+        }
+        Ok(Cloned {
+            uri: self.write(&input).await?.uri,
+            failed,
+        })
+    }
+
+    /// Validates `input`, and that its stacks are all indexed
+    async fn check(&self, input: &CollectionInput) -> Result<(), ServiceError> {
+        if let Some(error) = input.validate() {
+            return Err(ServiceError::Invalid(error));
+        }
+        let stacks = collections::normalize(&input.stacks);
+        let found = self.repo.get_stacks(&stacks).await?;
+        match stacks
+            .iter()
+            .find(|uri| !found.iter().any(|s| &s.uri == *uri))
+        {
+            Some(uri) => Err(ServiceError::Invalid(
+                Message::new("invalid-stack-not-found").arg("uri", uri),
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use super::ServiceError;
+use crate::{
+    db::{self, Repo},
+    i18n::Message,
+    matching::{MatchBoard, board},
+};
+use atrium_api::types::string::Did;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A matching game in progress, kept in the player's session so the time it took, and
+/// whether every pair was really matched, are the server's to tell
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchStart {
+    pub stack_uri: String,
+    pub pairs: usize,
+    pub started_at: DateTime<Utc>,
+    /// See [`MatchBoard::answers`]
+    answers: Vec<usize>,
+    /// The fronts matched so far
+    matched: Vec<usize>,
+}
+
+impl MatchStart {
+    /// Matches the `front`th front with the `back`th back, if they're a pair
+    pub fn pair(&mut self, front: usize, back: usize) -> bool {
+        let right = self.answers.get(front) == Some(&back);
+        if right && !self.matched.contains(&front) {
+            self.matched.push(front);
+        }
+        right
+    }
+
+    pub fn is_cleared(&self) -> bool {
+        self.matched.len() == self.answers.len()
+    }
+}
+
+/// How a finished matching game went
+pub struct MatchFinish {
+    pub duration_ms: i64,
+    /// The best time before this one, which a new best beats
+    pub best: Option<db::MatchScore>,
+    pub new_best: bool,
+}
+
+/// Games on anyone's stacks. Scores are only kept for a player who's logged in.
+pub struct GameService<'a> {
+    repo: &'a dyn Repo,
+    did: Option<&'a Did>,
+}
+
+impl<'a> GameService<'a> {
+    pub fn new(repo: &'a dyn Repo, did: Option<&'a Did>) -> Self {
+        Self { repo, did }
+    }
+
+    async fn best(
+        &self,
+        stack_uri: &str,
+        pairs: usize,
+    ) -> Result<Option<db::MatchScore>, ServiceError> {
+        match self.did {
+            Some(did) => Ok(self
+                .repo
+                .best_match(did.as_str(), stack_uri, pairs as i32)
+                .await?),
+            None => Ok(None),
+        }
+    }
+
+    /// A new board of up to `pairs` pairs, with the player's best time for as many
+    pub async fn match_board(
+        &self,
+        stack_uri: &str,
+        pairs: usize,
+        now: DateTime<Utc>,
+    ) -> Result<
+        (
+            db::StackDetails,
+            MatchBoard,
+            MatchStart,
+            Option<db::MatchScore>,
+        ),
+        ServiceError,
+    > {
+        let stack = self
+            .repo
+            .get_stack(stack_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let cards = self.repo.stack_cards(stack_uri).await?;
+        let board = board(&cards, pairs);
+        let start = MatchStart {
+            stack_uri: stack_uri.to_string(),
+            pairs: board.pairs(),
+            started_at: now,
+            answers: board.answers(),
+            matched: Vec::new(),
+        };
+        let best = self.best(stack_uri, board.pairs()).await?;
+        Ok((stack, board, start, best))
+    }
+
+    /// The time `start` took, saved if it's the player's, as long as every pair's been
+    /// matched
+    pub async fn finish_match(
+        &self,
+        start: &MatchStart,
+        now: DateTime<Utc>,
+    ) -> Result<MatchFinish, ServiceError> {
+        if !start.is_cleared() {
+            return Err(ServiceError::Invalid(Message::new(
+                "error-match-unfinished",
+            )));
+        }
+        let duration_ms = (now - start.started_at).num_milliseconds().max(0);
+        let best = self.best(&start.stack_uri, start.pairs).await?;
+        let new_best = match self.did {
+            Some(did) => {
+                self.repo
+                    .save_match(&db::MatchScore {
+                        player_did: did.to_string(),
+                        stack_uri: start.stack_uri.clone(),
+                        pairs: start.pairs as i32,
+                        duration_ms,
+                        played_at: now,
+                    })
+                    .await?
+            }
+            None => false,
+        };
+        Ok(MatchFinish {
+            duration_ms,
+            best,
+            new_best,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{GameRepo, MemoryRepo};
+
+    #[actix_web::test]
+    async fn only_cleared_boards_are_timed() {
+        let repo = MemoryRepo::default();
+        let did = Did::new("did:plc:alice".to_string()).unwrap();
+        let games = GameService::new(&repo, Some(&did));
+        let now = Utc::now();
+        let mut start = MatchStart {
+            stack_uri: "at://did:plc:bob/xyz.flatshcards.stack/1".to_string(),
+            pairs: 2,
+            started_at: now,
+            answers: vec![1, 0],
+            matched: Vec::new(),
+        };
+        let finish = games.finish_match(&start, now).await;
+        assert!(matches!(finish, Err(ServiceError::Invalid(_))));
+
+        assert!(!start.pair(0, 0));
+        assert!(!start.pair(2, 1));
+        assert!(start.pair(0, 1));
+        assert!(start.pair(0, 1));
+        assert!(!start.is_cleared());
+        let finish = games.finish_match(&start, now).await;
+        assert!(matches!(finish, Err(ServiceError::Invalid(_))));
+        let best = repo.best_match(did.as_str(), &start.stack_uri, 2).await;
+        assert!(best.unwrap().is_none());
+
+        assert!(start.pair(1, 0));
+        assert!(start.is_cleared());
+        let finish = games
+            .finish_match(&start, now + chrono::Duration::seconds(5))
+            .await
+            .unwrap();
+        assert_eq!(finish.duration_ms, 5000);
+        assert!(finish.new_best);
+    }
+}
@@ -0,0 +1,51 @@
+//! The rules for writing stacks, cards and collections, independent of how the request
+//! arrived: validate the input, check the caller owns what they're changing, write to the
+//! author's PDS, then mirror the result locally so it shows up before the firehose does.
+pub mod cards;
+pub mod collections;
+pub mod games;
+pub mod pds;
+pub mod quizzes;
+pub mod reviews;
+pub mod stacks;
+pub mod stats;
+
+pub use cards::{CardInput, CardOrder, CardService, Side};
+pub use collections::{CollectionInput, CollectionService, collection_stacks};
+pub use games::{GameService, MatchStart};
+pub use pds::{Pds, PdsError};
+pub use quizzes::QuizService;
+pub use reviews::{
+    LimitsInput, ReviewAnswer, ReviewFilter, ReviewOptions, ReviewQueue, ReviewService, TypedAnswer,
+};
+pub use stacks::{Cloned, StackInput, StackService};
+pub use stats::StatsService;
+
+use crate::i18n::Message;
+use crate::tts::TtsError;
+use atrium_api::types::string::RecordKey;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ServiceError {
+    /// What was wrong with the input, for the route to translate
+    #[error("{0}")]
+    Invalid(Message),
+    #[error("Not found")]
+    NotFound,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("PDS error: {0}")]
+    Pds(#[from] PdsError),
+    #[error("Database error: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("{0}")]
+    Speech(#[from] TtsError),
+}
+
+/// Pulls the record key off the end of an `at://` uri
+pub fn rkey_from_uri(uri: &str) -> Result<RecordKey, ServiceError> {
+    uri.rsplit_once('/')
+        .and_then(|(_, rkey)| RecordKey::new(rkey.to_string()).ok())
+        .ok_or_else(|| ServiceError::Invalid(Message::new("invalid-record-uri").arg("uri", uri)))
+}
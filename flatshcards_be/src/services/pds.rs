@@ -0,0 +1,245 @@
+//! The slice of `com.atproto.repo` the services need from the author's PDS
+use crate::lexicons::record::KnownRecord;
+use async_trait::async_trait;
+use atrium_api::agent::{Agent, SessionManager};
+use atrium_api::com::atproto::repo::{apply_writes, create_record, delete_record, put_record};
+use atrium_api::types::{
+    BlobRef,
+    string::{Did, Nsid, RecordKey},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("{0}")]
+pub struct PdsError(pub String);
+
+#[async_trait]
+pub trait Pds: Send + Sync {
+    /// Creates a record in `repo`, returning the new record's uri
+    async fn create_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        record: KnownRecord,
+    ) -> Result<String, PdsError>;
+    async fn put_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        rkey: RecordKey,
+        record: KnownRecord,
+    ) -> Result<(), PdsError>;
+    async fn delete_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        rkey: RecordKey,
+    ) -> Result<(), PdsError>;
+    /// Creates every one of `records` in `repo`, in as few commits as the PDS allows,
+    /// returning the new records' uris in order
+    async fn create_records(
+        &self,
+        repo: &Did,
+        collection: &str,
+        records: Vec<KnownRecord>,
+    ) -> Result<Vec<String>, PdsError>;
+    /// Puts every one of `records` in `repo`, each over the one with its rkey, in as few
+    /// commits as the PDS allows
+    async fn put_records(
+        &self,
+        repo: &Did,
+        collection: &str,
+        records: Vec<(RecordKey, KnownRecord)>,
+    ) -> Result<(), PdsError>;
+    /// Uploads a blob to the session's repo, for a record to reference
+    async fn upload_blob(&self, data: Vec<u8>) -> Result<BlobRef, PdsError>;
+}
+
+/// The most writes a PDS takes in one `applyWrites`
+pub const MAX_WRITES: usize = 200;
+
+/// Applies `writes` to `repo`, [`MAX_WRITES`] to a commit, returning their results in order
+async fn apply_all<M>(
+    agent: &Agent<M>,
+    repo: &Did,
+    mut writes: Vec<apply_writes::InputWritesItem>,
+) -> Result<Vec<apply_writes::OutputResultsItem>, PdsError>
+where
+    M: SessionManager + Send + Sync,
+{
+    let mut results = Vec::with_capacity(writes.len());
+    while !writes.is_empty() {
+        let rest = writes.split_off(writes.len().min(MAX_WRITES));
+        let output = agent
+            .api
+            .com
+            .atproto
+            .repo
+            .apply_writes(
+                apply_writes::InputData {
+                    repo: repo.clone().into(),
+                    swap_commit: None,
+                    validate: None,
+                    writes,
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| PdsError(err.to_string()))?;
+        results.extend(output.data.results.unwrap_or_default());
+        writes = rest;
+    }
+    Ok(results)
+}
+
+#[async_trait]
+impl<M> Pds for Agent<M>
+where
+    M: SessionManager + Send + Sync,
+{
+    async fn create_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        record: KnownRecord,
+    ) -> Result<String, PdsError> {
+        self.api
+            .com
+            .atproto
+            .repo
+            .create_record(
+                create_record::InputData {
+                    collection: collection.parse().map_err(|e: &str| PdsError(e.into()))?,
+                    repo: repo.clone().into(),
+                    rkey: None,
+                    record: record.into(),
+                    swap_commit: None,
+                    validate: None,
+                }
+                .into(),
+            )
+            .await
+            .map(|output| output.data.uri)
+            .map_err(|err| PdsError(err.to_string()))
+    }
+    async fn put_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        rkey: RecordKey,
+        record: KnownRecord,
+    ) -> Result<(), PdsError> {
+        self.api
+            .com
+            .atproto
+            .repo
+            .put_record(
+                put_record::InputData {
+                    collection: collection.parse().map_err(|e: &str| PdsError(e.into()))?,
+                    record: record.into(),
+                    repo: repo.clone().into(),
+                    rkey,
+                    swap_commit: None,
+                    swap_record: None,
+                    validate: None,
+                }
+                .into(),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|err| PdsError(err.to_string()))
+    }
+    async fn delete_record(
+        &self,
+        repo: &Did,
+        collection: &str,
+        rkey: RecordKey,
+    ) -> Result<(), PdsError> {
+        self.api
+            .com
+            .atproto
+            .repo
+            .delete_record(
+                delete_record::InputData {
+                    collection: collection.parse().map_err(|e: &str| PdsError(e.into()))?,
+                    repo: repo.clone().into(),
+                    rkey,
+                    swap_commit: None,
+                    swap_record: None,
+                }
+                .into(),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|err| PdsError(err.to_string()))
+    }
+    async fn create_records(
+        &self,
+        repo: &Did,
+        collection: &str,
+        records: Vec<KnownRecord>,
+    ) -> Result<Vec<String>, PdsError> {
+        let collection: Nsid = collection.parse().map_err(|e: &str| PdsError(e.into()))?;
+        let count = records.len();
+        let writes = records
+            .into_iter()
+            .map(|record| {
+                apply_writes::InputWritesItem::Create(Box::new(
+                    apply_writes::CreateData {
+                        collection: collection.clone(),
+                        rkey: None,
+                        value: record.into(),
+                    }
+                    .into(),
+                ))
+            })
+            .collect();
+        let uris: Vec<String> = apply_all(self, repo, writes)
+            .await?
+            .into_iter()
+            .filter_map(|result| match result {
+                apply_writes::OutputResultsItem::CreateResult(created) => Some(created.data.uri),
+                _ => None,
+            })
+            .collect();
+        if uris.len() != count {
+            return Err(PdsError(format!(
+                "created {count} records but got {} uris back",
+                uris.len()
+            )));
+        }
+        Ok(uris)
+    }
+    async fn put_records(
+        &self,
+        repo: &Did,
+        collection: &str,
+        records: Vec<(RecordKey, KnownRecord)>,
+    ) -> Result<(), PdsError> {
+        let collection: Nsid = collection.parse().map_err(|e: &str| PdsError(e.into()))?;
+        let writes = records
+            .into_iter()
+            .map(|(rkey, record)| {
+                apply_writes::InputWritesItem::Update(Box::new(
+                    apply_writes::UpdateData {
+                        collection: collection.clone(),
+                        rkey,
+                        value: record.into(),
+                    }
+                    .into(),
+                ))
+            })
+            .collect();
+        apply_all(self, repo, writes).await.map(|_| ())
+    }
+    async fn upload_blob(&self, data: Vec<u8>) -> Result<BlobRef, PdsError> {
+        self.api
+            .com
+            .atproto
+            .repo
+            .upload_blob(data)
+            .await
+            .map(|output| output.data.blob)
+            .map_err(|err| PdsError(err.to_string()))
+    }
+}
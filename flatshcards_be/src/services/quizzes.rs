@@ -0,0 +1,79 @@
+use super::ServiceError;
+use crate::{
+    db::{self, Repo},
+    quiz::{CHOICES, Question, QuizResult, QuizScore, questions},
+};
+use std::collections::{HashMap, HashSet};
+
+/// Cards of the same languages looked at for distractors, when a stack is short of them
+const POOL_SIZE: i64 = 50;
+
+/// Quizzes on anyone's stacks, by anyone: nothing about them is kept
+pub struct QuizService<'a> {
+    repo: &'a dyn Repo,
+}
+
+impl<'a> QuizService<'a> {
+    pub fn new(repo: &'a dyn Repo) -> Self {
+        Self { repo }
+    }
+
+    async fn stack(&self, stack_uri: &str) -> Result<db::StackDetails, ServiceError> {
+        self.repo
+            .get_stack(stack_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    pub async fn quiz(
+        &self,
+        stack_uri: &str,
+    ) -> Result<(db::StackDetails, Vec<Question>), ServiceError> {
+        let stack = self.stack(stack_uri).await?;
+        let cards = self.repo.stack_cards(stack_uri).await?;
+        let backs: HashSet<&str> = cards
+            .iter()
+            .filter(|c| !c.is_cloze())
+            .map(|c| c.back_text.as_str())
+            .collect();
+        let pool = match (&stack.front_lang, &stack.back_lang) {
+            (Some(front), Some(back)) if backs.len() < CHOICES => {
+                self.repo.lang_pair_cards(front, back, POOL_SIZE).await?
+            }
+            _ => Vec::new(),
+        };
+        let questions = questions(&cards, &pool);
+        Ok((stack, questions))
+    }
+
+    /// Scores a quiz from its submitted form: `question` fields give the cards asked, in
+    /// order, and `answer:<card uri>` fields the back picked for each
+    pub async fn score(
+        &self,
+        stack_uri: &str,
+        form: &[(String, String)],
+    ) -> Result<(db::StackDetails, QuizScore), ServiceError> {
+        let stack = self.stack(stack_uri).await?;
+        let mut cards: HashMap<String, db::DisplayCard> = self
+            .repo
+            .stack_cards(stack_uri)
+            .await?
+            .into_iter()
+            .map(|c| (c.uri.clone(), c))
+            .collect();
+        let answers: HashMap<&str, &str> = form
+            .iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix("answer:")?, v.as_str())))
+            .collect();
+        let results = form
+            .iter()
+            .filter(|(k, _)| k == "question")
+            .filter_map(|(_, uri)| cards.remove(uri))
+            .map(|card| QuizResult {
+                chosen: answers.get(card.uri.as_str()).map(|a| a.to_string()),
+                card,
+            })
+            .collect();
+        Ok((stack, QuizScore { results }))
+    }
+}
@@ -0,0 +1,459 @@
+use super::ServiceError;
+use crate::{
+    answer::{Checked, check},
+    db::{self, Repo},
+    i18n::Message,
+    review::{
+        DEFAULT_NEW_PER_DAY, DEFAULT_REVIEWS_PER_DAY, DIRECTION_FORWARD, Grade, MAX_PER_DAY,
+        ReviewItem, Schedule, directions_of, due_items, shuffle_new, sort_items, within_limits,
+    },
+};
+use atrium_api::types::string::Did;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// The longest a single review counts for, so walking away from one doesn't count
+const MAX_REVIEW_MS: i64 = 5 * 60 * 1000;
+
+/// A grade for one review item, as submitted by the review page
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewAnswer {
+    pub stack_id: String,
+    pub card_uri: String,
+    pub direction: String,
+    #[serde(default)]
+    pub ordinal: i32,
+    pub grade: Grade,
+    /// When the item was shown, to tell how long it took
+    pub shown_at: Option<DateTime<Utc>>,
+}
+
+/// A typed answer to one review item, checked before it's graded
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedAnswer {
+    pub stack_id: String,
+    pub card_uri: String,
+    pub direction: String,
+    #[serde(default)]
+    pub ordinal: i32,
+    pub typed: String,
+    /// When the item was shown, carried on to the grade
+    pub shown_at: Option<DateTime<Utc>>,
+}
+
+/// How items are answered on the review page
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewMode {
+    /// Recalled, then revealed
+    #[default]
+    Flip,
+    /// Typed, then checked
+    Typed,
+}
+
+/// Which order new items come in on the review page
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NewOrder {
+    /// The order of the cards in their stack, stack by stack
+    #[default]
+    InOrder,
+    /// Shuffled, the same way all day
+    Shuffled,
+}
+
+/// The review page's settings, kept in its query string
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewOptions {
+    #[serde(default)]
+    pub mode: ReviewMode,
+    /// Whether typed answers can leave accents off
+    #[serde(default)]
+    pub ignore_accents: bool,
+    #[serde(default)]
+    pub new_order: NewOrder,
+}
+
+impl ReviewOptions {
+    pub fn is_typed(&self) -> bool {
+        self.mode == ReviewMode::Typed
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.new_order == NewOrder::Shuffled
+    }
+
+    /// The options as a query string, to keep them from one item to the next
+    pub fn query(&self) -> String {
+        let mode = match self.mode {
+            ReviewMode::Flip => "flip",
+            ReviewMode::Typed => "typed",
+        };
+        let new_order = match self.new_order {
+            NewOrder::InOrder => "inOrder",
+            NewOrder::Shuffled => "shuffled",
+        };
+        format!(
+            "mode={mode}&ignoreAccents={}&newOrder={new_order}",
+            self.ignore_accents
+        )
+    }
+}
+
+/// Which stacks and cards a review session takes in, kept in its query string. Without
+/// `session` it's the review of a single stack, the one in the form.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewFilter {
+    #[serde(default)]
+    pub session: bool,
+    /// Only stacks from this language, if it's not empty
+    #[serde(default)]
+    pub front_lang: Option<String>,
+    /// Only stacks to this language, if it's not empty
+    #[serde(default)]
+    pub back_lang: Option<String>,
+    /// Only stacks with this tag, and cards with it from other stacks, if it's not empty
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl ReviewFilter {
+    pub fn front_lang(&self) -> Option<&str> {
+        self.front_lang.as_deref().filter(|l| !l.is_empty())
+    }
+
+    pub fn back_lang(&self) -> Option<&str> {
+        self.back_lang.as_deref().filter(|l| !l.is_empty())
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref().filter(|t| !t.trim().is_empty())
+    }
+
+    fn matches(&self, stack: &db::StackDetails) -> bool {
+        let lang_matches = |want: Option<&str>, have: &Option<String>| {
+            want.is_none_or(|w| have.as_deref() == Some(w))
+        };
+        lang_matches(self.front_lang(), &stack.front_lang)
+            && lang_matches(self.back_lang(), &stack.back_lang)
+    }
+
+    /// Which of `stack`'s `cards` are let in: all of them if the stack has the tag
+    fn cards(&self, stack: &db::StackDetails, cards: Vec<db::DisplayCard>) -> Vec<db::DisplayCard> {
+        match self.tag() {
+            Some(tag) if !stack.has_tag(tag) => {
+                cards.into_iter().filter(|card| card.has_tag(tag)).collect()
+            }
+            _ => cards,
+        }
+    }
+}
+
+/// A reviewer's daily limits, as submitted by the review page
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitsInput {
+    pub new_per_day: i32,
+    pub reviews_per_day: i32,
+}
+
+/// What's left to review, today, in a stack or a session
+pub struct ReviewQueue {
+    /// Nothing for a session over several stacks
+    pub stack: Option<db::StackDetails>,
+    /// Due items first, then new ones
+    pub items: Vec<ReviewItem>,
+    pub due: usize,
+    pub new: usize,
+    /// Items left for another day by the reviewer's limits
+    pub held_back: usize,
+    pub limits: db::ReviewLimits,
+}
+
+impl ReviewQueue {
+    pub fn next(&self) -> Option<&ReviewItem> {
+        self.items.first()
+    }
+}
+
+/// Reviews by `did`, of anyone's stacks
+pub struct ReviewService<'a> {
+    repo: &'a dyn Repo,
+    did: &'a Did,
+}
+
+impl<'a> ReviewService<'a> {
+    pub fn new(repo: &'a dyn Repo, did: &'a Did) -> Self {
+        Self { repo, did }
+    }
+
+    pub async fn limits(&self) -> Result<db::ReviewLimits, ServiceError> {
+        Ok(self
+            .repo
+            .review_limits(self.did.as_str())
+            .await?
+            .unwrap_or_else(|| db::ReviewLimits {
+                reviewer_did: self.did.to_string(),
+                new_per_day: DEFAULT_NEW_PER_DAY,
+                reviews_per_day: DEFAULT_REVIEWS_PER_DAY,
+            }))
+    }
+
+    pub async fn save_limits(&self, input: &LimitsInput) -> Result<(), ServiceError> {
+        let valid = 0..=MAX_PER_DAY;
+        if !valid.contains(&input.new_per_day) || !valid.contains(&input.reviews_per_day) {
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-daily-limits").arg("max", MAX_PER_DAY),
+            ));
+        }
+        let limits = db::ReviewLimits {
+            reviewer_did: self.did.to_string(),
+            new_per_day: input.new_per_day,
+            reviews_per_day: input.reviews_per_day,
+        };
+        Ok(self.repo.save_review_limits(&limits).await?)
+    }
+
+    /// `items`, sorted and cut down to what the reviewer's limits leave them today. Days
+    /// are UTC days, as in the stats.
+    async fn limited(
+        &self,
+        stack: Option<db::StackDetails>,
+        mut items: Vec<ReviewItem>,
+        new_order: NewOrder,
+        now: DateTime<Utc>,
+    ) -> Result<ReviewQueue, ServiceError> {
+        let limits = self.limits().await?;
+        let midnight = now
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc();
+        let done = self.repo.review_counts(self.did.as_str(), midnight).await?;
+        sort_items(&mut items);
+        if new_order == NewOrder::Shuffled {
+            shuffle_new(&mut items, &format!("{} {midnight}", self.did.as_str()));
+        }
+        let (items, held_back) = within_limits(
+            items,
+            i64::from(limits.new_per_day) - done.new_items,
+            i64::from(limits.reviews_per_day) - done.reviews,
+        );
+        let new = items.iter().filter(|item| item.is_new()).count();
+        Ok(ReviewQueue {
+            due: items.len() - new,
+            new,
+            items,
+            held_back,
+            stack,
+            limits,
+        })
+    }
+
+    pub async fn queue(
+        &self,
+        stack_uri: &str,
+        new_order: NewOrder,
+        now: DateTime<Utc>,
+    ) -> Result<ReviewQueue, ServiceError> {
+        let stack = self
+            .repo
+            .get_stack(stack_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let cards = self.repo.stack_cards(stack_uri).await?;
+        let states = self
+            .repo
+            .stack_review_states(self.did.as_str(), stack_uri)
+            .await?;
+        let items = due_items(&stack, cards, states, now);
+        self.limited(Some(stack), items, new_order, now).await
+    }
+
+    /// What's due across the reviewer's own stacks and any they've reviewed in, as far
+    /// as `filter` lets in
+    pub async fn session(
+        &self,
+        filter: &ReviewFilter,
+        new_order: NewOrder,
+        now: DateTime<Utc>,
+    ) -> Result<ReviewQueue, ServiceError> {
+        let did = self.did.as_str();
+        let stacks: BTreeMap<String, db::StackDetails> = self
+            .repo
+            .user_stacks(did)
+            .await?
+            .into_iter()
+            .chain(self.repo.reviewed_stacks(did).await?)
+            .filter(|stack| filter.matches(stack))
+            .map(|stack| (stack.uri.clone(), stack))
+            .collect();
+        let mut states: HashMap<String, Vec<db::ReviewState>> = HashMap::new();
+        for db::StackedReviewState { state, stack_uri } in self.repo.reviewer_states(did).await? {
+            states.entry(stack_uri).or_default().push(state);
+        }
+        let mut items = Vec::new();
+        for stack in stacks.values() {
+            let cards = filter.cards(stack, self.repo.stack_cards(&stack.uri).await?);
+            let states = states.remove(&stack.uri).unwrap_or_default();
+            items.extend(due_items(stack, cards, states, now));
+        }
+        self.limited(None, items, new_order, now).await
+    }
+
+    /// The queue grading goes back to: the session's, or the stack's
+    async fn queue_for(
+        &self,
+        stack_uri: &str,
+        filter: &ReviewFilter,
+        new_order: NewOrder,
+        now: DateTime<Utc>,
+    ) -> Result<ReviewQueue, ServiceError> {
+        if filter.session {
+            self.session(filter, new_order, now).await
+        } else {
+            self.queue(stack_uri, new_order, now).await
+        }
+    }
+
+    /// One of the stack's review items, with the reviewer's schedule for it
+    async fn item(
+        &self,
+        stack_uri: &str,
+        card_uri: &str,
+        direction: &str,
+        ordinal: i32,
+    ) -> Result<ReviewItem, ServiceError> {
+        let stack = self
+            .repo
+            .get_stack(stack_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let card = self
+            .repo
+            .stack_cards(stack_uri)
+            .await?
+            .into_iter()
+            .find(|card| card.uri == card_uri)
+            .ok_or(ServiceError::NotFound)?;
+        let valid = if card.is_cloze() {
+            direction == DIRECTION_FORWARD
+                && card
+                    .cloze_items()
+                    .iter()
+                    .any(|item| item.ordinal as i32 == ordinal)
+        } else {
+            ordinal == 0 && directions_of(&stack.directions).contains(&direction)
+        };
+        if !valid {
+            return Err(ServiceError::Invalid(
+                Message::new("invalid-review-item")
+                    .arg("direction", direction)
+                    .arg("ordinal", ordinal),
+            ));
+        }
+        let state = self
+            .repo
+            .stack_review_states(self.did.as_str(), stack_uri)
+            .await?
+            .into_iter()
+            .find(|s| s.card_uri == card_uri && s.direction == direction && s.ordinal == ordinal);
+        Ok(ReviewItem {
+            stack_uri: stack.uri,
+            card,
+            direction: direction.to_string(),
+            ordinal,
+            state,
+        })
+    }
+
+    /// Checks a typed answer, for the learner to grade themselves with the suggestion
+    pub async fn check(
+        &self,
+        answer: &TypedAnswer,
+        filter: &ReviewFilter,
+        options: ReviewOptions,
+        now: DateTime<Utc>,
+    ) -> Result<(ReviewQueue, ReviewItem, Checked), ServiceError> {
+        let item = self
+            .item(
+                &answer.stack_id,
+                &answer.card_uri,
+                &answer.direction,
+                answer.ordinal,
+            )
+            .await?;
+        let checked = check(
+            &item.expected(),
+            &answer.typed,
+            item.answer_lang(),
+            options.ignore_accents,
+        );
+        let queue = self
+            .queue_for(&answer.stack_id, filter, options.new_order, now)
+            .await?;
+        Ok((queue, item, checked))
+    }
+
+    /// Records `answer` and reschedules its item, then returns what's left. An item that
+    /// isn't due, say one graded twice by resending the form, is left as it was.
+    pub async fn grade(
+        &self,
+        answer: &ReviewAnswer,
+        filter: &ReviewFilter,
+        new_order: NewOrder,
+        now: DateTime<Utc>,
+    ) -> Result<ReviewQueue, ServiceError> {
+        let stack_uri = &answer.stack_id;
+        let prev = self
+            .item(
+                stack_uri,
+                &answer.card_uri,
+                &answer.direction,
+                answer.ordinal,
+            )
+            .await?
+            .state
+            .map(|s| s.schedule());
+        if prev.as_ref().is_some_and(|s| s.due_at > now) {
+            return self.queue_for(stack_uri, filter, new_order, now).await;
+        }
+        let did = self.did.to_string();
+        let schedule = Schedule::next(prev.as_ref(), answer.grade, now);
+        let duration_ms = answer
+            .shown_at
+            .map(|shown| (now - shown).num_milliseconds().clamp(0, MAX_REVIEW_MS))
+            .unwrap_or_default();
+        let state = db::ReviewState {
+            reviewer_did: did.clone(),
+            card_uri: answer.card_uri.clone(),
+            direction: answer.direction.clone(),
+            ordinal: answer.ordinal,
+            ease: schedule.ease,
+            interval_days: schedule.interval_days,
+            reps: schedule.reps,
+            lapses: schedule.lapses,
+            due_at: schedule.due_at,
+            reviewed_at: now,
+        };
+        let review = db::Review {
+            reviewer_did: did,
+            card_uri: answer.card_uri.clone(),
+            direction: answer.direction.clone(),
+            ordinal: answer.ordinal,
+            grade: answer.grade.as_str().to_string(),
+            interval_days: schedule.interval_days,
+            duration_ms,
+            reviewed_at: now,
+        };
+        self.repo.save_review(&state, &review).await?;
+        // Anything forgotten is due again a few minutes from now, so comes back later on
+        self.queue_for(stack_uri, filter, new_order, now).await
+    }
+}
@@ -0,0 +1,425 @@
+use super::{CardService, Pds, ServiceError, pds::MAX_WRITES, rkey_from_uri};
+use crate::{
+    blobs::{BlobCache, blob_cid},
+    db::{self, Repo},
+    i18n::Message,
+    lang::{is_lang, normalize_lang},
+    lexicons::{
+        record::KnownRecord,
+        xyz::flatshcards::{Card, Stack, card, stack},
+    },
+    review::{DIRECTION_FORWARD, is_directions},
+    stacks,
+    tags::{self, deserialize_tags},
+};
+use atrium_api::types::{
+    BlobRef, Collection,
+    string::{Datetime, Did},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// How many times cloning tries to create each batch of cards before giving up on it
+pub const CLONE_ATTEMPTS: u32 = 3;
+
+/// What [`StackService::clone_from`] and [`super::CollectionService::clone_from`] made
+#[derive(Debug)]
+pub struct Cloned {
+    pub uri: String,
+    /// The front text of every card that couldn't be copied, which the copy goes without
+    pub failed: Vec<String>,
+}
+
+/// A stack as submitted by the editor or the JSON API
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StackInput {
+    pub back_lang: Option<String>,
+    pub front_lang: Option<String>,
+    pub stack_label: String,
+    #[serde(default = "StackInput::forward")]
+    pub directions: String,
+    /// Separated by commas from the editor, or a list from the JSON API
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// What the stack is for, where its cards came from, and whether others may reuse
+    /// them; blank for none. See [`crate::stacks`].
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub license: String,
+    #[serde(default)]
+    pub difficulty: String,
+    /// A ref to a blob already uploaded with [`StackService::upload_cover`]
+    #[serde(default)]
+    pub cover: Option<BlobRef>,
+}
+
+impl StackInput {
+    /// The "(None)" option in the language selects submits an empty string.
+    /// Language tags are stored in canonical case.
+    fn lang(lang: &Option<String>) -> Option<String> {
+        lang.clone()
+            .filter(|l| !l.is_empty())
+            .map(|l| normalize_lang(&l).unwrap_or(l))
+    }
+    fn lang_valid(lang: &Option<String>) -> bool {
+        if let Some(ref l) = StackInput::lang(lang) {
+            is_lang(l)
+        } else {
+            true
+        }
+    }
+    pub fn validate(&self) -> Option<Message> {
+        if !StackInput::lang_valid(&self.front_lang) {
+            let l = self.front_lang.as_deref().unwrap_or_default();
+            Some(Message::new("invalid-front-lang").arg("lang", l))
+        } else if !StackInput::lang_valid(&self.back_lang) {
+            let l = self.back_lang.as_deref().unwrap_or_default();
+            Some(Message::new("invalid-back-lang").arg("lang", l))
+        } else if !is_directions(&self.directions) {
+            let d = &self.directions;
+            Some(Message::new("invalid-directions").arg("directions", d))
+        } else {
+            tags::validate(&self.tags).or_else(|| {
+                stacks::validate(
+                    &self.description,
+                    &self.source,
+                    &self.license,
+                    &self.difficulty,
+                )
+            })
+        }
+    }
+    fn forward() -> String {
+        DIRECTION_FORWARD.to_string()
+    }
+    fn tags(&self) -> Vec<String> {
+        tags::normalize(&self.tags)
+    }
+    fn to_args(&self, uri: String, author_did: String) -> db::StackArgs {
+        db::StackArgs {
+            uri,
+            author_did,
+            back_lang: StackInput::lang(&self.back_lang),
+            front_lang: StackInput::lang(&self.front_lang),
+            label: self.stack_label.clone(),
+            directions: self.directions.clone(),
+            description: stacks::text(&self.description),
+            source: stacks::text(&self.source),
+            license: stacks::text(&self.license),
+            difficulty: stacks::text(&self.difficulty),
+            cover: self.cover.clone(),
+            tags: self.tags(),
+            indexed_at: None,
+        }
+    }
+    fn to_record(&self) -> KnownRecord {
+        stack::Stack {
+            back_lang: StackInput::lang(&self.back_lang),
+            front_lang: StackInput::lang(&self.front_lang),
+            label: self.stack_label.clone(),
+            directions: Some(self.directions.clone()),
+            description: stacks::text(&self.description),
+            source: stacks::text(&self.source),
+            license: stacks::text(&self.license),
+            difficulty: stacks::text(&self.difficulty),
+            cover: self.cover.clone(),
+            tags: tags::record_tags(self.tags()),
+            created_at: Datetime::now(),
+        }
+        .into()
+    }
+    fn to_update_args(&self, uri: String, author_did: String) -> db::StackUpdateArgs {
+        db::StackUpdateArgs {
+            uri,
+            author_did,
+            back_lang: StackInput::lang(&self.back_lang),
+            front_lang: StackInput::lang(&self.front_lang),
+            label: self.stack_label.clone(),
+            directions: self.directions.clone(),
+            description: stacks::text(&self.description),
+            source: stacks::text(&self.source),
+            license: stacks::text(&self.license),
+            difficulty: stacks::text(&self.difficulty),
+            cover: self.cover.clone(),
+            tags: self.tags(),
+        }
+    }
+}
+
+/// Stack writes on behalf of `did`
+pub struct StackService<'a> {
+    pds: &'a dyn Pds,
+    repo: &'a dyn Repo,
+    did: &'a Did,
+}
+
+impl<'a> StackService<'a> {
+    pub fn new(pds: &'a dyn Pds, repo: &'a dyn Repo, did: &'a Did) -> Self {
+        Self { pds, repo, did }
+    }
+
+    /// Whether `input` can be written, as a new stack or over `stack_uri`: it's valid, and
+    /// the stack is ours. Worth asking before uploading a cover for it.
+    pub async fn check(
+        &self,
+        stack_uri: Option<&str>,
+        input: &StackInput,
+    ) -> Result<(), ServiceError> {
+        if let Some(error) = input.validate() {
+            return Err(ServiceError::Invalid(error));
+        }
+        let stack_is_ours = match stack_uri {
+            Some(stack_uri) => self.repo.stack_is_owned_by(self.did, stack_uri).await?,
+            None => true,
+        };
+        if !stack_is_ours {
+            return Err(ServiceError::Forbidden);
+        }
+        Ok(())
+    }
+
+    pub async fn create(&self, input: &StackInput) -> Result<db::DbStack, ServiceError> {
+        self.check(None, input).await?;
+        let uri = self
+            .pds
+            .create_record(self.did, Stack::NSID, input.to_record())
+            .await?;
+        let stack = db::DbStack::new(input.to_args(uri, self.did.to_string()));
+        if let Err(err) = self.repo.save_stack(&stack).await {
+            log::error!("error saving stack in db, will ingest later {err}");
+        }
+        Ok(stack)
+    }
+
+    pub async fn update(
+        &self,
+        stack_uri: String,
+        input: &StackInput,
+    ) -> Result<db::StackDetails, ServiceError> {
+        self.check(Some(&stack_uri), input).await?;
+        let rkey = rkey_from_uri(&stack_uri)?;
+        self.pds
+            .put_record(self.did, Stack::NSID, rkey, input.to_record())
+            .await?;
+        self.repo
+            .update_owned_stack(&input.to_update_args(stack_uri, self.did.to_string()))
+            .await?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    /// Uploads a cover picture to the author's repo, with the same limits as a card's images
+    pub async fn upload_cover(&self, data: Vec<u8>) -> Result<BlobRef, ServiceError> {
+        CardService::new(self.pds, self.repo, self.did)
+            .upload_image(data)
+            .await
+    }
+
+    /// Deletes the stack; its cards go with it locally through the foreign key
+    pub async fn delete(&self, stack_uri: &str) -> Result<(), ServiceError> {
+        if !self.repo.stack_is_owned_by(self.did, stack_uri).await? {
+            return Err(ServiceError::Forbidden);
+        }
+        self.pds
+            .delete_record(self.did, Stack::NSID, rkey_from_uri(stack_uri)?)
+            .await?;
+        self.repo.delete_stack(stack_uri, None).await?;
+        Ok(())
+    }
+
+    /// Copies any user's stack at `src_uri`, and all of its cards, into `did`'s repo.
+    /// Someone else's cover, and the images and audio on their cards, are fetched through
+    /// `blobs` and uploaded again, since a record can only use blobs from its own repo.
+    pub async fn clone_from(
+        &self,
+        src_uri: &str,
+        blobs: &BlobCache,
+    ) -> Result<Cloned, ServiceError> {
+        let db::StackCloneData {
+            back_lang,
+            front_lang,
+            label,
+            directions,
+            description,
+            source,
+            license,
+            difficulty,
+            mut cover,
+            tags,
+        } = self
+            .repo
+            .stack_clone_data(src_uri)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let src_did = src_uri
+            .strip_prefix("at://")
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or_default();
+        if src_did != self.did.as_str() {
+            cover = self.copy_blob(src_did, cover, blobs).await;
+        }
+        let record: KnownRecord = stack::Stack {
+            back_lang: back_lang.clone(),
+            front_lang: front_lang.clone(),
+            label: label.clone(),
+            directions: Some(directions.clone()),
+            description: description.clone(),
+            source: source.clone(),
+            license: license.clone(),
+            difficulty: difficulty.clone(),
+            cover: cover.clone(),
+            tags: tags::record_tags(tags.clone()),
+            created_at: Datetime::now(),
+        }
+        .into();
+        let new_stack_uri = self
+            .pds
+            .create_record(self.did, Stack::NSID, record)
+            .await?;
+        let new_stack = db::DbStack::new(db::StackArgs {
+            uri: new_stack_uri.clone(),
+            author_did: self.did.to_string(),
+            back_lang,
+            front_lang,
+            label,
+            directions,
+            description,
+            source,
+            license,
+            difficulty,
+            cover,
+            tags,
+            indexed_at: None,
+        });
+        if let Err(err) = self.repo.save_stack(&new_stack).await {
+            log::error!("error saving cloned stack in db, will ingest later {err}");
+        }
+        let cards = self.repo.card_clone_data(src_uri).await?;
+        let failed = self
+            .clone_cards(&new_stack_uri, cards, src_did, blobs)
+            .await?;
+        Ok(Cloned {
+            uri: new_stack_uri,
+            failed,
+        })
+    }
+
+    /// Creates copies of `cards` in the new stack, [`MAX_WRITES`] to a commit, returning the
+    /// front text of any that couldn't be
+    async fn clone_cards(
+        &self,
+        new_stack_uri: &str,
+        cards: Vec<db::CardCloneData>,
+        src_did: &str,
+        blobs: &BlobCache,
+    ) -> Result<Vec<String>, ServiceError> {
+        let mut failed = Vec::new();
+        let stack_rkey = rkey_from_uri(new_stack_uri)?;
+        let mut cards: Vec<(i32, db::CardCloneData)> = (0..).zip(cards).collect();
+        // Batches that fail go round again, up to CLONE_ATTEMPTS times, so each card keeps
+        // its place from the original
+        let mut work = VecDeque::new();
+        while !cards.is_empty() {
+            let rest = cards.split_off(cards.len().min(MAX_WRITES));
+            work.push_back((1, cards));
+            cards = rest;
+        }
+        while let Some((attempt, mut batch)) = work.pop_front() {
+            // Blobs copied on the first attempt are already ours
+            if attempt == 1 && src_did != self.did.as_str() {
+                for (_, clone_data) in batch.iter_mut() {
+                    clone_data.front_image = self
+                        .copy_blob(src_did, clone_data.front_image.take(), blobs)
+                        .await;
+                    clone_data.back_image = self
+                        .copy_blob(src_did, clone_data.back_image.take(), blobs)
+                        .await;
+                    clone_data.audio = self
+                        .copy_blob(src_did, clone_data.audio.take(), blobs)
+                        .await;
+                }
+            }
+            let now = Datetime::now();
+            let records = batch
+                .iter()
+                .map(|(position, clone_data)| {
+                    card::Card {
+                        back_lang: clone_data.back_lang.clone(),
+                        back_text: clone_data.back_text.clone(),
+                        created_at: now.clone(),
+                        front_lang: clone_data.front_lang.clone(),
+                        front_text: clone_data.front_text.clone(),
+                        kind: Some(clone_data.kind.clone()),
+                        format: Some(clone_data.format.clone()),
+                        front_image: clone_data.front_image.clone(),
+                        back_image: clone_data.back_image.clone(),
+                        audio: clone_data.audio.clone(),
+                        tags: tags::record_tags(clone_data.tags.clone()),
+                        position: Some(i64::from(*position)),
+                        stack_id: stack_rkey.clone(),
+                    }
+                    .into()
+                })
+                .collect();
+            match self.pds.create_records(self.did, Card::NSID, records).await {
+                Ok(uris) => {
+                    let indexed_at = Some(now.as_ref().to_utc());
+                    for (uri, (position, clone_data)) in uris.into_iter().zip(batch) {
+                        let card = db::DbCard::new(db::CardArgs {
+                            uri,
+                            author_did: self.did.to_string(),
+                            back_lang: clone_data.back_lang,
+                            back_text: clone_data.back_text,
+                            front_lang: clone_data.front_lang,
+                            front_text: clone_data.front_text,
+                            kind: clone_data.kind,
+                            format: clone_data.format,
+                            front_image: clone_data.front_image,
+                            back_image: clone_data.back_image,
+                            audio: clone_data.audio,
+                            tags: clone_data.tags,
+                            position: Some(position),
+                            indexed_at,
+                            stack_id: new_stack_uri.to_string(),
+                        });
+                        if let Err(err) = self.repo.save_card(&card).await {
+                            log::error!("error saving card in db, will ingest later {err}");
+                        };
+                    }
+                }
+                Err(err) if attempt < CLONE_ATTEMPTS => {
+                    log::error!("error saving cards in atmosphere, will try again {err}");
+                    work.push_back((attempt + 1, batch))
+                }
+                Err(err) => {
+                    log::error!("error saving cards in atmosphere, giving up on them {err}");
+                    failed.extend(batch.into_iter().map(|(_, card)| card.front_text));
+                }
+            }
+        }
+        Ok(failed)
+    }
+
+    /// `src_did`'s `blob`, uploaded to `did`'s repo. If that fails the copy goes without
+    /// it, rather than holding up the rest of the stack.
+    async fn copy_blob(
+        &self,
+        src_did: &str,
+        blob: Option<BlobRef>,
+        blobs: &BlobCache,
+    ) -> Option<BlobRef> {
+        let cid = blob_cid(&blob?);
+        let copied = match blobs.get(src_did, &cid).await {
+            Ok(data) => self.pds.upload_blob(data).await.map_err(|e| e.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        copied
+            .inspect_err(|err| {
+                log::error!("error copying blob {cid} from {src_did}, dropping it: {err}")
+            })
+            .ok()
+    }
+}
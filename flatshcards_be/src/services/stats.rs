@@ -0,0 +1,37 @@
+use super::ServiceError;
+use crate::{
+    db::Repo,
+    stats::{Stats, history_since, stats},
+};
+use atrium_api::types::string::Did;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+
+/// Stats on what `did` has studied
+pub struct StatsService<'a> {
+    repo: &'a dyn Repo,
+    did: &'a Did,
+}
+
+impl<'a> StatsService<'a> {
+    pub fn new(repo: &'a dyn Repo, did: &'a Did) -> Self {
+        Self { repo, did }
+    }
+
+    pub async fn stats(&self, now: DateTime<Utc>) -> Result<Stats, ServiceError> {
+        let history = self
+            .repo
+            .reviewer_history(self.did.as_str(), history_since(now))
+            .await?;
+        let states = self.repo.reviewer_states(self.did.as_str()).await?;
+        let stack_uris: Vec<String> = history
+            .iter()
+            .filter_map(|r| r.stack_uri.clone())
+            .chain(states.iter().map(|s| s.stack_uri.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let stacks = self.repo.get_stacks(&stack_uris).await?;
+        Ok(stats(&history, &states, &stacks, now))
+    }
+}
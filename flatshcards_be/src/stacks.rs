@@ -0,0 +1,111 @@
+//! What a stack says about itself besides its cards: what it's for, where the cards came
+//! from, whether others may reuse them, who it's pitched at, and a cover picture. All of it
+//! is optional, so stacks from before any of it could be given are as good as ever.
+use crate::i18n::Message;
+
+/// Characters in a stack's description, at most
+pub const MAX_DESCRIPTION_LENGTH: usize = 300;
+/// Characters in a stack's source, at most
+pub const MAX_SOURCE_LENGTH: usize = 200;
+/// Characters in a license identifier, at most
+pub const MAX_LICENSE_LENGTH: usize = 64;
+
+/// A license cards can be shared under
+pub struct License {
+    /// Its SPDX identifier, as records hold it
+    pub id: &'static str,
+    /// Its short name, which is the same in every language
+    pub name: &'static str,
+    pub url: &'static str,
+}
+
+/// The licenses the editor offers, all of which let anyone reuse the cards on their terms.
+/// Any other SPDX identifier is allowed too, and shown as it is.
+pub const LICENSES: &[License] = &[
+    License {
+        id: "CC0-1.0",
+        name: "CC0 1.0",
+        url: "https://creativecommons.org/publicdomain/zero/1.0/",
+    },
+    License {
+        id: "CC-BY-4.0",
+        name: "CC BY 4.0",
+        url: "https://creativecommons.org/licenses/by/4.0/",
+    },
+    License {
+        id: "CC-BY-SA-4.0",
+        name: "CC BY-SA 4.0",
+        url: "https://creativecommons.org/licenses/by-sa/4.0/",
+    },
+    License {
+        id: "CC-BY-NC-4.0",
+        name: "CC BY-NC 4.0",
+        url: "https://creativecommons.org/licenses/by-nc/4.0/",
+    },
+    License {
+        id: "CC-BY-NC-SA-4.0",
+        name: "CC BY-NC-SA 4.0",
+        url: "https://creativecommons.org/licenses/by-nc-sa/4.0/",
+    },
+];
+/// The author keeps every right to the cards, so they aren't to be reused
+pub const ALL_RIGHTS_RESERVED: &str = "all-rights-reserved";
+
+/// How hard a stack can say it is
+pub const DIFFICULTIES: &[&str] = &["beginner", "intermediate", "advanced"];
+
+/// One of [`LICENSES`], by its identifier
+pub fn license(id: &str) -> Option<&'static License> {
+    LICENSES.iter().find(|l| l.id == id)
+}
+
+/// Whether `id` is a license the editor doesn't offer, to offer it anyway for the stack
+/// that has it
+pub fn is_other_license(id: &str) -> bool {
+    !id.is_empty() && id != ALL_RIGHTS_RESERVED && license(id).is_none()
+}
+
+pub fn is_difficulty(difficulty: &str) -> bool {
+    DIFFICULTIES.contains(&difficulty)
+}
+
+/// Free text as it's stored, with nothing for a blank one
+pub fn text(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// `text` cut to `max` characters, for records from elsewhere that break the rules
+pub fn truncated(text: &str, max: usize) -> Option<String> {
+    self::text(text).map(|t| {
+        t.chars()
+            .take(max)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    })
+}
+
+/// Why a stack's description, source, license and difficulty can't be saved as they are,
+/// if they can't. Blank ones are left out, so they're always fine.
+pub fn validate(
+    description: &str,
+    source: &str,
+    license: &str,
+    difficulty: &str,
+) -> Option<Message> {
+    let license = license.trim();
+    let difficulty = difficulty.trim();
+    if description.trim().chars().count() > MAX_DESCRIPTION_LENGTH {
+        Some(Message::new("invalid-description-length").arg("max", MAX_DESCRIPTION_LENGTH))
+    } else if source.trim().chars().count() > MAX_SOURCE_LENGTH {
+        Some(Message::new("invalid-source-length").arg("max", MAX_SOURCE_LENGTH))
+    } else if license.chars().count() > MAX_LICENSE_LENGTH || license.contains(char::is_whitespace)
+    {
+        Some(Message::new("invalid-license").arg("license", license))
+    } else if !difficulty.is_empty() && !is_difficulty(difficulty) {
+        Some(Message::new("invalid-difficulty").arg("difficulty", difficulty))
+    } else {
+        None
+    }
+}
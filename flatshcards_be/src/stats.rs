@@ -0,0 +1,289 @@
+//! Study statistics, worked out from a reviewer's history and schedules. Days are UTC
+//! days, the same for everyone, since there's no telling where a reviewer is.
+use crate::db::{Review, ReviewState, StackDetails, StackedReview, StackedReviewState};
+use crate::review::Grade;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Days of history charted and counted, today included
+pub const HISTORY_DAYS: i64 = 30;
+/// Days the due forecast looks ahead, today included
+pub const FORECAST_DAYS: i64 = 30;
+/// Weeks in the streak calendar, which is as far back as streaks are counted
+pub const CALENDAR_WEEKS: i64 = 53;
+/// Items scheduled this many days apart or more are mature
+pub const MATURE_DAYS: i32 = 21;
+
+/// The first day in the streak calendar: a Monday, so the calendar ends with this week
+fn calendar_start(today: NaiveDate) -> NaiveDate {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+    monday - Duration::weeks(CALENDAR_WEEKS - 1)
+}
+
+/// How far back the history behind [`stats`] has to go, at `now`
+pub fn history_since(now: DateTime<Utc>) -> DateTime<Utc> {
+    calendar_start(now.date_naive())
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+}
+
+/// One day in a chart
+pub struct Day {
+    pub date: NaiveDate,
+    pub count: usize,
+    /// The height of its bar, as a percent of the chart's busiest day
+    pub percent: usize,
+    /// How dark it's shaded, from 0 for nothing to 4 for the busiest
+    pub level: usize,
+}
+
+fn chart(counts: impl Iterator<Item = (NaiveDate, usize)>) -> Vec<Day> {
+    let counts: Vec<(NaiveDate, usize)> = counts.collect();
+    let max = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    counts
+        .into_iter()
+        .map(|(date, count)| Day {
+            date,
+            count,
+            percent: (count * 100).checked_div(max).unwrap_or(0),
+            level: if max == 0 {
+                0
+            } else {
+                (count * 4).div_ceil(max)
+            },
+        })
+        .collect()
+}
+
+/// The figures for some part of what's been studied
+#[derive(Default)]
+pub struct Breakdown {
+    /// For a breakdown by stack
+    pub stack: Option<StackDetails>,
+    pub front_lang: Option<String>,
+    pub back_lang: Option<String>,
+    /// Reviews in the last [`HISTORY_DAYS`]
+    pub reviews: usize,
+    /// Of those, the ones of items that had already been learned...
+    pub recalls: usize,
+    /// ...and of those, the ones that weren't forgotten
+    pub remembered: usize,
+    pub duration_ms: i64,
+    /// Items still being learned, or relearned after being forgotten
+    pub learning: usize,
+    /// Items learned, but not yet [`MATURE_DAYS`] apart
+    pub young: usize,
+    pub mature: usize,
+    /// Items due by the end of today
+    pub due: usize,
+}
+
+impl Breakdown {
+    fn of_stack(stack: &StackDetails) -> Self {
+        Self {
+            stack: Some(stack.clone()),
+            front_lang: stack.front_lang.clone(),
+            back_lang: stack.back_lang.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn of_langs(stack: &StackDetails) -> Self {
+        Self {
+            front_lang: stack.front_lang.clone(),
+            back_lang: stack.back_lang.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// The percent of learned items that were remembered, if any were reviewed
+    pub fn retention(&self) -> Option<usize> {
+        (self.remembered * 100).checked_div(self.recalls)
+    }
+
+    /// Time spent reviewing, to the nearest minute
+    pub fn minutes(&self) -> i64 {
+        (self.duration_ms + 30_000) / 60_000
+    }
+
+    pub fn items(&self) -> usize {
+        self.learning + self.young + self.mature
+    }
+
+    /// A review in the last [`HISTORY_DAYS`], which was a recall if its item had been
+    /// `learned`
+    fn add_review(&mut self, review: &Review, learned: bool) {
+        self.reviews += 1;
+        self.duration_ms += review.duration_ms;
+        if learned {
+            self.recalls += 1;
+            if review.grade != Grade::Again.as_str() {
+                self.remembered += 1;
+            }
+        }
+    }
+
+    fn add_state(&mut self, state: &ReviewState, tomorrow: DateTime<Utc>) {
+        if state.reps == 0 || state.interval_days == 0 {
+            self.learning += 1;
+        } else if state.interval_days < MATURE_DAYS {
+            self.young += 1;
+        } else {
+            self.mature += 1;
+        }
+        if state.due_at < tomorrow {
+            self.due += 1;
+        }
+    }
+}
+
+/// Everything on the stats page
+pub struct Stats {
+    pub total: Breakdown,
+    pub reviews_today: usize,
+    /// Reviews a day for the last [`HISTORY_DAYS`], oldest first
+    pub per_day: Vec<Day>,
+    /// Items due a day for the next [`FORECAST_DAYS`], with anything overdue due today
+    pub forecast: Vec<Day>,
+    /// Reviews a day, a week at a time from Monday, up to today
+    pub calendar: Vec<Vec<Day>>,
+    /// Days in a row with reviews, up to today, or yesterday if there are none yet today
+    pub streak: usize,
+    pub longest_streak: usize,
+    /// Busiest first. Reviews of deleted cards only count towards the total.
+    pub stacks: Vec<Breakdown>,
+    pub lang_pairs: Vec<Breakdown>,
+}
+
+impl Stats {
+    pub fn is_empty(&self) -> bool {
+        self.calendar.iter().flatten().all(|day| day.count == 0) && self.total.items() == 0
+    }
+}
+
+fn busiest_first(breakdowns: impl Iterator<Item = Breakdown>) -> Vec<Breakdown> {
+    let mut breakdowns: Vec<Breakdown> = breakdowns.collect();
+    breakdowns.sort_by(|a, b| {
+        (b.reviews, b.items())
+            .cmp(&(a.reviews, a.items()))
+            .then_with(|| {
+                let label = |d: &Breakdown| d.stack.as_ref().map(|s| s.label.clone());
+                label(a).cmp(&label(b))
+            })
+    });
+    breakdowns
+}
+
+/// The stats at `now` from a reviewer's `history` since [`history_since`], their `states`,
+/// and the `stacks` either is in
+pub fn stats(
+    history: &[StackedReview],
+    states: &[StackedReviewState],
+    stacks: &[StackDetails],
+    now: DateTime<Utc>,
+) -> Stats {
+    let today = now.date_naive();
+    let tomorrow = (today + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+    let recent = today - Duration::days(HISTORY_DAYS - 1);
+    let stacks: HashMap<&str, &StackDetails> = stacks.iter().map(|s| (s.uri.as_str(), s)).collect();
+    let lang_key = |s: &StackDetails| (s.front_lang.clone(), s.back_lang.clone());
+
+    let mut total = Breakdown::default();
+    let mut by_stack: HashMap<&str, Breakdown> = HashMap::new();
+    let mut by_langs: BTreeMap<(Option<String>, Option<String>), Breakdown> = BTreeMap::new();
+    let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    // The interval each item was last scheduled, to tell whether it had been learned
+    let mut last_interval: HashMap<(&str, &str, i32), i32> = HashMap::new();
+    for StackedReview { review, stack_uri } in history {
+        let date = review.reviewed_at.date_naive();
+        *per_day.entry(date).or_default() += 1;
+        let key = (
+            review.card_uri.as_str(),
+            review.direction.as_str(),
+            review.ordinal,
+        );
+        let learned = last_interval
+            .insert(key, review.interval_days)
+            .is_some_and(|interval| interval > 0);
+        if date < recent {
+            continue;
+        }
+        total.add_review(review, learned);
+        if let Some(stack) = stack_uri.as_deref().and_then(|uri| stacks.get(uri)) {
+            by_stack
+                .entry(&stack.uri)
+                .or_insert_with(|| Breakdown::of_stack(stack))
+                .add_review(review, learned);
+            by_langs
+                .entry(lang_key(stack))
+                .or_insert_with(|| Breakdown::of_langs(stack))
+                .add_review(review, learned);
+        }
+    }
+
+    let mut due: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for StackedReviewState { state, stack_uri } in states {
+        *due.entry(state.due_at.date_naive().max(today)).or_default() += 1;
+        total.add_state(state, tomorrow);
+        if let Some(stack) = stacks.get(stack_uri.as_str()) {
+            by_stack
+                .entry(&stack.uri)
+                .or_insert_with(|| Breakdown::of_stack(stack))
+                .add_state(state, tomorrow);
+            by_langs
+                .entry(lang_key(stack))
+                .or_insert_with(|| Breakdown::of_langs(stack))
+                .add_state(state, tomorrow);
+        }
+    }
+
+    let days = |from: NaiveDate, n: i64| (0..n).map(move |i| from + Duration::days(i));
+    let count = |counts: &BTreeMap<NaiveDate, usize>, date: NaiveDate| {
+        counts.get(&date).copied().unwrap_or(0)
+    };
+    let start = calendar_start(today);
+    let calendar_days: Vec<NaiveDate> = days(start, CALENDAR_WEEKS * 7)
+        .take_while(|date| *date <= today)
+        .collect();
+    let calendar = chart(calendar_days.iter().map(|d| (*d, count(&per_day, *d))))
+        .into_iter()
+        .fold(Vec::new(), |mut weeks: Vec<Vec<Day>>, day| {
+            match weeks.last_mut() {
+                Some(week) if week.len() < 7 => week.push(day),
+                _ => weeks.push(vec![day]),
+            }
+            weeks
+        });
+
+    let reviewed: HashSet<NaiveDate> = per_day.keys().copied().collect();
+    let mut streak_day = if reviewed.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+    let mut streak = 0;
+    while streak_day >= start && reviewed.contains(&streak_day) {
+        streak += 1;
+        streak_day -= Duration::days(1);
+    }
+    let (_, longest_streak) = calendar_days.iter().fold((0, 0), |(run, longest), d| {
+        let run = if reviewed.contains(d) { run + 1 } else { 0 };
+        (run, longest.max(run))
+    });
+
+    Stats {
+        reviews_today: count(&per_day, today),
+        per_day: chart(days(recent, HISTORY_DAYS).map(|d| (d, count(&per_day, d)))),
+        forecast: chart(days(today, FORECAST_DAYS).map(|d| (d, count(&due, d)))),
+        calendar,
+        streak,
+        longest_streak,
+        total,
+        stacks: busiest_first(by_stack.into_values()),
+        lang_pairs: busiest_first(by_langs.into_values()),
+    }
+}
@@ -1,23 +1,21 @@
 /// Storage impls to persis OAuth sessions if you are not using the memory stores
 /// https://github.com/bluesky-social/statusphere-example-app/blob/main/src/auth/storage.ts
-use crate::db::{AuthSession, AuthState};
+use crate::db::{AuthSession, AuthState, Repo};
 use atrium_api::types::string::Did;
 use atrium_common::store::Store;
 use atrium_oauth::store::session::SessionStore;
 use atrium_oauth::store::state::StateStore;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use sqlx::postgres::PgPool;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DbStoreError {
     #[error("Invalid session")]
     InvalidSession,
-    #[error("No session found")]
-    NoSessionFound,
     #[error("Database error: {0}")]
     DatabaseError(sqlx::Error),
 }
@@ -26,12 +24,12 @@ pub enum DbStoreError {
 impl SessionStore for DbSessionStore {}
 
 pub struct DbSessionStore {
-    db_pool: PgPool,
+    repo: Arc<dyn Repo>,
 }
 
 impl DbSessionStore {
-    pub fn new(db: PgPool) -> Self {
-        Self { db_pool: db }
+    pub fn new(repo: Arc<dyn Repo>) -> Self {
+        Self { repo }
     }
 }
 
@@ -43,7 +41,7 @@ where
     type Error = DbStoreError;
     async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
         let did = key.as_ref();
-        match AuthSession::get_by_did(did, &self.db_pool).await {
+        match self.repo.get_session(did).await {
             Ok(Some(auth_session)) => {
                 let deserialized_session: V = serde_json::from_str(&auth_session.session)
                     .map_err(|_| DbStoreError::InvalidSession)?;
@@ -60,8 +58,8 @@ where
     async fn set(&self, key: K, value: V) -> Result<(), Self::Error> {
         let did = key.as_ref().to_string();
         let auth_session = AuthSession::new(did, value);
-        auth_session
-            .save_or_update(&self.db_pool)
+        self.repo
+            .save_session(&auth_session)
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
@@ -69,14 +67,16 @@ where
 
     async fn del(&self, _key: &K) -> Result<(), Self::Error> {
         let did = _key.as_ref();
-        AuthSession::delete_by_did(did, &self.db_pool)
+        self.repo
+            .delete_session(did)
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
     }
 
     async fn clear(&self) -> Result<(), Self::Error> {
-        AuthSession::delete_all(&self.db_pool)
+        self.repo
+            .clear_sessions()
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
@@ -87,12 +87,12 @@ where
 impl StateStore for DbStateStore {}
 
 pub struct DbStateStore {
-    db_pool: PgPool,
+    repo: Arc<dyn Repo>,
 }
 
 impl DbStateStore {
-    pub fn new(db: PgPool) -> Self {
-        Self { db_pool: db }
+    pub fn new(repo: Arc<dyn Repo>) -> Self {
+        Self { repo }
     }
 }
 
@@ -105,7 +105,7 @@ where
     async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
         let key = key.as_ref();
         log::info!("state store get key {key}");
-        match AuthState::get_by_key(key, &self.db_pool).await {
+        match self.repo.get_state(key).await {
             Ok(Some(auth_state)) => {
                 let deserialized_state: V = serde_json::from_str(&auth_state.state)
                     .map_err(|_| DbStoreError::InvalidSession)?;
@@ -122,8 +122,8 @@ where
     async fn set(&self, key: K, value: V) -> Result<(), Self::Error> {
         let did = key.as_ref().to_string();
         let auth_state = AuthState::new(did, value);
-        auth_state
-            .save_or_update(&self.db_pool)
+        self.repo
+            .save_state(&auth_state)
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
@@ -131,14 +131,16 @@ where
 
     async fn del(&self, _key: &K) -> Result<(), Self::Error> {
         let key = _key.as_ref();
-        AuthState::delete_by_key(key, &self.db_pool)
+        self.repo
+            .delete_state(key)
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
     }
 
     async fn clear(&self) -> Result<(), Self::Error> {
-        AuthState::delete_all(&self.db_pool)
+        self.repo
+            .clear_states()
             .await
             .map_err(DbStoreError::DatabaseError)?;
         Ok(())
@@ -0,0 +1,104 @@
+//! Tags: free-form labels on stacks and cards, like `verbs` or `JLPT N5`, to find and
+//! group them by. Tags are compared case-insensitively, so `Verbs` and `verbs` are one
+//! tag, spelled however it was first used.
+use crate::i18n::Message;
+use serde::{Deserialize, Deserializer};
+use unicode_normalization::UnicodeNormalization;
+
+/// Tags on one stack or card, at most
+pub const MAX_TAGS: usize = 10;
+/// Characters in one tag, at most
+pub const MAX_TAG_LENGTH: usize = 64;
+/// What the editors separate tags with
+pub const TAG_SEPARATOR: char = ',';
+
+/// `tag` as it's stored: composed, spaced singly, and without the `#` it may have been
+/// typed with. Nothing if that leaves nothing.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag: String = tag.nfc().collect();
+    let tag = tag.trim().trim_start_matches('#');
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// The key tags are compared by
+pub fn tag_key(tag: &str) -> String {
+    normalize_tag(tag).unwrap_or_default().to_lowercase()
+}
+
+/// Whether `tags` has `tag` in it
+pub fn has_tag(tags: &[String], tag: &str) -> bool {
+    let key = tag_key(tag);
+    tags.iter().any(|t| tag_key(t) == key)
+}
+
+/// Why `tags` can't be saved as they are, if they can't
+pub fn validate(tags: &[String]) -> Option<Message> {
+    let tags = tags.iter().filter_map(|t| normalize_tag(t));
+    let mut count = 0;
+    for tag in tags {
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Some(
+                Message::new("invalid-tag-length")
+                    .arg("max", MAX_TAG_LENGTH)
+                    .arg("tag", tag),
+            );
+        }
+        count += 1;
+    }
+    if count > MAX_TAGS {
+        Some(Message::new("invalid-tag-count").arg("max", MAX_TAGS))
+    } else {
+        None
+    }
+}
+
+/// `tags` as they're stored, with blanks and repeats dropped. Records from elsewhere can
+/// break the rules, so anything too long or past [`MAX_TAGS`] is dropped too.
+pub fn normalize(tags: &[String]) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut out = Vec::new();
+    for tag in tags.iter().filter_map(|t| normalize_tag(t)) {
+        let key = tag.to_lowercase();
+        if tag.chars().count() > MAX_TAG_LENGTH || seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        out.push(tag);
+        if out.len() == MAX_TAGS {
+            break;
+        }
+    }
+    out
+}
+
+/// `tags` as a record holds them, leaving the field out if there are none
+pub fn record_tags(tags: Vec<String>) -> Option<Vec<String>> {
+    (!tags.is_empty()).then_some(tags)
+}
+
+/// `tags` as the editors show them
+pub fn tags_text(tags: &[String]) -> String {
+    tags.join(&format!("{TAG_SEPARATOR} "))
+}
+
+/// Splits what was typed into an editor into tags
+pub fn split_tags(text: &str) -> Vec<String> {
+    text.split(TAG_SEPARATOR).map(str::to_string).collect()
+}
+
+/// Tags as either a list, from the JSON API, or separated text, from a form
+pub fn deserialize_tags<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Text(String),
+    }
+    Ok(match Tags::deserialize(deserializer)? {
+        Tags::List(tags) => tags,
+        Tags::Text(text) => split_tags(&text),
+    })
+}
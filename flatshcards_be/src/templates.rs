@@ -1,15 +1,140 @@
+use crate::answer::Checked;
 ///The askama template types for HTML
 ///
 use crate::db;
+use crate::i18n::{Locale, Message};
+use crate::lang::{LangGroup, LangPicker};
+use crate::matching::{MAX_PAIRS, MIN_PAIRS, MatchBoard, seconds};
+use crate::quiz::{Question, QuizScore};
+use crate::review::{MAX_PER_DAY, ReviewItem};
+use crate::services::{ReviewFilter, ReviewOptions, ReviewQueue, games::MatchFinish};
+use crate::stats::{FORECAST_DAYS, HISTORY_DAYS, Stats};
+use crate::tags::tag_key;
 use askama::Template;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 #[derive(Template)]
 #[template(path = "home.html")]
 pub struct HomeTemplate<'a> {
+    pub locale: Locale,
     pub title: &'a str,
     pub profile: Option<Profile>,
     pub stacks: Vec<db::StackDetails>,
+    pub collections: Vec<db::CollectionDetails>,
+    /// Every tag the user has used, to filter the stacks by
+    pub tags: Vec<String>,
+    /// The tag the stacks are filtered by, if they are
+    pub tag: Option<String>,
+}
+
+impl HomeTemplate<'_> {
+    /// The languages the stacks are from, to start a review session on
+    pub fn front_langs(&self) -> BTreeSet<&str> {
+        self.stacks
+            .iter()
+            .filter_map(|s| s.front_lang.as_deref())
+            .collect()
+    }
+
+    /// The languages the stacks are to
+    pub fn back_langs(&self) -> BTreeSet<&str> {
+        self.stacks
+            .iter()
+            .filter_map(|s| s.back_lang.as_deref())
+            .collect()
+    }
+
+    /// Whether the stacks are filtered by `tag`
+    pub fn is_tag(&self, tag: &str) -> bool {
+        self.tag
+            .as_deref()
+            .is_some_and(|t| tag_key(t) == tag_key(tag))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "search.html")]
+pub struct SearchTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    /// What was searched for, as it was typed
+    pub q: String,
+    pub tag: String,
+    pub searched: bool,
+    pub stacks: Vec<db::StackDetails>,
+    /// Whether there's anyone to clone stacks for
+    pub logged_in: bool,
+}
+
+/// A stack's own page, saying what it's for and whether it may be reused, for anyone to
+/// study or copy it from
+#[derive(Template)]
+#[template(path = "stack.html")]
+pub struct StackTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub stack: db::StackDetails,
+    /// How many cards of the stack's are indexed
+    pub card_count: usize,
+    /// Whether there's anyone to copy the stack for
+    pub logged_in: bool,
+    /// Whether they're the one who can edit it
+    pub owned: bool,
+}
+
+/// A collection's own page, for anyone to study or copy it from
+#[derive(Template)]
+#[template(path = "collection.html")]
+pub struct CollectionTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub collection: db::CollectionDetails,
+    /// The collection's stacks that are indexed, in order
+    pub stacks: Vec<db::StackDetails>,
+    /// Whether there's anyone to copy the collection for
+    pub logged_in: bool,
+    /// Whether they're the one who can edit it
+    pub owned: bool,
+}
+
+/// The collection editor, for a new collection if there's no `collection`
+#[derive(Template)]
+#[template(path = "edit_collection.html")]
+pub struct EditCollectionTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub collection: Option<db::CollectionDetails>,
+    /// The collection's stacks that are indexed, in order
+    pub stacks: Vec<db::StackDetails>,
+    /// The user's stacks, to add to the collection
+    pub user_stacks: Vec<db::StackDetails>,
+    /// A message id
+    pub error: Option<&'a str>,
+}
+
+impl EditCollectionTemplate<'_> {
+    pub fn label(&self) -> &str {
+        self.collection
+            .as_ref()
+            .map(|c| c.label.as_str())
+            .unwrap_or_default()
+    }
+    pub fn description(&self) -> &str {
+        self.collection
+            .as_ref()
+            .and_then(|c| c.description.as_deref())
+            .unwrap_or_default()
+    }
+    /// The uris of [`Self::stacks`], one a line, as the form submits them
+    pub fn stacks_text(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|s| s.uri.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -21,40 +146,58 @@ pub struct Profile {
 #[derive(Template)]
 #[template(path = "login.html")]
 pub struct LoginTemplate<'a> {
+    pub locale: Locale,
     pub title: &'a str,
+    /// A message id
     pub error: Option<&'a str>,
 }
 
 #[derive(Template)]
 #[template(path = "error.html")]
 pub struct ErrorTemplate<'a> {
+    pub locale: Locale,
     pub title: &'a str,
-    pub error: &'a str,
+    pub error: String,
 }
 
 impl<'a> ErrorTemplate<'a> {
-    pub fn session_agent_did() -> Self {
+    /// An error page with the message `error`
+    pub fn new(locale: Locale, error: &str) -> Self {
         Self {
-            title: "Error",
-            error: "Error retrieving AtProto agent",
+            locale,
+            title: "title-error",
+            error: locale.t(error),
         }
     }
-    pub fn stack_not_found() -> Self {
+    /// For a form that didn't validate, saying why
+    pub fn invalid(locale: Locale, error: &Message) -> Self {
         Self {
-            title: "Error",
-            error: "Stack not found",
+            locale,
+            title: "title-form-validation",
+            error: error.text(locale),
         }
     }
-    pub fn forbidden() -> Self {
+    pub fn session_agent_did(locale: Locale) -> Self {
+        Self::new(locale, "error-session-agent")
+    }
+    pub fn stack_not_found(locale: Locale) -> Self {
+        Self::new(locale, "error-stack-not-found")
+    }
+    pub fn forbidden(locale: Locale) -> Self {
         Self {
-            title: "Forbidden",
-            error: "You do not have permission to perform this action",
+            title: "title-forbidden",
+            ..Self::new(locale, "error-forbidden")
         }
     }
-    pub fn db_query() -> Self {
+    pub fn db_query(locale: Locale) -> Self {
+        Self::new(locale, "error-db-query")
+    }
+    /// For a copy that went without the cards whose front text is `failed`
+    pub fn clone_incomplete(locale: Locale, failed: &[String]) -> Self {
+        let error = Message::new("error-clone-incomplete").arg("cards", failed.join(", "));
         Self {
-            title: "Error",
-            error: "Error querying database",
+            error: error.text(locale),
+            ..Self::new(locale, "error-unknown")
         }
     }
 }
@@ -62,44 +205,66 @@ impl<'a> ErrorTemplate<'a> {
 #[derive(Template)]
 #[template(path = "create_stack.html")]
 pub struct CreateStackTemplate<'a> {
+    pub locale: Locale,
     pub title: &'a str,
-    pub lang_choices: Vec<(&'a str, &'a str)>,
+    pub langs: LangPicker,
+    /// A message id
     pub error: Option<&'a str>,
 }
 
 #[derive(Template)]
 #[template(path = "edit_stack.html")]
 pub struct EditStackTemplate<'a> {
+    pub locale: Locale,
     pub title: &'a str,
-    pub lang_choices: Vec<(&'a str, &'a str)>,
+    pub langs: LangPicker,
     pub stack: db::StackDetails,
+    /// A message id
     pub error: Option<&'a str>,
-    pub add_card: AddCardTemplate<'a>,
-    pub edit_cards: EditCardsTemplate<'a>,
+    pub add_card: AddCardTemplate,
+    pub edit_cards: EditCardsTemplate,
+    pub speech: bool,
 }
 
 #[derive(Template)]
 #[template(path = "add_card.html")]
-pub struct AddCardTemplate<'a> {
-    pub lang_choices: Vec<(&'a str, &'a str)>,
+pub struct AddCardTemplate {
+    pub locale: Locale,
+    pub langs: LangPicker,
     pub stack: db::StackDetails,
     pub error: Option<String>,
+    /// Whether audio can be generated from card text
+    pub speech: bool,
 }
 
 #[derive(Template)]
 #[template(path = "edit_cards.html")]
-pub struct EditCardsTemplate<'a> {
-    pub lang_choices: Vec<(&'a str, &'a str)>,
+pub struct EditCardsTemplate {
+    pub locale: Locale,
+    pub langs: LangPicker,
     pub cards: Vec<db::DisplayCard>,
     pub stack_id: String,
+    pub speech: bool,
 }
 
 #[derive(Template)]
 #[template(path = "edit_single_card.html")]
-pub struct EditSingleCardTemplate<'a> {
-    pub lang_choices: Vec<(&'a str, &'a str)>,
+pub struct EditSingleCardTemplate {
+    pub locale: Locale,
+    pub langs: LangPicker,
     pub card: db::DisplayCard,
     pub stack_id: String,
+    pub speech: bool,
+}
+
+/// The options of a language picker, after a search
+#[derive(Template)]
+#[template(path = "lang_options.html")]
+pub struct LangOptionsTemplate<'a> {
+    pub locale: Locale,
+    pub selected: &'a str,
+    pub none_label: &'a str,
+    pub groups: Vec<LangGroup>,
 }
 
 #[derive(Template)]
@@ -107,3 +272,188 @@ pub struct EditSingleCardTemplate<'a> {
 pub struct FormError<'a> {
     pub error: &'a str,
 }
+
+#[derive(Template)]
+#[template(path = "review.html")]
+pub struct ReviewTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    /// Nothing for a session over several stacks
+    pub stack: Option<db::StackDetails>,
+    pub options: ReviewOptions,
+    pub filter: ReviewFilter,
+    pub limits: db::ReviewLimits,
+    pub item: ReviewItemTemplate,
+}
+
+impl<'a> ReviewTemplate<'a> {
+    pub fn new(
+        locale: Locale,
+        queue: ReviewQueue,
+        options: ReviewOptions,
+        filter: ReviewFilter,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            locale,
+            title: if filter.session {
+                "title-review-session"
+            } else {
+                "title-review"
+            },
+            item: ReviewItemTemplate::new(locale, &queue, options, filter.clone(), now),
+            stack: queue.stack,
+            options,
+            filter,
+            limits: queue.limits,
+        }
+    }
+
+    pub fn max_per_day(&self) -> i32 {
+        MAX_PER_DAY
+    }
+}
+
+#[derive(Template)]
+#[template(path = "review_item.html")]
+pub struct ReviewItemTemplate {
+    pub locale: Locale,
+    /// Nothing once the stack's done for now
+    pub item: Option<ReviewItem>,
+    pub due: usize,
+    pub new: usize,
+    /// Left for another day by the reviewer's limits
+    pub held_back: usize,
+    /// RFC 3339, sent back with the grade
+    pub shown_at: String,
+    pub options: ReviewOptions,
+    pub filter: ReviewFilter,
+    /// What was typed for `item`, once it's been checked
+    pub typed: Option<(String, Checked)>,
+}
+
+impl ReviewItemTemplate {
+    pub fn new(
+        locale: Locale,
+        queue: &ReviewQueue,
+        options: ReviewOptions,
+        filter: ReviewFilter,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            locale,
+            item: queue.next().cloned(),
+            due: queue.due,
+            new: queue.new,
+            held_back: queue.held_back,
+            shown_at: now.to_rfc3339(),
+            options,
+            filter,
+            typed: None,
+        }
+    }
+
+    /// The page's settings and the session's filter, to post back with each grade
+    pub fn query(&self) -> String {
+        let mut query = self.options.query();
+        if self.filter.session {
+            query.push_str("&session=true");
+            let fields = [
+                ("frontLang", self.filter.front_lang()),
+                ("backLang", self.filter.back_lang()),
+                ("tag", self.filter.tag()),
+            ];
+            for (name, value) in fields {
+                if let Some(Ok(value)) = value.map(askama::filters::urlencode_strict) {
+                    query.push_str(&format!("&{name}={value}"));
+                }
+            }
+        }
+        query
+    }
+
+    /// Whether `grade` is the one suggested for what was typed
+    pub fn is_suggested(&self, grade: &str) -> bool {
+        self.typed
+            .as_ref()
+            .is_some_and(|(_, checked)| checked.is_suggested(grade))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "quiz.html")]
+pub struct QuizTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub stack: db::StackDetails,
+    pub questions: Vec<Question>,
+}
+
+#[derive(Template)]
+#[template(path = "quiz_score.html")]
+pub struct QuizScoreTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub stack: db::StackDetails,
+    pub score: QuizScore,
+}
+
+#[derive(Template)]
+#[template(path = "match.html")]
+pub struct MatchTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub stack: db::StackDetails,
+    pub board: MatchBoard,
+    pub best: Option<db::MatchScore>,
+}
+
+#[derive(Template)]
+#[template(path = "match_result.html")]
+pub struct MatchResultTemplate {
+    pub locale: Locale,
+    pub finish: MatchFinish,
+    pub logged_in: bool,
+}
+
+impl MatchTemplate<'_> {
+    pub fn best_time(&self) -> Option<String> {
+        self.best.as_ref().map(|best| seconds(best.duration_ms))
+    }
+
+    /// Choices of board size
+    pub fn pair_options(&self) -> Vec<usize> {
+        (MIN_PAIRS..=MAX_PAIRS).collect()
+    }
+}
+
+impl MatchResultTemplate {
+    pub fn time(&self) -> String {
+        seconds(self.finish.duration_ms)
+    }
+
+    pub fn best_time(&self) -> Option<String> {
+        self.finish
+            .best
+            .as_ref()
+            .map(|best| seconds(best.duration_ms))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "stats.html")]
+pub struct StatsTemplate<'a> {
+    pub locale: Locale,
+    pub title: &'a str,
+    pub stats: Stats,
+}
+
+impl StatsTemplate<'_> {
+    pub fn history_days(&self) -> String {
+        HISTORY_DAYS.to_string()
+    }
+
+    pub fn forecast_days(&self) -> String {
+        FORECAST_DAYS.to_string()
+    }
+}
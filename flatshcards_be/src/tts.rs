@@ -0,0 +1,200 @@
+//! Pronunciation audio generated from card text, so a card can have audio without anyone
+//! recording it. Speech comes from a local engine, and is kept on disk by text and
+//! language: the same text always sounds the same, so it's only ever generated once.
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+#[derive(Error, Debug)]
+pub enum TtsError {
+    #[error("No voice for {0}")]
+    NoVoice(String),
+    #[error("Error generating speech: {0}")]
+    Engine(String),
+    #[error("Error caching speech: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[async_trait]
+pub trait Tts: Send + Sync {
+    /// `text` said in the language with BCP-47 tag `lang`, as audio in one of
+    /// [`crate::blobs::AUDIO_TYPES`]
+    async fn speak(&self, text: &str, lang: &str) -> Result<Vec<u8>, TtsError>;
+}
+
+/// How long the engine gets to say something before it's stopped
+const SPEECH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// [eSpeak NG](https://github.com/espeak-ng/espeak-ng), run as `program`. It speaks
+/// most languages, none of them especially well, and writes WAV.
+pub struct Espeak {
+    program: String,
+}
+
+impl Espeak {
+    pub fn new(program: String) -> Self {
+        Self { program }
+    }
+
+    /// Its voices are named like lowercase tags, `pt-br` or `fr`, with the region left
+    /// off for most
+    fn voices(lang: &str) -> Vec<String> {
+        let lang = lang.to_ascii_lowercase();
+        let mut voices = vec![lang.clone()];
+        if let Some((primary, _)) = lang.split_once('-') {
+            voices.push(primary.to_string());
+        }
+        voices
+    }
+
+    async fn run(&self, text: &str, voice: &str) -> Result<Option<Vec<u8>>, TtsError> {
+        let mut child = Command::new(&self.program)
+            // The text is UTF-8, read from stdin so it can't be taken for options
+            .args(["-b", "1", "--stdin", "--stdout", "-v", voice])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| TtsError::Engine(format!("{}: {e}", self.program)))?;
+        let stdin = child.stdin.take();
+        let speaking = async {
+            if let Some(mut stdin) = stdin {
+                stdin.write_all(text.as_bytes()).await?;
+            }
+            child.wait_with_output().await
+        };
+        // Dropping the child kills it, should it run over
+        let output = tokio::time::timeout(SPEECH_TIMEOUT, speaking)
+            .await
+            .map_err(|_| {
+                TtsError::Engine(format!(
+                    "{} took longer than {}s",
+                    self.program,
+                    SPEECH_TIMEOUT.as_secs()
+                ))
+            })??;
+        // An unknown voice is an error exit with nothing written
+        if output.status.success() && !output.stdout.is_empty() {
+            Ok(Some(output.stdout))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[async_trait]
+impl Tts for Espeak {
+    async fn speak(&self, text: &str, lang: &str) -> Result<Vec<u8>, TtsError> {
+        for voice in Espeak::voices(lang) {
+            if let Some(audio) = self.run(text, &voice).await? {
+                return Ok(audio);
+            }
+        }
+        Err(TtsError::NoVoice(lang.to_string()))
+    }
+}
+
+/// Speech from `tts`, cached in `dir`, which is kept to `max_bytes` by deleting the
+/// oldest of it
+pub struct SpeechCache {
+    dir: PathBuf,
+    tts: Box<dyn Tts>,
+    max_bytes: u64,
+}
+
+impl SpeechCache {
+    pub fn new(dir: PathBuf, tts: Box<dyn Tts>, max_bytes: u64) -> Self {
+        Self {
+            dir,
+            tts,
+            max_bytes,
+        }
+    }
+
+    /// `text` said in `lang`, from the cache if it's been said before
+    pub async fn speak(&self, text: &str, lang: &str) -> Result<Vec<u8>, TtsError> {
+        let key = hex::encode(Sha256::digest(format!("{lang}\0{text}")));
+        let path = self.dir.join(&key);
+        if let Ok(data) = tokio::fs::read(&path).await {
+            return Ok(data);
+        }
+        let data = self.tts.speak(text, lang).await?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let partial = self.dir.join(format!("{key}.partial"));
+        tokio::fs::write(&partial, &data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        if let Err(err) = self.prune().await {
+            log::error!("error pruning speech cache {err}");
+        }
+        Ok(data)
+    }
+
+    /// Deletes the oldest speech until what's left fits in `max_bytes`
+    async fn prune(&self) -> Result<(), std::io::Error> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_file() {
+                files.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+        for (_, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                // Another request got to it first
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Says everything as 100 bytes, counting how often
+    #[derive(Default)]
+    struct Counting(AtomicU32);
+
+    #[async_trait]
+    impl Tts for std::sync::Arc<Counting> {
+        async fn speak(&self, _: &str, _: &str) -> Result<Vec<u8>, TtsError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![0; 100])
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_cache_to_its_size() {
+        let dir =
+            std::env::temp_dir().join(format!("flatshcards-speech-{:x}", rand::random::<u64>()));
+        let tts = std::sync::Arc::new(Counting::default());
+        let cache = SpeechCache::new(dir.clone(), Box::new(tts.clone()), 250);
+        for text in ["一", "二", "三", "四"] {
+            cache.speak(text, "ja").await.unwrap();
+        }
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        // The newest is kept, and the oldest has to be said again
+        cache.speak("四", "ja").await.unwrap();
+        assert_eq!(tts.0.load(Ordering::Relaxed), 4);
+        cache.speak("一", "ja").await.unwrap();
+        assert_eq!(tts.0.load(Ordering::Relaxed), 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}